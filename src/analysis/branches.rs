@@ -0,0 +1,252 @@
+use super::create_graph::get_typeck_results;
+use crate::graph::{CallGraph, CallNodeKind, Handling};
+use rustc_hir::def::{CtorKind, CtorOf, DefKind, Res};
+use rustc_hir::def_id::DefId;
+use rustc_hir::{Arm, Block, Expr, ExprKind, HirId, MatchSource, Node, Pat, PatKind, StmtKind};
+use rustc_middle::ty::{self, TyCtxt, TypeckResults};
+use rustc_span::{ExpnKind, Span};
+
+/// The macros and functions logging errors, besides the ones passed with `--log-fns`.
+const LOG_FUNCTIONS: [&str; 12] = [
+    "log::error",
+    "log::warn",
+    "log::info",
+    "log::debug",
+    "log::trace",
+    "tracing::error",
+    "tracing::warn",
+    "tracing::info",
+    "tracing::debug",
+    "tracing::trace",
+    "std::eprintln",
+    "std::eprint",
+];
+
+/// The functions ending the process without unwinding.
+const EXIT_FUNCTIONS: [&str; 2] = ["std::process::exit", "std::process::abort"];
+
+/// A branch taken on the error of a call: an arm or branch of an `if let`, or the `else` block of a `let`-`else`.
+#[derive(Clone, Copy)]
+enum Branch<'tcx> {
+    Expr(&'tcx Expr<'tcx>),
+    Block(&'tcx Block<'tcx>),
+}
+
+/// Mark the handled calls whose error is dealt with by ending the process (e.g. `Err(e) => std::process::exit(1)`),
+/// or only logged (e.g. `Err(e) => log::error!("{e}")`), which notices the error without acting on it.
+/// The branches taken on the error are the `Err` arms of a `match` on the result, the branch of an `if let` taken on an error,
+/// or the `else` block of a `let Ok(v) = f() else { .. }`.
+pub fn mark_error_branches(context: TyCtxt, graph: &mut CallGraph, log_fns: &[String]) {
+    for edge in &mut graph.edges {
+        if !edge.is_error
+            || edge.handling != Handling::Handled
+            || !matches!(graph.nodes[edge.from].kind, CallNodeKind::LocalFn(..))
+        {
+            continue;
+        }
+        let Some(call_id) = edge.call_id.hir_id() else {
+            continue;
+        };
+        let Some(typeck) = get_typeck_results(context, call_id) else {
+            continue;
+        };
+        let branches = get_error_branches(context, typeck, call_id);
+        if branches.is_empty() {
+            continue;
+        }
+
+        // Ending the process after logging the error still ends it
+        if branches
+            .iter()
+            .all(|branch| ends_process(context, typeck, *branch))
+        {
+            edge.handling = Handling::ProcessExit;
+        } else if branches
+            .iter()
+            .all(|branch| is_only_logging(context, typeck, log_fns, *branch))
+        {
+            edge.handling = Handling::LoggedAndDropped;
+        }
+    }
+}
+
+/// Get the branches taken on the error of a call matched on, if it is matched on by a `match`, `if let` or `let`-`else`.
+fn get_error_branches<'tcx>(
+    context: TyCtxt<'tcx>,
+    typeck: &TypeckResults,
+    call_id: HirId,
+) -> Vec<Branch<'tcx>> {
+    let mut parents = context.hir().parent_iter(call_id);
+    let Some((_id, parent)) = parents.next() else {
+        return vec![];
+    };
+
+    match parent {
+        Node::Expr(Expr {
+            kind: ExprKind::Match(scrutinee, arms, MatchSource::Normal),
+            ..
+        }) if scrutinee.hir_id == call_id => {
+            let err_arms: Vec<&Arm> = arms
+                .iter()
+                .filter(|arm| is_err_pattern(context, typeck, arm.pat))
+                .collect();
+            // Guarded arms do not take every error
+            if err_arms.iter().any(|arm| arm.guard.is_some()) {
+                return vec![];
+            }
+            err_arms
+                .into_iter()
+                .map(|arm| Branch::Expr(arm.body))
+                .collect()
+        }
+        Node::Expr(Expr {
+            kind: ExprKind::Let(let_expr),
+            ..
+        }) if let_expr.init.hir_id == call_id => {
+            let Some((_id, Node::Expr(if_expr))) = parents.next() else {
+                return vec![];
+            };
+            let ExprKind::If(_cond, then, otherwise) = if_expr.kind else {
+                return vec![];
+            };
+            // `if let Err(e) = f()` takes its branch on an error, `if let Ok(v) = f()` its `else` branch
+            if is_err_pattern(context, typeck, let_expr.pat) {
+                vec![Branch::Expr(then)]
+            } else if is_ok_pattern(context, typeck, let_expr.pat) {
+                otherwise.map(Branch::Expr).into_iter().collect()
+            } else {
+                vec![]
+            }
+        }
+        Node::LetStmt(local)
+            if local.init.is_some_and(|init| init.hir_id == call_id)
+                && is_ok_pattern(context, typeck, local.pat) =>
+        {
+            local.els.map(Branch::Block).into_iter().collect()
+        }
+        _ => vec![],
+    }
+}
+
+/// Whether a branch ends the process, i.e. it calls `std::process::exit` or `std::process::abort` (e.g. after printing the error).
+fn ends_process(context: TyCtxt, typeck: &TypeckResults, branch: Branch) -> bool {
+    let block = match branch {
+        Branch::Block(block) => block,
+        Branch::Expr(Expr {
+            kind: ExprKind::Block(block, _label),
+            ..
+        }) => block,
+        Branch::Expr(expr) => return is_exit_call(context, typeck, expr),
+    };
+
+    let statements = block.stmts.iter().filter_map(|stmt| match stmt.kind {
+        StmtKind::Semi(expr) | StmtKind::Expr(expr) => Some(expr),
+        StmtKind::Let(_) | StmtKind::Item(_) => None,
+    });
+    statements
+        .chain(block.expr)
+        .any(|expr| ends_process(context, typeck, Branch::Expr(expr)))
+}
+
+/// Whether an expression is a call to `std::process::exit` or `std::process::abort`.
+fn is_exit_call(context: TyCtxt, typeck: &TypeckResults, expr: &Expr) -> bool {
+    let ExprKind::Call(callee, _args) = expr.kind else {
+        return false;
+    };
+    match typeck.expr_ty_opt(callee).map(|ty| ty.kind()) {
+        Some(ty::FnDef(def_id, _args)) => {
+            EXIT_FUNCTIONS.contains(&context.def_path_str(*def_id).as_str())
+        }
+        _ => false,
+    }
+}
+
+/// Whether a branch does nothing but log, i.e. it is a (block of) call(s) to logging macros or functions.
+/// Empty blocks do not log, these drop the error silently.
+fn is_only_logging(
+    context: TyCtxt,
+    typeck: &TypeckResults,
+    log_fns: &[String],
+    branch: Branch,
+) -> bool {
+    let expr = match branch {
+        Branch::Block(block) => return is_only_logging_block(context, typeck, log_fns, block),
+        Branch::Expr(expr) => expr,
+    };
+    if is_log_macro_expansion(context, log_fns, expr.span) {
+        return true;
+    }
+
+    match expr.kind {
+        ExprKind::Block(block, _label) => is_only_logging_block(context, typeck, log_fns, block),
+        ExprKind::Call(callee, _args) => match typeck.expr_ty_opt(callee).map(|ty| ty.kind()) {
+            Some(ty::FnDef(def_id, _args)) => is_log_fn(context, log_fns, *def_id),
+            _ => false,
+        },
+        ExprKind::MethodCall(..) => typeck
+            .type_dependent_def_id(expr.hir_id)
+            .is_some_and(|def_id| is_log_fn(context, log_fns, def_id)),
+        _ => false,
+    }
+}
+
+/// Whether a block does nothing but log, having at least one statement or a tail expression.
+fn is_only_logging_block(
+    context: TyCtxt,
+    typeck: &TypeckResults,
+    log_fns: &[String],
+    block: &Block,
+) -> bool {
+    let statements = block.stmts.iter().map(|stmt| match stmt.kind {
+        StmtKind::Semi(expr) | StmtKind::Expr(expr) => {
+            is_only_logging(context, typeck, log_fns, Branch::Expr(expr))
+        }
+        StmtKind::Let(_) | StmtKind::Item(_) => false,
+    });
+    let tail = block
+        .expr
+        .map(|expr| is_only_logging(context, typeck, log_fns, Branch::Expr(expr)));
+    let mut parts = statements.chain(tail).peekable();
+    parts.peek().is_some() && parts.all(|logs| logs)
+}
+
+/// Whether code was expanded from a logging macro, which may use helper macros itself, so all macros it was expanded from are checked.
+fn is_log_macro_expansion(context: TyCtxt, log_fns: &[String], span: Span) -> bool {
+    span.macro_backtrace().any(|expn_data| {
+        matches!(expn_data.kind, ExpnKind::Macro(_kind, _name))
+            && expn_data
+                .macro_def_id
+                .is_some_and(|def_id| is_log_fn(context, log_fns, def_id))
+    })
+}
+
+/// Whether a macro or function logs, as one of the known ones or passed with `--log-fns`.
+fn is_log_fn(context: TyCtxt, log_fns: &[String], def_id: DefId) -> bool {
+    let path = context.def_path_str(def_id);
+    LOG_FUNCTIONS.contains(&path.as_str()) || log_fns.contains(&path)
+}
+
+/// Whether a pattern matches the `Err` variant of a `Result`, e.g. `Err(e)` or `Err(_)`.
+fn is_err_pattern(context: TyCtxt, typeck: &TypeckResults, pat: &Pat) -> bool {
+    get_variant(context, typeck, pat)
+        .is_some_and(|variant| context.lang_items().result_err_variant() == Some(variant))
+}
+
+/// Whether a pattern matches the `Ok` variant of a `Result`, e.g. `Ok(v)`.
+fn is_ok_pattern(context: TyCtxt, typeck: &TypeckResults, pat: &Pat) -> bool {
+    get_variant(context, typeck, pat)
+        .is_some_and(|variant| context.lang_items().result_ok_variant() == Some(variant))
+}
+
+/// Get the variant a tuple struct pattern matches, e.g. `Err` of `Err(e)`.
+fn get_variant(context: TyCtxt, typeck: &TypeckResults, pat: &Pat) -> Option<DefId> {
+    let PatKind::TupleStruct(qpath, _pats, _dots) = pat.kind else {
+        return None;
+    };
+    match typeck.qpath_res(&qpath, pat.hir_id) {
+        Res::Def(DefKind::Ctor(CtorOf::Variant, CtorKind::Fn), ctor_id) => {
+            Some(context.parent(ctor_id))
+        }
+        _ => None,
+    }
+}
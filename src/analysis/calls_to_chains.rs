@@ -1,93 +1,1219 @@
-use crate::graph::{CallEdge, CallGraph, ChainGraph};
-use std::collections::HashMap;
+use super::{functions, AnalysisOptions};
+use crate::graph::{
+    CallEdge, CallGraph, CallId, CallNodeKind, Chain, ChainEnd, ChainGraph, Downcast,
+    ErrorCategory, FailureConversion, FailureKind, Handling, ImplicitCall, Visibility,
+};
+use crate::interner::Interner;
+use crate::progress::Progress;
+use crate::scoring::{self, ScoreWeights};
+use crate::stats::{ChainHop, ChainStats, ChainSummary};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use tracing::warn;
 
-pub fn to_chains(graph: &CallGraph) -> ChainGraph {
+pub fn to_chains(
+    graph: &CallGraph,
+    options: &AnalysisOptions,
+    progress: &mut Progress,
+) -> ChainGraph {
+    let exclude_option_chains = options.exclude_option_chains;
+    let depth_limit = options.max_chain_depth.unwrap_or(DEFAULT_MAX_CHAIN_DEPTH);
+    let chain_limit = options.max_chains.unwrap_or(DEFAULT_MAX_CHAINS);
     let mut new_graph = ChainGraph::new(graph.crate_name.clone());
 
-    let mut count: usize = 0;
-    let mut max_size: usize = 0;
-    let mut total_size: usize = 0;
-    let mut max_depth: usize = 0;
+    // The nodes whose chains are fully explored, shared by all chains
+    let mut explored: HashMap<usize, Subchain> = HashMap::new();
+    let mut raw_count: usize = 0;
+    let mut identities: Vec<Vec<CallIdentity>> = vec![];
+    let mut left_out_count: usize = 0;
+    let mut unfiltered_count: usize = 0;
     // Loop over all edges (e.g. function calls)
-    for edge in &graph.edges {
-        // Start of a chain
-        if edge.is_error && !edge.propagates {
-            let mut node_map: HashMap<usize, usize> = HashMap::new();
+    for (i, edge) in graph.edges.iter().enumerate() {
+        progress.update(|| {
+            format!(
+                "{i}/{} calls, {} chains found",
+                graph.edges.len(),
+                new_graph.chains.len()
+            )
+        });
+
+        // Start of a chain, i.e. where the error stops propagating
+        // Errors propagated by functions without callers (e.g. main) leave the call graph there, which ends a chain as well,
+        // where the errors propagated by the entry point go on to the program exit
+        let leaves_graph = edge.propagates
+            && graph
+                .get_incoming_edges(edge.from)
+                .iter()
+                .all(|call| call.implicit == Some(ImplicitCall::ProcessExit));
+        let is_exit = edge.implicit == Some(ImplicitCall::ProcessExit);
+        if edge.is_error && !is_exit && (!edge.propagates || leaves_graph) {
+            let is_option = edge.failure_kind == FailureKind::Option;
+            if is_option && exclude_option_chains {
+                continue;
+            }
+
+            let (mut calls, depth, _cut, truncated_at) = get_chain_from_edge(
+                graph,
+                edge,
+                &mut vec![],
+                &mut explored,
+                depth_limit,
+                exclude_option_chains,
+            );
+            // The call where the chain ends is part of its longest path as well
+            let depth = depth + 1;
 
-            let (mut calls, depth) = get_chain_from_edge(graph, edge, &mut vec![], 1);
+            // Options are returned everywhere, so they only form a chain once `None` is actually propagated
+            if is_option && calls.is_empty() && !edge.propagates {
+                continue;
+            }
             calls.push(edge.clone());
 
-            count += 1;
-            let size = calls.len();
-            total_size += size;
-            if size > max_size {
-                max_size = size;
+            // The same calls can form a chain more than once (e.g. for calls with multiple edges), these are only kept once
+            raw_count += 1;
+            let identity = get_chain_identity(&calls);
+            if identities.contains(&identity) {
+                continue;
+            }
+            identities.push(identity);
+
+            // Only the chains involving the error types of interest are kept, if any are provided
+            unfiltered_count += 1;
+            if !options.error_filters.is_empty() && !matches_error_filters(graph, &calls, options) {
+                continue;
             }
-            if depth > max_depth {
-                max_depth = depth;
+
+            let constructors =
+                get_chain_constructors(graph, &calls, &get_chain_origins(graph, &calls));
+            let chain = Chain {
+                index: 0,
+                calls,
+                depth,
+                constructors,
+                merged_into: None,
+                termination: get_chain_end(graph, edge),
+                truncated_at,
+                suppressed: edge.suppressed,
+            };
+
+            // Past the maximum number of chains, the deepest chains are kept
+            if new_graph.chains.len() < chain_limit {
+                new_graph.chains.push(chain);
+                continue;
             }
+            left_out_count += 1;
+            let shallowest = new_graph
+                .chains
+                .iter()
+                .enumerate()
+                .min_by_key(|(_i, kept)| kept.depth)
+                .map(|(i, _kept)| i);
+            if let Some(i) = shallowest.filter(|i| new_graph.chains[*i].depth < chain.depth) {
+                new_graph.chains.remove(i);
+                new_graph.chains.push(chain);
+            }
+        }
+    }
+
+    // The chains are numbered once it is known which are kept
+    for (i, chain) in new_graph.chains.iter_mut().enumerate() {
+        chain.index = i;
+    }
 
-            for call in calls {
-                // If we've already added the node to the new graph, refer to that, otherwise, add a new node
-                let from = if node_map.contains_key(&call.from) {
-                    node_map.get(&call.from).unwrap().clone()
-                } else {
-                    let id = new_graph.add_node(graph.nodes[call.from].label.clone());
-                    node_map.insert(call.from, id);
-                    id
-                };
+    let mut stats = ChainStats {
+        raw_count,
+        unfiltered_count: (!options.error_filters.is_empty()).then_some(unfiltered_count),
+        filtered_count: new_graph.chains.len() + left_out_count,
+        depth_limit,
+        left_out_count,
+        chain_limit,
+        ..ChainStats::default()
+    };
+    let mut endings: HashMap<ChainEnd, usize> = HashMap::new();
+    let mut origin_counts: HashMap<usize, usize> = HashMap::new();
+    for chain in &new_graph.chains {
+        let Some(edge) = chain.calls.last() else {
+            continue;
+        };
 
-                // Ditto
-                let to = if node_map.contains_key(&call.to) {
-                    node_map.get(&call.to).unwrap().clone()
-                } else {
-                    let id = new_graph.add_node(graph.nodes[call.to].label.clone());
-                    node_map.insert(call.to, id);
-                    id
-                };
+        // Note where the errors of the chain are born
+        for origin in get_chain_origins(graph, &chain.calls) {
+            *origin_counts.entry(origin).or_default() += 1;
+        }
 
-                // Add the edge
-                new_graph.add_edge(from, to, call.ty);
+        if edge.failure_kind == FailureKind::Option {
+            stats.option_count += 1;
+        }
+        if !chain.truncated_at.is_empty() {
+            stats.truncated_count += 1;
+        }
+        // Accepted ways to lose the error are counted apart
+        if chain.suppressed {
+            stats.suppressed_count += 1;
+        } else {
+            *endings.entry(chain.termination).or_default() += 1;
+        }
+        let size = chain.calls.len();
+        stats.total_size += size;
+        stats.max_size = stats.max_size.max(size);
+        stats.max_depth = stats.max_depth.max(chain.depth);
+
+        stats.type_erased_count += chain.calls.iter().filter(|call| call.type_erased).count();
+        stats.error_family_count += chain
+            .calls
+            .iter()
+            .filter(|call| call.error_family.is_some())
+            .count();
+
+        // Note how the error changes type along the chain
+        let depths = get_call_depths(edge, &chain.calls);
+        let types = get_chain_types(graph, &depths);
+        if types.len() > 1 {
+            stats
+                .conversions
+                .push((graph.label(edge.from).to_owned(), types));
+        }
+        for (caller, called, from_ty, to_ty) in
+            get_unexplained_transitions(graph, &depths, &chain.calls)
+        {
+            let transition = (
+                graph.label(called).to_owned(),
+                from_ty,
+                graph.label(caller).to_owned(),
+                to_ty,
+            );
+            if !stats.unexplained.contains(&transition) {
+                stats.unexplained.push(transition);
             }
         }
     }
-    let average_size = (total_size as f64) / (count as f64);
 
-    println!();
-    println!("There are {count} error propagation chains in this program.");
-    println!("The biggest chain consists of {max_size} function calls.");
-    println!("The longest error path consists of {max_depth} chained function calls.");
-    println!("The average chain consists of {average_size} function calls.");
-    println!();
+    // Chains reaching the same propagation path from different calls are shown (and counted) as one
+    merge_shared_chains(&mut new_graph.chains);
 
+    // Count the chains by their depth and size, the merged chains are counted as part of the chain they are merged into
+    let unmerged: Vec<&Chain> = new_graph
+        .chains
+        .iter()
+        .filter(|chain| chain.merged_into.is_none())
+        .collect();
+    stats.count = unmerged.len();
+    stats.merged_count = new_graph.chains.len() - stats.count;
+    stats.depth_histogram = get_histogram(unmerged.iter().map(|chain| chain.depth));
+    stats.size_histogram = get_histogram(unmerged.iter().map(|chain| chain.calls.len()));
+
+    // Note which chains have the longest error paths and the most calls, ties are broken by the other measure, leaving out the accepted ones
+    let mut summaries: Vec<ChainSummary> = unmerged
+        .iter()
+        .filter(|chain| !chain.suppressed)
+        .map(|chain| get_chain_summary(graph, chain, &options.score_weights))
+        .collect();
+    summaries.sort_by(|a, b| b.depth.cmp(&a.depth).then(b.size.cmp(&a.size)));
+    stats.deepest_chains = summaries.iter().take(TOP_CHAINS).cloned().collect();
+    summaries.sort_by(|a, b| b.size.cmp(&a.size).then(b.depth.cmp(&a.depth)));
+    stats.largest_chains = summaries.into_iter().take(TOP_CHAINS).collect();
+
+    // Note which chains are most severe, including the merged chains as they end in a call of their own, but not the accepted ones
+    let mut summaries: Vec<ChainSummary> = new_graph
+        .chains
+        .iter()
+        .filter(|chain| !chain.suppressed)
+        .map(|chain| get_chain_summary(graph, chain, &options.score_weights))
+        .collect();
+    summaries.sort_by(|a, b| b.score.total_cmp(&a.score).then(b.depth.cmp(&a.depth)));
+    stats.most_severe_chains = summaries.into_iter().take(TOP_CHAINS).collect();
+
+    let mut endings: Vec<(ChainEnd, usize)> = endings.into_iter().collect();
+    endings.sort_unstable();
+    stats.endings = endings;
+
+    // Chains sharing their propagation path with another chain are listed as well, as their errors reach the exit from a call of their own
+    stats.entry_return_ty.clone_from(&graph.entry_return_ty);
+    stats.entry_returns_result = graph.entry_returns_result;
+    stats.exit_chains = new_graph
+        .chains
+        .iter()
+        .filter(|chain| chain.termination == ChainEnd::ReturnedFromMain)
+        .map(|chain| get_chain_summary(graph, chain, &options.score_weights))
+        .collect();
+
+    stats.recursive_count = graph
+        .edges
+        .iter()
+        .filter(|edge| edge.is_recursive && edge.is_error && edge.propagates)
+        .count();
+    stats.ok_discard_count = graph
+        .edges
+        .iter()
+        .filter(|edge| edge.is_error && edge.conversion == Some(FailureConversion::Ok))
+        .count();
+    stats.lost_to_tasks_count = graph
+        .edges
+        .iter()
+        .filter(|edge| {
+            edge.implicit == Some(ImplicitCall::Spawn)
+                && edge.is_error
+                && edge.handling == Handling::Discarded
+                && !edge.suppressed
+        })
+        .count();
+    stats.must_use_discard_count = graph
+        .edges
+        .iter()
+        .filter(|edge| {
+            edge.is_error
+                && edge.must_use
+                && edge.handling == Handling::Discarded
+                && !edge.suppressed
+        })
+        .count();
+    stats.filtered_macro_calls = graph.filtered_macro_calls;
+    stats.aggregated_fns = graph.aggregated_fns;
+    stats.filtered_fns.clone_from(&graph.filtered_fns);
+    stats.discarded_calls.clone_from(&graph.discarded_calls);
+    stats.unreachable_fns = graph.unreachable_fns;
+    stats.opaque_error_fns.clone_from(&graph.opaque_error_fns);
+    let mut error_categories: HashMap<ErrorCategory, usize> = HashMap::new();
+    for edge in &graph.edges {
+        if edge.implicit == Some(ImplicitCall::ProcessExit) {
+            continue;
+        }
+        if let Some(category) = edge.error_category.filter(|_category| edge.is_error) {
+            *error_categories.entry(category).or_default() += 1;
+        }
+    }
+    let mut error_categories: Vec<(ErrorCategory, usize)> = error_categories.into_iter().collect();
+    error_categories.sort_unstable();
+    stats.error_categories = error_categories;
+    stats.infallible_fns = (0..graph.nodes.len())
+        .filter(|node| graph.nodes[*node].infallible)
+        .map(|node| graph.full_path(node))
+        .collect();
+
+    let mut origins: Vec<(usize, usize)> = origin_counts.into_iter().collect();
+    origins.sort_unstable_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then(a.cmp(b)));
+    stats.origins = origins
+        .into_iter()
+        .take(TOP_ORIGINS)
+        .map(|(node, n)| (graph.label(node).to_owned(), n))
+        .collect();
+    stats.functions = functions::get_function_summaries(graph);
+
+    // Only the selected chain is shown if there is one, it is shown in full even if it shares its propagation path with another chain
+    match options.chain {
+        Some(i) if i < new_graph.chains.len() => {
+            new_graph = to_selected_chains(graph, &new_graph, |chain| chain.index == i, options);
+        }
+        Some(i) => {
+            warn!(
+                target: "analyzer::chains",
+                "There is no chain #{i}, as there are {} chains, so all chains are shown.",
+                new_graph.chains.len()
+            );
+            add_chains(graph, &mut new_graph, options);
+        }
+        None => add_chains(graph, &mut new_graph, options),
+    }
+
+    new_graph.stats = stats;
     new_graph
 }
 
-fn get_chain_from_edge(graph: &CallGraph, from: &CallEdge, explored: &mut Vec<usize>, depth: usize) -> (Vec<CallEdge>, usize) {
+/// Attach the number of distinct chains passing through each function to its node in the call graph.
+/// This is the scale of the heatmap coloring the call graph, so the choke points of the chains stand out.
+pub fn attach_chain_counts(graph: &mut CallGraph, chain_graph: &ChainGraph) {
+    for chain in &chain_graph.chains {
+        let mut nodes: Vec<usize> = chain
+            .calls
+            .iter()
+            .flat_map(|call| [call.from, call.to])
+            .collect();
+        nodes.sort_unstable();
+        nodes.dedup();
+        for node in nodes {
+            graph.nodes[node].chain_count += 1;
+        }
+    }
+}
+
+/// Get the number of values per value, starting at one, e.g. `[1, 0, 2]` for `3, 1, 3`.
+fn get_histogram(values: impl Iterator<Item = usize>) -> Vec<usize> {
+    let mut histogram: Vec<usize> = vec![];
+    for value in values.filter(|value| *value > 0) {
+        if histogram.len() < value {
+            histogram.resize(value, 0);
+        }
+        histogram[value - 1] += 1;
+    }
+    histogram
+}
+
+/// Whether the error type of a call in a chain matches one of the error filters.
+/// Both the type returned by a call and the type it is converted into (e.g. by `map_err`) are matched.
+fn matches_error_filters(graph: &CallGraph, calls: &[CallEdge], options: &AnalysisOptions) -> bool {
+    calls
+        .iter()
+        .flat_map(|call| [graph.ty(call), graph.mapped_ty(call)])
+        .flatten()
+        .any(|ty| {
+            options
+                .error_filters
+                .iter()
+                .any(|filter| filter.is_match(ty))
+        })
+}
+
+/// Get a graph of only the chains whose error is returned from the entry point, reaching the program exit.
+pub fn to_exit_chains(
+    graph: &CallGraph,
+    chain_graph: &ChainGraph,
+    options: &AnalysisOptions,
+) -> ChainGraph {
+    to_selected_chains(
+        graph,
+        chain_graph,
+        |chain| chain.termination == ChainEnd::ReturnedFromMain,
+        options,
+    )
+}
+
+/// Get a graph of only the chains of a chain graph that are selected, keeping their numbers.
+fn to_selected_chains(
+    graph: &CallGraph,
+    chain_graph: &ChainGraph,
+    selected: impl Fn(&Chain) -> bool,
+    options: &AnalysisOptions,
+) -> ChainGraph {
+    let mut selected_graph = ChainGraph::new(graph.crate_name.clone());
+    selected_graph.stable_paths = chain_graph.stable_paths.clone();
+    selected_graph.chains = chain_graph
+        .chains
+        .iter()
+        .filter(|chain| selected(chain))
+        .map(|chain| Chain {
+            merged_into: None,
+            ..chain.clone()
+        })
+        .collect();
+
+    // The chains these were merged into may be left out, so they are merged among each other again
+    merge_shared_chains(&mut selected_graph.chains);
+    add_chains(graph, &mut selected_graph, options);
+
+    selected_graph
+}
+
+/// Add the chains of a chain graph to it, the merged chains only add the call where they end to the chain they are merged into.
+fn add_chains(graph: &CallGraph, new_graph: &mut ChainGraph, options: &AnalysisOptions) {
+    let mut node_maps: Vec<HashMap<usize, usize>> = vec![HashMap::new(); new_graph.chains.len()];
+    let (merged, unmerged): (Vec<usize>, Vec<usize>) =
+        (0..new_graph.chains.len()).partition(|i| new_graph.chains[*i].merged_into.is_some());
+    for i in unmerged.into_iter().chain(merged) {
+        let chain = new_graph.chains[i].clone();
+        let Some(end) = chain.calls.last() else {
+            continue;
+        };
+        let (node_map, calls) = match chain.merged_into {
+            Some(j) => (&mut node_maps[j], std::slice::from_ref(end)),
+            None => (&mut node_maps[i], chain.calls.as_slice()),
+        };
+        add_chain_calls(graph, new_graph, &chain, calls, node_map, options);
+        // The errors returned from the entry point end in the program exit
+        let mut terminal = end.from;
+        if let Some(exit_call) = get_exit_call(graph, end.from).filter(|_call| end.propagates) {
+            let exit_call = std::slice::from_ref(exit_call);
+            add_chain_calls(graph, new_graph, &chain, exit_call, node_map, options);
+            terminal = exit_call[0].from;
+        }
+        new_graph.mark_termination(node_map[&terminal], chain.termination);
+        for node in &chain.truncated_at {
+            if let Some(chain_node) = node_map.get(node) {
+                new_graph.mark_truncated(*chain_node);
+            }
+        }
+    }
+
+    // The nodes of merged chains are part of the chain they are merged into
+    for (i, node_map) in node_maps.iter().enumerate() {
+        let index = new_graph.chains[i].index;
+        for node in node_map.values() {
+            new_graph.set_chain(*node, index);
+        }
+    }
+}
+
+/// Get how a chain ends, given the call where it ends.
+fn get_chain_end(graph: &CallGraph, end: &CallEdge) -> ChainEnd {
+    if end.propagates {
+        // The error is propagated to the program exit, or out of an entry point returning nothing (e.g. through a closure it passes on)
+        if get_exit_call(graph, end.from).is_some() || graph.entry_node == Some(end.from) {
+            ChainEnd::ReturnedFromMain
+        } else {
+            ChainEnd::LeavesCrate
+        }
+    } else if !matches!(graph.nodes[end.from].kind, CallNodeKind::LocalFn(..)) {
+        // The error is returned to a function of a followed dependency
+        ChainEnd::LeavesCrate
+    } else {
+        match end.handling {
+            Handling::Handled => ChainEnd::Handled,
+            Handling::LoggedAndDropped => ChainEnd::LoggedAndDropped,
+            Handling::ProcessExit => ChainEnd::ProcessExit,
+            Handling::Unwrapped => ChainEnd::Unwrapped,
+            Handling::Discarded => ChainEnd::Discarded,
+            Handling::Propagated | Handling::Unknown => ChainEnd::Unknown,
+        }
+    }
+}
+
+/// Get the call of a function by the program exit, if it is the entry point of a program returning anything.
+fn get_exit_call(graph: &CallGraph, node: usize) -> Option<&CallEdge> {
+    graph
+        .get_incoming_edges(node)
+        .into_iter()
+        .find(|call| Some(call.from) == graph.exit_node)
+}
+
+/// The identity of a call in the call graph, i.e. its call expression and the nodes it connects.
+type CallIdentity = (CallId, usize, usize);
+
+/// Get the canonical form of a chain, i.e. the sorted identities of its calls.
+fn get_chain_identity(calls: &[CallEdge]) -> Vec<CallIdentity> {
+    let mut identity: Vec<CallIdentity> = calls
+        .iter()
+        .map(|call| (call.call_id, call.from, call.to))
+        .collect();
+    identity.sort_unstable();
+    identity
+}
+
+/// Merge the chains whose propagation path (i.e. the calls besides the one where the chain ends) is part of that of another chain.
+/// Each is merged into the chain with the largest propagation path containing it, or the first of those if there are several.
+fn merge_shared_chains(chains: &mut [Chain]) {
+    let paths: Vec<Vec<CallIdentity>> = chains
+        .iter()
+        .map(|chain| get_chain_identity(&chain.calls[..chain.calls.len() - 1]))
+        .collect();
+
+    for i in 0..chains.len() {
+        // Chains without a propagation path have nothing to share
+        if paths[i].is_empty() {
+            continue;
+        }
+
+        let mut merged_into: Option<usize> = None;
+        for j in 0..chains.len() {
+            let contains = paths[i].iter().all(|call| paths[j].contains(call));
+            // Of chains with the same propagation path, the first is kept
+            if i == j || !contains || (paths[i].len() == paths[j].len() && j > i) {
+                continue;
+            }
+            // Chains whose error loss is not accepted are not hidden in an accepted one
+            if chains[j].suppressed && !chains[i].suppressed {
+                continue;
+            }
+            if merged_into.map_or(true, |k| paths[j].len() > paths[k].len()) {
+                merged_into = Some(j);
+            }
+        }
+        chains[i].merged_into = merged_into;
+    }
+}
+
+/// Add the calls of a chain to the chain graph, `node_map` maps the nodes of the call graph to those already added for the chain.
+fn add_chain_calls(
+    graph: &CallGraph,
+    new_graph: &mut ChainGraph,
+    chain: &Chain,
+    calls: &[CallEdge],
+    node_map: &mut HashMap<usize, usize>,
+    options: &AnalysisOptions,
+) {
+    // The function where the chain ends is labeled with the constructors of its errors
+    let end = chain.calls.last().map(|call| call.from);
+    let label_of = |node: usize| {
+        let mut label = get_node_label(graph, &chain.calls, node, options);
+        if Some(node) == end && !chain.constructors.is_empty() {
+            label.push_str(&format!("\nerrors: {}", chain.constructors.join(", ")));
+        }
+        label
+    };
+
+    for call in calls {
+        // If we've already added the node to the new graph, refer to that, otherwise, add a new node
+        let from = *node_map
+            .entry(call.from)
+            .or_insert_with(|| new_graph.add_node(&label_of(call.from)));
+
+        // Ditto
+        let to = *node_map
+            .entry(call.to)
+            .or_insert_with(|| new_graph.add_node(&label_of(call.to)));
+
+        // Add the edge, noting where the chain loops back and how it ends
+        let mut label = if call.is_recursive {
+            graph.type_label(call).map(|ty| format!("{ty} (recursive)"))
+        } else {
+            graph.type_label(call)
+        };
+        if let Some(handling) = call.handling_label() {
+            label = label.map(|ty| format!("{ty} ({handling})"));
+        }
+        // The error of a closure passed to an iterator adapter comes out of `collect` instead
+        if call.implicit == Some(ImplicitCall::Collect) {
+            label = label.map(|ty| format!("{ty} (via iterator)"));
+        }
+        // The location of the call can be shown as well, to find it in the code
+        if let Some(location) = call.location.as_ref().filter(|_| options.edge_locations) {
+            label = label.map(|ty| format!("{ty}\n@ {location}"));
+        }
+        new_graph.add_edge(from, to, label.as_deref(), call.location.clone());
+
+        for node in [call.from, call.to] {
+            if graph.nodes[node].error_origin {
+                new_graph.mark_error_origin(node_map[&node]);
+            }
+            if graph.nodes[node].choke_point {
+                new_graph.mark_choke_point(node_map[&node]);
+            }
+        }
+    }
+}
+
+/// The maximum number of chained calls followed from the call where a chain ends, if not provided.
+const DEFAULT_MAX_CHAIN_DEPTH: usize = 100;
+
+/// The maximum number of chains extracted, if not provided.
+const DEFAULT_MAX_CHAINS: usize = 10_000;
+
+/// The number of chains listed in the summary of the deepest and largest chains.
+const TOP_CHAINS: usize = 5;
+
+/// Get the summaries of all chains of a chain graph, including those sharing their propagation path with another chain.
+pub fn get_chain_summaries(
+    graph: &CallGraph,
+    chain_graph: &ChainGraph,
+    weights: &ScoreWeights,
+) -> Vec<ChainSummary> {
+    chain_graph
+        .chains
+        .iter()
+        .map(|chain| get_chain_summary(graph, chain, weights))
+        .collect()
+}
+
+/// Convert the chains of a chain graph to JSON, with all calls of each chain from the call where it ends down.
+/// The paths of the functions and the error types are shared by many calls, so they are stored once in a table of strings,
+/// and each call refers to them by their index in it. The attributes of the functions of the calls (e.g. whether they are async) are listed once as well.
+pub fn to_chains_json(graph: &CallGraph, chain_graph: &ChainGraph) -> String {
+    let mut strings = Interner::default();
+    let mut functions: BTreeSet<usize> = BTreeSet::new();
+    let chains: Vec<serde_json::Value> = chain_graph
+        .chains
+        .iter()
+        .map(|chain| {
+            let end = chain.calls.last().expect("Chains end in a call");
+            let hops: Vec<serde_json::Value> = get_call_depths(end, &chain.calls)
+                .into_iter()
+                .map(|(depth, call)| {
+                    functions.extend([call.from, call.to]);
+                    serde_json::json!({
+                        "depth": depth,
+                        "caller": strings.intern(&graph.full_path(call.from)),
+                        "callee": strings.intern(&graph.full_path(call.to)),
+                        "location": call.location,
+                        "type": graph.ty(call).map(|ty| strings.intern(ty)),
+                        "type_approximate": call.type_approximate,
+                        "mapped_type": graph.mapped_ty(call).map(|ty| strings.intern(ty)),
+                        "propagates": call.propagates,
+                        "handling": call.handling_label(),
+                    })
+                })
+                .collect();
+
+            serde_json::json!({
+                "index": chain.index,
+                "termination": chain.termination.label(),
+                "depth": chain.depth,
+                "size": chain.calls.len(),
+                "merged_into": chain.merged_into.map(|i| chain_graph.chains[i].index),
+                "truncated": !chain.truncated_at.is_empty(),
+                "suppressed": chain.suppressed,
+                "constructors": chain.constructors,
+                "hops": hops,
+            })
+        })
+        .collect();
+
+    let functions: Vec<serde_json::Value> = functions
+        .into_iter()
+        .map(|node| {
+            let function = &graph.nodes[node];
+            serde_json::json!({
+                "path": strings.intern(&graph.full_path(node)),
+                "async": function.is_async,
+                "unsafe": function.is_unsafe,
+                "visibility": function.visibility.map(Visibility::label),
+                "test": function.is_test,
+            })
+        })
+        .collect();
+
+    serde_json::to_string_pretty(&serde_json::json!({
+        "strings": strings,
+        "functions": functions,
+        "chains": chains,
+    }))
+    .unwrap()
+}
+
+/// Get the endpoints and measures of a chain.
+/// Its tail is the function where its errors originate, at the end of its longest path if there are several.
+fn get_chain_summary(graph: &CallGraph, chain: &Chain, weights: &ScoreWeights) -> ChainSummary {
+    let path_of = |node: usize| graph.full_path(node);
+    let end = chain.calls.last().expect("Chains end in a call");
+
+    let mut functions = vec![path_of(end.from)];
+    for (_depth, call) in get_call_depths(end, &chain.calls) {
+        let function = path_of(call.to);
+        if !functions.contains(&function) {
+            functions.push(function);
+        }
+    }
+
+    let path = get_chain_path(graph, chain);
+    let origin_call = path.last().copied().unwrap_or(end);
+    let hops = path
+        .iter()
+        .map(|call| ChainHop {
+            function: path_of(call.to),
+            ty: graph.type_label(call),
+            location: call.location.clone(),
+        })
+        .collect();
+
+    let mut summary = ChainSummary {
+        index: chain.index,
+        head: path_of(end.from),
+        tail: path_of(origin_call.to),
+        functions,
+        depth: chain.depth,
+        size: chain.calls.len(),
+        termination: chain.termination,
+        location: origin_call.location.clone(),
+        hops,
+        end_location: end.location.clone(),
+        lost_in_task: end.implicit == Some(ImplicitCall::Spawn)
+            && end.handling == Handling::Discarded,
+        suppressed: chain.suppressed,
+        type_erased: path.iter().any(|call| call.type_erased),
+        downcasts: get_chain_downcasts(graph, &path),
+        must_use: end.must_use && end.handling == Handling::Discarded,
+        score: 0.0,
+    };
+    summary.score = scoring::score(&summary, weights);
+    summary
+}
+
+/// Get the types the functions of a chain path downcast the type-erased errors they receive to, i.e. the concrete types they regain.
+fn get_chain_downcasts(graph: &CallGraph, path: &[&CallEdge]) -> Vec<String> {
+    let mut downcasts: Vec<String> = vec![];
+    for call in path {
+        for downcast in &graph.nodes[call.from].downcasts {
+            if is_downcast_of(downcast, call) && !downcasts.contains(&downcast.ty) {
+                downcasts.push(downcast.ty.clone());
+            }
+        }
+    }
+    downcasts
+}
+
+/// Whether a downcast can be of the error returned by a call, i.e. the error is of the same kind of type-erased error.
+/// A `dyn Error` is only matched with the downcasts of a `dyn Error`, and e.g. an `anyhow::Error` with those of an `anyhow::Error`.
+fn is_downcast_of(downcast: &Downcast, call: &CallEdge) -> bool {
+    match downcast.family {
+        Some(family) => call.error_family == Some(family),
+        None => call.type_erased,
+    }
+}
+
+/// Get the calls of the path from the call where a chain ends down to the call to the function where its error originates.
+/// Calls to the functions constructing errors are preferred, otherwise the error is passed on from outside of the chain.
+pub fn get_chain_path<'a>(graph: &CallGraph, chain: &'a Chain) -> Vec<&'a CallEdge> {
+    let end = chain.calls.last().expect("Chains end in a call");
+    let depths = get_call_depths(end, &chain.calls);
+
+    let origin_call = depths
+        .iter()
+        .filter(|(_depth, call)| graph.nodes[call.to].error_origin)
+        .max_by_key(|(depth, _call)| *depth)
+        .or_else(|| depths.iter().max_by_key(|(depth, _call)| *depth))
+        .copied()
+        .unwrap_or((0, end));
+
+    // Walk back from the call to the origin to the end of the chain, through the calls one level closer each
+    let mut path = vec![origin_call];
+    while let Some((depth, call)) = path.last().copied().filter(|(depth, _call)| *depth > 0) {
+        let Some(parent) = depths
+            .iter()
+            .find(|(other_depth, other)| *other_depth + 1 == depth && other.to == call.from)
+        else {
+            break;
+        };
+        path.push(*parent);
+    }
+
+    path.into_iter().rev().map(|(_depth, call)| call).collect()
+}
+
+/// The number of functions listed in the summary of where errors originate.
+const TOP_ORIGINS: usize = 5;
+
+/// Get the functions where the errors of a chain originate.
+/// Chains are anchored at local functions constructing errors when there are any, followed dependencies are only used otherwise.
+fn get_chain_origins(graph: &CallGraph, calls: &[CallEdge]) -> Vec<usize> {
+    let mut origins: Vec<usize> = vec![];
+    for call in calls {
+        for node in [call.from, call.to] {
+            if graph.nodes[node].error_origin && !origins.contains(&node) {
+                origins.push(node);
+            }
+        }
+    }
+
+    let local: Vec<usize> = origins
+        .iter()
+        .copied()
+        .filter(|node| matches!(graph.nodes[*node].kind, CallNodeKind::LocalFn(..)))
+        .collect();
+    if local.is_empty() {
+        origins
+    } else {
+        local
+    }
+}
+
+/// Get the expressions constructing the errors of a chain, i.e. those of the functions where its errors originate.
+/// Errors converted using `From` (e.g. by the try operator) are attributed to the conversion, as it constructs the new error.
+fn get_chain_constructors(graph: &CallGraph, calls: &[CallEdge], origins: &[usize]) -> Vec<String> {
+    let mut constructors: Vec<String> = origins
+        .iter()
+        .flat_map(|origin| graph.nodes[*origin].error_constructors.iter().cloned())
+        .collect();
+    for call in calls {
+        if call.implicit == Some(ImplicitCall::From) {
+            constructors.push(graph.label(call.to).to_owned());
+        }
+    }
+
+    constructors.sort_unstable();
+    constructors.dedup();
+    constructors
+}
+
+/// Get the depth of each call in a chain, i.e. the number of calls between it and the start of the chain.
+/// The calls are grouped by their caller first, so large chains (e.g. of wide fan-outs) take time linear in their size.
+fn get_call_depths<'a>(start: &'a CallEdge, calls: &'a [CallEdge]) -> Vec<(usize, &'a CallEdge)> {
+    let mut depths: Vec<(usize, &CallEdge)> = vec![(0, start)];
+    let mut explored: HashSet<CallIdentity> =
+        HashSet::from([(start.call_id, start.from, start.to)]);
+
+    let mut by_caller: HashMap<usize, Vec<&CallEdge>> = HashMap::new();
+    for call in calls {
+        by_caller.entry(call.from).or_default().push(call);
+    }
+
+    let mut i = 0;
+    while i < depths.len() {
+        let (depth, edge) = depths[i];
+        for call in by_caller.get(&edge.to).into_iter().flatten() {
+            if explored.insert((call.call_id, call.from, call.to)) {
+                depths.push((depth + 1, call));
+            }
+        }
+        i += 1;
+    }
+
+    depths
+}
+
+/// Get the distinct error types in a chain, in the order the error is converted into them.
+/// Types that reach closer to the start of the chain come later, e.g. `std::io::Error → ConfigError → anyhow::Error`.
+fn get_chain_types(graph: &CallGraph, depths: &[(usize, &CallEdge)]) -> Vec<String> {
+    // The smallest depth at which each type occurs, in order of first occurrence
+    let mut types: Vec<(usize, &str)> = vec![];
+    for (depth, call) in depths {
+        let mut call_types = vec![(*depth, graph.ty(call))];
+        // The converted error is returned by the caller, one call closer to the start
+        call_types.push((depth.saturating_sub(1), graph.mapped_ty(call)));
+
+        for (depth, ty) in call_types {
+            let Some(ty) = ty else {
+                continue;
+            };
+            if let Some(existing) = types.iter_mut().find(|(_depth, other)| *other == ty) {
+                existing.0 = existing.0.min(depth);
+            } else {
+                types.push((depth, ty));
+            }
+        }
+    }
+
+    // Stable, so types at the same depth keep their order
+    types.sort_by(|(a, _a), (b, _b)| b.cmp(a));
+    types
+        .into_iter()
+        .map(|(_depth, ty)| ty.to_owned())
+        .collect()
+}
+
+/// Get the calls returning a different error type than their caller returns, without the caller converting it.
+/// These are usually a sign of a missed conversion. Returns the caller, called function and both error types.
+fn get_unexplained_transitions(
+    graph: &CallGraph,
+    depths: &[(usize, &CallEdge)],
+    calls: &[CallEdge],
+) -> Vec<(usize, usize, String, String)> {
     let mut res = vec![];
-    let mut max_depth = depth;
 
-    explored.push(from.to);
+    for (depth, call) in depths {
+        // The start of the chain has no caller in the chain
+        if *depth == 0 {
+            continue;
+        }
+        let Some((_depth, parent)) = depths.iter().find(|(_depth, other)| other.to == call.from)
+        else {
+            continue;
+        };
+        let (Some(to_ty), Some(from_ty)) = (parent.ty, call.mapped_ty.or(call.ty)) else {
+            continue;
+        };
+
+        // The error is converted using `From` by the try operator, or by adding context to it
+        let converted = call.implicit == Some(ImplicitCall::From)
+            || call.context_message.is_some()
+            || calls
+                .iter()
+                .any(|other| other.from == call.from && other.implicit == Some(ImplicitCall::From));
+        if from_ty != to_ty && !converted {
+            res.push((
+                call.from,
+                call.to,
+                graph.strings.resolve(from_ty).to_owned(),
+                graph.strings.resolve(to_ty).to_owned(),
+            ));
+        }
+    }
+
+    res
+}
+
+/// Get the label of a node in a chain, optionally followed by the error types it receives and returns within the chain.
+fn get_node_label(
+    graph: &CallGraph,
+    calls: &[CallEdge],
+    node: usize,
+    options: &AnalysisOptions,
+) -> String {
+    let label = graph.label(node).to_owned();
+    if !options.chain_types {
+        return label;
+    }
+
+    let mut incoming: Vec<&str> = vec![];
+    let mut outgoing: Vec<&str> = vec![];
+    for call in calls {
+        if let Some(ty) = graph.mapped_ty(call).or(graph.ty(call)) {
+            if call.from == node && !incoming.contains(&ty) {
+                incoming.push(ty);
+            }
+        }
+        if let Some(ty) = graph.ty(call) {
+            if call.to == node && !outgoing.contains(&ty) {
+                outgoing.push(ty);
+            }
+        }
+    }
+
+    // The concrete types regained from type-erased errors
+    let downcasts: Vec<&str> = graph.nodes[node]
+        .downcasts
+        .iter()
+        .filter(|downcast| {
+            calls
+                .iter()
+                .any(|call| call.from == node && is_downcast_of(downcast, call))
+        })
+        .map(|downcast| downcast.ty.as_str())
+        .collect();
+    let label = if downcasts.is_empty() {
+        label
+    } else {
+        format!("{label}\ndowncasts to {}", downcasts.join(", "))
+    };
+
+    match (incoming.is_empty(), outgoing.is_empty()) {
+        (true, true) => label,
+        (true, false) => format!("{label}\nout: {}", outgoing.join(", ")),
+        (false, true) => format!("{label}\nin: {}", incoming.join(", ")),
+        (false, false) => format!(
+            "{label}\nin: {}\nout: {}",
+            incoming.join(", "),
+            outgoing.join(", ")
+        ),
+    }
+}
+
+/// The calls below a node that is fully explored, and the number of chained calls of its longest path.
+type Subchain = (Vec<CallEdge>, usize);
+
+/// Get the propagating error calls below the node called by the provided edge, and the number of chained calls of its longest path.
+/// Cycles are prevented per path (`path` holds the nodes on the current path), so nodes shared by sibling paths are explored for each path,
+/// keeping their depth correct. Fully explored nodes are remembered in `explored`, so each is only explored once.
+/// Also returns whether the calls were cut short by a node on the current path, in which case they are not remembered.
+/// Paths are not followed past `depth_limit` chained calls (including the provided edge), the nodes where they are truncated are returned last.
+fn get_chain_from_edge(
+    graph: &CallGraph,
+    from: &CallEdge,
+    path: &mut Vec<usize>,
+    explored: &mut HashMap<usize, Subchain>,
+    depth_limit: usize,
+    exclude_option_chains: bool,
+) -> (Vec<CallEdge>, usize, bool, Vec<usize>) {
+    // The number of chained calls up to and including the provided edge
+    let depth_so_far = path.len() + 1;
+    if let Some((calls, depth)) = explored.get(&from.to) {
+        // A remembered subchain can only be reused if it fits within the limit here
+        if depth_so_far + depth <= depth_limit {
+            return (calls.clone(), *depth, false, vec![]);
+        }
+    }
+
+    let mut res: Vec<CallEdge> = vec![];
+    let mut max_depth = 0;
+    let mut cut = false;
+    let mut truncated_at: Vec<usize> = vec![];
+
+    path.push(from.to);
 
     // Add all outgoing propagating error edges from the 'to' node to the list
-    // And do the same once for each node this edge calls to
+    // And do the same for each node this edge calls to, unless that closes a cycle
     for edge in graph.get_outgoing_edges(from.to) {
-        if edge.is_error && edge.propagates {
-            if !explored.contains(&edge.to) && !res.contains(edge) && edge != from {
-                // If we haven't had this edge yet, explore the node
-                res.push(edge.clone());
-
-                let (chain, d) = get_chain_from_edge(graph, edge, explored, depth + 1);
-                if d > max_depth {
-                    max_depth = d;
-                }
-                res.extend(chain);
-            } else {
-                // Otherwise just add the edge
-                res.push(edge.clone());
+        if !edge.is_error
+            || !edge.propagates
+            || (exclude_option_chains && edge.failure_kind == FailureKind::Option)
+        {
+            continue;
+        }
+        if depth_so_far >= depth_limit {
+            // Propagating further would exceed the maximum depth
+            if !truncated_at.contains(&from.to) {
+                truncated_at.push(from.to);
+            }
+            continue;
+        }
+
+        if !res.contains(edge) {
+            res.push(edge.clone());
+        }
+        max_depth = max_depth.max(1);
+
+        if edge.is_recursive {
+            // Stop at the edge closing the cycle, it is annotated when added to the chain graph
+            continue;
+        }
+        if path.contains(&edge.to) {
+            // A cycle that is not marked as recursive, as it was entered elsewhere while creating the call graph
+            cut = true;
+            continue;
+        }
+
+        let (calls, depth, calls_cut, calls_truncated_at) = get_chain_from_edge(
+            graph,
+            edge,
+            path,
+            explored,
+            depth_limit,
+            exclude_option_chains,
+        );
+        max_depth = max_depth.max(depth + 1);
+        cut |= calls_cut;
+        for node in calls_truncated_at {
+            if !truncated_at.contains(&node) {
+                truncated_at.push(node);
             }
         }
+        for call in calls {
+            if !res.contains(&call) {
+                res.push(call);
+            }
+        }
+    }
+
+    path.pop();
+
+    // Truncated calls depend on where the node is reached, so these are not remembered either
+    if !cut && truncated_at.is_empty() {
+        explored.insert(from.to, (res.clone(), max_depth));
+    }
+
+    (res, max_depth, cut, truncated_at)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::tests::{add_call, add_function, graph};
+
+    /// The errors of `fetch` reach `both` through `short_path` and through the longer `long_path`,
+    /// which both call `lookup`, like the `diamond_chains` fixture. `main` handles the errors of `both`.
+    fn diamond() -> CallGraph {
+        let mut graph = graph(&[
+            "main",
+            "both",
+            "short_path",
+            "long_path",
+            "validated",
+            "lookup",
+            "fetch",
+        ]);
+        let handled = add_call(&mut graph, "main", "both", true, false);
+        graph.edges[handled].handling = Handling::Handled;
+        // The short path is explored first, so `lookup` is visited before the long path reaches it
+        add_call(&mut graph, "both", "short_path", true, true);
+        add_call(&mut graph, "both", "long_path", true, true);
+        add_call(&mut graph, "short_path", "lookup", true, true);
+        add_call(&mut graph, "long_path", "validated", true, true);
+        add_call(&mut graph, "validated", "lookup", true, true);
+        add_call(&mut graph, "lookup", "fetch", true, true);
+        graph
+    }
+
+    /// Extract the chains of a graph with the default options.
+    fn chains(graph: &CallGraph) -> ChainGraph {
+        to_chains(graph, &AnalysisOptions::default(), &mut Progress::new(true))
+    }
+
+    /// Get the calls of a chain as `caller -> called`.
+    fn calls(graph: &CallGraph, chain: &Chain) -> Vec<String> {
+        chain
+            .calls
+            .iter()
+            .map(|call| format!("{} -> {}", graph.label(call.from), graph.label(call.to)))
+            .collect()
     }
 
-    (res, max_depth)
+    #[test]
+    fn diamond_is_one_chain_with_its_shared_suffix_once() {
+        let graph = diamond();
+        let chain_graph = chains(&graph);
+        assert_eq!(chain_graph.stats.count, 1);
+        assert_eq!(chain_graph.stats.raw_count, 1);
+
+        let chain = &chain_graph.chains[0];
+        assert_eq!(chain.termination, ChainEnd::Handled);
+        let calls = calls(&graph, chain);
+        assert_eq!(calls.len(), 7);
+        assert_eq!(calls.last().unwrap(), "main -> both");
+        assert_eq!(
+            calls
+                .iter()
+                .filter(|call| *call == "lookup -> fetch")
+                .count(),
+            1
+        );
+        assert_eq!(chain_graph.stats.max_size, 7);
+    }
+
+    #[test]
+    fn diamond_depth_is_its_longest_path() {
+        // main -> both -> long_path -> validated -> lookup -> fetch, although `lookup` is reached through the short path first
+        let chain_graph = chains(&diamond());
+        assert_eq!(chain_graph.chains[0].depth, 5);
+        assert_eq!(chain_graph.stats.max_depth, 5);
+        assert_eq!(chain_graph.stats.depth_histogram, [0, 0, 0, 0, 1]);
+    }
+
+    #[test]
+    fn chains_reaching_a_shared_path_are_merged() {
+        let mut graph = diamond();
+        // `main` calls `both` a second time, discarding its errors, and `cached` handles the errors of `lookup`
+        add_function(&mut graph, "cached");
+        let discarded = add_call(&mut graph, "main", "both", true, false);
+        graph.edges[discarded].handling = Handling::Discarded;
+        let cached = add_call(&mut graph, "cached", "lookup", true, false);
+        graph.edges[cached].handling = Handling::Handled;
+
+        let chain_graph = chains(&graph);
+        assert_eq!(chain_graph.chains.len(), 3);
+        assert_eq!(chain_graph.stats.raw_count, 3);
+        // Both start from the propagation path of the first chain, so they are counted as part of it
+        assert_eq!(chain_graph.stats.count, 1);
+        assert_eq!(chain_graph.stats.merged_count, 2);
+        let merged: Vec<(Option<usize>, ChainEnd)> = chain_graph
+            .chains
+            .iter()
+            .map(|chain| (chain.merged_into, chain.termination))
+            .collect();
+        assert_eq!(
+            merged,
+            [
+                (None, ChainEnd::Handled),
+                (Some(0), ChainEnd::Discarded),
+                (Some(0), ChainEnd::Handled)
+            ]
+        );
+        // Their endings are counted apart still
+        assert_eq!(
+            chain_graph.stats.endings,
+            [(ChainEnd::Handled, 2), (ChainEnd::Discarded, 1)]
+        );
+    }
+
+    #[test]
+    fn chains_with_overlapping_paths_are_kept_apart() {
+        let mut graph = diamond();
+        // `report` unwraps the errors `audit` propagates from `validated` and `check`,
+        // so its path overlaps with that of the first chain, but is not part of it
+        add_function(&mut graph, "report");
+        add_function(&mut graph, "audit");
+        add_function(&mut graph, "check");
+        let report = add_call(&mut graph, "report", "audit", true, false);
+        graph.edges[report].handling = Handling::Unwrapped;
+        add_call(&mut graph, "audit", "validated", true, true);
+        add_call(&mut graph, "audit", "check", true, true);
+
+        let chain_graph = chains(&graph);
+        assert_eq!(chain_graph.chains.len(), 2);
+        assert_eq!(chain_graph.stats.count, 2);
+        assert_eq!(chain_graph.stats.merged_count, 0);
+        let report_chain = &chain_graph.chains[1];
+        assert_eq!(report_chain.termination, ChainEnd::Unwrapped);
+        assert_eq!(report_chain.depth, 4);
+        let mut calls = calls(&graph, report_chain);
+        assert_eq!(calls.pop().unwrap(), "report -> audit");
+        calls.sort();
+        assert_eq!(
+            calls,
+            [
+                "audit -> check",
+                "audit -> validated",
+                "lookup -> fetch",
+                "validated -> lookup"
+            ]
+        );
+    }
+
+    #[test]
+    fn identical_chains_are_kept_once() {
+        let mut graph = diamond();
+        // A second edge for the same call, as added for calls with several possible targets
+        let mut duplicate = graph.edges[0].clone();
+        duplicate.handling = Handling::Handled;
+        graph.add_edge(duplicate);
+
+        let chain_graph = chains(&graph);
+        assert_eq!(chain_graph.stats.raw_count, 2);
+        assert_eq!(chain_graph.chains.len(), 1);
+        assert_eq!(chain_graph.stats.count, 1);
+    }
 }
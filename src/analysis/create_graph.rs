@@ -1,142 +1,1101 @@
-use crate::graph::{CallEdge, CallGraph, CallNodeKind};
-use rustc_hir::def::{DefKind, Res};
-use rustc_hir::def_id::{DefId, LOCAL_CRATE};
+use super::filter::{FilterDecision, GraphFilter};
+use super::mir_calls::MirCallMaps;
+use super::panics::is_in_panic_boundary;
+use super::types::{get_try_residual, is_type_erased};
+use super::AnalysisOptions;
+use crate::graph::{
+    CallEdge, CallGraph, CallId, CallNodeKind, FailureConversion, Handling, ImplicitCall,
+    Visibility,
+};
+use crate::progress::Progress;
+use rustc_ast::LitKind;
+use rustc_data_structures::fx::{FxHashMap, FxHashSet};
+use rustc_hir::def::{CtorKind, CtorOf, DefKind, Res};
+use rustc_hir::def_id::{DefId, LocalDefId, LOCAL_CRATE};
+use rustc_hir::intravisit::{walk_expr, walk_stmt, Visitor};
 use rustc_hir::{
-    Block, Expr, ExprKind, HirId, ImplItemKind, Item, ItemKind, MatchSource, Pat, PatKind, QPath,
-    StmtKind, TyKind,
+    Arm, ArrayLen, Block, BlockCheckMode, BodyId, Expr, ExprKind, GenericArg, HirId, ImplItemKind,
+    ItemId, ItemKind, MatchSource, Pat, PatKind, PathSegment, QPath, Stmt, StmtKind, TraitFn,
+    TraitItemKind, UnsafeSource, Unsafety,
+};
+use rustc_middle::hir::nested_filter;
+use rustc_middle::middle::codegen_fn_attrs::CodegenFnAttrFlags;
+use rustc_middle::mir::{Body, Local, TerminatorKind, RETURN_PLACE};
+use rustc_middle::ty::adjustment::Adjust;
+use rustc_middle::ty::{
+    self, GenericArgsRef, Instance, InstanceDef, TyCtxt, TypeVisitableExt, TypeckResults,
 };
-use rustc_middle::mir::TerminatorKind;
-use rustc_middle::ty::TyCtxt;
+use rustc_span::{sym, ExpnKind, MacroKind, Span, DUMMY_SP};
 
-/// Create a call graph starting from the provided root node.
-pub fn create_call_graph_from_root(context: TyCtxt, item: &Item) -> CallGraph {
-    let mut graph = CallGraph::new(context.crate_name(LOCAL_CRATE).to_ident_string());
+/// A function call found within the body of a function.
+struct FunctionCall {
+    /// The kind of node that is called.
+    node_kind: CallNodeKind,
+    /// The id of the call.
+    call_id: CallId,
+    /// Whether an edge should be added for this call (e.g. not for closure definitions).
+    add_edge: bool,
+    /// Whether the result of this call is propagated by the caller.
+    propagates: bool,
+    /// Whether this call is evaluated at compile-time (e.g. in a const initializer or array length).
+    const_evaluated: bool,
+    /// Whether this call is made from within an unsafe block.
+    is_unsafe_call: bool,
+    /// Whether this call closes a cycle (e.g. a function calling itself).
+    is_recursive: bool,
+    /// Whether the result of this call must be used beyond being a `Result`.
+    must_use: bool,
+    /// Whether the result of this call is propagated to an enclosing try block, instead of out of the caller.
+    propagates_locally: bool,
+    /// Whether the result of this call is an iterator consumed by the method called on it, rather than passed on.
+    consumed: bool,
+    /// The id of the `map_err` call converting the error of this call, if any.
+    map_err_id: Option<HirId>,
+    /// How the caller deals with the result of this call (e.g. destructuring it in a let-else statement).
+    handling: Handling,
+    /// The message of the `expect` call unwrapping the result of this call, if it is a string literal.
+    expect_message: Option<String>,
+    /// The kind of implicit call this is, if it is not written as a call (e.g. an overloaded operator).
+    implicit: Option<ImplicitCall>,
+    /// The concrete error type that is boxed into a `dyn Error` by this (`From`) call, if any.
+    origin_ty: Option<String>,
+    /// The message of the context added to the error of this call (e.g. using `anyhow::Context`), if it is a string literal.
+    context_message: Option<String>,
+    /// How the caller converts between a `Result` and an `Option` (e.g. using `ok`), if it does.
+    conversion: Option<FailureConversion>,
+}
 
-    // Access the function
-    if let ItemKind::Fn(_sig, _gen, id) = item.kind {
-        // Create a node for the function
-        let node = CallNodeKind::local_fn(item.hir_id().owner.to_def_id(), item.hir_id());
-        let node_id = graph.add_node(&context.def_path_str(node.def_id()), node);
+impl FunctionCall {
+    /// Create a new, non-propagating, function call.
+    fn new(node_kind: CallNodeKind, call_id: CallId, add_edge: bool) -> Self {
+        FunctionCall {
+            node_kind,
+            call_id,
+            add_edge,
+            propagates: false,
+            propagates_locally: false,
+            consumed: false,
+            const_evaluated: false,
+            is_unsafe_call: false,
+            is_recursive: false,
+            must_use: false,
+            map_err_id: None,
+            handling: Handling::Unknown,
+            expect_message: None,
+            implicit: None,
+            origin_ty: None,
+            context_message: None,
+            conversion: None,
+        }
+    }
 
-        // Add edges/nodes for all functions called from within this function (and recursively do it for those functions as well)
-        graph = add_calls_from_function(context, node_id, id.hir_id, graph);
+    /// Whether the result of this call can be propagated by the caller.
+    /// Compile-time evaluated calls, consumed calls and calls whose error is handled or unwrapped never propagate.
+    fn can_propagate(&self) -> bool {
+        !self.const_evaluated && !self.consumed && self.handling == Handling::Unknown
     }
+}
+
+/// The state of building a call graph, passed along while exploring the functions of the crate.
+struct GraphBuilder<'a, 'tcx> {
+    context: TyCtxt<'tcx>,
+    options: &'a AnalysisOptions,
+    /// The calls in the MIR of the functions, to resolve calls the type check results do not resolve.
+    mir_calls: &'a MirCallMaps<'tcx>,
+    /// The nodes that are still being explored, calls to these close a cycle.
+    in_progress: Vec<usize>,
+    /// The local functions whose bodies have been explored, each body is only walked once however many roots or calls reach it.
+    explored: FxHashSet<DefId>,
+    /// The filter consulted before adding a function to the graph.
+    filter: GraphFilter,
+    /// The decision of the filter per function, so it is consulted once per function.
+    decisions: FxHashMap<DefId, FilterDecision>,
+    /// The functions left out of the graph whose bodies are being explored, calls to these close a cycle.
+    skipping: Vec<DefId>,
+    /// The progress of building the graph, updated as functions are explored.
+    progress: &'a mut Progress,
+}
+
+/// Create a call graph starting from the provided root function (e.g. `main`).
+pub fn create_call_graph_from_root<'tcx>(
+    context: TyCtxt<'tcx>,
+    options: &AnalysisOptions,
+    mir_calls: &MirCallMaps<'tcx>,
+    root: LocalDefId,
+    progress: &mut Progress,
+) -> CallGraph {
+    let mut graph = CallGraph::new(context.crate_name(LOCAL_CRATE).to_ident_string());
+    let mut builder = GraphBuilder::new(context, options, mir_calls, progress);
+
+    // Create a node for the function
+    let def_id = root.to_def_id();
+    let hir_id = context.local_def_id_to_hir_id(root);
+    let node = CallNodeKind::local_fn(def_id, hir_id);
+    let node_id = add_fn_node(context, &mut graph, &context.def_path_str(def_id), node);
+    graph.entry_node = Some(node_id);
+
+    // An entry point run in an async runtime (e.g. using `#[tokio::main]`) only sets up the runtime,
+    // so the body written by the user is explored in its place, with its calls made by the entry point
+    let body_id = context.hir().body_owned_by(root);
+    let (body_def_id, body_hir_id) = match get_wrapped_entry_body(context, body_id) {
+        Some(body) => {
+            builder.explored.insert(def_id);
+            body
+        }
+        None => (def_id, body_id.hir_id),
+    };
+
+    // Add edges/nodes for all functions called from within this function (and recursively do it for those functions as well)
+    builder.explore_function(&mut graph, node_id, body_def_id, body_hir_id);
 
     graph
 }
 
-/// Retrieve all function calls within a function, and add the nodes and edges to the graph.
-fn add_calls_from_function(
-    context: TyCtxt,
-    from_node: usize,
-    fn_id: HirId,
-    mut graph: CallGraph,
+/// Add the exit of the program as the caller of the entry point, receiving what it returns (e.g. the error of its `Result`, which the runtime prints).
+/// The chains whose error is returned from the entry point reach the program exit.
+pub fn add_exit_node(graph: &mut CallGraph) {
+    let Some(entry) = graph.entry_node else {
+        return;
+    };
+    let CallNodeKind::LocalFn(_def_id, hir_id) = graph.nodes[entry].kind else {
+        return;
+    };
+
+    let exit = graph.add_node("process exit", CallNodeKind::ProcessExit);
+    let mut edge = CallEdge::new(exit, entry, CallId::Hir(hir_id), false);
+    edge.implicit = Some(ImplicitCall::ProcessExit);
+    graph.add_edge(edge);
+    graph.exit_node = Some(exit);
+}
+
+/// The crates of the attribute macros running an async entry point in a runtime: `#[tokio::main]`, `#[async_std::main]` and `#[actix_web::main]`.
+const ENTRY_MACRO_CRATES: [&str; 3] = ["tokio_macros", "async_attributes", "actix_macros"];
+
+/// Get the body written by the user of an entry point wrapped by an attribute macro running it in an async runtime (e.g. `#[tokio::main]`).
+/// The macro moves the body into an async block (or an inner `async fn main`) that it runs on the runtime it sets up.
+/// Returns the closure of the async block or the inner function, or `None` if the entry point is not wrapped.
+fn get_wrapped_entry_body(context: TyCtxt, body_id: BodyId) -> Option<(DefId, HirId)> {
+    struct WrapperFinder<'tcx> {
+        context: TyCtxt<'tcx>,
+        wrapped: bool,
+        closures: Vec<(DefId, HirId)>,
+        inner_main: Option<(DefId, HirId)>,
+    }
+
+    impl<'tcx> Visitor<'tcx> for WrapperFinder<'tcx> {
+        fn visit_expr(&mut self, expr: &'tcx Expr<'tcx>) {
+            let expn_data = expr.span.ctxt().outer_expn_data();
+            if matches!(expn_data.kind, ExpnKind::Macro(MacroKind::Attr, _name))
+                && expn_data.macro_def_id.is_some_and(|def_id| {
+                    ENTRY_MACRO_CRATES.contains(&self.context.crate_name(def_id.krate).as_str())
+                })
+            {
+                self.wrapped = true;
+            }
+            // The bodies of closures are not walked, so only the closures of the entry point itself are found
+            if let ExprKind::Closure(closure) = expr.kind {
+                self.closures
+                    .push((closure.def_id.to_def_id(), expr.hir_id));
+            }
+            walk_expr(self, expr);
+        }
+
+        fn visit_stmt(&mut self, stmt: &'tcx Stmt<'tcx>) {
+            if let StmtKind::Item(item_id) = stmt.kind {
+                let item = self.context.hir().item(item_id);
+                if matches!(item.kind, ItemKind::Fn(..)) && item.ident.name == sym::main {
+                    self.inner_main = Some((item.owner_id.to_def_id(), item.hir_id()));
+                }
+            }
+            walk_stmt(self, stmt);
+        }
+    }
+
+    let mut finder = WrapperFinder {
+        context,
+        wrapped: false,
+        closures: vec![],
+        inner_main: None,
+    };
+    finder.visit_body(context.hir().body(body_id));
+    if !finder.wrapped {
+        return None;
+    }
+
+    // `#[async_std::main]` keeps the entry point as an inner function, the others move its body into an async block
+    finder.inner_main.or(match finder.closures[..] {
+        [closure] => Some(closure),
+        _ => None,
+    })
+}
+
+/// Create a call graph of a library, with every function that can be called from outside of the crate as a root.
+pub fn create_call_graph_from_public_api<'tcx>(
+    context: TyCtxt<'tcx>,
+    options: &AnalysisOptions,
+    mir_calls: &MirCallMaps<'tcx>,
+    progress: &mut Progress,
 ) -> CallGraph {
-    let node = context.hir_node(fn_id);
+    let mut graph = CallGraph::new(context.crate_name(LOCAL_CRATE).to_ident_string());
+    let mut builder = GraphBuilder::new(context, options, mir_calls, progress);
+
+    let visibilities = context.effective_visibilities(());
+    for local_id in context.hir().body_owners() {
+        if !matches!(context.def_kind(local_id), DefKind::Fn | DefKind::AssocFn)
+            || !visibilities.is_exported(local_id)
+        {
+            continue;
+        }
+        let def_id = local_id.to_def_id();
+        if builder.explored.contains(&def_id) {
+            // Already explored from another public function
+            continue;
+        }
+        if builder.filter(&mut graph, def_id) != FilterDecision::Keep {
+            // Functions left out of the graph have no node to attribute their calls to
+            continue;
+        }
+        let hir_id = context.local_def_id_to_hir_id(local_id);
+        let node = CallNodeKind::local_fn(def_id, hir_id);
+        let node_id = add_fn_node(context, &mut graph, &context.def_path_str(def_id), node);
+
+        builder.explore_function(&mut graph, node_id, def_id, hir_id);
+    }
+
+    graph
+}
+
+/// Add every local function that is not yet in the graph as an additional root (e.g. functions only called through trait objects or from tests).
+/// The number of these functions is stored in the graph, as they are unreachable from the entry point.
+pub fn add_unreachable_functions<'tcx>(
+    context: TyCtxt<'tcx>,
+    options: &AnalysisOptions,
+    mir_calls: &MirCallMaps<'tcx>,
+    graph: &mut CallGraph,
+    progress: &mut Progress,
+) {
+    let mut builder = GraphBuilder::new(context, options, mir_calls, progress);
+
+    // The functions already in the graph were explored from the entry point or public API
+    for node in &graph.nodes {
+        if let CallNodeKind::LocalFn(def_id, _hir_id) = node.kind {
+            builder.explored.insert(def_id);
+        }
+    }
+
+    // Collect the unreachable functions first, as exploring one can reach another
+    let mut unreachable: Vec<(DefId, HirId)> = vec![];
+    for local_id in context.hir().body_owners() {
+        if !matches!(context.def_kind(local_id), DefKind::Fn | DefKind::AssocFn) {
+            continue;
+        }
+        let def_id = local_id.to_def_id();
+        if !builder.explored.contains(&def_id)
+            && builder.filter(graph, def_id) == FilterDecision::Keep
+        {
+            unreachable.push((def_id, context.local_def_id_to_hir_id(local_id)));
+        }
+    }
+    graph.unreachable_fns = Some(unreachable.len());
 
-    // Access the code block of the function
-    match node {
-        rustc_hir::Node::Expr(expr) => {
-            if let ExprKind::Block(block, _) = expr.kind {
-                graph = add_calls_from_block(context, from_node, block, graph);
-            } else if let ExprKind::Closure(closure) = expr.kind {
-                graph = add_calls_from_function(context, from_node, closure.body.hir_id, graph);
+    for (def_id, hir_id) in unreachable {
+        if builder.explored.contains(&def_id) {
+            // Already explored from another unreachable function
+            continue;
+        }
+        let node = CallNodeKind::local_fn(def_id, hir_id);
+        let node_id = add_fn_node(context, graph, &context.def_path_str(def_id), node);
+
+        builder.explore_function(graph, node_id, def_id, hir_id);
+    }
+
+    // The items evaluated at compile-time outside of function bodies call functions as well, each gets a node of its own
+    for (def_id, label) in get_const_contexts(context) {
+        if builder.filter(graph, def_id) != FilterDecision::Keep {
+            continue;
+        }
+        let hir_id = context.local_def_id_to_hir_id(def_id.expect_local());
+        let node = CallNodeKind::local_fn(def_id, hir_id);
+        let node_id = add_fn_node(context, graph, &label, node);
+        graph.nodes[node_id].const_context = true;
+
+        let body = context.hir().body_owned_by(def_id.expect_local());
+        let calls = get_function_calls_in_const_body(context, mir_calls, body);
+        builder.add_calls(graph, node_id, calls);
+    }
+}
+
+/// Get the items evaluated at compile-time outside of function bodies, with the labels of their nodes:
+/// consts and statics (e.g. `Config::DEFAULT` in an impl block), and the discriminants of enum variants (e.g. `Level::High discriminant`).
+/// The items within function bodies are left out, their calls are attributed to the function when it is explored.
+fn get_const_contexts(context: TyCtxt) -> Vec<(DefId, String)> {
+    let mut contexts = vec![];
+    for local_id in context.hir().body_owners() {
+        let def_id = local_id.to_def_id();
+        let label = match context.def_kind(def_id) {
+            DefKind::Const | DefKind::AssocConst | DefKind::Static { .. } => {
+                context.def_path_str(def_id)
+            }
+            DefKind::AnonConst if context.def_kind(context.parent(def_id)) == DefKind::Variant => {
+                format!(
+                    "{} discriminant",
+                    context.def_path_str(context.parent(def_id))
+                )
             }
+            _ => continue,
+        };
+
+        let mut parent = context.opt_parent(def_id);
+        let mut in_fn_body = false;
+        while let Some(id) = parent.filter(|id| context.def_kind(*id) != DefKind::Mod) {
+            in_fn_body |= matches!(
+                context.def_kind(id),
+                DefKind::Fn | DefKind::AssocFn | DefKind::Closure
+            );
+            parent = context.opt_parent(id);
+        }
+        if !in_fn_body {
+            contexts.push((def_id, label));
         }
-        rustc_hir::Node::Block(block) => {
-            graph = add_calls_from_block(context, from_node, block, graph);
+    }
+    contexts
+}
+
+impl<'a, 'tcx> GraphBuilder<'a, 'tcx> {
+    /// Create a builder exploring the functions of the crate with the given options.
+    fn new(
+        context: TyCtxt<'tcx>,
+        options: &'a AnalysisOptions,
+        mir_calls: &'a MirCallMaps<'tcx>,
+        progress: &'a mut Progress,
+    ) -> Self {
+        GraphBuilder {
+            context,
+            options,
+            mir_calls,
+            in_progress: vec![],
+            explored: FxHashSet::default(),
+            filter: GraphFilter::new(context, options),
+            decisions: FxHashMap::default(),
+            skipping: vec![],
+            progress,
+        }
+    }
+
+    /// Decide whether to add a function to the graph, consulting the filter once per function.
+    /// The functions that are left out are listed in the graph.
+    fn filter(&mut self, graph: &mut CallGraph, def_id: DefId) -> FilterDecision {
+        if let Some(decision) = self.decisions.get(&def_id) {
+            return *decision;
         }
-        rustc_hir::Node::Item(item) => {
-            if let ItemKind::Fn(_sig, _gen, id) = item.kind {
-                graph = add_calls_from_function(context, from_node, id.hir_id, graph);
+
+        let decision = self.filter.decide(self.context, def_id);
+        self.decisions.insert(def_id, decision);
+        if decision != FilterDecision::Keep {
+            // Another builder may have left the function out before (e.g. when adding the unreachable functions)
+            let path = self.context.def_path_str(def_id);
+            if !graph.filtered_fns.contains(&path) {
+                graph.filtered_fns.push(path);
             }
         }
-        rustc_hir::Node::ImplItem(item) => {
-            if let ImplItemKind::Fn(_sig, id) = item.kind {
-                graph = add_calls_from_function(context, from_node, id.hir_id, graph);
+        decision
+    }
+
+    /// Add the calls made by a local function to the graph, unless its body was explored before.
+    /// The calls to an explored function still get their own edges, as these are added per call site.
+    fn explore_function(
+        &mut self,
+        graph: &mut CallGraph,
+        node: usize,
+        def_id: DefId,
+        fn_id: HirId,
+    ) {
+        if self.explored.insert(def_id) {
+            let (explored, calls) = (self.explored.len(), graph.edges.len());
+            self.progress
+                .update(|| format!("{explored} functions explored, {calls} calls added"));
+            self.add_calls_from_function(graph, node, fn_id);
+        }
+    }
+
+    /// Retrieve all function calls within a function, and add the nodes and edges to the graph.
+    /// The type check results of the body are retrieved once here, and passed down to its expressions.
+    fn add_calls_from_function(&mut self, graph: &mut CallGraph, from_node: usize, fn_id: HirId) {
+        let node = self.context.hir_node(fn_id);
+
+        // Access the code block of the function
+        match node {
+            rustc_hir::Node::Expr(expr) => {
+                if let ExprKind::Block(block, _) = expr.kind {
+                    self.add_calls_from_block(graph, from_node, block);
+                } else if let ExprKind::Closure(closure) = expr.kind {
+                    self.add_calls_from_function(graph, from_node, closure.body.hir_id);
+                } else {
+                    // The body of a closure without braces, its value is returned by the closure itself
+                    let typeck = get_typeck_results(self.context, fn_id);
+                    let mut calls: Vec<FunctionCall> = vec![];
+                    for mut call in
+                        get_function_calls_in_expression(self.context, self.mir_calls, typeck, expr)
+                    {
+                        call.propagates = call.can_propagate();
+                        calls.push(call);
+                    }
+                    self.add_calls(graph, from_node, calls);
+                }
             }
+            rustc_hir::Node::Block(block) => {
+                self.add_calls_from_block(graph, from_node, block);
+            }
+            rustc_hir::Node::Item(item) => {
+                if let ItemKind::Fn(_sig, _gen, id) = item.kind {
+                    self.add_calls_from_function(graph, from_node, id.hir_id);
+                }
+            }
+            rustc_hir::Node::ImplItem(item) => {
+                if let ImplItemKind::Fn(_sig, id) = item.kind {
+                    self.add_calls_from_function(graph, from_node, id.hir_id);
+                }
+            }
+            rustc_hir::Node::TraitItem(item) => {
+                // Only default methods have a body to explore
+                if let TraitItemKind::Fn(_sig, TraitFn::Provided(id)) = item.kind {
+                    self.add_calls_from_function(graph, from_node, id.hir_id);
+                }
+            }
+            _ => {}
         }
-        _ => {}
     }
 
-    graph
-}
+    /// Retrieve all function calls within a block, and add the nodes and edges to the graph.
+    fn add_calls_from_block(&mut self, graph: &mut CallGraph, from: usize, block: &Block) {
+        // Get the function calls from within this block
+        let typeck = get_typeck_results(self.context, block.hir_id);
+        let calls = get_function_calls_in_block(self.context, self.mir_calls, typeck, block, true);
 
-/// Retrieve all function calls within a block, and add the nodes and edges to the graph.
-fn add_calls_from_block(
-    context: TyCtxt,
-    from: usize,
-    block: &Block,
-    mut graph: CallGraph,
-) -> CallGraph {
-    // Get the function calls from within this block
-    let calls = get_function_calls_in_block(context, block, true);
-
-    // Add edges for all function calls
-    for (node_kind, call_id, add_edge, propagates) in calls {
-        match node_kind {
-            CallNodeKind::LocalFn(def_id, hir_id) => {
-                if let Some(node) = graph.find_local_fn_node(hir_id) {
-                    // We have already encountered this local function, so just add the edge
-                    if add_edge {
-                        graph.add_edge(CallEdge::new(from, node.id(), call_id, propagates));
+        self.add_calls(graph, from, calls);
+    }
+
+    /// Add the nodes and edges for function calls made by the function of the provided node to the graph.
+    /// The calls are attributed to that node, so propagation inside e.g. closures is never attributed to the enclosing function.
+    /// Calls to the nodes that are still being explored close a cycle.
+    fn add_calls(&mut self, graph: &mut CallGraph, from: usize, mut calls: Vec<FunctionCall>) {
+        let context = self.context;
+        let options = self.options;
+        self.in_progress.push(from);
+
+        // Add the implicit calls to local drop implementations, if asked to
+        if options.drop_edges {
+            if let Some(def_id) = graph.nodes[from].kind.def_id().filter(|id| id.is_local()) {
+                calls.extend(get_drop_calls(context, def_id));
+            }
+        }
+
+        // Unwrapping the result of a call can panic
+        if calls
+            .iter()
+            .any(|call| call.handling == Handling::Unwrapped)
+        {
+            graph.nodes[from].panics = true;
+        }
+
+        // Add edges for all function calls
+        for mut call in calls {
+            // Skip calls the user did not write themselves, unless asked not to
+            // The errors created by e.g. `anyhow::bail!` are kept, as these are where errors originate
+            if !options.include_macro_calls
+                && is_external_macro_call(context, call.call_id)
+                && !is_error_origin_macro_call(context, &call)
+            {
+                graph.filtered_macro_calls += 1;
+                continue;
+            }
+
+            // Closures and async blocks run as a spawned task are called by the spawner, which only gets their error back through the task's handle
+            if let CallNodeKind::LocalFn(def_id, _hir_id) = call.node_kind {
+                if context.is_closure_like(def_id) {
+                    if let Some(handling) = call
+                        .call_id
+                        .hir_id()
+                        .and_then(|call_id| get_spawned_task_handling(context, options, call_id))
+                    {
+                        call.add_edge = true;
+                        call.implicit = Some(ImplicitCall::Spawn);
+                        call.propagates = false;
+                        call.handling = handling;
                     }
-                } else {
-                    // We have not yet explored this local function, so add new node and edge,
-                    // and explore it.
-                    let id = graph.add_node(&context.def_path_str(def_id), node_kind);
+                }
+            }
 
-                    if add_edge {
-                        graph.add_edge(CallEdge::new(from, id, call_id, propagates));
+            if let Some(def_id) = call.node_kind.def_id() {
+                call.must_use = is_must_use(context, def_id);
+            }
+
+            // Consult the filter before creating a node, functions whose subtree is skipped are never explored
+            if let Some(def_id) = call.node_kind.def_id() {
+                match self.filter(graph, def_id) {
+                    FilterDecision::Keep => {}
+                    FilterDecision::SkipNode => {
+                        self.add_calls_of_skipped_function(graph, from, &call.node_kind);
+                        continue;
+                    }
+                    FilterDecision::SkipSubtree => continue,
+                }
+            }
+
+            match call.node_kind {
+                CallNodeKind::LocalFn(def_id, hir_id) => {
+                    if let Some(node) = graph.find_local_fn_node(hir_id) {
+                        // We have already encountered this local function, so just add the edge
+                        // If we are still exploring it, this call is recursive
+                        let to = node.id();
+                        call.is_recursive = self.in_progress.contains(&to);
+                        add_call_edge(graph, from, to, &call);
+                    } else {
+                        // We have not yet explored this local function, so add new node and edge,
+                        // and explore it.
+                        let id = add_fn_node(
+                            context,
+                            graph,
+                            &context.def_path_str(def_id),
+                            call.node_kind.clone(),
+                        );
+
+                        add_call_edge(graph, from, id, &call);
+
+                        self.explore_function(graph, id, def_id, hir_id);
                     }
+                }
+                CallNodeKind::NonLocalFn(def_id) => {
+                    let label = get_non_local_fn_label(context, def_id, call.call_id);
+                    if let Some(node) = graph.find_non_local_fn_node(def_id, &label) {
+                        // We have already encountered this non-local function, so just add the edge
+                        let to = node.id();
+                        call.is_recursive = self.in_progress.contains(&to);
+                        add_call_edge(graph, from, to, &call);
+                    } else {
+                        // We have not yet explored this non-local function, so add new node and edge
+                        let id = add_fn_node(context, graph, &label, call.node_kind.clone());
+                        let path = context.def_path_str(def_id);
+                        if path != label {
+                            graph.nodes[id].path = Some(path);
+                        }
 
-                    graph = add_calls_from_function(context, id, hir_id, graph);
+                        add_call_edge(graph, from, id, &call);
+
+                        // Explore functions of followed dependencies using their MIR
+                        if let Some(name) = get_followed_crate(context, options, def_id) {
+                            graph.nodes[id].cluster = Some(name);
+                            let calls = get_function_calls_in_mir(context, def_id);
+                            self.add_calls(graph, id, calls);
+                        }
+                    }
+                }
+                CallNodeKind::ForeignFn(def_id) => {
+                    if let Some(node) = graph.find_foreign_fn_node(def_id) {
+                        // We have already encountered this foreign function, so just add the edge
+                        add_call_edge(graph, from, node.id(), &call);
+                    } else {
+                        // We have not yet encountered this foreign function, so add new node and edge
+                        let id = add_fn_node(
+                            context,
+                            graph,
+                            &context.def_path_str(def_id),
+                            call.node_kind.clone(),
+                        );
+
+                        add_call_edge(graph, from, id, &call);
+                    }
+                }
+                CallNodeKind::Unresolved(ref name) => {
+                    if let Some(node) = graph.find_unresolved_node(name) {
+                        // We have already encountered an unresolved call with this name, so just add the edge
+                        add_call_edge(graph, from, node.id(), &call);
+                    } else {
+                        // We have not yet encountered this name, so add new node and edge
+                        let label = format!("unresolved call: {name}");
+                        let id = graph.add_node(&label, call.node_kind.clone());
+
+                        add_call_edge(graph, from, id, &call);
+                    }
+                }
+                CallNodeKind::Indirect(ref signature) => {
+                    if let Some(node) = graph.find_indirect_node(signature) {
+                        // We have already encountered an indirect call with this signature, so just add the edge
+                        add_call_edge(graph, from, node.id(), &call);
+                    } else {
+                        // We have not yet encountered this signature, so add new node and edge
+                        let label = format!("indirect call: {signature}");
+                        let id = graph.add_node(&label, call.node_kind.clone());
+
+                        add_call_edge(graph, from, id, &call);
+                    }
                 }
+                CallNodeKind::Aggregated(_) => {
+                    unreachable!("Functions are only aggregated once the call graph is created")
+                }
+                CallNodeKind::ProcessExit => {
+                    unreachable!("The program exit is only added once the call graph is created")
+                }
+            }
+        }
+
+        self.in_progress.pop();
+    }
+
+    /// Add the calls made by a function that is left out of the graph as made by its caller, if its body can be explored.
+    /// Its body is explored again for each call, as the calls are attributed to each caller, unless the call is recursive.
+    fn add_calls_of_skipped_function(
+        &mut self,
+        graph: &mut CallGraph,
+        from: usize,
+        node_kind: &CallNodeKind,
+    ) {
+        let Some(def_id) = node_kind.def_id() else {
+            return;
+        };
+        if self.skipping.contains(&def_id) {
+            return;
+        }
+
+        self.skipping.push(def_id);
+        match *node_kind {
+            CallNodeKind::LocalFn(_def_id, hir_id) => {
+                self.add_calls_from_function(graph, from, hir_id);
             }
             CallNodeKind::NonLocalFn(def_id) => {
-                if let Some(node) = graph.find_non_local_fn_node(def_id) {
-                    // We have already encountered this non-local function, so just add the edge
-                    if add_edge {
-                        graph.add_edge(CallEdge::new(from, node.id(), call_id, propagates));
+                if get_followed_crate(self.context, self.options, def_id).is_some() {
+                    let calls = get_function_calls_in_mir(self.context, def_id);
+                    self.add_calls(graph, from, calls);
+                }
+            }
+            _ => {}
+        }
+        self.skipping.pop();
+    }
+}
+
+/// Add the node of a function to the graph, with the attributes of the function (e.g. whether it is async), returning its id.
+fn add_fn_node(
+    context: TyCtxt,
+    graph: &mut CallGraph,
+    label: &str,
+    node_kind: CallNodeKind,
+) -> usize {
+    let def_id = node_kind.def_id();
+    let id = graph.add_node(label, node_kind);
+    let Some(def_id) = def_id else {
+        return id;
+    };
+    // Closures and constructors do not have a signature or visibility of their own
+    if !matches!(context.def_kind(def_id), DefKind::Fn | DefKind::AssocFn) {
+        return id;
+    }
+
+    let node = &mut graph.nodes[id];
+    node.is_async = context.asyncness(def_id).is_async();
+    node.is_unsafe = context.fn_sig(def_id).skip_binder().unsafety() == Unsafety::Unsafe;
+    node.visibility = Some(get_visibility(context, def_id));
+    node.is_test = is_test_fn(context, def_id);
+    node.track_caller = context
+        .codegen_fn_attrs(def_id)
+        .flags
+        .contains(CodegenFnAttrFlags::TRACK_CALLER);
+    id
+}
+
+/// Whether the result of a call to a function must be used beyond being a `Result`:
+/// the function has `#[must_use]`, or the type it returns or one of the types of the `Result` it returns does (e.g. a guard).
+fn is_must_use(context: TyCtxt, def_id: DefId) -> bool {
+    if context.has_attr(def_id, sym::must_use) {
+        return true;
+    }
+    if !matches!(context.def_kind(def_id), DefKind::Fn | DefKind::AssocFn) {
+        return false;
+    }
+
+    // Every `Result` is `#[must_use]`, so only the types it holds tell whether one is more so
+    let is_result = |ty: ty::Ty| {
+        ty.ty_adt_def()
+            .is_some_and(|adt| context.is_diagnostic_item(sym::Result, adt.did()))
+    };
+    let is_must_use_ty = |ty: ty::Ty| {
+        !is_result(ty)
+            && ty
+                .ty_adt_def()
+                .is_some_and(|adt| context.has_attr(adt.did(), sym::must_use))
+    };
+    let output = context.fn_sig(def_id).skip_binder().output().skip_binder();
+    match output.kind() {
+        ty::Adt(_adt, args) if is_result(output) => args.types().any(is_must_use_ty),
+        _ => is_must_use_ty(output),
+    }
+}
+
+/// Get the declared visibility of a function, where being visible in its parent module only is private.
+fn get_visibility(context: TyCtxt, def_id: DefId) -> Visibility {
+    match context.visibility(def_id) {
+        ty::Visibility::Public => Visibility::Public,
+        ty::Visibility::Restricted(module) => {
+            let mut parent = context.opt_parent(def_id);
+            while let Some(id) = parent.filter(|id| context.def_kind(*id) != DefKind::Mod) {
+                parent = context.opt_parent(id);
+            }
+            if parent == Some(module) {
+                Visibility::Private
+            } else {
+                Visibility::Crate
+            }
+        }
+    }
+}
+
+/// Whether a local function is a test: it has `#[test]`, or it or one of its enclosing modules has `#[cfg(test)]`.
+/// Without `--test`, these are not compiled at all, so they are only found when analyzing a test build.
+fn is_test_fn(context: TyCtxt, def_id: DefId) -> bool {
+    if !def_id.is_local() {
+        return false;
+    }
+    if context.has_attr(def_id, sym::test) || context.has_attr(def_id, sym::rustc_test_marker) {
+        return true;
+    }
+
+    let mut current = Some(def_id);
+    while let Some(id) = current {
+        let cfg_test = context.get_attrs(id, sym::cfg).any(|attr| {
+            attr.meta_item_list()
+                .is_some_and(|items| items.iter().any(|item| item.has_name(sym::test)))
+        });
+        if cfg_test {
+            return true;
+        }
+        current = context.opt_parent(id);
+    }
+    false
+}
+
+/// Get a readable label for a non-local function, e.g. `dyn std::io::Write::write_fmt` instead of `<dyn std::io::Write as std::io::Write>::write_fmt`.
+/// Trait methods are labeled with the type they are called on if it is a trait object or concrete, and methods of implementations with the implementing type.
+fn get_non_local_fn_label(context: TyCtxt, def_id: DefId, call_id: CallId) -> String {
+    let name = context.item_name(def_id);
+
+    // Methods of implementations, e.g. `core::str::<impl str>::parse` becomes `str::parse`
+    if let Some(impl_id) = context.impl_of_method(def_id) {
+        let self_ty = context.erase_regions(context.type_of(impl_id).instantiate_identity());
+        return if let Some(trait_ref) = context.impl_trait_ref(impl_id) {
+            let trait_path = context.def_path_str(trait_ref.skip_binder().def_id);
+            format!("{self_ty} as {trait_path}::{name}")
+        } else {
+            format!("{self_ty}::{name}")
+        };
+    }
+
+    let path = context.def_path_str(def_id);
+    if context.trait_of_item(def_id).is_none() {
+        return path;
+    }
+
+    match call_id
+        .hir_id()
+        .and_then(|call_id| get_self_type_of_call(context, call_id))
+    {
+        Some(self_ty) if self_ty.is_trait() => format!("dyn {path}"),
+        Some(self_ty) if !self_ty.has_param() && !self_ty.has_infer() => {
+            format!("{self_ty} as {path}")
+        }
+        _ => path,
+    }
+}
+
+/// Get the type a (trait) method is called on, i.e. the type of `Self` in the call.
+/// Returns `None` for calls without a call expression (e.g. implicit calls), and for desugared calls (e.g. of the try operator), to keep a single node for them.
+fn get_self_type_of_call(context: TyCtxt, call_id: HirId) -> Option<ty::Ty> {
+    let rustc_hir::Node::Expr(expr) = context.hir_node(call_id) else {
+        return None;
+    };
+    if expr.span.desugaring_kind().is_some() {
+        return None;
+    }
+
+    let typeck = get_typeck_results(context, call_id)?;
+    let args = if let ExprKind::Call(callee, _args) = expr.kind {
+        let ty::FnDef(_def_id, args) = typeck.expr_ty_opt(callee)?.kind() else {
+            return None;
+        };
+        *args
+    } else {
+        typeck.node_args_opt(call_id)?
+    };
+
+    args.types().next()
+}
+
+/// Get the name of the crate a function is defined in, if it is one of the dependencies that should be followed.
+fn get_followed_crate(context: TyCtxt, options: &AnalysisOptions, def_id: DefId) -> Option<String> {
+    let name = context.crate_name(def_id.krate).to_string();
+
+    options.follow_deps.contains(&name).then_some(name)
+}
+
+/// Retrieve a vec of all function calls made by a function of a followed dependency, using its MIR as there is no HIR.
+/// A call is assumed to propagate if its result is returned directly or passed to the try operator.
+fn get_function_calls_in_mir(context: TyCtxt, def_id: DefId) -> Vec<FunctionCall> {
+    let mut res: Vec<FunctionCall> = vec![];
+
+    let Some(mir) = get_mir(context, def_id) else {
+        return res;
+    };
+
+    // Find the locals the try operator is used on
+    let branch_fn = context.lang_items().branch_fn();
+    let mut tried: Vec<Local> = vec![];
+    for block in mir.basic_blocks.iter() {
+        if let Some(terminator) = &block.terminator {
+            if let TerminatorKind::Call { func, args, .. } = &terminator.kind {
+                if func.const_fn_def().map(|(id, _args)| id) == branch_fn {
+                    for arg in args.iter() {
+                        if let Some(place) = arg.node.place() {
+                            tried.push(place.local);
+                        }
                     }
-                } else {
-                    // We have not yet explored this non-local function, so add new node and edge
-                    let id = graph.add_node(&context.def_path_str(node_kind.def_id()), node_kind);
+                }
+            }
+        }
+    }
+
+    for (block_id, block) in mir.basic_blocks.iter_enumerated() {
+        if let Some(terminator) = &block.terminator {
+            if let TerminatorKind::Call {
+                func, destination, ..
+            } = &terminator.kind
+            {
+                if let Some((called_id, _args)) = func.const_fn_def() {
+                    // There is no call expression, so the call is identified by the block of the MIR it ends
+                    let mut call = FunctionCall::new(
+                        get_node_kind_from_def_id(context, called_id),
+                        CallId::Mir(def_id, block_id),
+                        true,
+                    );
+                    call.propagates = destination.local == RETURN_PLACE
+                        || tried.contains(&destination.local)
+                        || Some(called_id) == branch_fn;
+                    res.push(call);
+                }
+            }
+        }
+    }
+
+    res
+}
+
+/// The macros of error handling libraries (e.g. `anyhow`) that return a new error.
+const ERROR_ORIGIN_MACROS: [&str; 2] = ["bail", "ensure"];
+
+/// Whether a call is the construction of the error returned by e.g. `anyhow::bail!` or `eyre::ensure!`.
+/// These macros may construct the error using helper macros, so all macros the call was expanded from are checked.
+fn is_error_origin_macro_call(context: TyCtxt, call: &FunctionCall) -> bool {
+    let is_err_constructor = call.node_kind.def_id().is_some_and(|def_id| {
+        context.def_kind(def_id) == DefKind::Ctor(CtorOf::Variant, CtorKind::Fn)
+            && context.lang_items().result_err_variant() == Some(context.parent(def_id))
+    });
+
+    is_err_constructor
+        && get_call_span(context, call.call_id)
+            .macro_backtrace()
+            .any(|expn_data| {
+                let ExpnKind::Macro(_kind, name) = expn_data.kind else {
+                    return false;
+                };
+
+                ERROR_ORIGIN_MACROS.contains(&name.as_str())
+                    && expn_data.macro_def_id.is_some_and(|def_id| {
+                        ERROR_LIBRARIES.contains(&context.crate_name(def_id.krate).as_str())
+                    })
+            })
+}
+
+/// Whether a call was expanded from a macro defined outside of the crate the call is made in (e.g. `println!`).
+fn is_external_macro_call(context: TyCtxt, call_id: CallId) -> bool {
+    let expn_data = get_call_span(context, call_id).ctxt().outer_expn_data();
+    let krate = match call_id {
+        CallId::Hir(_hir_id) => LOCAL_CRATE,
+        CallId::Mir(def_id, _block) => def_id.krate,
+    };
+
+    matches!(expn_data.kind, ExpnKind::Macro(_kind, _name))
+        && expn_data
+            .macro_def_id
+            .is_some_and(|def_id| def_id.krate != krate)
+}
+
+/// Add an edge for a function call to the graph, unless the call should not have one.
+fn add_call_edge(graph: &mut CallGraph, from: usize, to: usize, call: &FunctionCall) {
+    if call.add_edge {
+        let mut edge = CallEdge::new(from, to, call.call_id, call.propagates);
+        edge.const_evaluated = call.const_evaluated;
+        edge.is_unsafe_call = call.is_unsafe_call;
+        edge.is_recursive = call.is_recursive;
+        edge.must_use = call.must_use;
+        edge.handling = if call.propagates {
+            Handling::Propagated
+        } else {
+            call.handling
+        };
+        edge.expect_message.clone_from(&call.expect_message);
+        edge.map_err_id = call.map_err_id;
+        edge.propagates_locally = call.propagates_locally;
+        edge.implicit = call.implicit;
+        edge.origin_ty.clone_from(&call.origin_ty);
+        edge.context_message.clone_from(&call.context_message);
+        edge.conversion = call.conversion;
+        graph.add_edge(edge);
+    }
+}
 
-                    if add_edge {
-                        graph.add_edge(CallEdge::new(from, id, call_id, propagates));
+/// The functions spawning a task (e.g. a thread) that runs the closure or async block passed to them, returning a handle to the task.
+const SPAWN_FUNCTIONS: [&str; 5] = [
+    "tokio::spawn",
+    "tokio::task::spawn",
+    "async_std::task::spawn",
+    "std::thread::spawn",
+    "std::thread::Builder::spawn",
+];
+
+/// Get how the error of a closure or async block that is spawned as a task (e.g. `tokio::spawn(async { f().await })`) is dealt with by the spawner.
+/// The error is handled if the handle of the task is used (e.g. awaited or joined), and discarded if it is dropped right away.
+/// Returns `None` if the closure is not spawned.
+fn get_spawned_task_handling(
+    context: TyCtxt,
+    options: &AnalysisOptions,
+    closure_id: HirId,
+) -> Option<Handling> {
+    let (_id, rustc_hir::Node::Expr(spawn_expr)) = context.hir().parent_iter(closure_id).next()?
+    else {
+        return None;
+    };
+
+    let typeck = get_typeck_results(context, spawn_expr.hir_id)?;
+    let spawn_id = match spawn_expr.kind {
+        ExprKind::Call(callee, args) if args.iter().any(|arg| arg.hir_id == closure_id) => {
+            match typeck.expr_ty_opt(callee)?.kind() {
+                ty::FnDef(def_id, _args) => *def_id,
+                _ => return None,
+            }
+        }
+        ExprKind::MethodCall(_path, _receiver, args, _span)
+            if args.iter().any(|arg| arg.hir_id == closure_id) =>
+        {
+            typeck.type_dependent_def_id(spawn_expr.hir_id)?
+        }
+        _ => return None,
+    };
+    let path = context.def_path_str(spawn_id);
+    if !SPAWN_FUNCTIONS.contains(&path.as_str()) && !options.spawn_fns.contains(&path) {
+        return None;
+    }
+
+    if is_task_handle_used(context, spawn_expr) {
+        Some(Handling::Handled)
+    } else {
+        Some(Handling::Discarded)
+    }
+}
+
+/// Whether the handle returned by a call spawning a task is used, e.g. awaited, joined, returned or stored.
+/// Handles that are dropped right away (e.g. `tokio::spawn(f);` or `let _ = thread::spawn(f);`) or never used after being bound are not.
+fn is_task_handle_used(context: TyCtxt, spawn_expr: &Expr) -> bool {
+    let mut handle_id = spawn_expr.hir_id;
+    for (id, node) in context.hir().parent_iter(spawn_expr.hir_id) {
+        match node {
+            // Spawning can fail itself, e.g. `thread::Builder::new().spawn(f).expect("..")`
+            rustc_hir::Node::Expr(Expr {
+                kind: ExprKind::MethodCall(path, receiver, _args, _span),
+                ..
+            }) if receiver.hir_id == handle_id && UNWRAP_METHODS.contains(&path.ident.as_str()) => {
+                handle_id = id;
+            }
+            rustc_hir::Node::Stmt(stmt) => return !matches!(stmt.kind, StmtKind::Semi(_)),
+            rustc_hir::Node::LetStmt(local) => {
+                return match local.pat.kind {
+                    PatKind::Binding(_mode, binding_id, _ident, _pat) => {
+                        is_local_used(context, binding_id)
                     }
+                    _ => false,
+                };
+            }
+            _ => return true,
+        }
+    }
+
+    true
+}
+
+/// Whether a local variable is used anywhere in the body it is defined in (including closures within it).
+fn is_local_used(context: TyCtxt, local_id: HirId) -> bool {
+    struct LocalUseFinder<'tcx> {
+        map: rustc_middle::hir::map::Map<'tcx>,
+        local_id: HirId,
+        used: bool,
+    }
+
+    impl<'tcx> Visitor<'tcx> for LocalUseFinder<'tcx> {
+        type NestedFilter = nested_filter::OnlyBodies;
+
+        fn nested_visit_map(&mut self) -> Self::Map {
+            self.map
+        }
+
+        fn visit_expr(&mut self, expr: &'tcx Expr<'tcx>) {
+            if let ExprKind::Path(QPath::Resolved(None, path)) = expr.kind {
+                if path.res == Res::Local(self.local_id) {
+                    self.used = true;
                 }
             }
+            walk_expr(self, expr);
         }
     }
 
-    graph
+    let Some(body_id) = context
+        .hir()
+        .maybe_body_owned_by(context.hir().enclosing_body_owner(local_id))
+    else {
+        return true;
+    };
+
+    let mut finder = LocalUseFinder {
+        map: context.hir(),
+        local_id,
+        used: false,
+    };
+    finder.visit_body(context.hir().body(body_id));
+    finder.used
 }
 
 /// Retrieve a vec of all function calls made within the body of a block.
-fn get_function_calls_in_block(
-    context: TyCtxt,
+fn get_function_calls_in_block<'tcx>(
+    context: TyCtxt<'tcx>,
+    mir_calls: &MirCallMaps<'tcx>,
+    typeck: Option<&'tcx TypeckResults<'tcx>>,
     block: &Block,
     is_fn: bool,
-) -> Vec<(CallNodeKind, HirId, bool, bool)> {
-    let mut res: Vec<(CallNodeKind, HirId, bool, bool)> = vec![];
+) -> Vec<FunctionCall> {
+    let mut res: Vec<FunctionCall> = vec![];
 
     // If the block has an ending expression add calls from there
     // If this block is that of a function, this is a return statement
     if let Some(exp) = block.expr {
         if let ExprKind::DropTemps(ex) = exp.kind {
             if let ExprKind::Block(b, _lbl) = ex.kind {
-                return get_function_calls_in_block(context, b, is_fn);
+                return get_function_calls_in_block(context, mir_calls, typeck, b, is_fn);
             }
-        } else {
-            if is_fn {
-                for (kind, id, add_edge, _) in get_function_calls_in_expression(context, exp) {
-                    res.push((kind, id, add_edge, true));
-                }
-            } else {
-                res.extend(get_function_calls_in_expression(context, exp));
+        } else if is_fn {
+            for mut call in get_function_calls_in_expression(context, mir_calls, typeck, exp) {
+                call.propagates = call.can_propagate();
+                res.push(call);
             }
+        } else {
+            res.extend(get_function_calls_in_expression(
+                context, mir_calls, typeck, exp,
+            ));
         }
     }
 
@@ -146,14 +1105,40 @@ fn get_function_calls_in_block(
         match statement.kind {
             StmtKind::Let(stmt) => {
                 if let Some(exp) = stmt.init {
-                    res.extend(get_function_calls_in_expression(context, exp));
+                    let mut calls =
+                        get_function_calls_in_expression(context, mir_calls, typeck, exp);
+                    // The result of a call bound to `_` is thrown away
+                    if let PatKind::Wild = stmt.pat.kind {
+                        mark_discarded(context, typeck, &mut calls, exp);
+                    }
+                    for mut call in calls {
+                        // In a let-else statement, the result of the scrutinee call is destructured rather than forwarded
+                        if stmt.els.is_some() && call.call_id == CallId::Hir(exp.hir_id) {
+                            call.propagates = false;
+                            call.handling = Handling::Handled;
+                        }
+                        res.push(call);
+                    }
+                }
+                if let Some(els) = stmt.els {
+                    res.extend(get_function_calls_in_block(
+                        context, mir_calls, typeck, els, false,
+                    ));
                 }
             }
-            StmtKind::Item(_id) => {
-                // No function calls here
+            StmtKind::Item(id) => {
+                res.extend(get_function_calls_in_item(context, mir_calls, id));
             }
-            StmtKind::Expr(exp) | StmtKind::Semi(exp) => {
-                res.extend(get_function_calls_in_expression(context, exp));
+            StmtKind::Expr(exp) => {
+                res.extend(get_function_calls_in_expression(
+                    context, mir_calls, typeck, exp,
+                ));
+            }
+            StmtKind::Semi(exp) => {
+                // The result of a call used as a statement (e.g. `f();`) is thrown away
+                let mut calls = get_function_calls_in_expression(context, mir_calls, typeck, exp);
+                mark_discarded(context, typeck, &mut calls, exp);
+                res.extend(calls);
             }
         }
     }
@@ -161,72 +1146,295 @@ fn get_function_calls_in_block(
     res
 }
 
+/// Retrieve a vec of all function calls made within the initializer of a const or static item nested in a function body.
+/// These calls are evaluated at compile-time.
+fn get_function_calls_in_item<'tcx>(
+    context: TyCtxt<'tcx>,
+    mir_calls: &MirCallMaps<'tcx>,
+    id: ItemId,
+) -> Vec<FunctionCall> {
+    match context.hir().item(id).kind {
+        ItemKind::Const(_, _, body) | ItemKind::Static(_, _, body) => {
+            get_function_calls_in_const_body(context, mir_calls, body)
+        }
+        _ => {
+            // Other items (e.g. nested functions) are not executed here
+            vec![]
+        }
+    }
+}
+
+/// Retrieve a vec of all function calls made within a const body (e.g. an array length or const initializer).
+/// These calls are evaluated at compile-time.
+fn get_function_calls_in_const_body<'tcx>(
+    context: TyCtxt<'tcx>,
+    mir_calls: &MirCallMaps<'tcx>,
+    body: BodyId,
+) -> Vec<FunctionCall> {
+    // The body is type checked on its own (e.g. an array length), or along with the enclosing function (an inline const)
+    let typeck = get_body_typeck_results(context, context.hir().body_owner_def_id(body));
+    let mut res = get_function_calls_in_expression(
+        context,
+        mir_calls,
+        typeck,
+        context.hir().body(body).value,
+    );
+
+    for call in &mut res {
+        call.const_evaluated = true;
+    }
+
+    res
+}
+
+/// Retrieve a vec of all function calls made within the const generic arguments of path segments.
+/// These calls are evaluated at compile-time.
+fn get_function_calls_in_generic_args<'tcx>(
+    context: TyCtxt<'tcx>,
+    mir_calls: &MirCallMaps<'tcx>,
+    segments: &[PathSegment],
+) -> Vec<FunctionCall> {
+    let mut res: Vec<FunctionCall> = vec![];
+
+    for segment in segments {
+        if let Some(args) = segment.args {
+            for arg in args.args {
+                if let GenericArg::Const(arg) = arg {
+                    res.extend(get_function_calls_in_const_body(
+                        context,
+                        mir_calls,
+                        arg.value.body,
+                    ));
+                }
+            }
+        }
+    }
+
+    res
+}
+
+/// Get the segments of a `QPath` that can contain generic arguments.
+fn get_path_segments<'a>(qpath: &QPath<'a>) -> &'a [PathSegment<'a>] {
+    match qpath {
+        QPath::Resolved(_ty, path) => path.segments,
+        QPath::TypeRelative(_ty, segment) => std::slice::from_ref(*segment),
+        QPath::LangItem(_item, _span) => &[],
+    }
+}
+
 /// Retrieve a vec of all function calls made within an expression.
 #[allow(clippy::too_many_lines)]
-fn get_function_calls_in_expression(
-    context: TyCtxt,
+fn get_function_calls_in_expression<'tcx>(
+    context: TyCtxt<'tcx>,
+    mir_calls: &MirCallMaps<'tcx>,
+    typeck: Option<&'tcx TypeckResults<'tcx>>,
     expr: &Expr,
-) -> Vec<(CallNodeKind, HirId, bool, bool)> {
-    let mut res: Vec<(CallNodeKind, HirId, bool, bool)> = vec![];
+) -> Vec<FunctionCall> {
+    let mut res: Vec<FunctionCall> = get_overloaded_deref_calls(context, typeck, expr);
 
     // Match the kind of expression
     match expr.kind {
         ExprKind::Call(func, args) => {
-            if let Some(def_id) = get_call_def_id(context, expr.hir_id) {
+            if let Some(node_kind) = get_node_kind_from_callee_type(context, typeck, func) {
+                res.push(FunctionCall::new(node_kind, CallId::Hir(expr.hir_id), true));
+            } else if let Some(def_id) = get_call_def_id(context, mir_calls, expr.hir_id) {
+                let node_kind = get_node_kind_from_def_id(context, def_id);
+                res.push(FunctionCall::new(node_kind, CallId::Hir(expr.hir_id), true));
+            } else if let ExprKind::Path(qpath) = func.kind {
+                if let Some((node_kind, _add_edge)) =
+                    get_node_kind_from_path(context, typeck, qpath, func.hir_id)
+                {
+                    res.push(FunctionCall::new(node_kind, CallId::Hir(expr.hir_id), true));
+                }
+            } else if typeck.is_none() {
+                // Without type information (e.g. as type checking the caller failed), the callee cannot be resolved
+                let callee = context
+                    .sess
+                    .source_map()
+                    .span_to_snippet(func.span)
+                    .unwrap_or_default();
+                let node_kind = CallNodeKind::unresolved(callee);
+                res.push(FunctionCall::new(node_kind, CallId::Hir(expr.hir_id), true));
+            }
+            if let ExprKind::Path(qpath) = func.kind {
+                res.extend(get_function_calls_in_generic_args(
+                    context,
+                    mir_calls,
+                    get_path_segments(&qpath),
+                ));
+            }
+            for exp in args {
+                res.extend(get_function_calls_in_expression(
+                    context, mir_calls, typeck, exp,
+                ));
+            }
+        }
+        ExprKind::MethodCall(path, exp, args, _span) => {
+            if let Some(def_id) =
+                typeck.and_then(|typeck| typeck.type_dependent_def_id(expr.hir_id))
+            {
+                let args = typeck.and_then(|typeck| typeck.node_args_opt(expr.hir_id));
+                let def_id = resolve_trait_fn(context, expr.hir_id, def_id, args);
+                let node_kind = get_node_kind_from_def_id(context, def_id);
+                res.push(FunctionCall::new(node_kind, CallId::Hir(expr.hir_id), true));
+            } else if let Some(def_id) = get_call_def_id(context, mir_calls, expr.hir_id) {
                 let node_kind = get_node_kind_from_def_id(context, def_id);
-                res.push((node_kind, expr.hir_id, true, false));
-            } else if let ExprKind::Path(qpath) = func.kind {
-                if let Some((node_kind, _add_edge)) = get_node_kind_from_path(context, qpath) {
-                    res.push((node_kind, expr.hir_id, true, false));
+                res.push(FunctionCall::new(node_kind, CallId::Hir(expr.hir_id), true));
+            } else {
+                // Without type information (e.g. as type checking the caller failed), the method cannot be resolved
+                let node_kind = CallNodeKind::unresolved(path.ident.to_string());
+                res.push(FunctionCall::new(node_kind, CallId::Hir(expr.hir_id), true));
+            }
+            // `f().map_err(g)` converts the error of `f`, so remember the conversion on the call of `f`
+            let map_err_id = is_map_err_call(context, typeck, expr, path).then_some(expr.hir_id);
+            // `f().context("..")` adds context to the error of `f`, so remember the message on the call of `f`
+            let context_message = get_context_message(context, typeck, expr, path, args);
+            // The iterator an `Iterator` method is called on is consumed by it, only the result of the method is passed on
+            let consumed = is_consuming_iterator_call(context, typeck, expr);
+            let mut calls = get_function_calls_in_expression(context, mir_calls, typeck, exp);
+            for call in &mut calls {
+                if call.call_id == CallId::Hir(exp.hir_id) {
+                    call.map_err_id = map_err_id;
+                    call.context_message.clone_from(&context_message);
+                    call.consumed = consumed;
                 }
             }
-            for exp in args {
-                res.extend(get_function_calls_in_expression(context, exp));
+            // Combinators like `f().unwrap_or_else(g)` deal with the error of `f` themselves
+            if is_fallible_call(context, typeck, exp)
+                && HANDLING_COMBINATORS.contains(&path.ident.as_str())
+            {
+                mark_handled(&mut calls, exp);
             }
-        }
-        ExprKind::MethodCall(_path, exp, args, _span) => {
-            if let Some(def_id) = get_call_def_id(context, expr.hir_id) {
-                let node_kind = get_node_kind_from_def_id(context, def_id);
-                res.push((node_kind, expr.hir_id, true, false));
-            } else if let Some(def_id) = context
-                .typeck(expr.hir_id.owner.def_id)
-                .type_dependent_def_id(expr.hir_id)
+            // `iter.map(f).collect::<Result<_, _>>()` returns the first error of `f`, so `f` is (indirectly) called here
+            if is_result_collect(context, typeck, expr) {
+                let fallible_fns = get_fallible_iterator_fns(context, typeck, exp);
+                for call in &mut calls {
+                    if call
+                        .call_id
+                        .hir_id()
+                        .is_some_and(|call_id| fallible_fns.contains(&call_id))
+                    {
+                        call.add_edge = true;
+                        call.implicit = Some(ImplicitCall::Collect);
+                    }
+                }
+            }
+            // `f().ok()` drops the error of `f`, `f().err()` turns it into a value, and `f().ok_or(e)` creates a new error for `None`
+            match get_failure_conversion(context, typeck, expr, path) {
+                Some(FailureConversion::OkOr) => {
+                    mark_handled(&mut calls, exp);
+                    for call in &mut res {
+                        if call.call_id == CallId::Hir(expr.hir_id) {
+                            call.conversion = Some(FailureConversion::OkOr);
+                        }
+                    }
+                }
+                Some(conversion) => {
+                    for call in &mut calls {
+                        if call.call_id == CallId::Hir(exp.hir_id)
+                            && call.handling == Handling::Unknown
+                        {
+                            call.conversion = Some(conversion);
+                            call.handling = if conversion == FailureConversion::Ok {
+                                Handling::Discarded
+                            } else {
+                                Handling::Handled
+                            };
+                        }
+                    }
+                }
+                None => {}
+            }
+            // `f().unwrap()` panics on the error of `f`
+            if is_fallible_call(context, typeck, exp)
+                && UNWRAP_METHODS.contains(&path.ident.as_str())
             {
-                if let Some(local_id) = def_id.as_local() {
-                    res.push((
-                        CallNodeKind::local_fn(def_id, context.local_def_id_to_hir_id(local_id)),
-                        expr.hir_id,
-                        true,
-                        false,
-                    ));
-                } else {
-                    res.push((CallNodeKind::non_local_fn(def_id), expr.hir_id, true, false));
+                let message = args.first().and_then(get_string_literal);
+                for call in &mut calls {
+                    if call.call_id == CallId::Hir(exp.hir_id) {
+                        call.handling = Handling::Unwrapped;
+                        call.expect_message.clone_from(&message);
+                    }
                 }
             }
-            res.extend(get_function_calls_in_expression(context, exp));
+            res.extend(calls);
             for exp in args {
-                res.extend(get_function_calls_in_expression(context, exp));
+                res.extend(get_function_calls_in_expression(
+                    context, mir_calls, typeck, exp,
+                ));
             }
+            res.extend(get_function_calls_in_generic_args(
+                context,
+                mir_calls,
+                std::slice::from_ref(path),
+            ));
         }
         ExprKind::Match(exp, arms, src) => {
             match src {
                 MatchSource::TryDesugar(_hir) => {
-                    for (kind, id, add_edge, _) in get_function_calls_in_expression(context, exp) {
-                        res.push((kind, id, add_edge, true));
+                    // Inside a try block, the residual breaks out of that block instead of returning from the function
+                    let in_try_block = arms
+                        .iter()
+                        .any(|arm| matches!(arm.body.kind, ExprKind::Break(_dest, _opt)));
+
+                    let mut calls =
+                        get_function_calls_in_expression(context, mir_calls, typeck, exp);
+                    calls.extend(get_from_conversion_call(context, typeck, exp, arms));
+                    for mut call in calls {
+                        if in_try_block {
+                            call.propagates_locally = call.can_propagate();
+                        } else {
+                            call.propagates = call.can_propagate();
+                        }
+                        res.push(call);
                     }
 
                     return res;
                 }
+                MatchSource::ForLoopDesugar => {
+                    // The scrutinee is the call to either `IntoIterator::into_iter` or `Iterator::next`
+                    if let Some(call) = get_iterator_protocol_call(context, typeck, exp) {
+                        res.push(call);
+                        if let ExprKind::Call(_func, args) = exp.kind {
+                            for exp in args {
+                                res.extend(get_function_calls_in_expression(
+                                    context, mir_calls, typeck, exp,
+                                ));
+                            }
+                        }
+                    } else {
+                        res.extend(get_function_calls_in_expression(
+                            context, mir_calls, typeck, exp,
+                        ));
+                    }
+                }
+                MatchSource::Normal => {
+                    // Matching on the result of a call handles its error (or `None`)
+                    let mut calls =
+                        get_function_calls_in_expression(context, mir_calls, typeck, exp);
+                    if is_fallible_call(context, typeck, exp) {
+                        mark_handled(&mut calls, exp);
+                    }
+                    res.extend(calls);
+                }
                 _ => {
-                    res.extend(get_function_calls_in_expression(context, exp));
+                    res.extend(get_function_calls_in_expression(
+                        context, mir_calls, typeck, exp,
+                    ));
                 }
             }
             for arm in arms {
-                res.extend(get_function_calls_in_expression(context, arm.body));
+                res.extend(get_function_calls_in_expression(
+                    context, mir_calls, typeck, arm.body,
+                ));
                 if let Some(guard) = arm.guard {
-                    res.extend(get_function_calls_in_expression(context, guard));
+                    res.extend(get_function_calls_in_expression(
+                        context, mir_calls, typeck, guard,
+                    ));
                 }
-                res.extend(get_function_calls_in_pattern(context, arm.pat));
+                res.extend(get_function_calls_in_pattern(
+                    context, mir_calls, typeck, arm.pat,
+                ));
             }
         }
         ExprKind::Closure(closure) => {
@@ -234,79 +1442,146 @@ fn get_function_calls_in_expression(
                 closure.def_id.to_def_id(),
                 context.local_def_id_to_hir_id(closure.def_id),
             );
-            res.push((node_kind, expr.hir_id, false, false));
+            // Closures run by a panic boundary (e.g. `catch_unwind(|| f())`) get an edge, to show where their panics are caught
+            let add_edge = is_in_panic_boundary(context, expr.hir_id);
+            res.push(FunctionCall::new(
+                node_kind,
+                CallId::Hir(expr.hir_id),
+                add_edge,
+            ));
         }
         ExprKind::ConstBlock(block) => {
-            let node = context.hir_node(block.hir_id);
-            res.extend(get_function_calls_in_block(
-                context,
-                node.expect_block(),
-                false,
+            res.extend(get_function_calls_in_const_body(
+                context, mir_calls, block.body,
             ));
         }
         ExprKind::Array(args) | ExprKind::Tup(args) => {
             for exp in args {
-                res.extend(get_function_calls_in_expression(context, exp));
+                res.extend(get_function_calls_in_expression(
+                    context, mir_calls, typeck, exp,
+                ));
             }
         }
         ExprKind::Binary(_op, a, b) => {
-            res.extend(get_function_calls_in_expression(context, a));
-            res.extend(get_function_calls_in_expression(context, b));
+            res.extend(get_function_calls_in_expression(
+                context, mir_calls, typeck, a,
+            ));
+            res.extend(get_function_calls_in_expression(
+                context, mir_calls, typeck, b,
+            ));
+            res.extend(get_overloaded_operator_call(context, typeck, expr));
         }
         ExprKind::Unary(_op, exp) => {
-            res.extend(get_function_calls_in_expression(context, exp));
+            res.extend(get_function_calls_in_expression(
+                context, mir_calls, typeck, exp,
+            ));
+            res.extend(get_overloaded_operator_call(context, typeck, expr));
         }
         ExprKind::Lit(_lit) => {
             // No function calls here
         }
         ExprKind::Cast(exp, _ty) | ExprKind::Type(exp, _ty) => {
-            res.extend(get_function_calls_in_expression(context, exp));
+            res.extend(get_function_calls_in_expression(
+                context, mir_calls, typeck, exp,
+            ));
         }
         ExprKind::DropTemps(exp) | ExprKind::Become(exp) => {
-            res.extend(get_function_calls_in_expression(context, exp));
+            res.extend(get_function_calls_in_expression(
+                context, mir_calls, typeck, exp,
+            ));
         }
         ExprKind::Let(exp) => {
-            res.extend(get_function_calls_in_expression(context, exp.init));
+            // Destructuring the result of a call (e.g. `if let Err(e) = f()`) handles its error (or `None`)
+            let mut calls = get_function_calls_in_expression(context, mir_calls, typeck, exp.init);
+            if is_fallible_call(context, typeck, exp.init) {
+                mark_handled(&mut calls, exp.init);
+            }
+            res.extend(calls);
         }
         ExprKind::If(a, b, c) => {
-            res.extend(get_function_calls_in_expression(context, a));
-            res.extend(get_function_calls_in_expression(context, b));
+            res.extend(get_function_calls_in_expression(
+                context, mir_calls, typeck, a,
+            ));
+            res.extend(get_function_calls_in_expression(
+                context, mir_calls, typeck, b,
+            ));
             if let Some(exp) = c {
-                res.extend(get_function_calls_in_expression(context, exp));
+                res.extend(get_function_calls_in_expression(
+                    context, mir_calls, typeck, exp,
+                ));
             }
         }
         ExprKind::Loop(block, _lbl, _src, _span) => {
-            res.extend(get_function_calls_in_block(context, block, false));
+            res.extend(get_function_calls_in_block(
+                context, mir_calls, typeck, block, false,
+            ));
         }
         ExprKind::Block(block, _lbl) => {
-            res.extend(get_function_calls_in_block(context, block, false));
+            let mut calls = get_function_calls_in_block(context, mir_calls, typeck, block, false);
+            if let BlockCheckMode::UnsafeBlock(UnsafeSource::UserProvided) = block.rules {
+                for call in &mut calls {
+                    call.is_unsafe_call = true;
+                }
+            }
+            res.extend(calls);
         }
         ExprKind::Assign(a, b, _span) => {
-            res.extend(get_function_calls_in_expression(context, a));
-            res.extend(get_function_calls_in_expression(context, b));
+            res.extend(get_function_calls_in_expression(
+                context, mir_calls, typeck, a,
+            ));
+            res.extend(get_function_calls_in_expression(
+                context, mir_calls, typeck, b,
+            ));
         }
         ExprKind::AssignOp(_op, a, b) => {
-            res.extend(get_function_calls_in_expression(context, a));
-            res.extend(get_function_calls_in_expression(context, b));
+            res.extend(get_function_calls_in_expression(
+                context, mir_calls, typeck, a,
+            ));
+            res.extend(get_function_calls_in_expression(
+                context, mir_calls, typeck, b,
+            ));
+            res.extend(get_overloaded_operator_call(context, typeck, expr));
         }
         ExprKind::Field(exp, _ident) => {
-            res.extend(get_function_calls_in_expression(context, exp));
+            res.extend(get_function_calls_in_expression(
+                context, mir_calls, typeck, exp,
+            ));
         }
         ExprKind::Index(a, b, _span) => {
-            res.extend(get_function_calls_in_expression(context, a));
-            res.extend(get_function_calls_in_expression(context, b));
+            res.extend(get_function_calls_in_expression(
+                context, mir_calls, typeck, a,
+            ));
+            res.extend(get_function_calls_in_expression(
+                context, mir_calls, typeck, b,
+            ));
+            res.extend(get_overloaded_operator_call(context, typeck, expr));
         }
         ExprKind::Path(path) => {
-            if let Some((node_kind, add_edge)) = get_node_kind_from_path(context, path) {
-                res.push((node_kind, expr.hir_id, add_edge, false));
+            if let Some((node_kind, add_edge)) =
+                get_node_kind_from_path(context, typeck, path, expr.hir_id)
+            {
+                res.push(FunctionCall::new(
+                    node_kind,
+                    CallId::Hir(expr.hir_id),
+                    add_edge,
+                ));
             }
+            res.extend(get_function_calls_in_generic_args(
+                context,
+                mir_calls,
+                get_path_segments(&path),
+            ));
         }
         ExprKind::AddrOf(_borrow, _mut, exp) => {
-            res.extend(get_function_calls_in_expression(context, exp));
+            res.extend(get_function_calls_in_expression(
+                context, mir_calls, typeck, exp,
+            ));
         }
         ExprKind::Break(_dest, opt) => {
             if let Some(exp) = opt {
-                res.extend(get_function_calls_in_expression(context, exp));
+                res.extend(get_function_calls_in_expression(
+                    context, mir_calls, typeck, exp,
+                ));
             }
         }
         ExprKind::Continue(_dest) => {
@@ -314,8 +1589,9 @@ fn get_function_calls_in_expression(
         }
         ExprKind::Ret(opt) => {
             if let Some(exp) = opt {
-                for (kind, id, add_edge, _) in get_function_calls_in_expression(context, exp) {
-                    res.push((kind, id, add_edge, true));
+                for mut call in get_function_calls_in_expression(context, mir_calls, typeck, exp) {
+                    call.propagates = call.can_propagate();
+                    res.push(call);
                 }
             }
         }
@@ -327,17 +1603,32 @@ fn get_function_calls_in_expression(
         }
         ExprKind::Struct(_path, args, base) => {
             for exp in args {
-                res.extend(get_function_calls_in_expression(context, exp.expr));
+                res.extend(get_function_calls_in_expression(
+                    context, mir_calls, typeck, exp.expr,
+                ));
             }
             if let Some(exp) = base {
-                res.extend(get_function_calls_in_expression(context, exp));
+                res.extend(get_function_calls_in_expression(
+                    context, mir_calls, typeck, exp,
+                ));
             }
         }
-        ExprKind::Repeat(exp, _len) => {
-            res.extend(get_function_calls_in_expression(context, exp));
+        ExprKind::Repeat(exp, len) => {
+            res.extend(get_function_calls_in_expression(
+                context, mir_calls, typeck, exp,
+            ));
+            if let ArrayLen::Body(anon_const) = len {
+                res.extend(get_function_calls_in_const_body(
+                    context,
+                    mir_calls,
+                    anon_const.body,
+                ));
+            }
         }
         ExprKind::Yield(exp, _src) => {
-            res.extend(get_function_calls_in_expression(context, exp));
+            res.extend(get_function_calls_in_expression(
+                context, mir_calls, typeck, exp,
+            ));
         }
         ExprKind::Err(_err) => {
             // No function calls here
@@ -348,11 +1639,13 @@ fn get_function_calls_in_expression(
 }
 
 /// Retrieve a vec of all function calls made from within a pattern (although I think it can never contain one).
-fn get_function_calls_in_pattern(
-    context: TyCtxt,
+fn get_function_calls_in_pattern<'tcx>(
+    context: TyCtxt<'tcx>,
+    mir_calls: &MirCallMaps<'tcx>,
+    typeck: Option<&'tcx TypeckResults<'tcx>>,
     pat: &Pat,
-) -> Vec<(CallNodeKind, HirId, bool, bool)> {
-    let mut res: Vec<(CallNodeKind, HirId, bool, bool)> = vec![];
+) -> Vec<FunctionCall> {
+    let mut res: Vec<FunctionCall> = vec![];
 
     match pat.kind {
         PatKind::Wild | PatKind::Never => {
@@ -360,22 +1653,24 @@ fn get_function_calls_in_pattern(
         }
         PatKind::Binding(_mode, _hir_id, _ident, opt_pat) => {
             if let Some(p) = opt_pat {
-                res.extend(get_function_calls_in_pattern(context, p));
+                res.extend(get_function_calls_in_pattern(context, mir_calls, typeck, p));
             }
         }
         PatKind::Struct(_path, fields, _other) => {
             for field in fields {
-                res.extend(get_function_calls_in_pattern(context, field.pat));
+                res.extend(get_function_calls_in_pattern(
+                    context, mir_calls, typeck, field.pat,
+                ));
             }
         }
         PatKind::TupleStruct(_path, pats, _pos) => {
             for p in pats {
-                res.extend(get_function_calls_in_pattern(context, p));
+                res.extend(get_function_calls_in_pattern(context, mir_calls, typeck, p));
             }
         }
         PatKind::Or(pats) => {
             for p in pats {
-                res.extend(get_function_calls_in_pattern(context, p));
+                res.extend(get_function_calls_in_pattern(context, mir_calls, typeck, p));
             }
         }
         PatKind::Path(_path) => {
@@ -383,35 +1678,41 @@ fn get_function_calls_in_pattern(
         }
         PatKind::Tuple(pats, _pos) => {
             for p in pats {
-                res.extend(get_function_calls_in_pattern(context, p));
+                res.extend(get_function_calls_in_pattern(context, mir_calls, typeck, p));
             }
         }
         PatKind::Box(p) | PatKind::Deref(p) => {
-            res.extend(get_function_calls_in_pattern(context, p));
+            res.extend(get_function_calls_in_pattern(context, mir_calls, typeck, p));
         }
         PatKind::Ref(p, _mut) => {
-            res.extend(get_function_calls_in_pattern(context, p));
+            res.extend(get_function_calls_in_pattern(context, mir_calls, typeck, p));
         }
         PatKind::Lit(exp) => {
-            res.extend(get_function_calls_in_expression(context, exp));
+            res.extend(get_function_calls_in_expression(
+                context, mir_calls, typeck, exp,
+            ));
         }
         PatKind::Range(a, b, _end) => {
             if let Some(exp) = a {
-                res.extend(get_function_calls_in_expression(context, exp));
+                res.extend(get_function_calls_in_expression(
+                    context, mir_calls, typeck, exp,
+                ));
             }
             if let Some(exp) = b {
-                res.extend(get_function_calls_in_expression(context, exp));
+                res.extend(get_function_calls_in_expression(
+                    context, mir_calls, typeck, exp,
+                ));
             }
         }
         PatKind::Slice(pats1, opt_pat, pats2) => {
             for p in pats1 {
-                res.extend(get_function_calls_in_pattern(context, p));
+                res.extend(get_function_calls_in_pattern(context, mir_calls, typeck, p));
             }
             if let Some(p) = opt_pat {
-                res.extend(get_function_calls_in_pattern(context, p));
+                res.extend(get_function_calls_in_pattern(context, mir_calls, typeck, p));
             }
             for p in pats2 {
-                res.extend(get_function_calls_in_pattern(context, p));
+                res.extend(get_function_calls_in_pattern(context, mir_calls, typeck, p));
             }
         }
         PatKind::Err(_err) => {
@@ -423,31 +1724,573 @@ fn get_function_calls_in_pattern(
 }
 
 /// Get the node kind from a given `QPath`.
-fn get_node_kind_from_path(context: TyCtxt, qpath: QPath) -> Option<(CallNodeKind, bool)> {
-    match qpath {
-        QPath::Resolved(_ty, path) => {
-            if let Res::Def(kind, id) = path.res {
-                let add_edge: bool = matches!(
-                    kind,
-                    DefKind::Fn | DefKind::Ctor(_, _) | DefKind::AssocFn | DefKind::Closure
+fn get_node_kind_from_path<'tcx>(
+    context: TyCtxt<'tcx>,
+    typeck: Option<&'tcx TypeckResults<'tcx>>,
+    qpath: QPath,
+    path_id: HirId,
+) -> Option<(CallNodeKind, bool)> {
+    let res = match qpath {
+        QPath::Resolved(_ty, path) => path.res,
+        // Paths relative to a type (e.g. `Type::method` or `<Type as Trait>::method`) are resolved during type checking
+        QPath::TypeRelative(_ty, _segment) => typeck?.qpath_res(&qpath, path_id),
+        QPath::LangItem(_, _) => return None,
+    };
+
+    let Res::Def(kind, id) = res else {
+        return None;
+    };
+    let add_edge: bool = matches!(
+        kind,
+        DefKind::Fn | DefKind::Ctor(_, _) | DefKind::AssocFn | DefKind::Closure
+    );
+    Some((get_node_kind_from_def_id(context, id), add_edge))
+}
+
+/// Get the call to the local function an overloaded operator (e.g. `a + b` or `x[i]`) resolves to.
+/// Operators implemented outside of the analyzed crate (e.g. in std) are left out to avoid noise.
+fn get_overloaded_operator_call<'tcx>(
+    context: TyCtxt<'tcx>,
+    typeck: Option<&'tcx TypeckResults<'tcx>>,
+    expr: &Expr,
+) -> Option<FunctionCall> {
+    let typeck = typeck?;
+    let def_id = typeck.type_dependent_def_id(expr.hir_id)?;
+    let args = typeck.node_args_opt(expr.hir_id)?;
+
+    let local_id = resolve_local_fn(context, expr.hir_id, def_id, args)?;
+
+    let mut call = FunctionCall::new(
+        get_node_kind_from_def_id(context, local_id),
+        CallId::Hir(expr.hir_id),
+        true,
+    );
+    call.implicit = Some(ImplicitCall::Operator);
+    Some(call)
+}
+
+/// Retrieve the calls to local `Deref` implementations made implicitly when auto-dereferencing an expression.
+/// Implementations outside of the analyzed crate (e.g. for `Box`) are left out to avoid noise.
+fn get_overloaded_deref_calls<'tcx>(
+    context: TyCtxt<'tcx>,
+    typeck: Option<&'tcx TypeckResults<'tcx>>,
+    expr: &Expr,
+) -> Vec<FunctionCall> {
+    let mut res: Vec<FunctionCall> = vec![];
+
+    let Some(typeck) = typeck else {
+        return res;
+    };
+    let Some(mut source) = typeck.expr_ty_opt(expr) else {
+        return res;
+    };
+
+    for adjustment in typeck.expr_adjustments(expr) {
+        if let Adjust::Deref(Some(deref)) = adjustment.kind {
+            if let ty::FnDef(def_id, args) = *deref.method_call(context, source).kind() {
+                if let Some(local_id) = resolve_local_fn(context, expr.hir_id, def_id, args) {
+                    let mut call = FunctionCall::new(
+                        get_node_kind_from_def_id(context, local_id),
+                        CallId::Hir(expr.hir_id),
+                        true,
+                    );
+                    call.implicit = Some(ImplicitCall::Deref);
+                    res.push(call);
+                }
+            }
+        }
+        source = adjustment.target;
+    }
+
+    res
+}
+
+/// Retrieve the implicit calls to local `Drop` implementations made when values are dropped within a function.
+/// Every implementation is only called once per function, and implementations outside of the analyzed crate are left out.
+fn get_drop_calls(context: TyCtxt, def_id: DefId) -> Vec<FunctionCall> {
+    let mut res: Vec<FunctionCall> = vec![];
+
+    let Some(mir) = get_mir(context, def_id) else {
+        return res;
+    };
+
+    let mut found: Vec<DefId> = vec![];
+    for block in mir.basic_blocks.iter() {
+        if let Some(terminator) = &block.terminator {
+            if let TerminatorKind::Drop { place, .. } = &terminator.kind {
+                // The dropped value also drops all the values it owns
+                let mut destructors: Vec<DefId> = vec![];
+                let mut visited: Vec<ty::Ty> = vec![];
+                get_local_destructors(
+                    context,
+                    place.ty(&mir.local_decls, context).ty,
+                    &mut destructors,
+                    &mut visited,
                 );
-                return Some((get_node_kind_from_def_id(context, id), add_edge));
+
+                for destructor in destructors {
+                    if found.contains(&destructor) {
+                        continue;
+                    }
+                    found.push(destructor);
+
+                    // There is no call expression, so use the closest node we know of
+                    let call_id = mir.source_scopes[terminator.source_info.scope]
+                        .local_data
+                        .as_ref()
+                        .assert_crate_local()
+                        .lint_root;
+
+                    let mut call = FunctionCall::new(
+                        get_node_kind_from_def_id(context, destructor),
+                        CallId::Hir(call_id),
+                        true,
+                    );
+                    call.implicit = Some(ImplicitCall::Drop);
+                    res.push(call);
+                }
+            }
+        }
+    }
+
+    res
+}
+
+/// Collect the local `Drop` implementations that are run when a value of the given type is dropped.
+fn get_local_destructors<'tcx>(
+    context: TyCtxt<'tcx>,
+    ty: ty::Ty<'tcx>,
+    destructors: &mut Vec<DefId>,
+    visited: &mut Vec<ty::Ty<'tcx>>,
+) {
+    if visited.contains(&ty) {
+        return;
+    }
+    visited.push(ty);
+
+    match ty.kind() {
+        ty::Adt(adt, args) => {
+            if let Some(destructor) = adt.destructor(context) {
+                if destructor.did.is_local() && !destructors.contains(&destructor.did) {
+                    destructors.push(destructor.did);
+                }
+            }
+
+            // Fields of local types are dropped as well
+            if adt.did().is_local() {
+                for field in adt.all_fields() {
+                    get_local_destructors(context, field.ty(context, args), destructors, visited);
+                }
+            }
+
+            // Values owned by generic containers (e.g. Vec<T>, Box<T>)
+            for arg in args.types() {
+                get_local_destructors(context, arg, destructors, visited);
             }
         }
-        QPath::TypeRelative(ty, _segment) => {
-            if let TyKind::Path(path) = ty.kind {
-                return get_node_kind_from_path(context, path);
+        ty::Tuple(types) => {
+            for ty in types.iter() {
+                get_local_destructors(context, ty, destructors, visited);
             }
         }
-        QPath::LangItem(_, _) => {}
+        ty::Array(ty, _) | ty::Slice(ty) => {
+            get_local_destructors(context, *ty, destructors, visited);
+        }
+        _ => {}
+    }
+}
+
+/// Get the call to the `From` implementation the try operator uses to convert the error it propagates, if the error types differ.
+/// The scrutinee is the `Try::branch` call, and the arms are those of the desugared try operator.
+fn get_from_conversion_call<'tcx>(
+    context: TyCtxt<'tcx>,
+    typeck: Option<&'tcx TypeckResults<'tcx>>,
+    scrutinee: &Expr,
+    arms: &[Arm],
+) -> Option<FunctionCall> {
+    let ExprKind::Call(_func, [inner]) = scrutinee.kind else {
+        return None;
+    };
+
+    // The residual is passed to `FromResidual::from_residual`, which returns the type of the function (or try block)
+    let from_residual = arms.iter().find_map(|arm| match arm.body.kind {
+        ExprKind::Ret(Some(exp)) | ExprKind::Break(_, Some(exp)) => Some(exp),
+        _ => None,
+    })?;
+
+    let typeck = typeck?;
+    let from_ty = get_result_error_type(context, typeck.expr_ty_opt(inner)?)?;
+    let to_ty = get_result_error_type(context, typeck.expr_ty_opt(from_residual)?)?;
+    if from_ty == to_ty {
+        return None;
+    }
+
+    // Resolve `<to_ty as From<from_ty>>::from`
+    let from_fn = context.get_diagnostic_item(sym::from_fn)?;
+    let args = context.mk_args(&[to_ty.into(), from_ty.into()]);
+    let param_env = context.param_env(scrutinee.hir_id.owner.to_def_id());
+    let instance = Instance::resolve(context, param_env, from_fn, args).ok()??;
+
+    let mut call = FunctionCall::new(
+        get_node_kind_from_def_id(context, instance.def_id()),
+        CallId::Hir(from_residual.hir_id),
+        true,
+    );
+    call.implicit = Some(ImplicitCall::From);
+    // Boxing the error as a `dyn Error` hides its concrete type, so remember it
+    if is_type_erased(context, to_ty) {
+        call.origin_ty = Some(format!("{from_ty}"));
+    }
+    Some(call)
+}
+
+/// Get the error type of a `Result` type.
+fn get_result_error_type<'tcx>(context: TyCtxt<'tcx>, ty: ty::Ty<'tcx>) -> Option<ty::Ty<'tcx>> {
+    match ty.kind() {
+        ty::Adt(adt, args) if context.is_diagnostic_item(sym::Result, adt.did()) => {
+            Some(args.type_at(1))
+        }
+        _ => None,
+    }
+}
+
+/// The methods of `Result` and `Option` that deal with the error (or `None`) themselves, instead of forwarding it.
+const HANDLING_COMBINATORS: [&str; 6] = [
+    "unwrap_or",
+    "unwrap_or_else",
+    "unwrap_or_default",
+    "or_else",
+    "map_or",
+    "map_or_else",
+];
+
+/// The methods of `Result` and `Option` that panic instead of returning the error (or value).
+const UNWRAP_METHODS: [&str; 4] = ["unwrap", "expect", "unwrap_err", "expect_err"];
+
+/// Whether an expression is a call returning a `Result`, an `Option`, or another type implementing `Try`.
+fn is_fallible_call<'tcx>(
+    context: TyCtxt<'tcx>,
+    typeck: Option<&'tcx TypeckResults<'tcx>>,
+    expr: &Expr,
+) -> bool {
+    matches!(expr.kind, ExprKind::Call(..) | ExprKind::MethodCall(..))
+        && typeck
+            .and_then(|typeck| typeck.expr_ty_opt(expr))
+            .is_some_and(|ty| {
+                ty.ty_adt_def().is_some_and(|adt| {
+                    context.is_diagnostic_item(sym::Result, adt.did())
+                        || context.is_diagnostic_item(sym::Option, adt.did())
+                }) || get_try_residual(context, ty, Some(expr.hir_id.owner.to_def_id())).is_some()
+            })
+}
+
+/// Get the contents of a string literal expression (e.g. the message passed to `expect`).
+fn get_string_literal(expr: &Expr) -> Option<String> {
+    if let ExprKind::Lit(lit) = expr.kind {
+        if let LitKind::Str(symbol, _style) = lit.node {
+            return Some(symbol.to_string());
+        }
     }
 
     None
 }
 
+/// Mark the call made by the given expression as handled, its other (nested) calls are left as is.
+fn mark_handled(calls: &mut [FunctionCall], expr: &Expr) {
+    for call in calls {
+        if call.call_id == CallId::Hir(expr.hir_id) {
+            call.handling = Handling::Handled;
+        }
+    }
+}
+
+/// Mark the call made by the given expression as discarded if it returns a `Result`, its other (nested) calls are left as is.
+/// Awaited calls (e.g. `f().await`) are marked as well.
+fn mark_discarded<'tcx>(
+    context: TyCtxt<'tcx>,
+    typeck: Option<&'tcx TypeckResults<'tcx>>,
+    calls: &mut [FunctionCall],
+    expr: &Expr,
+) {
+    let call_expr = match expr.kind {
+        ExprKind::Match(scrutinee, _arms, MatchSource::AwaitDesugar) => {
+            // The awaited expression is passed to `IntoFuture::into_future`
+            let ExprKind::Call(_func, [inner]) = scrutinee.kind else {
+                return;
+            };
+            inner
+        }
+        ExprKind::Call(..) | ExprKind::MethodCall(..) => expr,
+        _ => return,
+    };
+
+    // The awaited value (or call result) should be a `Result`
+    let is_result = typeck
+        .and_then(|typeck| typeck.expr_ty_opt(expr))
+        .is_some_and(|ty| get_result_error_type(context, ty).is_some());
+    if !is_result {
+        return;
+    }
+
+    for call in calls {
+        if call.call_id == CallId::Hir(call_expr.hir_id) && call.handling == Handling::Unknown {
+            call.handling = Handling::Discarded;
+        }
+    }
+}
+
+/// The error handling libraries whose error context and error creating macros are recognized.
+pub const ERROR_LIBRARIES: [&str; 2] = ["anyhow", "eyre"];
+
+/// The methods of error handling libraries that add context to an error.
+const CONTEXT_METHODS: [&str; 4] = ["context", "with_context", "wrap_err", "wrap_err_with"];
+
+/// Get the message of the context added by a method call like `f().context("..")` or `f().with_context(|| "..")`.
+/// Returns `None` if the message is not a string literal.
+fn get_context_message<'tcx>(
+    context: TyCtxt<'tcx>,
+    typeck: Option<&'tcx TypeckResults<'tcx>>,
+    expr: &Expr,
+    path: &PathSegment,
+    args: &[Expr],
+) -> Option<String> {
+    if !CONTEXT_METHODS.contains(&path.ident.as_str()) {
+        return None;
+    }
+
+    let def_id = typeck?.type_dependent_def_id(expr.hir_id)?;
+    if !ERROR_LIBRARIES.contains(&context.crate_name(def_id.krate).as_str()) {
+        return None;
+    }
+
+    let message = args.first()?;
+    if let ExprKind::Closure(closure) = message.kind {
+        // The message is returned by the closure, e.g. `|| "message"`
+        let mut value = context.hir().body(closure.body).value;
+        if let ExprKind::Block(block, _label) = value.kind {
+            value = block.expr?;
+        }
+        get_string_literal(value)
+    } else {
+        get_string_literal(message)
+    }
+}
+
+/// Whether a method call is a call to `Result::map_err`.
+fn is_map_err_call<'tcx>(
+    context: TyCtxt<'tcx>,
+    typeck: Option<&'tcx TypeckResults<'tcx>>,
+    expr: &Expr,
+    path: &PathSegment,
+) -> bool {
+    let ExprKind::MethodCall(_path, receiver, _args, _span) = expr.kind else {
+        return false;
+    };
+
+    path.ident.name.as_str() == "map_err"
+        && typeck
+            .and_then(|typeck| typeck.expr_ty_adjusted_opt(receiver))
+            .and_then(|ty| ty.ty_adt_def())
+            .is_some_and(|adt| context.is_diagnostic_item(sym::Result, adt.did()))
+}
+
+/// Whether a method call is a call of an `Iterator` method on an iterator, consuming it (e.g. `collect` in `iter.map(f).collect()`).
+fn is_consuming_iterator_call<'tcx>(
+    context: TyCtxt<'tcx>,
+    typeck: Option<&'tcx TypeckResults<'tcx>>,
+    expr: &Expr,
+) -> bool {
+    let ExprKind::MethodCall(_path, receiver, _args, _span) = expr.kind else {
+        return false;
+    };
+
+    typeck
+        .and_then(|typeck| typeck.type_dependent_def_id(expr.hir_id))
+        .and_then(|def_id| context.trait_of_item(def_id))
+        .is_some_and(|trait_id| context.is_diagnostic_item(sym::Iterator, trait_id))
+        && !is_fallible_call(context, typeck, receiver)
+}
+
+/// Whether the result of a call is an iterator consumed by the `Iterator` method called on it.
+/// The errors its type mentions (e.g. of the closure of `map`) are not returned by the call itself, but by the method or the closure.
+pub(crate) fn is_consumed_iterator(context: TyCtxt, call_id: CallId) -> bool {
+    let CallId::Hir(hir_id) = call_id else {
+        return false;
+    };
+    let rustc_hir::Node::Expr(parent) = context.parent_hir_node(hir_id) else {
+        return false;
+    };
+
+    matches!(parent.kind, ExprKind::MethodCall(_path, receiver, _args, _span) if receiver.hir_id == hir_id)
+        && is_consuming_iterator_call(context, get_typeck_results(context, hir_id), parent)
+}
+
+/// Whether a method call is a call to `Iterator::collect` into a `Result`.
+fn is_result_collect<'tcx>(
+    context: TyCtxt<'tcx>,
+    typeck: Option<&'tcx TypeckResults<'tcx>>,
+    expr: &Expr,
+) -> bool {
+    let Some(typeck) = typeck else {
+        return false;
+    };
+
+    typeck
+        .type_dependent_def_id(expr.hir_id)
+        .is_some_and(|def_id| context.is_diagnostic_item(sym::iterator_collect_fn, def_id))
+        && typeck
+            .expr_ty_opt(expr)
+            .is_some_and(|ty| get_result_error_type(context, ty).is_some())
+}
+
+/// Get the ids of the closures and functions returning a `Result` that are passed to the adapters of an iterator, e.g. `f` in `iter.filter(p).map(f)`.
+fn get_fallible_iterator_fns<'tcx>(
+    context: TyCtxt<'tcx>,
+    typeck: Option<&'tcx TypeckResults<'tcx>>,
+    mut expr: &Expr,
+) -> Vec<HirId> {
+    let mut res = vec![];
+
+    while let ExprKind::MethodCall(_path, receiver, args, _span) = expr.kind {
+        for arg in args {
+            if !matches!(arg.kind, ExprKind::Closure(_) | ExprKind::Path(_)) {
+                continue;
+            }
+            let Some(ty) = typeck.and_then(|typeck| typeck.expr_ty_opt(arg)) else {
+                continue;
+            };
+            let output = match ty.kind() {
+                ty::Closure(_def_id, args) => args.as_closure().sig().output().skip_binder(),
+                ty::FnDef(def_id, args) => context
+                    .fn_sig(def_id)
+                    .instantiate(context, args)
+                    .output()
+                    .skip_binder(),
+                _ => continue,
+            };
+            if get_result_error_type(context, output).is_some() {
+                res.push(arg.hir_id);
+            }
+        }
+        expr = receiver;
+    }
+
+    res
+}
+
+/// Get the conversion between a `Result` and an `Option` made by a method call like `f().ok()` or `f().ok_or(e)`, if any.
+fn get_failure_conversion<'tcx>(
+    context: TyCtxt<'tcx>,
+    typeck: Option<&'tcx TypeckResults<'tcx>>,
+    expr: &Expr,
+    path: &PathSegment,
+) -> Option<FailureConversion> {
+    let ExprKind::MethodCall(_path, receiver, _args, _span) = expr.kind else {
+        return None;
+    };
+    if !is_fallible_call(context, typeck, receiver) {
+        return None;
+    }
+
+    let adt = typeck?.expr_ty_adjusted_opt(receiver)?.ty_adt_def()?;
+    if context.is_diagnostic_item(sym::Result, adt.did()) {
+        match path.ident.as_str() {
+            "ok" => Some(FailureConversion::Ok),
+            "err" => Some(FailureConversion::Err),
+            _ => None,
+        }
+    } else if context.is_diagnostic_item(sym::Option, adt.did()) {
+        matches!(path.ident.as_str(), "ok_or" | "ok_or_else").then_some(FailureConversion::OkOr)
+    } else {
+        None
+    }
+}
+
+/// Get the call to the local `into_iter` or `next` implementation used by a desugared for loop.
+/// Implementations outside of the analyzed crate (e.g. for `Vec`) are left out, like for operators.
+fn get_iterator_protocol_call<'tcx>(
+    context: TyCtxt<'tcx>,
+    typeck: Option<&'tcx TypeckResults<'tcx>>,
+    expr: &Expr,
+) -> Option<FunctionCall> {
+    let ExprKind::Call(func, _args) = expr.kind else {
+        return None;
+    };
+
+    let ty = typeck?.expr_ty_opt(func)?;
+    let ty::FnDef(def_id, args) = ty.kind() else {
+        return None;
+    };
+
+    let local_id = resolve_local_fn(context, expr.hir_id, *def_id, args)?;
+
+    let mut call = FunctionCall::new(
+        get_node_kind_from_def_id(context, local_id),
+        CallId::Hir(expr.hir_id),
+        true,
+    );
+    call.implicit = Some(ImplicitCall::Iterator);
+    Some(call)
+}
+
+/// Resolve a (trait) function called from the body containing `call_id` to the function that is actually called.
+/// Returns `None` if it cannot be resolved, or if the called function is not local.
+fn resolve_local_fn<'tcx>(
+    context: TyCtxt<'tcx>,
+    call_id: HirId,
+    def_id: DefId,
+    args: GenericArgsRef<'tcx>,
+) -> Option<DefId> {
+    let param_env = context.param_env(call_id.owner.to_def_id());
+    let instance = Instance::resolve(context, param_env, def_id, args).ok()??;
+
+    Some(instance.def_id()).filter(|def_id| def_id.is_local())
+}
+
+/// Resolve a called trait method to its local implementation (e.g. `Trait::method` to `<Type as Trait>::method`), so the implementation is explored.
+/// Other functions, and trait methods that cannot be resolved (e.g. called on a generic type) or are implemented elsewhere, are returned as is.
+fn resolve_trait_fn<'tcx>(
+    context: TyCtxt<'tcx>,
+    call_id: HirId,
+    def_id: DefId,
+    args: Option<GenericArgsRef<'tcx>>,
+) -> DefId {
+    let Some(args) = args.filter(|_args| context.trait_of_item(def_id).is_some()) else {
+        return def_id;
+    };
+
+    resolve_local_fn(context, call_id, def_id, args).unwrap_or(def_id)
+}
+
+/// Get the node kind of a callee using its type, which also works for callees that do not name a function themselves (e.g. a variable or field holding a function).
+/// Returns `None` if the type of the callee is unknown or not callable.
+fn get_node_kind_from_callee_type<'tcx>(
+    context: TyCtxt<'tcx>,
+    typeck: Option<&'tcx TypeckResults<'tcx>>,
+    callee: &Expr,
+) -> Option<CallNodeKind> {
+    let ty = typeck?.expr_ty_opt(callee)?;
+
+    // Closures can be called through references
+    match ty.peel_refs().kind() {
+        ty::FnDef(def_id, args) => {
+            let def_id = resolve_trait_fn(context, callee.hir_id, *def_id, Some(args));
+            Some(get_node_kind_from_def_id(context, def_id))
+        }
+        ty::FnPtr(sig) => Some(CallNodeKind::indirect(format!("{}", sig.skip_binder()))),
+        ty::Closure(_def_id, args) => {
+            let sig = context.signature_unclosure(args.as_closure().sig(), Unsafety::Normal);
+            Some(CallNodeKind::indirect(format!("{}", sig.skip_binder())))
+        }
+        _ => None,
+    }
+}
+
 /// Get the `CallNodeKind` from a given `DefId`.
 fn get_node_kind_from_def_id(context: TyCtxt, def_id: DefId) -> CallNodeKind {
-    if let Some(local_id) = def_id.as_local() {
+    if context.is_foreign_item(def_id) {
+        CallNodeKind::foreign_fn(def_id)
+    } else if let Some(local_id) = def_id.as_local() {
         let hir_id = context.local_def_id_to_hir_id(local_id);
         CallNodeKind::local_fn(def_id, hir_id)
     } else {
@@ -455,25 +2298,239 @@ fn get_node_kind_from_def_id(context: TyCtxt, def_id: DefId) -> CallNodeKind {
     }
 }
 
-/// Get the `DefId` of the called function using the `HirId` of the call.
-pub fn get_call_def_id(context: TyCtxt, call_id: HirId) -> Option<DefId> {
-    if !context.is_mir_available(call_id.owner.to_def_id()) {
+/// Get the `DefId` of the called function using the `HirId` of the call, by matching its span against the calls in the MIR.
+/// Only used as a last resort, as calls can be reshaped or optimized away in MIR, and macro-expanded calls can share spans.
+fn get_call_def_id<'tcx>(
+    context: TyCtxt<'tcx>,
+    mir_calls: &MirCallMaps<'tcx>,
+    call_id: HirId,
+) -> Option<DefId> {
+    let caller_id = context.hir().enclosing_body_owner(call_id).to_def_id();
+    let span = context.hir_node(call_id).expect_expr().span;
+    mir_calls
+        .get(context, caller_id, span)
+        .map(|call| call.callee)
+}
+
+/// Get the type check results of the body an expression is in (e.g. a function, or a const argument within it).
+/// Returns `None` if the expression is not within a body, or if type checking the body failed (e.g. due to compilation errors).
+pub fn get_typeck_results(context: TyCtxt, hir_id: HirId) -> Option<&TypeckResults> {
+    let (body_owner, _body_id) = context
+        .hir()
+        .parent_iter(hir_id)
+        .find_map(|(_id, node)| node.associated_body())?;
+    get_body_typeck_results(context, body_owner)
+}
+
+/// Get the type check results of a body by its owner.
+/// Returns `None` if type checking the body failed (e.g. due to compilation errors).
+fn get_body_typeck_results(context: TyCtxt, body_owner: LocalDefId) -> Option<&TypeckResults> {
+    if !context.has_typeck_results(body_owner) {
         return None;
     }
 
-    let mir = context.optimized_mir(call_id.owner.to_def_id());
+    let typeck = context.typeck(body_owner);
+    typeck.tainted_by_errors.is_none().then_some(typeck)
+}
 
-    for block in mir.basic_blocks.iter() {
-        if let Some(terminator) = &block.terminator {
-            if let TerminatorKind::Call { func, fn_span, .. } = &terminator.kind {
-                if context.hir_node(call_id).expect_expr().span.hi() == fn_span.hi() {
-                    if let Some((def_id, _)) = func.const_fn_def() {
-                        return Some(def_id);
-                    }
+/// Get the span of a call: that of its call expression, or that of the call in the MIR of a followed dependency.
+pub fn get_call_span(context: TyCtxt, call_id: CallId) -> Span {
+    match call_id {
+        CallId::Hir(hir_id) => context.hir().span(hir_id),
+        CallId::Mir(def_id, block) => get_mir(context, def_id).map_or(DUMMY_SP, |mir| {
+            mir.basic_blocks[block].terminator().source_info.span
+        }),
+    }
+}
+
+/// Get the span a call is reported at: that of the call, or for a call expanded from a local macro (e.g. a `macro_rules!`),
+/// that of the invocation of the macro, as the call is written in the definition of the macro rather than where it is made.
+pub fn get_call_site_span(context: TyCtxt, call_id: CallId) -> Span {
+    let span = get_call_span(context, call_id);
+    let from_local_macro = span.macro_backtrace().any(|expn_data| {
+        matches!(expn_data.kind, ExpnKind::Macro(_kind, _name))
+            && expn_data
+                .macro_def_id
+                .is_some_and(|def_id| def_id.is_local())
+    });
+
+    if from_local_macro {
+        span.source_callsite()
+    } else {
+        span
+    }
+}
+
+/// Get the MIR of a body, using the MIR for compile-time evaluation for const items (e.g. const initializers).
+/// Returns `None` if no MIR is available, or if the body has errors, as building its MIR could make the compiler crash.
+///
+/// This is the optimized MIR rather than `mir_built`, as building the optimized MIR of a body (e.g. to find its drops) steals
+/// the built MIR, after which reading it makes the compiler panic. The package is compiled using `-Zmir-opt-level=0`,
+/// so no calls of its bodies are inlined or optimized away.
+pub fn get_mir(context: TyCtxt, def_id: DefId) -> Option<&Body> {
+    if !context.is_mir_available(def_id) {
+        return None;
+    }
+    if let Some(local_id) = def_id.as_local() {
+        if context.has_typeck_results(local_id)
+            && context.typeck(local_id).tainted_by_errors.is_some()
+        {
+            return None;
+        }
+    }
+
+    let mir = context.instance_mir(InstanceDef::Item(def_id));
+    mir.tainted_by_errors.is_none().then_some(mir)
+}
+
+/// These build the fixture crates in `test/` and run the compiler on them, so they only run using `cargo test --features integration`.
+#[cfg(all(test, feature = "integration"))]
+mod tests {
+    use super::*;
+    use crate::driver;
+    use crate::timings::Timings;
+    use std::path::PathBuf;
+    use std::time::{Duration, Instant};
+
+    /// Get the manifest of the fixture crate `test/<fixture>`.
+    fn fixture_manifest(fixture: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("test")
+            .join(fixture)
+            .join("Cargo.toml")
+    }
+
+    /// Create the call graph of the entry point of a crate `runs` times, returning the last graph and how long creating each took.
+    /// The first creation runs the queries of the compiler for the bodies it walks (e.g. type checking them), the others reuse their results.
+    fn time_call_graph(context: TyCtxt, runs: usize) -> (CallGraph, Vec<Duration>) {
+        let (entry_fn, _entry_type) = context.entry_fn(()).unwrap();
+        let options = AnalysisOptions::default();
+        let mut durations = vec![];
+        let mut graph = None;
+        for _run in 0..runs {
+            let mir_calls = MirCallMaps::default();
+            let start = Instant::now();
+            graph = Some(create_call_graph_from_root(
+                context,
+                &options,
+                &mir_calls,
+                entry_fn.expect_local(),
+                &mut Progress::new(true),
+            ));
+            durations.push(start.elapsed());
+        }
+        (graph.unwrap(), durations)
+    }
+
+    #[test]
+    #[ignore = "times walking the bodies of test/large_function, run using --ignored on a release build"]
+    fn walking_a_large_function_looks_up_its_type_check_results_once() {
+        let (graph, durations) =
+            driver::with_type_context(&fixture_manifest("large_function"), |context| {
+                time_call_graph(context, 12)
+            })
+            .unwrap();
+
+        // `run` makes five calls per repetition and returns `Ok`, each walked using the type check results of `run`
+        let run = (0..graph.nodes.len())
+            .find(|node| graph.label(*node) == "run")
+            .unwrap();
+        assert_eq!(graph.get_outgoing_edges(run).len(), 5001);
+
+        let (first, walks) = durations.split_first().unwrap();
+        let mut walks = walks.to_vec();
+        walks.sort_unstable();
+        let median = walks[walks.len() / 2];
+        eprintln!(
+            "Created the call graph of test/large_function in {first:.2?}, and in {median:.2?} (median, {:.2?} to {:.2?}) once its queries were cached",
+            walks[0],
+            walks[walks.len() - 1]
+        );
+        eprintln!(
+            "Walking took {:.2?} per call",
+            median / graph.get_outgoing_edges(run).len() as u32
+        );
+        // Walking the bodies is cheap next to type checking them, as their type check results are looked up once per body
+        assert!(
+            median * 10 < *first,
+            "{median:.2?} is not much less than {first:.2?}"
+        );
+    }
+
+    /// Analyze a crate from the given entry `runs` times, returning its number of calls and the shortest time
+    /// building the call graph and extracting the chains took, so the queries the first run needs are left out.
+    fn time_analysis(context: TyCtxt, entry: &str, runs: usize) -> (usize, Duration, Duration) {
+        let options = AnalysisOptions {
+            entry: Some(entry.to_owned()),
+            quiet: true,
+            ..AnalysisOptions::default()
+        };
+        let (mut calls, mut construction, mut extraction) = (0, Duration::MAX, Duration::MAX);
+        for _run in 0..runs {
+            let mut timings = Timings::default();
+            let mut call_graph =
+                super::super::build_call_graph(context, &options, &mut timings).unwrap();
+            super::super::extract_chains(&mut call_graph, &options, &mut timings);
+            calls = call_graph.edges.len();
+            for stage in timings.stages() {
+                match stage.name {
+                    "graph construction" => construction = construction.min(stage.duration),
+                    "chain extraction" => extraction = extraction.min(stage.duration),
+                    _ => {}
                 }
             }
         }
+        (calls, construction, extraction)
     }
 
-    None
+    #[test]
+    fn each_body_of_a_wide_fan_out_is_walked_once() {
+        let manifest = fixture_manifest("wide_fanout");
+        let (graph, explored, timed) = driver::with_type_context(&manifest, |context| {
+            let (entry_fn, _entry_type) = context.entry_fn(()).unwrap();
+            let hir_id = context.local_def_id_to_hir_id(entry_fn.expect_local());
+            let options = AnalysisOptions::default();
+            let mir_calls = MirCallMaps::default();
+            let mut progress = Progress::new(true);
+            let mut graph = CallGraph::new(String::from("wide_fanout"));
+            let mut builder = GraphBuilder::new(context, &options, &mir_calls, &mut progress);
+            let node = add_fn_node(
+                context,
+                &mut graph,
+                "main",
+                CallNodeKind::local_fn(entry_fn, hir_id),
+            );
+            builder.explore_function(&mut graph, node, entry_fn, hir_id);
+            let explored = builder.explored.len();
+
+            // A tree of 1111 functions, and one of its subtrees of 111 functions
+            let timed =
+                ["tree::call", "tree::m0::call"].map(|entry| time_analysis(context, entry, 3));
+            (graph, explored, timed)
+        })
+        .unwrap();
+
+        // The tree, `main`, `shared`, `validate` and the constructor of `FanError` are each explored once
+        let local_fns = (0..graph.nodes.len())
+            .filter(|node| matches!(graph.nodes[*node].kind, CallNodeKind::LocalFn(..)))
+            .count();
+        assert_eq!(local_fns, 1115);
+        assert_eq!(explored, local_fns);
+        // `shared` is called by all 1000 leaves, but its own calls (`validate`, `?` and `Ok`) are only added once
+        let shared = (0..graph.nodes.len())
+            .find(|node| graph.label(*node) == "shared")
+            .unwrap();
+        assert_eq!(graph.get_incoming_edges(shared).len(), 1000);
+        assert_eq!(graph.get_outgoing_edges(shared).len(), 3);
+
+        // Ten times the calls should take about ten times as long, a quadratic walk would take about a hundred
+        let [(calls, construction, extraction), (sub_calls, sub_construction, sub_extraction)] =
+            timed;
+        eprintln!(
+            "Analyzed {calls} calls in {construction:.2?} + {extraction:.2?}, {sub_calls} calls in {sub_construction:.2?} + {sub_extraction:.2?}"
+        );
+        let ratio = calls as u32 / sub_calls as u32;
+        assert!(construction < sub_construction * ratio * 3);
+        assert!(extraction < sub_extraction * ratio * 3);
+    }
 }
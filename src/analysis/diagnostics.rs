@@ -0,0 +1,80 @@
+use super::calls_to_chains::get_chain_path;
+use super::create_graph::get_call_span;
+use crate::graph::{CallEdge, CallGraph, CallId, CallNodeKind, ChainEnd, ChainGraph};
+use rustc_middle::ty::TyCtxt;
+
+/// Emit the chains losing their error as compiler diagnostics, at the calls where they end.
+/// Unwrapped errors propagated through more than `min_depth` calls are warnings, discarded errors are notes.
+/// Each diagnostic notes where the error is propagated along the chain, down to where it originates.
+pub fn emit_diagnostics(
+    context: TyCtxt,
+    graph: &CallGraph,
+    chain_graph: &ChainGraph,
+    min_depth: usize,
+) {
+    // Several chains can end at the same call (e.g. through both `Try::branch` and the called function), the longest path is reported
+    let mut ends: Vec<(CallId, ChainEnd, Vec<&CallEdge>)> = vec![];
+    for chain in &chain_graph.chains {
+        if chain.suppressed
+            || !matches!(chain.termination, ChainEnd::Unwrapped | ChainEnd::Discarded)
+        {
+            continue;
+        }
+
+        let path = get_chain_path(graph, chain);
+        let end_id = path[0].call_id;
+        match ends.iter_mut().find(|(call_id, ..)| *call_id == end_id) {
+            Some((_call_id, _termination, longest)) => {
+                if longest.len() < path.len() {
+                    *longest = path;
+                }
+            }
+            None => ends.push((end_id, chain.termination, path)),
+        }
+    }
+
+    for (call_id, termination, path) in ends {
+        let origin = path.last().expect("Chains end in a call");
+        let ty = graph.type_label(path[0]).unwrap_or(String::from("unknown"));
+        let span = get_call_span(context, call_id);
+        let mut diagnostic = if termination == ChainEnd::Unwrapped {
+            if path.len() <= min_depth {
+                continue;
+            }
+            context.dcx().struct_span_warn(
+                span,
+                format!(
+                    "the `{ty}` error of `{}` is unwrapped here, after propagating through {} calls",
+                    graph.label(origin.to),
+                    path.len()
+                ),
+            )
+        } else {
+            context.dcx().struct_span_note(
+                span,
+                format!(
+                    "the `{ty}` error of `{}` is discarded here, after propagating through {} calls",
+                    graph.label(origin.to),
+                    path.len()
+                ),
+            )
+        };
+
+        // Calls in followed dependencies have no span in the analyzed crate
+        for call in &path[1..] {
+            if !matches!(graph.nodes[call.from].kind, CallNodeKind::LocalFn(..)) {
+                continue;
+            }
+            diagnostic.span_note(
+                get_call_span(context, call.call_id),
+                format!(
+                    "`{}` propagates the `{}` error of `{}` here",
+                    graph.label(call.from),
+                    graph.type_label(call).unwrap_or(String::from("unknown")),
+                    graph.label(call.to)
+                ),
+            );
+        }
+        diagnostic.emit();
+    }
+}
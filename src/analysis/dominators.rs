@@ -0,0 +1,208 @@
+use crate::graph::CallGraph;
+use std::collections::HashMap;
+
+/// Mark the functions all errors of an origin pass through as choke points, and list them per origin on the graph.
+/// These are the dominators of the end of the error flow from each origin, i.e. good places to add context to errors or measure them.
+/// Errors flow from a called function to its caller, and further through the callers that propagate them.
+pub fn attach_choke_points(graph: &mut CallGraph) {
+    let origins: Vec<usize> = graph
+        .nodes
+        .iter()
+        .filter(|node| node.error_origin)
+        .map(|node| node.id())
+        .collect();
+
+    for origin in origins {
+        // Errors never leaving the function they originate in do not flow anywhere
+        if !graph
+            .get_incoming_edges(origin)
+            .iter()
+            .any(|edge| edge.is_error)
+        {
+            continue;
+        }
+
+        let dominators = get_exit_dominators(graph, origin);
+        for node in &dominators {
+            graph.nodes[*node].choke_point = true;
+        }
+        let labels = dominators
+            .iter()
+            .map(|node| graph.label(*node).to_owned())
+            .collect();
+        graph
+            .choke_points
+            .push((graph.label(origin).to_owned(), labels));
+    }
+}
+
+/// Get the functions all errors of an origin pass through, from the origin up, excluding the origin itself.
+/// The error flow graph has an extra exit node where errors end, i.e. where they are not propagated further.
+/// Its dominators are found using the iterative algorithm by Cooper, Harvey and Kennedy.
+fn get_exit_dominators(graph: &CallGraph, origin: usize) -> Vec<usize> {
+    let exit = graph.nodes.len();
+
+    // Number the nodes the errors flow to in reverse postorder, so each node comes before the nodes it flows to (except in loops)
+    let mut postorder = vec![];
+    let mut visited = vec![false; exit + 1];
+    visit_flow(graph, origin, &mut visited, &mut postorder);
+    let order: HashMap<usize, usize> = postorder
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, node)| (*node, i))
+        .collect();
+
+    let mut predecessors: HashMap<usize, Vec<usize>> = HashMap::new();
+    for node in &postorder {
+        for next in get_flow_successors(graph, *node) {
+            predecessors.entry(next).or_default().push(*node);
+        }
+    }
+
+    let mut idoms: HashMap<usize, usize> = HashMap::from([(origin, origin)]);
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for node in postorder.iter().rev().skip(1) {
+            let mut idom: Option<usize> = None;
+            for pred in &predecessors[node] {
+                if !idoms.contains_key(pred) {
+                    continue;
+                }
+                idom = Some(match idom {
+                    Some(idom) => intersect(&idoms, &order, *pred, idom),
+                    None => *pred,
+                });
+            }
+            if let Some(idom) = idom {
+                if idoms.get(node) != Some(&idom) {
+                    idoms.insert(*node, idom);
+                    changed = true;
+                }
+            }
+        }
+    }
+
+    // Walk up the dominator tree from the exit to the origin
+    let mut res = vec![];
+    let mut node = idoms[&exit];
+    while node != origin {
+        res.push(node);
+        node = idoms[&node];
+    }
+    res.reverse();
+    res
+}
+
+/// Find the common dominator of two nodes, by walking up the dominator tree from the node that comes later.
+fn intersect(
+    idoms: &HashMap<usize, usize>,
+    order: &HashMap<usize, usize>,
+    mut a: usize,
+    mut b: usize,
+) -> usize {
+    while a != b {
+        while order[&a] > order[&b] {
+            a = idoms[&a];
+        }
+        while order[&b] > order[&a] {
+            b = idoms[&b];
+        }
+    }
+    a
+}
+
+/// Visit the nodes the errors of a node flow to depth-first, adding them to the postorder once all they flow to are visited.
+fn visit_flow(graph: &CallGraph, node: usize, visited: &mut [bool], postorder: &mut Vec<usize>) {
+    visited[node] = true;
+    for next in get_flow_successors(graph, node) {
+        if !visited[next] {
+            visit_flow(graph, next, visited, postorder);
+        }
+    }
+    postorder.push(node);
+}
+
+/// Get the nodes the errors of a node flow to: the callers propagating them, and the exit if a caller deals with them or there is no caller.
+fn get_flow_successors(graph: &CallGraph, node: usize) -> Vec<usize> {
+    let exit = graph.nodes.len();
+    if node == exit {
+        return vec![];
+    }
+
+    let mut res = vec![];
+    for edge in graph.get_incoming_edges(node) {
+        if !edge.is_error {
+            continue;
+        }
+        let next = if edge.propagates { edge.from } else { exit };
+        if !res.contains(&next) {
+            res.push(next);
+        }
+    }
+    if res.is_empty() {
+        res.push(exit);
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::tests::{add_call, graph};
+
+    #[test]
+    fn diamond_is_dominated_by_where_its_branches_join() {
+        // The errors of `read` reach `run` through both `load` and `parse`, which `main` handles
+        let mut graph = graph(&["main", "run", "load", "parse", "read"]);
+        add_call(&mut graph, "main", "run", true, false);
+        add_call(&mut graph, "run", "load", true, true);
+        add_call(&mut graph, "run", "parse", true, true);
+        add_call(&mut graph, "load", "read", true, true);
+        add_call(&mut graph, "parse", "read", true, true);
+        graph.nodes[4].error_origin = true;
+
+        attach_choke_points(&mut graph);
+        let labels: Vec<&str> = (0..graph.nodes.len())
+            .filter(|node| graph.nodes[*node].choke_point)
+            .map(|node| graph.label(node))
+            .collect();
+        assert_eq!(labels, ["run"]);
+        assert_eq!(
+            graph.choke_points,
+            [(String::from("read"), vec![String::from("run")])]
+        );
+    }
+
+    #[test]
+    fn chain_of_callers_is_dominated_by_each_of_them() {
+        let mut graph = graph(&["main", "run", "load", "read"]);
+        add_call(&mut graph, "main", "run", true, true);
+        add_call(&mut graph, "run", "load", true, true);
+        add_call(&mut graph, "load", "read", true, true);
+        assert_eq!(get_exit_dominators(&graph, 3), [2, 1, 0]);
+    }
+
+    #[test]
+    fn errors_ending_in_two_roots_have_no_choke_point() {
+        // Neither `cli` nor `server` is called, so the errors of `read` end in either of them
+        let mut graph = graph(&["cli", "server", "load", "read"]);
+        add_call(&mut graph, "cli", "load", true, true);
+        add_call(&mut graph, "server", "load", true, true);
+        add_call(&mut graph, "load", "read", true, true);
+        assert_eq!(get_exit_dominators(&graph, 3), [2]);
+
+        add_call(&mut graph, "server", "read", true, true);
+        assert!(get_exit_dominators(&graph, 3).is_empty());
+    }
+
+    #[test]
+    fn origins_whose_errors_do_not_leave_them_are_skipped() {
+        let mut graph = graph(&["main", "read"]);
+        add_call(&mut graph, "main", "read", false, false);
+        graph.nodes[1].error_origin = true;
+        attach_choke_points(&mut graph);
+        assert!(graph.choke_points.is_empty());
+    }
+}
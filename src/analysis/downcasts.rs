@@ -0,0 +1,95 @@
+use super::create_graph::get_mir;
+use super::types::{get_error_family, is_type_erased};
+use crate::graph::{CallGraph, CallNodeKind, Downcast};
+use rustc_hir::def_id::DefId;
+use rustc_middle::mir::visit::Visitor;
+use rustc_middle::mir::{Location, Terminator, TerminatorKind};
+use rustc_middle::ty::{GenericArgsRef, Ty, TyCtxt, TyKind};
+use rustc_span::sym;
+
+/// The methods recovering the concrete type of a type-erased error.
+const DOWNCAST_METHODS: [&str; 3] = ["downcast", "downcast_ref", "downcast_mut"];
+
+/// Attach the types each function downcasts type-erased errors to, e.g. `SqlxError` for `error.downcast_ref::<SqlxError>()`.
+/// Downcasting a `dyn Error` (boxed or borrowed) or an `anyhow::Error` or `eyre::Report` regains the concrete type of the error.
+pub fn attach_downcasts(context: TyCtxt, graph: &mut CallGraph) {
+    for node in &mut graph.nodes {
+        let def_id = match node.kind {
+            CallNodeKind::LocalFn(def_id, _hir_id) => def_id,
+            // Functions of followed dependencies are explored as well
+            CallNodeKind::NonLocalFn(def_id) if node.cluster.is_some() => def_id,
+            _ => continue,
+        };
+
+        node.downcasts = get_downcasts(context, def_id);
+    }
+}
+
+/// Get the types a function downcasts errors to, according to its MIR.
+/// The closures defined in the function are not included, as they have nodes of their own.
+fn get_downcasts(context: TyCtxt, def_id: DefId) -> Vec<Downcast> {
+    let Some(mir) = get_mir(context, def_id) else {
+        return vec![];
+    };
+
+    let mut finder = DowncastFinder {
+        context,
+        downcasts: vec![],
+    };
+    finder.visit_body(mir);
+    finder.downcasts
+}
+
+/// Finds the calls downcasting errors in the MIR of a function.
+struct DowncastFinder<'tcx> {
+    context: TyCtxt<'tcx>,
+    downcasts: Vec<Downcast>,
+}
+
+impl<'tcx> DowncastFinder<'tcx> {
+    /// Get the downcast made by a call, if it calls a downcast method of a type-erased error.
+    fn get_downcast(&self, called_id: DefId, args: GenericArgsRef<'tcx>) -> Option<Downcast> {
+        if !DOWNCAST_METHODS.contains(&self.context.item_name(called_id).as_str()) {
+            return None;
+        }
+        let impl_id = self.context.impl_of_method(called_id)?;
+        let self_ty = self.context.type_of(impl_id).instantiate_identity();
+        let family = get_error_family(self.context, self_ty);
+        if family.is_none() && !is_dyn_error(self.context, self_ty) {
+            return None;
+        }
+
+        // The type downcast to is the generic argument of the method, e.g. `T` of `downcast_ref::<T>`
+        let target = args.types().last()?;
+        Some(Downcast {
+            ty: format!("{target}"),
+            family,
+        })
+    }
+}
+
+impl<'tcx> Visitor<'tcx> for DowncastFinder<'tcx> {
+    fn visit_terminator(&mut self, terminator: &Terminator<'tcx>, location: Location) {
+        if let TerminatorKind::Call { func, .. } = &terminator.kind {
+            if let Some((called_id, args)) = func.const_fn_def() {
+                if let Some(downcast) = self.get_downcast(called_id, args) {
+                    if !self.downcasts.contains(&downcast) {
+                        self.downcasts.push(downcast);
+                    }
+                }
+            }
+        }
+
+        self.super_terminator(terminator, location);
+    }
+}
+
+/// Whether a type is a `dyn Error`, boxed or not (e.g. `Box<dyn Error + Send + Sync>`).
+fn is_dyn_error(context: TyCtxt, ty: Ty) -> bool {
+    match ty.kind() {
+        TyKind::Dynamic(predicates, _region, _kind) => predicates
+            .principal_def_id()
+            .is_some_and(|def_id| context.is_diagnostic_item(sym::Error, def_id)),
+        _ => is_type_erased(context, ty),
+    }
+}
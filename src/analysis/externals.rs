@@ -0,0 +1,57 @@
+use crate::graph::{CallGraph, CallNodeKind};
+use rustc_hir::def_id::CrateNum;
+use rustc_middle::ty::TyCtxt;
+
+/// The number of functions of a dependency whose calls carry no errors, past which these are aggregated, if not configured.
+const DEFAULT_EXTERNAL_THRESHOLD: usize = 25;
+
+/// Aggregate the functions of each dependency whose calls carry no errors into a single node per dependency,
+/// once there are more than `threshold` of them (25 by default), as these only add noise to the graph of a large dependency tree.
+/// Functions with a call carrying an error are kept, so the chains still go through the individual fallible functions,
+/// as are the functions that can panic and the functions of followed dependencies.
+pub fn aggregate_external_nodes(context: TyCtxt, graph: &mut CallGraph, threshold: Option<usize>) {
+    let threshold = threshold.unwrap_or(DEFAULT_EXTERNAL_THRESHOLD);
+
+    // The functions without errors per dependency, in the order the dependencies are first called
+    let mut per_crate: Vec<(CrateNum, Vec<usize>)> = vec![];
+    for node in &graph.nodes {
+        let CallNodeKind::NonLocalFn(def_id) = node.kind else {
+            continue;
+        };
+        if node.cluster.is_some() || node.panics || node.may_panic {
+            continue;
+        }
+        let carries_errors = graph
+            .get_incoming_edges(node.id())
+            .into_iter()
+            .chain(graph.get_outgoing_edges(node.id()))
+            .any(|edge| edge.is_error);
+        if carries_errors {
+            continue;
+        }
+
+        match per_crate
+            .iter_mut()
+            .find(|(krate, _nodes)| *krate == def_id.krate)
+        {
+            Some((_krate, nodes)) => nodes.push(node.id()),
+            None => per_crate.push((def_id.krate, vec![node.id()])),
+        }
+    }
+
+    let groups: Vec<(Vec<usize>, String, CallNodeKind)> = per_crate
+        .into_iter()
+        .filter(|(_krate, nodes)| nodes.len() > threshold)
+        .map(|(krate, nodes)| {
+            let name = context.crate_name(krate).to_string();
+            let label = format!("{name} ({} functions)", nodes.len());
+            (nodes, label, CallNodeKind::Aggregated(name))
+        })
+        .collect();
+
+    graph.aggregated_fns = groups
+        .iter()
+        .map(|(nodes, _label, _kind)| nodes.len())
+        .sum();
+    graph.merge_nodes(groups);
+}
@@ -0,0 +1,85 @@
+use super::suppression::path_patterns;
+use super::AnalysisOptions;
+use regex::Regex;
+use rustc_hir::def::DefKind;
+use rustc_hir::def_id::{DefId, LOCAL_CRATE};
+use rustc_middle::ty::TyCtxt;
+
+/// What to do with a function when it is about to be added to the call graph.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FilterDecision {
+    /// Add the function to the graph as usual.
+    Keep,
+    /// Leave the function out of the graph, but explore its body, attributing its calls to its caller.
+    SkipNode,
+    /// Leave the function out of the graph, without exploring its body, so nothing it calls is added through it.
+    SkipSubtree,
+}
+
+/// A filter consulted before a function is added to the call graph, e.g. to leave out generated or vendored code.
+pub type NodeFilter = fn(&TyCtxt, DefId) -> FilterDecision;
+
+/// The filter of the functions added to the call graph: the built-in one driven by the module paths to include and exclude
+/// (`--include-path` and `--exclude-path`), combined with the filter of the user of the library, if any.
+/// A path matches a function if it matches the path of the function or of one of its enclosing modules, where `*` matches any part.
+pub(super) struct GraphFilter {
+    include: Vec<Regex>,
+    exclude: Vec<Regex>,
+    /// The filter of the user of the library, if any.
+    node_filter: Option<NodeFilter>,
+}
+
+impl GraphFilter {
+    /// Create the filter of the given analysis options.
+    pub(super) fn new(context: TyCtxt, options: &AnalysisOptions) -> Self {
+        let crate_name = context.crate_name(LOCAL_CRATE).to_ident_string();
+        GraphFilter {
+            include: path_patterns(&crate_name, &options.include_paths),
+            exclude: path_patterns(&crate_name, &options.exclude_paths),
+            node_filter: options.node_filter,
+        }
+    }
+
+    /// Decide what to do with a function. The strictest decision of the paths and the filter of the user is taken.
+    /// Functions matching an included path are kept even within an excluded module (e.g. excluding `generated` but including `generated::api::*`),
+    /// and if there are included paths, the local functions matching none of them are left out, while their calls are still explored.
+    pub(super) fn decide(&self, context: TyCtxt, def_id: DefId) -> FilterDecision {
+        let custom = self
+            .node_filter
+            .map_or(FilterDecision::Keep, |filter| filter(&context, def_id));
+        if self.include.is_empty() && self.exclude.is_empty() {
+            return custom;
+        }
+
+        let paths = get_module_paths(context, def_id);
+        let matches = |patterns: &[Regex]| {
+            paths
+                .iter()
+                .any(|path| patterns.iter().any(|pattern| pattern.is_match(path)))
+        };
+        let decision = if matches(&self.include) {
+            FilterDecision::Keep
+        } else if matches(&self.exclude) {
+            FilterDecision::SkipSubtree
+        } else if !self.include.is_empty() && def_id.is_local() {
+            FilterDecision::SkipNode
+        } else {
+            FilterDecision::Keep
+        };
+
+        decision.max(custom)
+    }
+}
+
+/// Get the path of a function, followed by the paths of the modules it is in, innermost first (leaving out the root of the local crate).
+fn get_module_paths(context: TyCtxt, def_id: DefId) -> Vec<String> {
+    let mut paths = vec![context.def_path_str(def_id)];
+    let mut parent = context.opt_parent(def_id);
+    while let Some(module) = parent {
+        if context.def_kind(module) == DefKind::Mod && module != LOCAL_CRATE.as_def_id() {
+            paths.push(context.def_path_str(module));
+        }
+        parent = context.opt_parent(module);
+    }
+    paths
+}
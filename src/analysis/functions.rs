@@ -0,0 +1,247 @@
+use crate::graph::{CallEdge, CallGraph, CallNodeKind, FailureKind, Handling};
+use crate::stats::FunctionSummary;
+use std::collections::HashMap;
+
+/// Get how each local function deals with the errors of the calls it makes, sorted by the number of errors it does not handle.
+/// Functions without fallible calls are left out.
+pub fn get_function_summaries(graph: &CallGraph) -> Vec<FunctionSummary> {
+    let mut depths: HashMap<usize, usize> = HashMap::new();
+    let mut res: Vec<FunctionSummary> = vec![];
+
+    for node in &graph.nodes {
+        if !matches!(node.kind, CallNodeKind::LocalFn(..)) {
+            continue;
+        }
+
+        let calls: Vec<_> = graph
+            .get_outgoing_edges(node.id())
+            .into_iter()
+            .filter(|call| call.is_error)
+            .collect();
+        if calls.is_empty() {
+            continue;
+        }
+        let count = |handling: Handling| {
+            calls
+                .iter()
+                .filter(|call| call.handling == handling)
+                .count()
+        };
+
+        res.push(FunctionSummary {
+            function: graph.full_path(node.id()),
+            fallible_calls: calls.len(),
+            propagated: count(Handling::Propagated),
+            handled: count(Handling::Handled),
+            logged: count(Handling::LoggedAndDropped),
+            exited: count(Handling::ProcessExit),
+            unwrapped: count(Handling::Unwrapped),
+            discarded: count(Handling::Discarded),
+            returns_result: returns_result(graph, node.id()),
+            deepest_chain: get_downstream_depth(graph, node.id(), &mut depths),
+        });
+    }
+
+    res.sort_by(|a, b| {
+        b.unhandled()
+            .cmp(&a.unhandled())
+            .then(a.function.cmp(&b.function))
+    });
+    res
+}
+
+/// Whether a function returns a `Result`, i.e. its callers receive an error from it, or it propagates one itself.
+/// The entry point has no callers, so whether it returns a `Result` is known separately.
+fn returns_result(graph: &CallGraph, node: usize) -> bool {
+    let is_result_edge =
+        |call: &&CallEdge| call.is_error && call.failure_kind == FailureKind::Result;
+
+    (graph.entry_node == Some(node) && graph.entry_returns_result)
+        || graph.get_incoming_edges(node).iter().any(is_result_edge)
+        || graph
+            .get_outgoing_edges(node)
+            .iter()
+            .any(|call| is_result_edge(call) && call.propagates)
+}
+
+/// Get the number of chained calls of the longest error path below a function, i.e. how deep the errors it receives are propagated from.
+fn get_downstream_depth(
+    graph: &CallGraph,
+    node: usize,
+    depths: &mut HashMap<usize, usize>,
+) -> usize {
+    let mut path = vec![node];
+    graph
+        .get_outgoing_edges(node)
+        .into_iter()
+        .filter(|call| call.is_error)
+        .map(|call| {
+            if call.is_recursive || call.to == node {
+                1
+            } else {
+                get_propagated_depth(graph, call.to, &mut path, depths).0 + 1
+            }
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+/// Get the number of chained calls of the longest path of propagated errors below a function.
+/// Cycles are cut where they close, depths are remembered in `depths` once a function is fully explored.
+/// Also returns whether the path was cut short by a function on the current path.
+fn get_propagated_depth(
+    graph: &CallGraph,
+    node: usize,
+    path: &mut Vec<usize>,
+    depths: &mut HashMap<usize, usize>,
+) -> (usize, bool) {
+    if let Some(depth) = depths.get(&node) {
+        return (*depth, false);
+    }
+
+    let mut max_depth = 0;
+    let mut cut = false;
+
+    path.push(node);
+    for call in graph.get_outgoing_edges(node) {
+        if !call.is_error || !call.propagates {
+            continue;
+        }
+        max_depth = max_depth.max(1);
+
+        if call.is_recursive {
+            // Stop at the call closing the cycle
+            continue;
+        }
+        if path.contains(&call.to) {
+            cut = true;
+            continue;
+        }
+        let (depth, depth_cut) = get_propagated_depth(graph, call.to, path, depths);
+        max_depth = max_depth.max(depth + 1);
+        cut |= depth_cut;
+    }
+    path.pop();
+
+    // Depths cut short by the current path depend on where the function is reached, so these are not remembered
+    if !cut {
+        depths.insert(node, max_depth);
+    }
+    (max_depth, cut)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::tests::{add_call, graph};
+
+    /// Add a call of which the caller deals with the error in the given way.
+    fn add_handled_call(graph: &mut CallGraph, from: &str, to: &str, handling: Handling) -> usize {
+        let call = add_call(graph, from, to, true, handling == Handling::Propagated);
+        graph.edges[call].handling = handling;
+        call
+    }
+
+    /// Get the summary of the function with the given path.
+    fn summary<'a>(summaries: &'a [FunctionSummary], function: &str) -> &'a FunctionSummary {
+        summaries
+            .iter()
+            .find(|summary| summary.function == function)
+            .unwrap_or_else(|| panic!("No summary of {function}"))
+    }
+
+    #[test]
+    fn calls_are_counted_by_how_their_errors_are_dealt_with() {
+        let mut graph = graph(&["main", "run", "read"]);
+        let handlings = [
+            Handling::Propagated,
+            Handling::Handled,
+            Handling::Handled,
+            Handling::LoggedAndDropped,
+            Handling::ProcessExit,
+            Handling::Unwrapped,
+            Handling::Discarded,
+            Handling::Unknown,
+        ];
+        for handling in handlings {
+            add_handled_call(&mut graph, "run", "read", handling);
+        }
+        // Calls without an error are not fallible
+        add_call(&mut graph, "run", "read", false, false);
+        add_handled_call(&mut graph, "main", "run", Handling::Handled);
+
+        let summaries = get_function_summaries(&graph);
+        let run = summary(&summaries, "run");
+        assert_eq!(run.fallible_calls, handlings.len());
+        assert_eq!(run.propagated, 1);
+        assert_eq!(run.handled, 2);
+        assert_eq!(run.logged, 1);
+        assert_eq!(run.exited, 1);
+        assert_eq!(run.unwrapped, 1);
+        assert_eq!(run.discarded, 1);
+        assert_eq!(run.unhandled(), 2);
+        assert!(run.returns_result);
+    }
+
+    #[test]
+    fn functions_without_fallible_calls_are_left_out() {
+        let mut graph = graph(&["main", "run", "read"]);
+        add_call(&mut graph, "main", "run", false, false);
+        add_handled_call(&mut graph, "run", "read", Handling::Unwrapped);
+        // Functions of dependencies are not summarized
+        graph.nodes[1].kind = CallNodeKind::unresolved(String::from("run"));
+
+        assert!(get_function_summaries(&graph).is_empty());
+    }
+
+    #[test]
+    fn functions_are_sorted_by_their_unhandled_errors() {
+        let mut graph = graph(&["main", "load", "save", "read"]);
+        add_handled_call(&mut graph, "main", "load", Handling::Handled);
+        add_handled_call(&mut graph, "main", "save", Handling::Handled);
+        add_handled_call(&mut graph, "load", "read", Handling::Discarded);
+        add_handled_call(&mut graph, "save", "read", Handling::Unwrapped);
+        add_handled_call(&mut graph, "save", "read", Handling::Discarded);
+
+        let functions: Vec<String> = get_function_summaries(&graph)
+            .into_iter()
+            .map(|summary| summary.function)
+            .collect();
+        assert_eq!(functions, ["save", "load", "main"]);
+    }
+
+    #[test]
+    fn results_are_returned_to_callers_or_propagated() {
+        let mut graph = graph(&["main", "run", "load", "read", "parse"]);
+        add_handled_call(&mut graph, "main", "run", Handling::Unwrapped);
+        add_handled_call(&mut graph, "run", "load", Handling::Handled);
+        add_handled_call(&mut graph, "load", "read", Handling::Propagated);
+        let option = add_handled_call(&mut graph, "load", "parse", Handling::Handled);
+        graph.edges[option].failure_kind = FailureKind::Option;
+
+        let summaries = get_function_summaries(&graph);
+        assert!(!summary(&summaries, "main").returns_result);
+        assert!(summary(&summaries, "load").returns_result);
+
+        graph.entry_node = Some(0);
+        graph.entry_returns_result = true;
+        assert!(summary(&get_function_summaries(&graph), "main").returns_result);
+    }
+
+    #[test]
+    fn deepest_chain_follows_propagated_errors() {
+        let mut graph = graph(&["main", "run", "load", "read", "retry"]);
+        add_handled_call(&mut graph, "main", "run", Handling::Handled);
+        add_handled_call(&mut graph, "run", "load", Handling::Propagated);
+        add_handled_call(&mut graph, "load", "read", Handling::Propagated);
+        // `retry` and `load` propagate the errors of each other, which is cut where the cycle closes
+        add_handled_call(&mut graph, "load", "retry", Handling::Propagated);
+        add_handled_call(&mut graph, "retry", "load", Handling::Propagated);
+
+        let summaries = get_function_summaries(&graph);
+        assert_eq!(summary(&summaries, "main").deepest_chain, 4);
+        assert_eq!(summary(&summaries, "run").deepest_chain, 3);
+        assert_eq!(summary(&summaries, "load").deepest_chain, 2);
+        assert_eq!(summary(&summaries, "retry").deepest_chain, 2);
+    }
+}
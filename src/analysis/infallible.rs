@@ -0,0 +1,114 @@
+use crate::graph::{CallEdge, CallGraph, CallNodeKind};
+use rustc_hir::def::DefKind;
+use rustc_hir::def_id::DefId;
+use rustc_hir::{ExprKind, LangItem, Node};
+use rustc_middle::ty::{self, Ty, TyCtxt};
+use rustc_span::sym;
+
+/// Mark the local functions declared to return a `Result` that can never return an error, as candidates to return their value directly.
+/// A function can return an error if it constructs one itself, receives one as an argument,
+/// or propagates the error of a call that is not known to be infallible.
+/// Functions only propagating the errors of infallible functions are infallible as well, which is found using a fixpoint over the call graph.
+pub fn attach_infallibility(context: TyCtxt, graph: &mut CallGraph) {
+    // Start from every candidate being infallible, and drop those that turn out to have a source of errors until nothing changes
+    // Functions calling each other without any source of errors stay infallible this way
+    for node in &mut graph.nodes {
+        let CallNodeKind::LocalFn(def_id, _hir_id) = node.kind else {
+            continue;
+        };
+        node.infallible = !node.error_origin && is_infallible_candidate(context, def_id);
+    }
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for node in 0..graph.nodes.len() {
+            if !graph.nodes[node].infallible {
+                continue;
+            }
+            let has_error_source = graph
+                .get_outgoing_edges(node)
+                .into_iter()
+                .any(|call| is_error_source(context, graph, call));
+            if has_error_source {
+                graph.nodes[node].infallible = false;
+                changed = true;
+            }
+        }
+    }
+}
+
+/// Whether a function is declared to return a `Result`, and none of its arguments carries its error type.
+/// Closures and other bodies are left out, as their signature is inferred.
+fn is_infallible_candidate(context: TyCtxt, def_id: DefId) -> bool {
+    if !matches!(context.def_kind(def_id), DefKind::Fn | DefKind::AssocFn) {
+        return false;
+    }
+
+    let sig = context.fn_sig(def_id).instantiate_identity();
+    let sig = context.instantiate_bound_regions_with_erased(sig);
+    let Some(error_ty) = get_result_error_type(context, sig.output()) else {
+        return false;
+    };
+
+    // An error passed in can be passed on, e.g. `fn check(res: Result<(), E>) -> Result<(), E> { res }`
+    !sig.inputs()
+        .iter()
+        .any(|input| input.walk().any(|arg| arg.as_type() == Some(error_ty)))
+}
+
+/// Get the error type of a `Result` type, if it is one.
+fn get_result_error_type<'tcx>(context: TyCtxt<'tcx>, ty: Ty<'tcx>) -> Option<Ty<'tcx>> {
+    let ty::Adt(adt, args) = ty.kind() else {
+        return None;
+    };
+    if !context.is_diagnostic_item(sym::Result, adt.did()) {
+        return None;
+    }
+    args.types().nth(1)
+}
+
+/// Whether a call can pass an error on to the caller, i.e. it propagates an error that is not known to be impossible.
+fn is_error_source(context: TyCtxt, graph: &CallGraph, call: &CallEdge) -> bool {
+    if !call.is_error || !call.propagates {
+        return false;
+    }
+
+    match graph.nodes[call.to].kind {
+        CallNodeKind::LocalFn(..) => !graph.nodes[call.to].infallible,
+        CallNodeKind::NonLocalFn(def_id) => {
+            !is_ok_constructor(context, def_id) && !is_try_of_call(context, def_id, call)
+        }
+        _ => true,
+    }
+}
+
+/// Whether a function is the constructor of `Ok(..)`, which wraps a value rather than an error.
+fn is_ok_constructor(context: TyCtxt, def_id: DefId) -> bool {
+    matches!(context.def_kind(def_id), DefKind::Ctor(..))
+        && context.lang_items().result_ok_variant() == Some(context.parent(def_id))
+}
+
+/// Whether a call is the `Try::branch` of a `?` applied to a call, e.g. `load()?`.
+/// The error it propagates comes from that call, which has an edge of its own.
+fn is_try_of_call(context: TyCtxt, def_id: DefId, call: &CallEdge) -> bool {
+    let branch = context
+        .opt_associated_item(def_id)
+        .and_then(|item| item.trait_item_def_id)
+        .unwrap_or(def_id);
+    if context.lang_items().get(LangItem::TryTraitBranch) != Some(branch) {
+        return false;
+    }
+
+    let Some(Node::Expr(expr)) = call
+        .call_id
+        .hir_id()
+        .map(|call_id| context.hir_node(call_id))
+    else {
+        return false;
+    };
+    let ExprKind::Call(_func, [operand]) = expr.kind else {
+        return false;
+    };
+    matches!(operand.kind, ExprKind::Call(..) | ExprKind::MethodCall(..))
+}
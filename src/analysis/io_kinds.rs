@@ -0,0 +1,327 @@
+use super::create_graph::get_mir;
+use crate::graph::{CallEdge, CallGraph, CallNodeKind};
+use rustc_hir::def::DefKind;
+use rustc_hir::def_id::DefId;
+use rustc_hir::LangItem;
+use rustc_middle::mir::visit::Visitor;
+use rustc_middle::mir::{
+    AggregateKind, Body, Const, Local, Location, Operand, Rvalue, Statement, StatementKind,
+    Terminator, TerminatorKind,
+};
+use rustc_middle::ty::{Ty, TyCtxt};
+use rustc_span::sym;
+use std::collections::{BTreeSet, HashMap};
+
+/// The type of the errors whose kinds are determined.
+const IO_ERROR: &str = "std::io::Error";
+
+/// The type of the kinds of these errors.
+const IO_ERROR_KIND: &str = "std::io::ErrorKind";
+
+/// The kinds of `std::io::Error` the functions of std are documented to return (or commonly return), by path.
+/// Trait methods are listed by the path of the method of the trait, e.g. `std::io::Read::read_exact`.
+const STD_IO_KINDS: [(&str, &[&str]); 20] = [
+    ("std::fs::File::open", &["NotFound", "PermissionDenied"]),
+    ("std::fs::File::create", &["NotFound", "PermissionDenied"]),
+    ("std::fs::read", &["NotFound", "PermissionDenied"]),
+    (
+        "std::fs::read_to_string",
+        &["InvalidData", "NotFound", "PermissionDenied"],
+    ),
+    ("std::fs::write", &["NotFound", "PermissionDenied"]),
+    ("std::fs::metadata", &["NotFound", "PermissionDenied"]),
+    ("std::fs::read_dir", &["NotFound", "PermissionDenied"]),
+    (
+        "std::fs::create_dir",
+        &["AlreadyExists", "NotFound", "PermissionDenied"],
+    ),
+    ("std::fs::create_dir_all", &["PermissionDenied"]),
+    ("std::fs::remove_file", &["NotFound", "PermissionDenied"]),
+    ("std::fs::remove_dir", &["NotFound", "PermissionDenied"]),
+    ("std::fs::remove_dir_all", &["NotFound", "PermissionDenied"]),
+    ("std::fs::rename", &["NotFound", "PermissionDenied"]),
+    ("std::fs::copy", &["NotFound", "PermissionDenied"]),
+    ("std::fs::canonicalize", &["NotFound"]),
+    (
+        "std::net::TcpStream::connect",
+        &["ConnectionRefused", "TimedOut"],
+    ),
+    (
+        "std::net::TcpListener::bind",
+        &["AddrInUse", "AddrNotAvailable", "PermissionDenied"],
+    ),
+    ("std::io::Read::read_exact", &["UnexpectedEof"]),
+    ("std::io::Read::read_to_string", &["InvalidData"]),
+    ("std::io::Write::write_all", &["WriteZero"]),
+];
+
+/// The kinds of `std::io::Error` a function can return.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct KindSet {
+    kinds: BTreeSet<String>,
+    /// Whether some of the returned errors are of a kind that cannot be determined (e.g. `io::Error::last_os_error()`).
+    incomplete: bool,
+}
+
+/// Label the calls returning a `std::io::Error` with the kinds (`std::io::ErrorKind`) of the errors they can return, e.g. `NotFound, PermissionDenied`,
+/// or `io` if none can be determined. The kinds of the functions of std come from a table of their documented kinds,
+/// the kinds a local function constructs itself are found in its MIR (e.g. `io::Error::new(ErrorKind::InvalidInput, ..)`),
+/// and are combined with those of the calls it propagates. Since this is an approximation, an `…` marks sets that could not be fully determined.
+pub fn attach_io_error_kinds(context: TyCtxt, graph: &mut CallGraph) {
+    let is_io_call = |graph: &CallGraph, edge: &CallEdge| {
+        edge.is_error && graph.ty(edge) == Some(IO_ERROR) && !passes_on(context, graph, edge.to)
+    };
+
+    // The kinds each function returns by itself
+    let mut sets: HashMap<usize, KindSet> = HashMap::new();
+    for edge in &graph.edges {
+        if is_io_call(graph, edge) {
+            add_own_kinds(context, graph, &mut sets, edge.to);
+        }
+    }
+
+    // Add the kinds of the propagated calls, until nothing changes anymore
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for edge in &graph.edges {
+            if !is_io_call(graph, edge) || !edge.propagates {
+                continue;
+            }
+
+            add_own_kinds(context, graph, &mut sets, edge.from);
+            let called = sets[&edge.to].clone();
+            let caller = sets
+                .get_mut(&edge.from)
+                .expect("Kinds of caller not collected!");
+
+            let before = caller.clone();
+            caller.kinds.extend(called.kinds);
+            caller.incomplete |= called.incomplete;
+            changed |= *caller != before;
+        }
+    }
+
+    // Label the calls
+    for i in 0..graph.edges.len() {
+        if !is_io_call(graph, &graph.edges[i]) {
+            continue;
+        }
+
+        let set = &sets[&graph.edges[i].to];
+        let mut kinds: Vec<&str> = set.kinds.iter().map(String::as_str).collect();
+        if kinds.is_empty() {
+            kinds.push("io");
+        } else if set.incomplete {
+            kinds.push("…");
+        }
+        graph.edges[i].io_error_kinds = Some(kinds.join(", "));
+    }
+}
+
+/// Collect the kinds of the errors a function returns by itself, if not done already:
+/// the documented kinds of a function of std, or the kinds a local function constructs.
+fn add_own_kinds(
+    context: TyCtxt,
+    graph: &CallGraph,
+    sets: &mut HashMap<usize, KindSet>,
+    node: usize,
+) {
+    sets.entry(node)
+        .or_insert_with(|| match graph.nodes[node].kind {
+            CallNodeKind::LocalFn(def_id, _hir_id) => {
+                let mut collector = KindCollector {
+                    context,
+                    set: KindSet::default(),
+                    definitions: HashMap::new(),
+                };
+                collector.visit_fn(def_id);
+                collector.set
+            }
+            CallNodeKind::NonLocalFn(def_id) => match get_std_kinds(context, def_id) {
+                Some(kinds) => KindSet {
+                    kinds: kinds.iter().map(|kind| String::from(*kind)).collect(),
+                    incomplete: false,
+                },
+                None => KindSet {
+                    kinds: BTreeSet::new(),
+                    incomplete: true,
+                },
+            },
+            _ => KindSet {
+                kinds: BTreeSet::new(),
+                incomplete: true,
+            },
+        });
+}
+
+/// Whether a function only passes on an error constructed elsewhere: a constructor (e.g. `Err(..)`), the `Try::branch` of a `?`,
+/// or a conversion (`From::from` or `Into::into`). The kinds of these errors are those of the caller or of the call the `?` is applied to.
+fn passes_on(context: TyCtxt, graph: &CallGraph, node: usize) -> bool {
+    let CallNodeKind::NonLocalFn(def_id) = graph.nodes[node].kind else {
+        return false;
+    };
+    if matches!(context.def_kind(def_id), DefKind::Ctor(..)) {
+        return true;
+    }
+    let method = context
+        .opt_associated_item(def_id)
+        .and_then(|item| item.trait_item_def_id)
+        .unwrap_or(def_id);
+    context.lang_items().get(LangItem::TryTraitBranch) == Some(method)
+        || context.is_diagnostic_item(sym::from_fn, method)
+        || context
+            .trait_of_item(method)
+            .is_some_and(|trait_id| context.is_diagnostic_item(sym::Into, trait_id))
+}
+
+/// Get the documented kinds of the errors of a function of std, if it is known.
+/// The implementations of trait methods (e.g. `<File as Read>::read_exact`) are looked up as the method of the trait.
+fn get_std_kinds(context: TyCtxt, def_id: DefId) -> Option<&'static [&'static str]> {
+    let def_id = context
+        .opt_associated_item(def_id)
+        .and_then(|item| item.trait_item_def_id)
+        .unwrap_or(def_id);
+    let path = context.def_path_str(def_id);
+    STD_IO_KINDS
+        .iter()
+        .find(|(std_path, _kinds)| *std_path == path)
+        .map(|(_path, kinds)| *kinds)
+}
+
+/// Whether a type is the type with the given path, e.g. `std::io::ErrorKind`.
+fn is_type(context: TyCtxt, ty: Ty, path: &str) -> bool {
+    ty.ty_adt_def()
+        .is_some_and(|adt| context.def_path_str(adt.did()) == path)
+}
+
+/// Collects the kinds of the `std::io::Error`s constructed in a function, including the closures defined in it.
+struct KindCollector<'tcx> {
+    context: TyCtxt<'tcx>,
+    set: KindSet,
+    /// The kind assigned to each local, e.g. `_2 = ErrorKind::NotFound`.
+    definitions: HashMap<Local, String>,
+}
+
+impl<'tcx> KindCollector<'tcx> {
+    /// Visit the MIR of a function (or closure).
+    fn visit_fn(&mut self, def_id: DefId) {
+        if let Some(mir) = get_mir(self.context, def_id) {
+            let definitions = std::mem::take(&mut self.definitions);
+            self.visit_body(mir);
+            self.visit_calls(mir);
+            self.definitions = definitions;
+        }
+    }
+
+    /// Find the calls constructing an `std::io::Error` (e.g. `io::Error::new(kind, ..)` or `kind.into()`), and the kinds passed to them.
+    /// These are visited after the statements, as the kinds passed can be assigned in a later block.
+    fn visit_calls(&mut self, mir: &Body<'tcx>) {
+        for block in mir.basic_blocks.iter() {
+            let Some(Terminator {
+                kind:
+                    TerminatorKind::Call {
+                        func,
+                        args,
+                        destination,
+                        ..
+                    },
+                ..
+            }) = &block.terminator
+            else {
+                continue;
+            };
+            let Some((called_id, _args)) = func.const_fn_def() else {
+                continue;
+            };
+            if !is_type(self.context, destination.ty(mir, self.context).ty, IO_ERROR) {
+                continue;
+            }
+
+            let kind_arg = args
+                .iter()
+                .find(|arg| is_type(self.context, arg.node.ty(mir, self.context), IO_ERROR_KIND));
+            if let Some(arg) = kind_arg {
+                match self.get_kind(&arg.node) {
+                    Some(kind) => {
+                        self.set.kinds.insert(kind);
+                    }
+                    // The kind is passed in, e.g. `fn io_error(kind: ErrorKind) -> io::Error`
+                    None => self.set.incomplete = true,
+                }
+            } else if self.context.def_path_str(called_id) == "std::io::Error::other" {
+                self.set.kinds.insert(String::from("Other"));
+            } else if !args
+                .iter()
+                .any(|arg| is_type(self.context, arg.node.ty(mir, self.context), IO_ERROR))
+            {
+                // Not a conversion or copy of an existing error, e.g. `io::Error::last_os_error()`
+                self.set.incomplete = true;
+            }
+        }
+    }
+
+    /// Get the kind an operand of type `std::io::ErrorKind` has, e.g. `NotFound`, if it is constant.
+    fn get_kind(&self, operand: &Operand<'tcx>) -> Option<String> {
+        match operand {
+            Operand::Constant(constant) => {
+                let Const::Val(value, ty) = constant.const_ else {
+                    return None;
+                };
+                if !is_type(self.context, ty, IO_ERROR_KIND) {
+                    return None;
+                }
+                let destructured = self
+                    .context
+                    .try_destructure_mir_constant_for_user_output(value, ty)?;
+                let adt = ty.ty_adt_def()?;
+                Some(adt.variant(destructured.variant?).name.to_string())
+            }
+            Operand::Copy(place) | Operand::Move(place) => {
+                self.definitions.get(&place.as_local()?).cloned()
+            }
+        }
+    }
+}
+
+impl<'tcx> Visitor<'tcx> for KindCollector<'tcx> {
+    fn visit_statement(&mut self, statement: &Statement<'tcx>, location: Location) {
+        if let StatementKind::Assign(assign) = &statement.kind {
+            let (place, rvalue) = &**assign;
+            let kind = match rvalue {
+                Rvalue::Use(operand) => self.get_kind(operand),
+                Rvalue::Aggregate(kind, _operands) => match **kind {
+                    AggregateKind::Adt(adt_id, variant, ..)
+                        if self.context.def_path_str(adt_id) == IO_ERROR_KIND =>
+                    {
+                        Some(
+                            self.context
+                                .adt_def(adt_id)
+                                .variant(variant)
+                                .name
+                                .to_string(),
+                        )
+                    }
+                    _ => None,
+                },
+                _ => None,
+            };
+            if let (Some(local), Some(kind)) = (place.as_local(), kind) {
+                self.definitions.insert(local, kind);
+            }
+        }
+
+        self.super_statement(statement, location);
+    }
+
+    fn visit_rvalue(&mut self, rvalue: &Rvalue<'tcx>, location: Location) {
+        // A closure is defined, which may construct errors, e.g. `map_err(|e| io::Error::new(ErrorKind::InvalidData, e))`
+        if let Rvalue::Aggregate(kind, _operands) = rvalue {
+            if let AggregateKind::Closure(closure_id, _args) = **kind {
+                self.visit_fn(closure_id);
+            }
+        }
+
+        self.super_rvalue(rvalue, location);
+    }
+}
@@ -0,0 +1,84 @@
+use crate::graph::{CallGraph, CallId, ChainEnd, ChainGraph};
+use std::fmt;
+
+/// The lints that can be denied, making the analyzer fail if they are found.
+pub const LINTS: [&str; 1] = ["lib-unwrap"];
+
+/// An unwrapped error that can be reached from the public API of a library, so the library can panic on behalf of its users.
+#[derive(Debug, Clone)]
+pub struct LibUnwrap {
+    /// The path of the function unwrapping the error.
+    pub function: String,
+    /// The location of the call whose error is unwrapped, if known.
+    pub location: Option<String>,
+    /// The error type that is unwrapped, if known.
+    pub ty: Option<String>,
+    /// The paths of the public functions from which the unwrap can be reached.
+    pub entry_points: Vec<String>,
+}
+
+/// Find the chains ending in an unwrap inside a function reachable from a public function, leaving out the suppressed chains.
+/// Test code (e.g. `#[cfg(test)]` modules) is not compiled when analyzing, so unwraps only reachable from tests are not found.
+pub fn find_lib_unwraps(graph: &CallGraph, chain_graph: &ChainGraph) -> Vec<LibUnwrap> {
+    // Several chains can end at the same call, these are reported once
+    let mut found: Vec<CallId> = vec![];
+    let mut res = vec![];
+    for chain in &chain_graph.chains {
+        if chain.termination != ChainEnd::Unwrapped || chain.suppressed {
+            continue;
+        }
+        let end = chain.calls.last().expect("Chains end in a call");
+        if found.contains(&end.call_id) {
+            continue;
+        }
+        found.push(end.call_id);
+
+        let entry_points = get_public_callers(graph, end.from);
+        if entry_points.is_empty() {
+            continue;
+        }
+        res.push(LibUnwrap {
+            function: graph.full_path(end.from),
+            location: end.location.clone(),
+            ty: graph.type_label(end),
+            entry_points,
+        });
+    }
+    res
+}
+
+/// Get the paths of the public functions from which a function can be reached, including the function itself.
+fn get_public_callers(graph: &CallGraph, node: usize) -> Vec<String> {
+    let mut visited = vec![node];
+    let mut queue = vec![node];
+    let mut res = vec![];
+    while let Some(current) = queue.pop() {
+        if graph.nodes[current].public {
+            res.push(graph.full_path(current));
+        }
+        for edge in graph.get_incoming_edges(current) {
+            if !visited.contains(&edge.from) {
+                visited.push(edge.from);
+                queue.push(edge.from);
+            }
+        }
+    }
+    res.sort_unstable();
+    res
+}
+
+/// Formats the unwrap for the terminal, e.g. `parse unwraps a std::io::Error at src/lib.rs:12:5, reachable from: load, Config::new`.
+impl fmt::Display for LibUnwrap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} unwraps a {}",
+            self.function,
+            self.ty.as_deref().unwrap_or("unknown error")
+        )?;
+        if let Some(location) = &self.location {
+            write!(f, " at {location}")?;
+        }
+        write!(f, ", reachable from: {}", self.entry_points.join(", "))
+    }
+}
@@ -0,0 +1,78 @@
+use super::create_graph::get_mir;
+use rustc_data_structures::fx::FxHashMap;
+use rustc_hir::def_id::DefId;
+use rustc_middle::mir::TerminatorKind;
+use rustc_middle::ty::{Interner, Ty, TyCtxt};
+use rustc_span::{BytePos, Span, SyntaxContext};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// A call in the MIR of a function, resolved to the called function.
+#[derive(Debug, Clone, Copy)]
+pub struct MirCall<'tcx> {
+    /// The `DefId` of the called function.
+    pub callee: DefId,
+    /// The return type of the called function, instantiated with the arguments of the call.
+    pub ty: Ty<'tcx>,
+}
+
+/// The calls in the MIR of a function, by the end and syntax context of their span.
+type MirCallMap<'tcx> = FxHashMap<(BytePos, SyntaxContext), MirCall<'tcx>>;
+
+/// The calls in the MIR of each function, built once per function the first time one of its calls is looked up.
+/// Matching a call against the MIR of its caller would otherwise scan all of its basic blocks for every call.
+#[derive(Default)]
+pub struct MirCallMaps<'tcx> {
+    maps: RefCell<FxHashMap<DefId, Rc<MirCallMap<'tcx>>>>,
+}
+
+impl<'tcx> MirCallMaps<'tcx> {
+    /// Get the call in the MIR of the caller whose span ends at the same place as the given span.
+    /// Returns `None` if no MIR is available for the caller, or the call was not found (e.g. due to desugaring/optimizations).
+    pub fn get(
+        &self,
+        context: TyCtxt<'tcx>,
+        caller_id: DefId,
+        span: Span,
+    ) -> Option<MirCall<'tcx>> {
+        let map = Rc::clone(
+            self.maps
+                .borrow_mut()
+                .entry(caller_id)
+                .or_insert_with(|| Rc::new(get_mir_call_map(context, caller_id))),
+        );
+        map.get(&(span.hi(), span.ctxt())).copied()
+    }
+
+    /// The number of functions whose calls have been looked up.
+    pub fn function_count(&self) -> usize {
+        self.maps.borrow().len()
+    }
+}
+
+/// Build the map of the calls in the MIR of a function to statically known functions.
+/// Calls sharing the end of their span (e.g. within the same macro expansion) are mapped to the first of these calls.
+fn get_mir_call_map(context: TyCtxt, def_id: DefId) -> MirCallMap {
+    let mut res = MirCallMap::default();
+    let Some(mir) = get_mir(context, def_id) else {
+        return res;
+    };
+
+    for block in mir.basic_blocks.iter() {
+        if let Some(terminator) = &block.terminator {
+            if let TerminatorKind::Call { func, fn_span, .. } = &terminator.kind {
+                if let Some((callee, args)) = func.const_fn_def() {
+                    let ty = context
+                        .type_of_instantiated(callee, args)
+                        .fn_sig(context)
+                        .output()
+                        .skip_binder();
+                    res.entry((fn_span.hi(), fn_span.ctxt()))
+                        .or_insert(MirCall { callee, ty });
+                }
+            }
+        }
+    }
+
+    res
+}
@@ -1,13 +1,118 @@
+mod branches;
 mod calls_to_chains;
 mod create_graph;
+mod diagnostics;
+mod dominators;
+mod downcasts;
+mod externals;
+mod filter;
+mod functions;
+mod infallible;
+mod io_kinds;
+mod lints;
+mod mir_calls;
+mod origins;
+mod panics;
+mod suppression;
 mod types;
+mod variants;
 
-use crate::graph::{CallGraph, ChainGraph};
-use rustc_middle::ty::TyCtxt;
+use crate::error::AnalyzerError;
+use crate::graph::{
+    CallGraph, CallId, CallNodeKind, ChainGraph, ErrorCategory, FailureKind, Handling,
+};
+use crate::progress::Progress;
+use crate::schema::StablePaths;
+use crate::scoring::ScoreWeights;
+use crate::stats::ChainSummary;
+use crate::summary::ColorChoice;
+use crate::timings::Timings;
+use create_graph::get_call_site_span;
+use mir_calls::MirCallMaps;
+use regex::Regex;
+use rustc_hir::def::DefKind;
+use rustc_hir::def_id::{LocalDefId, LOCAL_CRATE};
+use rustc_middle::ty::{Ty, TyCtxt};
+use rustc_session::config::CrateType;
+use rustc_span::sym;
+use tracing::{info, warn};
+use types::TypeLabels;
+
+pub use filter::{FilterDecision, NodeFilter};
+pub use lints::LINTS;
+
+/// Options influencing how the analysis is performed.
+#[derive(Debug, Clone, Default)]
+pub struct AnalysisOptions {
+    /// Whether to keep calls expanded from macros defined outside of the analyzed crate.
+    pub include_macro_calls: bool,
+    /// Whether to add edges for the implicit calls to local `Drop` implementations.
+    pub drop_edges: bool,
+    /// The names of the dependencies whose functions are explored as well, using their MIR.
+    pub follow_deps: Vec<String>,
+    /// Whether to analyze every local function, instead of only those reachable from the entry point.
+    pub all_functions: bool,
+    /// The path of the local function to analyze the crate from instead of its entry point or public API (e.g. `run`), if any.
+    pub entry: Option<String>,
+    /// Whether to leave chains propagating `None` out of the chain graph.
+    pub exclude_option_chains: bool,
+    /// The paths of types carrying a Result, besides Result itself (e.g. newtypes around a Result).
+    pub result_types: Vec<String>,
+    /// Whether to add the error types received and returned by each function to its label in the chain graph.
+    pub chain_types: bool,
+    /// The paths of functions spawning a task, besides the known ones (e.g. `tokio::spawn` or `std::thread::spawn`).
+    pub spawn_fns: Vec<String>,
+    /// The paths of macros and functions logging errors, besides the known ones (e.g. `log::error` or `eprintln`).
+    pub log_fns: Vec<String>,
+    /// Which chains are shown in the chain graph.
+    pub graph: GraphKind,
+    /// The maximum number of chained calls followed from the call where a chain ends, if not the default.
+    pub max_chain_depth: Option<usize>,
+    /// The maximum number of chains extracted, if not the default. Past this number, the deepest chains are kept.
+    pub max_chains: Option<usize>,
+    /// The number of functions of a dependency whose calls carry no errors past which these are aggregated into one node, if not the default.
+    pub external_threshold: Option<usize>,
+    /// The patterns of which the error types of a chain should match at least one, if any, for the chain to be kept.
+    pub error_filters: Vec<Regex>,
+    /// The number of the only chain shown in the chain graph, if any.
+    pub chain: Option<usize>,
+    /// Whether to add the location of each call to its label in the chain graph.
+    pub edge_locations: bool,
+    /// Whether to color the nodes of the call graph by the number of chains passing through them.
+    pub heatmap: bool,
+    /// Whether to find and mark the functions all errors of an origin pass through.
+    pub choke_points: bool,
+    /// The paths of the functions whose unwrapped or discarded errors are accepted, where `*` matches any part (e.g. `cli::*`).
+    pub ignore: Vec<String>,
+    /// The module paths whose functions are left out of the call graph along with everything only they call, where `*` matches any part (e.g. `generated::*`).
+    pub exclude_paths: Vec<String>,
+    /// The module paths the local functions in the call graph are restricted to, if any. The calls of the other local functions are still explored.
+    pub include_paths: Vec<String>,
+    /// The filter consulted before a function is added to the call graph, if any, along with the included and excluded paths.
+    pub node_filter: Option<NodeFilter>,
+    /// The weights of the severity score of the chains.
+    pub score_weights: ScoreWeights,
+    /// Whether to print how long the steps of the analysis take.
+    pub verbose: bool,
+    /// Whether to leave out the progress of the analysis on stderr.
+    pub quiet: bool,
+    /// Whether to color the summary printed after the analysis.
+    pub color: ColorChoice,
+}
+
+/// The kind of chains shown in the chain graph.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GraphKind {
+    /// Chains of calls propagating errors.
+    #[default]
+    Error,
+    /// Chains of calls through which panics reach the callers.
+    Panic,
+}
 
 /// Analysis steps:
 ///
-/// Step 1: Create call graph
+/// Step 1: Create call graph, from the entry point or the public API of a library
 /// Step 1.1: Node for each function
 /// Step 1.2: Edge for each function call
 /// Step 1.3: Add function call information (e.g. whether it propagates using the try op)
@@ -15,43 +120,454 @@ use rustc_middle::ty::TyCtxt;
 /// Step 2: Attach return type info to functions in call graph
 /// Step 2.1: Loop over each edge in call graph
 /// Step 2.2: Label edge with type info extracted from MIR
+/// Step 2.3: Label edges returning local error enums with the variants that can be returned
+/// Step 2.4: Mark functions constructing errors themselves as the origins of errors
+/// Step 2.5: Mark functions returning a Result that can never return an error
 ///
 /// Step 3: Attach panic info to functions in call graph
-/// NOTE: skipped due to lack of time
+/// Step 3.1: Mark functions calling the functions the panic macros expand to, or unwrapping errors
+/// Step 3.2: Mark functions that can panic through the functions they call
 ///
-/// Step 4: Parse the output graph to show individual propagation chains
-pub fn analyze(context: TyCtxt) -> (CallGraph, ChainGraph) {
-    // Get the entry point of the program
-    let entry_node = get_entry_node(context);
+/// Step 3.3: Aggregate the functions of dependencies whose calls carry no errors into a node per dependency
+///
+/// The chains are extracted from the call graph by `extract_chains`, which does not need the compiler,
+/// so a cached call graph can be shown with other chain options.
+pub(crate) fn build_call_graph(
+    context: TyCtxt,
+    options: &AnalysisOptions,
+    timings: &mut Timings,
+) -> Result<CallGraph, AnalyzerError> {
+    // The calls in the MIR of each function, built once per function as calls are matched against it
+    let mir_calls = MirCallMaps::default();
+
+    // A binary without a main function cannot be analyzed from its entry point, nor does it have a public API
+    let entry_fn = get_entry_fn(context, options)?;
+    if entry_fn.is_none() && context.crate_types().contains(&CrateType::Executable) {
+        return Err(AnalyzerError::EntryFnMissing {
+            crate_name: context.crate_name(LOCAL_CRATE).to_string(),
+        });
+    }
+
+    // Create call graph, from the entry point of the program, or from the public API of a library
+    let mut progress = Progress::new(options.quiet);
+    progress.start("Building the call graph");
+    let mut call_graph = timings.time("graph construction", || {
+        let mut call_graph = match entry_fn {
+            Some(entry_fn) => create_graph::create_call_graph_from_root(
+                context,
+                options,
+                &mir_calls,
+                entry_fn,
+                &mut progress,
+            ),
+            None => create_graph::create_call_graph_from_public_api(
+                context,
+                options,
+                &mir_calls,
+                &mut progress,
+            ),
+        };
+        if options.all_functions {
+            create_graph::add_unreachable_functions(
+                context,
+                options,
+                &mir_calls,
+                &mut call_graph,
+                &mut progress,
+            );
+        }
+        call_graph
+    });
+    progress.finish();
+    let graph_size = format!(
+        "{} functions and {} calls",
+        call_graph.nodes.len(),
+        call_graph.edges.len()
+    );
+    if options.verbose {
+        info!(
+            target: "analyzer::graph",
+            "Created the call graph of {graph_size} in {:.2?}",
+            timings.stages().last().unwrap().duration
+        );
+    }
+    timings.note(graph_size);
 
-    // Create call graph
-    let mut call_graph =
-        create_graph::create_call_graph_from_root(context, entry_node.expect_item());
+    // Note whether errors can be returned from the entry point, reaching the program exit
+    if let Some(entry_fn) = entry_fn {
+        let entry_return_ty = get_return_type(context, entry_fn);
+        call_graph.entry_returns_result = entry_return_ty
+            .ty_adt_def()
+            .is_some_and(|adt| context.is_diagnostic_item(sym::Result, adt.did()));
+        call_graph.entry_return_ty = Some(entry_return_ty.to_string());
+
+        // Anything else than unit is received by the program exit, e.g. the error of a `Result` or an `ExitCode`,
+        // unless another function than `main` is analyzed from
+        let is_main = context
+            .entry_fn(())
+            .is_some_and(|(def_id, _entry_type)| def_id == entry_fn.to_def_id());
+        if is_main && !entry_return_ty.is_unit() && !entry_return_ty.is_never() {
+            create_graph::add_exit_node(&mut call_graph);
+        }
+    }
+
+    // Mark the functions that can be called from outside of the crate
+    let visibilities = context.effective_visibilities(());
+    for node in &mut call_graph.nodes {
+        if let CallNodeKind::LocalFn(def_id, _hir_id) = node.kind {
+            node.public = def_id
+                .as_local()
+                .is_some_and(|def_id| visibilities.is_exported(def_id));
+        }
+    }
 
     // Attach return type info
-    for edge in &mut call_graph.edges {
-        let (ty, error) = types::get_error_or_type(
+    let stage = timings.start("edge typing");
+    let result_types = types::resolve_result_types(context, &options.result_types);
+    progress.start("Typing the calls");
+    let call_count = call_graph.edges.len();
+    let mut type_labels = TypeLabels::default();
+    for (typed, edge) in call_graph.edges.iter_mut().enumerate() {
+        progress.update(|| format!("{typed}/{call_count} calls typed"));
+        edge.location = Some(get_span_location(context, edge.call_id));
+        if matches!(call_graph.nodes[edge.to].kind, CallNodeKind::Unresolved(_)) {
+            call_graph
+                .unresolved_calls
+                .push(get_location(context, edge.call_id));
+        }
+        let Some(call_type) = types::get_error_or_type(
             context,
+            &mir_calls,
             edge.call_id,
             call_graph.nodes[edge.from].kind.def_id(),
             call_graph.nodes[edge.to].kind.def_id(),
+            &result_types,
+        ) else {
+            // The type is unknown as well (e.g. as type checking the caller failed), so leave it out
+            if !matches!(call_graph.nodes[edge.to].kind, CallNodeKind::Unresolved(_)) {
+                call_graph
+                    .unresolved_calls
+                    .push(get_location(context, edge.call_id));
+            }
+            continue;
+        };
+        edge.ty = Some(type_labels.get(&call_type, &mut call_graph.strings));
+        edge.is_error = call_type.failure_kind.is_some()
+            && !create_graph::is_consumed_iterator(context, edge.call_id);
+        edge.type_erased = call_type.type_erased;
+        edge.type_approximate = call_type.approximate;
+        edge.error_family = call_type.error_family;
+        edge.error_category = call_type.error_category;
+        edge.error_enum = call_type.error_enum;
+        if let Some(failure_kind) = call_type.failure_kind {
+            edge.failure_kind = failure_kind;
+        }
+
+        // The error type after conversion using `map_err`
+        if let Some(map_err_id) = edge.map_err_id.filter(|_id| edge.is_error) {
+            let mapped_type = types::get_error_or_type(
+                context,
+                &mir_calls,
+                CallId::Hir(map_err_id),
+                call_graph.nodes[edge.from].kind.def_id(),
+                None,
+                &result_types,
+            );
+            if let Some(mapped_type) =
+                mapped_type.filter(|ty| ty.failure_kind == Some(FailureKind::Result))
+            {
+                edge.mapped_ty = Some(type_labels.get(&mapped_type, &mut call_graph.strings));
+            }
+        }
+    }
+    progress.finish();
+    let duration = timings.finish(stage);
+    if options.verbose {
+        info!(
+            target: "analyzer::graph",
+            "Attached return type info to {} calls in {:.2?}, using the MIR of {} functions",
+            call_graph.edges.len(),
+            duration,
+            mir_calls.function_count()
+        );
+    }
+    timings.note(format!(
+        "{} calls, using the MIR of {} functions",
+        call_graph.edges.len(),
+        mir_calls.function_count()
+    ));
+
+    // Attach the variants of local error enums that can be returned
+    let stage = timings.start("error and panic info");
+    variants::attach_error_variants(context, &mut call_graph);
+
+    // Attach the kinds of the `std::io::Error`s that can be returned
+    io_kinds::attach_io_error_kinds(context, &mut call_graph);
+
+    // Attach the concrete errors boxed by a function to the type-erased errors it returns
+    for i in 0..call_graph.edges.len() {
+        let edge = &call_graph.edges[i];
+        if !edge.type_erased || edge.origin_ty.is_some() || edge.implicit.is_some() {
+            continue;
+        }
+
+        let mut origins: Vec<&str> = call_graph
+            .get_outgoing_edges(edge.to)
+            .into_iter()
+            .filter_map(|call| call.origin_ty.as_deref())
+            .collect();
+        origins.sort_unstable();
+        origins.dedup();
+        if !origins.is_empty() {
+            call_graph.edges[i].origin_ty = Some(origins.join(", "));
+        }
+    }
+
+    // List the public functions returning errors their callers cannot match on
+    for node in &call_graph.nodes {
+        let CallNodeKind::LocalFn(def_id, _hir_id) = node.kind else {
+            continue;
+        };
+        if !matches!(context.def_kind(def_id), DefKind::Fn | DefKind::AssocFn)
+            || !context.visibility(def_id).is_public()
+        {
+            continue;
+        }
+        let Some(error) = types::get_declared_error_type(context, def_id) else {
+            continue;
+        };
+        if error.error_category.is_some_and(ErrorCategory::is_opaque) {
+            let function = call_graph.full_path(node.id());
+            call_graph.opaque_error_fns.push((function, error.label()));
+        }
+    }
+
+    // Mark where errors originate
+    origins::attach_error_origins(context, &mut call_graph);
+
+    // Attach the types type-erased errors are downcast to
+    downcasts::attach_downcasts(context, &mut call_graph);
+
+    // Mark the functions returning a `Result` without being able to return an error, which needs the origins of errors
+    infallible::attach_infallibility(context, &mut call_graph);
+
+    // Attach panic info
+    panics::attach_panic_info(context, &mut call_graph);
+
+    // Mark the handled calls whose error ends the process or is only logged
+    branches::mark_error_branches(context, &mut call_graph, &options.log_fns);
+
+    // Mark where losing the error is accepted, so the chains ending there are counted apart
+    suppression::mark_suppressed_calls(context, &mut call_graph, &options.ignore);
+
+    // List where errors are thrown away, leaving out where this is accepted
+    for edge in &call_graph.edges {
+        if edge.is_error && edge.handling == Handling::Discarded && !edge.suppressed {
+            call_graph
+                .discarded_calls
+                .push(get_location(context, edge.call_id));
+        }
+    }
+
+    timings.finish(stage);
+
+    // Aggregate the functions of dependencies without errors, before the chains refer to the nodes of the fallible ones
+    timings.time("external aggregation", || {
+        externals::aggregate_external_nodes(context, &mut call_graph, options.external_threshold);
+    });
+
+    // Warn about the parts of the program that could not be analyzed
+    if !call_graph.unresolved_calls.is_empty() {
+        warn!(
+            target: "analyzer::graph",
+            "{} calls could not be resolved or typed, as type information is unavailable (e.g. due to compilation errors):\n  {}",
+            call_graph.unresolved_calls.len(),
+            call_graph.unresolved_calls.join("\n  ")
         );
-        edge.ty = Some(ty);
-        edge.is_error = error;
     }
 
-    // Parse graph to show chains
-    let chain_graph = calls_to_chains::to_chains(&call_graph);
+    // Give the functions and calls the stable paths the graph is serialized with, as the ids of the compiler are only meaningful within this compilation
+    call_graph.stable_paths = StablePaths::collect(context, &call_graph);
+
+    Ok(call_graph)
+}
+
+/// Extract the chains from a call graph, built in this compilation or cached:
+///
+/// Step 4: Mark the functions all errors of an origin pass through, if requested, so the chains can show these
+///
+/// Step 5: Parse the call graph to show individual propagation chains
+/// Step 5.1: Either the chains of propagated errors, or the chains of calls through which panics reach the callers
+/// Step 5.2: Count the chains passing through each function, to color the call graph by if requested
+pub(crate) fn extract_chains(
+    call_graph: &mut CallGraph,
+    options: &AnalysisOptions,
+    timings: &mut Timings,
+) -> ChainGraph {
+    let stage = timings.start("chain extraction");
+    if options.choke_points {
+        dominators::attach_choke_points(call_graph);
+    }
+
+    let mut progress = Progress::new(options.quiet);
+    progress.start("Extracting the chains");
+    let mut chain_graph = match options.graph {
+        GraphKind::Error => calls_to_chains::to_chains(call_graph, options, &mut progress),
+        GraphKind::Panic => panics::to_chains(call_graph),
+    };
+    progress.finish();
+    if options.heatmap {
+        calls_to_chains::attach_chain_counts(call_graph, &chain_graph);
+    }
+    timings.finish(stage);
+    timings.note(format!("{} chains", chain_graph.chains.len()));
+
+    // The calls of the chains are those of the call graph, so they share its stable paths
+    chain_graph.stable_paths = call_graph.stable_paths.clone();
+    chain_graph
+}
+
+/// Get a graph of only the chains whose error is returned from the entry point, i.e. the errors the runtime prints when exiting the program.
+pub fn exit_chains(
+    call_graph: &CallGraph,
+    chain_graph: &ChainGraph,
+    options: &AnalysisOptions,
+) -> ChainGraph {
+    calls_to_chains::to_exit_chains(call_graph, chain_graph, options)
+}
+
+/// Get the summaries of all chains, e.g. to report the chains ending in an unwrap.
+pub fn chain_summaries(
+    call_graph: &CallGraph,
+    chain_graph: &ChainGraph,
+    options: &AnalysisOptions,
+) -> Vec<ChainSummary> {
+    calls_to_chains::get_chain_summaries(call_graph, chain_graph, &options.score_weights)
+}
+
+/// Find the errors unwrapped in functions reachable from the public API of a library, with the public functions they are reachable from.
+pub fn lib_unwraps(call_graph: &CallGraph, chain_graph: &ChainGraph) -> Vec<lints::LibUnwrap> {
+    lints::find_lib_unwraps(call_graph, chain_graph)
+}
 
-    (call_graph, chain_graph)
+/// Convert the chains to JSON, with the details of each of their calls.
+pub fn chains_json(call_graph: &CallGraph, chain_graph: &ChainGraph) -> String {
+    calls_to_chains::to_chains_json(call_graph, chain_graph)
 }
 
-/// Retrieve the entry node (aka main function) from the type context.
-fn get_entry_node(context: TyCtxt) -> rustc_hir::Node {
-    let (def_id, _entry_type) = context
-        .entry_fn(())
-        .expect("Could not find entry function!");
-    let id = context
-        .local_def_id_to_hir_id(def_id.as_local().expect("Entry function def id not local!"));
-    context.hir_node(id)
+/// Emit the chains ending in an unwrapped or discarded error as compiler diagnostics, noting the calls they propagate through.
+/// Unwrapped errors are only reported if they propagated through more than `min_depth` calls.
+pub(crate) fn emit_diagnostics(
+    context: TyCtxt,
+    call_graph: &CallGraph,
+    chain_graph: &ChainGraph,
+    min_depth: usize,
+) {
+    diagnostics::emit_diagnostics(context, call_graph, chain_graph, min_depth);
+}
+
+/// Get a graph of only the calls carrying errors of the given type (e.g. `sqlx::Error`), keeping the shape of the call graph.
+/// Calls converting from or to the type using `map_err` are kept, as are calls returning a type-erased error known to box it.
+pub fn error_subgraph(call_graph: &CallGraph, ty: &str) -> CallGraph {
+    let mut edges = vec![];
+    for (i, edge) in call_graph.edges.iter().enumerate() {
+        let carries = edge.is_error
+            && [call_graph.ty(edge), call_graph.mapped_ty(edge)]
+                .into_iter()
+                .flatten()
+                .any(|label| types::label_mentions_type(label, ty));
+        // The concrete errors boxed into a `dyn Error` are known from the `From` conversion boxing them
+        let boxes = edge.type_erased
+            && edge
+                .origin_ty
+                .as_ref()
+                .is_some_and(|origin| types::label_mentions_type(origin, ty));
+        if carries || boxes {
+            edges.push(i);
+        }
+    }
+
+    let mut subgraph = call_graph.with_edges(&edges);
+    subgraph.subgraph_ty = Some(ty.to_owned());
+    subgraph
+}
+
+/// Get the location of a call, e.g. `src/main.rs:12`, which is the invocation of the macro for calls expanded from a local macro.
+fn get_location(context: TyCtxt, call_id: CallId) -> String {
+    let location = context
+        .sess
+        .source_map()
+        .lookup_char_pos(get_call_site_span(context, call_id).lo());
+    format!("{}:{}", location.file.name.prefer_local(), location.line)
+}
+
+/// Get the location of a call including its column, e.g. `src/main.rs:12:5`.
+/// The source map is needed to resolve it, so this is done while the type context is available.
+fn get_span_location(context: TyCtxt, call_id: CallId) -> String {
+    let location = context
+        .sess
+        .source_map()
+        .lookup_char_pos(get_call_site_span(context, call_id).lo());
+    format!(
+        "{}:{}:{}",
+        location.file.name.prefer_local(),
+        location.line,
+        location.col_display + 1
+    )
+}
+
+/// Get the return type of the entry point (e.g. `Result<(), MyError>` or `ExitCode`, implementing `Termination`).
+fn get_return_type(context: TyCtxt, entry_fn: LocalDefId) -> Ty {
+    let sig = context.fn_sig(entry_fn).instantiate_identity();
+    context.instantiate_bound_regions_with_erased(sig).output()
+}
+
+/// Retrieve the function to analyze the crate from: the one given using `--entry`, or else the entry point (aka main function),
+/// if there is one (i.e. not for libraries, nor for `#![no_main]` binaries).
+fn get_entry_fn(
+    context: TyCtxt,
+    options: &AnalysisOptions,
+) -> Result<Option<LocalDefId>, AnalyzerError> {
+    let Some(path) = &options.entry else {
+        let entry_fn = context.entry_fn(());
+        return Ok(entry_fn.and_then(|(def_id, _entry_type)| def_id.as_local()));
+    };
+
+    // The path can be given with or without the name of the crate, e.g. `app::cli::run` or `cli::run`
+    let crate_name = context.crate_name(LOCAL_CRATE).to_ident_string();
+    let path = path.trim();
+    let relative = path
+        .strip_prefix(&format!("{crate_name}::"))
+        .or_else(|| path.strip_prefix("crate::"))
+        .unwrap_or(path);
+
+    let functions: Vec<(LocalDefId, String)> = context
+        .hir()
+        .body_owners()
+        .filter(|local_id| matches!(context.def_kind(*local_id), DefKind::Fn | DefKind::AssocFn))
+        .map(|local_id| (local_id, context.def_path_str(local_id)))
+        .collect();
+    if let Some((local_id, _path)) = functions.iter().find(|(_id, path)| path == relative) {
+        return Ok(Some(*local_id));
+    }
+
+    // The candidates are the functions of the same name, or else those whose path contains it
+    let name = relative.rsplit("::").next().unwrap_or(relative);
+    let mut candidates: Vec<String> = functions
+        .iter()
+        .filter(|(_id, path)| path.rsplit("::").next() == Some(name))
+        .map(|(_id, path)| path.clone())
+        .collect();
+    if candidates.is_empty() {
+        candidates = functions
+            .iter()
+            .filter(|(_id, path)| path.contains(name))
+            .map(|(_id, path)| path.clone())
+            .collect();
+    }
+    candidates.sort_unstable();
+    Err(AnalyzerError::EntryFnNotFound {
+        path: path.to_owned(),
+        candidates,
+    })
 }
@@ -0,0 +1,171 @@
+use super::create_graph::get_mir;
+use super::types::is_error_type;
+use crate::graph::{CallGraph, CallNodeKind, FailureConversion};
+use rustc_hir::def_id::DefId;
+use rustc_middle::mir::visit::Visitor;
+use rustc_middle::mir::{
+    AggregateKind, Body, Local, Location, Operand, Rvalue, Statement, StatementKind, Terminator,
+    TerminatorKind,
+};
+use rustc_middle::ty::{self, Instance, TyCtxt};
+use rustc_span::sym;
+use std::collections::{BTreeSet, HashMap};
+
+/// Mark the nodes of the functions where errors originate, i.e. the functions constructing an error themselves.
+/// These construct an `Err(..)` value (including through e.g. `anyhow::bail!`), call an error constructor (e.g. `io::Error::new`),
+/// or create an error for `None` (e.g. using `ok_or`).
+/// The expressions constructing the errors (e.g. `MyError::Io`) are attached to the nodes as well.
+pub fn attach_error_origins(context: TyCtxt, graph: &mut CallGraph) {
+    for node in &mut graph.nodes {
+        let def_id = match node.kind {
+            CallNodeKind::LocalFn(def_id, _hir_id) => def_id,
+            // Functions of followed dependencies are explored as well
+            CallNodeKind::NonLocalFn(def_id) if node.cluster.is_some() => def_id,
+            _ => continue,
+        };
+
+        if let Some(constructors) = get_error_constructors(context, def_id) {
+            node.error_origin = true;
+            node.error_constructors = constructors.into_iter().collect();
+        }
+    }
+
+    for edge in &graph.edges {
+        if edge.conversion == Some(FailureConversion::OkOr) {
+            graph.nodes[edge.from].error_origin = true;
+        }
+    }
+}
+
+/// Get the expressions constructing the errors a function constructs itself, according to its MIR.
+/// Returns `None` if the function does not construct an error, the set can be empty if the constructed errors are only passed on (e.g. `Err(e)`).
+/// The closures defined in the function are not included, as they have nodes of their own.
+fn get_error_constructors(context: TyCtxt, def_id: DefId) -> Option<BTreeSet<String>> {
+    let mir = get_mir(context, def_id)?;
+
+    let mut finder = ErrorConstructionFinder {
+        context,
+        def_id,
+        mir,
+        found: false,
+        constructors: BTreeSet::new(),
+        definitions: HashMap::new(),
+        err_values: vec![],
+    };
+    finder.visit_body(mir);
+    if !finder.found {
+        return None;
+    }
+
+    // The values wrapped in `Err(..)` are constructed before, e.g. `_3 = MyError::Parse(move _2); _0 = Err(move _3)`
+    for local in &finder.err_values {
+        if let Some(constructor) = finder.definitions.get(local) {
+            finder.constructors.insert(constructor.clone());
+        }
+    }
+
+    Some(finder.constructors)
+}
+
+/// Finds the constructions of errors in the MIR of a function.
+struct ErrorConstructionFinder<'a, 'tcx> {
+    context: TyCtxt<'tcx>,
+    def_id: DefId,
+    mir: &'a Body<'tcx>,
+    found: bool,
+    /// The constructors of errors that are not wrapped in `Err(..)` (e.g. `io::Error::new`), or whose wrapped value is known.
+    constructors: BTreeSet<String>,
+    /// The struct, variant or function constructing the value of each local, as far as known.
+    definitions: HashMap<Local, String>,
+    /// The locals wrapped in `Err(..)`.
+    err_values: Vec<Local>,
+}
+
+impl<'a, 'tcx> ErrorConstructionFinder<'a, 'tcx> {
+    /// Get the path of a called function, or of the `From` implementation it resolves to for conversions (`From::from` and `Into::into`).
+    fn get_called_path(&self, called_id: DefId, args: ty::GenericArgsRef<'tcx>) -> String {
+        let is_into = self
+            .context
+            .trait_of_item(called_id)
+            .is_some_and(|trait_id| self.context.is_diagnostic_item(sym::Into, trait_id));
+        let from_args = if self.context.is_diagnostic_item(sym::from_fn, called_id) {
+            Some(args)
+        } else if is_into {
+            // `Into` is implemented using `From` for all types, so `a.into()` is `From::from(a)` with the arguments swapped
+            Some(self.context.mk_args(&[args[1], args[0]]))
+        } else {
+            None
+        };
+
+        let from_fn = self.context.get_diagnostic_item(sym::from_fn);
+        if let (Some(from_fn), Some(from_args)) = (from_fn, from_args) {
+            let param_env = self.context.param_env(self.def_id);
+            if let Ok(Some(instance)) =
+                Instance::resolve(self.context, param_env, from_fn, from_args)
+            {
+                return self.context.def_path_str(instance.def_id());
+            }
+        }
+
+        self.context.def_path_str(called_id)
+    }
+}
+
+impl<'a, 'tcx> Visitor<'tcx> for ErrorConstructionFinder<'a, 'tcx> {
+    fn visit_statement(&mut self, statement: &Statement<'tcx>, location: Location) {
+        if let StatementKind::Assign(assign) = &statement.kind {
+            let (place, rvalue) = &**assign;
+            if let Rvalue::Aggregate(kind, operands) = rvalue {
+                if let AggregateKind::Adt(adt_id, variant, ..) = **kind {
+                    let adt = self.context.adt_def(adt_id);
+                    let variant_id = adt.variant(variant).def_id;
+                    if self.context.lang_items().result_err_variant() == Some(variant_id) {
+                        // An `Err(..)` value is constructed
+                        self.found = true;
+                        if let Some(Operand::Move(value) | Operand::Copy(value)) =
+                            operands.iter().next()
+                        {
+                            if let Some(local) = value.as_local() {
+                                self.err_values.push(local);
+                            }
+                        }
+                    } else if let Some(local) = place.as_local() {
+                        // A struct or variant is constructed, e.g. `MyError::Parse(e)`
+                        let path = if adt.is_enum() { variant_id } else { adt_id };
+                        self.definitions
+                            .insert(local, self.context.def_path_str(path));
+                    }
+                }
+            }
+        }
+
+        self.super_statement(statement, location);
+    }
+
+    fn visit_terminator(&mut self, terminator: &Terminator<'tcx>, location: Location) {
+        if let TerminatorKind::Call {
+            func, destination, ..
+        } = &terminator.kind
+        {
+            if let Some((called_id, args)) = func.const_fn_def() {
+                let path = self.get_called_path(called_id, args);
+
+                // An error constructor is called, e.g. `io::Error::new(..)` or `anyhow::Error::msg(..)`
+                // Trait methods are left out, as these mostly convert (`From::from`) or copy (`Clone::clone`) an existing error
+                let ty = destination.ty(self.mir, self.context).ty;
+                if self.context.trait_of_item(called_id).is_none()
+                    && is_error_type(self.context, ty)
+                {
+                    self.found = true;
+                    self.constructors.insert(path.clone());
+                }
+
+                if let Some(local) = destination.as_local() {
+                    self.definitions.insert(local, path);
+                }
+            }
+        }
+
+        self.super_terminator(terminator, location);
+    }
+}
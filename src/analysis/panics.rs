@@ -0,0 +1,218 @@
+use super::create_graph::{get_mir, get_typeck_results};
+use crate::graph::{CallEdge, CallGraph, CallNodeKind, ChainGraph};
+use crate::stats::PanicStats;
+use rustc_hir::def_id::DefId;
+use rustc_hir::{ExprKind, HirId, Node};
+use rustc_middle::mir::TerminatorKind;
+use rustc_middle::ty::{self, TyCtxt};
+use std::collections::HashMap;
+
+/// Mark the nodes of functions that can panic themselves, and the nodes of their (transitive) callers.
+pub fn attach_panic_info(context: TyCtxt, graph: &mut CallGraph) {
+    // Functions that panic themselves
+    for node in &mut graph.nodes {
+        let def_id = match node.kind {
+            CallNodeKind::LocalFn(def_id, _hir_id) => def_id,
+            // Functions of followed dependencies are explored as well
+            CallNodeKind::NonLocalFn(def_id) if node.cluster.is_some() => def_id,
+            _ => continue,
+        };
+
+        if contains_panic(context, def_id) {
+            node.panics = true;
+        }
+    }
+
+    // Calls whose panics are caught do not make the caller panic
+    for edge in &mut graph.edges {
+        edge.catches_panic = edge
+            .call_id
+            .hir_id()
+            .is_some_and(|call_id| is_in_panic_boundary(context, call_id));
+    }
+
+    // Functions calling panicking functions, until nothing changes anymore
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for edge in &graph.edges {
+            let to = &graph.nodes[edge.to];
+            if !edge.catches_panic
+                && (to.panics || to.may_panic)
+                && !graph.nodes[edge.from].may_panic
+            {
+                graph.nodes[edge.from].may_panic = true;
+                changed = true;
+            }
+        }
+    }
+}
+
+/// Create a graph of the chains through which panics reach the callers of the functions panicking themselves.
+pub fn to_chains(graph: &CallGraph) -> ChainGraph {
+    let mut new_graph = ChainGraph::new(graph.crate_name.clone());
+
+    let mut count: usize = 0;
+    let mut max_depth: usize = 0;
+    // Loop over all nodes (e.g. functions)
+    for node in &graph.nodes {
+        // Start of a chain
+        if node.panics {
+            let mut node_map: HashMap<usize, usize> = HashMap::new();
+
+            let (calls, depth) = get_chain_from_node(graph, node.id(), &mut vec![], 0);
+
+            count += 1;
+            if depth > max_depth {
+                max_depth = depth;
+            }
+
+            for call in calls {
+                // If we've already added the node to the new graph, refer to that, otherwise, add a new node
+                let from = *node_map
+                    .entry(call.from)
+                    .or_insert_with(|| new_graph.add_node(graph.label(call.from)));
+
+                // Ditto
+                let to = *node_map
+                    .entry(call.to)
+                    .or_insert_with(|| new_graph.add_node(graph.label(call.to)));
+
+                let mut label = String::from(if call.catches_panic {
+                    "panic (caught)"
+                } else if call.is_recursive {
+                    "panic (recursive)"
+                } else {
+                    "panic"
+                });
+                // The panic is reported at this call, rather than within the called function
+                if graph.nodes[call.to].track_caller {
+                    label.push_str(" (#[track_caller])");
+                }
+                new_graph.add_edge(from, to, Some(&label), call.location.clone());
+            }
+        }
+    }
+    let panicking_count = graph
+        .nodes
+        .iter()
+        .filter(|node| node.panics || node.may_panic)
+        .count();
+    let caught_count = graph.edges.iter().filter(|edge| edge.catches_panic).count();
+
+    new_graph.stats.panics = Some(PanicStats {
+        count,
+        max_depth,
+        panicking_count,
+        caught_count,
+    });
+    new_graph
+}
+
+/// Get the calls through which a panic of the provided node reaches its (transitive) callers.
+fn get_chain_from_node(
+    graph: &CallGraph,
+    node: usize,
+    explored: &mut Vec<usize>,
+    depth: usize,
+) -> (Vec<CallEdge>, usize) {
+    let mut res = vec![];
+    let mut max_depth = depth;
+
+    explored.push(node);
+
+    // Add all incoming edges to the list
+    // And do the same once for each caller, unless it catches the panic, which ends the chain there
+    for edge in graph.get_incoming_edges(node) {
+        res.push(edge.clone());
+        if !edge.catches_panic && !explored.contains(&edge.from) {
+            let (chain, d) = get_chain_from_node(graph, edge.from, explored, depth + 1);
+            if d > max_depth {
+                max_depth = d;
+            }
+            res.extend(chain);
+        }
+    }
+
+    (res, max_depth)
+}
+
+/// Whether the body of a function calls a panicking function (e.g. through `panic!`, `unreachable!`, `todo!` or `assert!`).
+fn contains_panic(context: TyCtxt, def_id: DefId) -> bool {
+    let Some(mir) = get_mir(context, def_id) else {
+        return false;
+    };
+
+    for block in mir.basic_blocks.iter() {
+        if let Some(terminator) = &block.terminator {
+            if let TerminatorKind::Call { func, .. } = &terminator.kind {
+                if let Some((called_id, _args)) = func.const_fn_def() {
+                    if is_panic_fn(context, called_id) {
+                        return true;
+                    }
+                }
+            }
+        }
+    }
+
+    false
+}
+
+/// Whether a function is one of the functions the panic macros expand to, which all live in `core::panicking` or `std::panicking`.
+fn is_panic_fn(context: TyCtxt, def_id: DefId) -> bool {
+    let krate = context.crate_name(def_id.krate);
+
+    (krate.as_str() == "core" || krate.as_str() == "std")
+        && context
+            .def_path(def_id)
+            .to_string_no_crate_verbose()
+            .starts_with("::panicking::")
+}
+
+/// The functions that run their argument in a way that stops its panics from reaching the caller.
+/// `catch_unwind` returns the panic as an error, and the panics of spawned threads are returned by `JoinHandle::join`.
+const PANIC_BOUNDARIES: [&str; 3] = [
+    "std::panic::catch_unwind",
+    "std::thread::spawn",
+    "std::thread::Builder::spawn",
+];
+
+/// Whether a call (or closure) is part of an argument to a panic boundary, e.g. `catch_unwind(f)`, `catch_unwind(AssertUnwindSafe(f))` or `thread::spawn(|| f())`.
+pub fn is_in_panic_boundary(context: TyCtxt, call_id: HirId) -> bool {
+    let mut child_id = call_id;
+    for (id, node) in context.hir().parent_iter(call_id) {
+        let Node::Expr(expr) = node else {
+            return false;
+        };
+
+        // Only the arguments are run by the boundary, not e.g. the receiver of `builder.spawn(f)`
+        let called_id = match expr.kind {
+            ExprKind::Call(callee, args) if args.iter().any(|arg| arg.hir_id == child_id) => {
+                get_typeck_results(context, expr.hir_id)
+                    .and_then(|typeck_results| typeck_results.expr_ty_opt(callee))
+                    .and_then(|ty| match ty.kind() {
+                        ty::FnDef(def_id, _args) => Some(*def_id),
+                        _ => None,
+                    })
+            }
+            ExprKind::MethodCall(_path, _receiver, args, _span)
+                if args.iter().any(|arg| arg.hir_id == child_id) =>
+            {
+                get_typeck_results(context, expr.hir_id)
+                    .and_then(|typeck_results| typeck_results.type_dependent_def_id(expr.hir_id))
+            }
+            // Calls within closures are attributed to the closure itself
+            ExprKind::Closure(_closure) => return false,
+            _ => None,
+        };
+        if called_id
+            .is_some_and(|def_id| PANIC_BOUNDARIES.contains(&context.def_path_str(def_id).as_str()))
+        {
+            return true;
+        }
+
+        child_id = id;
+    }
+
+    false
+}
@@ -0,0 +1,76 @@
+use crate::graph::{CallGraph, CallNodeKind, Handling};
+use regex::Regex;
+use rustc_middle::ty::TyCtxt;
+
+/// The comment allowing the errors of a call to be lost, followed by what is allowed, e.g. `// error-analyzer: allow(unwrap)`.
+const ALLOW_COMMENT: &str = "error-analyzer: allow(";
+
+/// Mark the calls whose unwrapped or discarded errors are accepted, so the chains ending in them are not reported.
+/// These are made by a function matching one of the ignored paths (e.g. `config::load` or `cli::*`),
+/// or on a line with a comment allowing it, e.g. `// error-analyzer: allow(unwrap)` or `// error-analyzer: allow(discard)`.
+pub fn mark_suppressed_calls(context: TyCtxt, graph: &mut CallGraph, ignore: &[String]) {
+    let patterns = path_patterns(&graph.crate_name, ignore);
+
+    for i in 0..graph.edges.len() {
+        let edge = &graph.edges[i];
+        let kind = match edge.handling {
+            Handling::Unwrapped => "unwrap",
+            Handling::Discarded => "discard",
+            _ => continue,
+        };
+        if !edge.is_error {
+            continue;
+        }
+
+        let caller = graph.full_path(edge.from);
+        let ignored = patterns.iter().any(|pattern| pattern.is_match(&caller));
+        // Only calls in the analyzed crate have a line to comment on
+        let is_local = matches!(graph.nodes[edge.from].kind, CallNodeKind::LocalFn(..));
+        let allowed = edge
+            .call_id
+            .hir_id()
+            .is_some_and(|call_id| is_allowed_by_comment(context, call_id, kind));
+        if ignored || (is_local && allowed) {
+            graph.edges[i].suppressed = true;
+        }
+    }
+}
+
+/// Compile paths relative to the crate where `*` matches any part (e.g. `cli::*`) into regexes matching the whole path of a function.
+pub(super) fn path_patterns(crate_name: &str, paths: &[String]) -> Vec<Regex> {
+    let crate_prefix = format!("{crate_name}::");
+    paths
+        .iter()
+        .filter_map(|pattern| {
+            // Paths are relative to the crate, but may be written from its root
+            let pattern = pattern
+                .strip_prefix("crate::")
+                .or_else(|| pattern.strip_prefix(&crate_prefix))
+                .unwrap_or(pattern);
+            Regex::new(&format!(
+                "^{}$",
+                regex::escape(pattern).replace("\\*", ".*")
+            ))
+            .ok()
+        })
+        .collect()
+}
+
+/// Whether the line of a call has a comment allowing its error to be lost in the given way (`unwrap` or `discard`).
+fn is_allowed_by_comment(context: TyCtxt, call_id: rustc_hir::HirId, kind: &str) -> bool {
+    let span = context.hir().span(call_id);
+    let location = context.sess.source_map().lookup_char_pos(span.hi());
+    let Some(line) = location.file.get_line(location.line - 1) else {
+        return false;
+    };
+    let Some((_code, allowed)) = line.split_once(ALLOW_COMMENT) else {
+        return false;
+    };
+
+    allowed
+        .split(')')
+        .next()
+        .unwrap_or_default()
+        .split(',')
+        .any(|allowed| allowed.trim() == kind)
+}
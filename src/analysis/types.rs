@@ -1,22 +1,71 @@
-use rustc_hir::def_id::DefId;
-use rustc_hir::HirId;
-use rustc_middle::mir::TerminatorKind;
-use rustc_middle::ty::{GenericArg, Interner, Ty, TyCtxt, TyKind};
+use super::create_graph::{get_mir, get_typeck_results};
+use super::mir_calls::MirCallMaps;
+use crate::graph::{CallId, ErrorCategory, ErrorFamily, FailureKind};
+use crate::interner::{Interner, Symbol};
+use rustc_data_structures::fx::FxHashMap;
+use rustc_hir::def::{DefKind, Res};
+use rustc_hir::def_id::{DefId, CRATE_DEF_ID, LOCAL_CRATE};
+use rustc_hir::{ExprKind, HirId, Node};
+use rustc_middle::mir::{BasicBlock, TerminatorKind};
+use rustc_middle::ty::{AssocKind, ParamEnv, Ty, TyCtxt, TyKind, TypeVisitableExt};
+use rustc_span::sym;
+use rustc_span::symbol::Ident;
+use tracing::warn;
 
-/// Get the return type of a called function.
+/// Get the return type of a called function, and whether it is only approximate.
+/// It is approximate if only the generic signature of the called function is known (e.g. `Result<T, E>` for `Ok`),
+/// as the caller could not be type checked and has no MIR.
+/// Calls in the MIR of followed dependencies get the type the MIR stores their result as.
+/// Returns `None` if it is unknown, e.g. for an indirect call in a function that could not be type checked.
 #[allow(clippy::similar_names)]
-fn get_call_type(context: TyCtxt, call_id: HirId, caller_id: DefId, called_id: DefId) -> Ty {
-    if let Some(ty) = get_call_type_using_mir(context, call_id, caller_id) {
-        ty
+fn get_call_type<'tcx>(
+    context: TyCtxt<'tcx>,
+    mir_calls: &MirCallMaps<'tcx>,
+    call_id: CallId,
+    caller_id: Option<DefId>,
+    called_id: Option<DefId>,
+) -> Option<(Ty<'tcx>, bool)> {
+    let call_id = match call_id {
+        CallId::Hir(hir_id) => hir_id,
+        CallId::Mir(def_id, block) => {
+            return get_call_type_in_mir(context, def_id, block).map(|ty| (ty, false));
+        }
+    };
+
+    if let Some(ty) = get_call_type_using_typeck_results(context, call_id) {
+        Some((ty, false))
+    } else if let Some(ty) = get_call_type_using_mir(context, mir_calls, call_id, caller_id) {
+        Some((ty, false))
+    } else if let Some(called_id) = called_id {
+        let ty = get_call_type_using_context(context, called_id);
+        Some((ty, ty.has_param()))
     } else {
-        get_call_type_using_context(context, called_id)
+        get_call_type_using_typeck(context, call_id).map(|ty| (ty, false))
     }
 }
 
+/// Extracts the return type of a call in the MIR of a function of a followed dependency, i.e. the type of the place its result is stored in.
+fn get_call_type_in_mir(context: TyCtxt, def_id: DefId, block: BasicBlock) -> Option<Ty> {
+    let mir = get_mir(context, def_id)?;
+    let TerminatorKind::Call { destination, .. } = &mir.basic_blocks[block].terminator().kind
+    else {
+        return None;
+    };
+    Some(destination.ty(&mir.local_decls, context).ty)
+}
+
 /// Extracts the return type of a called function using just the function's `DefId`.
 /// Should always succeed.
 fn get_call_type_using_context(context: TyCtxt, called_id: DefId) -> Ty {
-    if context.type_of(called_id).instantiate_identity().is_fn() {
+    if let TyKind::Closure(_def_id, args) = context.type_of(called_id).instantiate_identity().kind()
+    {
+        args.as_closure().sig().output().skip_binder()
+    } else if let TyKind::Coroutine(_def_id, args) =
+        context.type_of(called_id).instantiate_identity().kind()
+    {
+        // The value of an async block, which is what awaiting it gives
+        args.as_coroutine().return_ty()
+    } else if context.type_of(called_id).instantiate_identity().is_fn() {
         context
             .fn_sig(called_id)
             .instantiate_identity()
@@ -27,101 +76,592 @@ fn get_call_type_using_context(context: TyCtxt, called_id: DefId) -> Ty {
     }
 }
 
+/// Extracts the return type of a call using the type of the call expression itself.
+/// Used for indirect calls, where there is no called `DefId`.
+fn get_call_type_using_typeck(context: TyCtxt, call_id: HirId) -> Option<Ty> {
+    get_typeck_results(context, call_id)?.node_type_opt(call_id)
+}
+
+/// Extracts the return type of an explicit call (e.g. `f(x)` or `x.f()`) using the type of the call expression.
+/// This is the fully instantiated return type, e.g. `Result<u32, ParseIntError>` for `"1".parse::<u32>()`.
+/// Functions passed by path (e.g. `str::parse` in `iter.map(str::parse)`) get the return type of their instantiated signature.
+/// Returns `None` for implicit calls (e.g. operators or drops), whose expression does not have the return type of the call.
+fn get_call_type_using_typeck_results(context: TyCtxt, call_id: HirId) -> Option<Ty> {
+    let Node::Expr(call_expr) = context.hir_node(call_id) else {
+        return None;
+    };
+    if let ExprKind::Path(_qpath) = call_expr.kind {
+        let ty = get_typeck_results(context, call_id)?.node_type_opt(call_id)?;
+        let TyKind::FnDef(def_id, args) = ty.kind() else {
+            return None;
+        };
+        let output = context
+            .fn_sig(def_id)
+            .instantiate(context, args)
+            .output()
+            .skip_binder();
+        let param_env = context.param_env(call_id.owner.to_def_id());
+        return Some(
+            context
+                .try_normalize_erasing_regions(param_env, output)
+                .unwrap_or(output),
+        );
+    }
+    if !matches!(
+        call_expr.kind,
+        ExprKind::Call(..) | ExprKind::MethodCall(..)
+    ) {
+        return None;
+    }
+
+    get_typeck_results(context, call_id)?.node_type_opt(call_id)
+}
+
 /// Extracts the return type of a called function using its call's `HirId`, as well as the caller's `DefId`.
+/// Used as a fallback, as it matches the call's span against the calls in the MIR.
 /// Returns `None` if no MIR is available or the call was not found (e.g. due to desugaring/optimizations).
-fn get_call_type_using_mir(context: TyCtxt, call_id: HirId, caller_id: DefId) -> Option<Ty> {
-    if !context.is_mir_available(caller_id) {
+fn get_call_type_using_mir<'tcx>(
+    context: TyCtxt<'tcx>,
+    mir_calls: &MirCallMaps<'tcx>,
+    call_id: HirId,
+    caller_id: Option<DefId>,
+) -> Option<Ty<'tcx>> {
+    // Implicit calls (e.g. drops) do not have a call expression
+    let Node::Expr(call_expr) = context.hir_node(call_id) else {
         return None;
+    };
+
+    mir_calls
+        .get(context, caller_id?, call_expr.span)
+        .map(|call| call.ty)
+}
+
+/// The type of a call, as shown on its edge.
+pub struct CallType<'tcx> {
+    /// The error type if the call can fail, otherwise the full return type.
+    pub ty: Ty<'tcx>,
+    /// The label shown instead of the type, if any (e.g. `Box<dyn Error>` for any boxed `dyn Error`).
+    fixed_label: Option<&'static str>,
+    /// How the call can fail, if it can.
+    pub failure_kind: Option<FailureKind>,
+    /// Whether the error is a boxed `dyn Error`, hiding its concrete type.
+    pub type_erased: bool,
+    /// The error handling library the error type belongs to, if any.
+    pub error_family: Option<ErrorFamily>,
+    /// What kind of type the error is, if it is the error of a `Result`.
+    pub error_category: Option<ErrorCategory>,
+    /// The error type, if it is an enum defined in the analyzed crate.
+    pub error_enum: Option<DefId>,
+    /// Whether the type is only approximate, as it is taken from the generic signature of the called function.
+    pub approximate: bool,
+}
+
+impl<'tcx> CallType<'tcx> {
+    /// The type of a call that cannot fail.
+    fn infallible(ty: Ty<'tcx>) -> Self {
+        CallType {
+            ty,
+            fixed_label: None,
+            failure_kind: None,
+            type_erased: false,
+            error_family: None,
+            error_category: None,
+            error_enum: None,
+            approximate: false,
+        }
     }
 
-    let mir = context.optimized_mir(caller_id);
-    let call_expr = context.hir_node(call_id).expect_expr();
-
-    for block in mir.basic_blocks.iter() {
-        if let Some(terminator) = &block.terminator {
-            if let TerminatorKind::Call { func, fn_span, .. } = &terminator.kind {
-                if call_expr.span.hi() == fn_span.hi() {
-                    if let Some((def_id, args)) = func.const_fn_def() {
-                        return Some(
-                            context
-                                .type_of_instantiated(def_id, args)
-                                .fn_sig(context)
-                                .output()
-                                .skip_binder(),
-                        );
-                    }
-                }
-            }
+    /// The type of a call that can fail.
+    fn fallible(ty: Ty<'tcx>, failure_kind: FailureKind) -> Self {
+        CallType {
+            ty,
+            fixed_label: None,
+            failure_kind: Some(failure_kind),
+            type_erased: false,
+            error_family: None,
+            error_category: None,
+            error_enum: None,
+            approximate: false,
         }
     }
 
-    None
+    /// The type of a call failing with `None`.
+    fn none(ty: Ty<'tcx>) -> Self {
+        CallType {
+            fixed_label: Some("None"),
+            ..CallType::fallible(ty, FailureKind::Option)
+        }
+    }
+
+    /// Get the label of this type, as shown on its edge.
+    pub fn label(&self) -> String {
+        self.fixed_label
+            .map_or_else(|| format!("{}", self.ty), String::from)
+    }
 }
 
-/// Extract the error type from Result, or return the full type if it doesn't contain a Result (along with a flag of whether it is an extract error).
+/// The labels of the types of calls, which formats each distinct type once, as many calls share their type.
+#[derive(Default)]
+pub struct TypeLabels<'tcx> {
+    labels: FxHashMap<Ty<'tcx>, Symbol>,
+}
+
+impl<'tcx> TypeLabels<'tcx> {
+    /// Get the label of the type of a call, interned in the given strings.
+    pub fn get(&mut self, call_type: &CallType<'tcx>, strings: &mut Interner) -> Symbol {
+        if let Some(label) = call_type.fixed_label {
+            return strings.intern(label);
+        }
+        *self
+            .labels
+            .entry(call_type.ty)
+            .or_insert_with(|| strings.intern(&format!("{}", call_type.ty)))
+    }
+}
+
+/// Extract the error type from Result, or `None` from Option, or return the full type if it is neither (along with the kind of failure if it is one).
+/// Only a returned Option itself is a failure, as it is common to return e.g. collections of Options.
+/// Other types implementing `Try` fail with the error in their residual.
+/// Boxed trait object errors are labeled `Box<dyn Error>`, and flagged as type-erased.
+/// Types listed as Result-like (e.g. newtypes around a Result) fail with the error they carry.
+/// Returns `None` if the type of the call is unknown (e.g. as type checking the caller failed).
 #[allow(clippy::similar_names)]
-pub fn get_error_or_type(
-    context: TyCtxt,
-    call_id: HirId,
-    caller_id: DefId,
-    called_id: DefId,
-) -> (String, bool) {
-    let ret_ty = get_call_type(context, call_id, caller_id, called_id);
+pub fn get_error_or_type<'tcx>(
+    context: TyCtxt<'tcx>,
+    mir_calls: &MirCallMaps<'tcx>,
+    call_id: CallId,
+    caller_id: Option<DefId>,
+    called_id: Option<DefId>,
+    result_types: &[DefId],
+) -> Option<CallType<'tcx>> {
+    let (ret_ty, approximate) = get_call_type(context, mir_calls, call_id, caller_id, called_id)?;
 
-    let result = if context.ty_is_opaque_future(ret_ty) {
-        extract_result_from_future(context, ret_ty)
+    let mut call_type = get_error_or_type_of(context, ret_ty, caller_id, result_types);
+    // Fixed labels (e.g. `None`) do not depend on the generic parameters
+    call_type.approximate = approximate && call_type.fixed_label.is_none();
+    Some(call_type)
+}
+
+/// Extract the error type, or `None`, from the return type of a call, as described for `get_error_or_type`.
+fn get_error_or_type_of<'tcx>(
+    context: TyCtxt<'tcx>,
+    ret_ty: Ty<'tcx>,
+    caller_id: Option<DefId>,
+    result_types: &[DefId],
+) -> CallType<'tcx> {
+    // Types the user listed as carrying a Result
+    if ret_ty
+        .ty_adt_def()
+        .is_some_and(|adt| result_types.contains(&adt.did()))
+    {
+        if let Some(call_type) = get_result_like_error(context, ret_ty) {
+            return call_type;
+        }
+    }
+
+    // Other types that can be used with the try operator (e.g. `Poll<Result<T, E>>` or `ControlFlow<B, C>`)
+    if !is_result(context, ret_ty) && !is_option(context, ret_ty) {
+        if let Some(residual) = get_try_residual(context, ret_ty, caller_id) {
+            return get_error_from_residual(context, residual);
+        }
+    }
+
+    // The output of a future (e.g. of an async fn) is what the caller gets when awaiting it
+    let ty = if context.ty_is_opaque_future(ret_ty) {
+        get_future_output(context, ret_ty, caller_id).unwrap_or(ret_ty)
+    } else if let Some(output) = get_boxed_future_output(context, ret_ty) {
+        output
     } else {
-        extract_result(ret_ty)
+        ret_ty
     };
 
-    let res = extract_error_from_result(result);
-
-    (res.clone().unwrap_or(format!("{ret_ty}")), res.is_some())
+    if let Some(error) = extract_result(context, ty).map(extract_error_from_result) {
+        get_result_error_type(context, error)
+    } else if is_option(context, ty) {
+        CallType::none(ty)
+    } else {
+        CallType::infallible(ret_ty)
+    }
 }
 
-/// Extract the Result type from any type.
-fn extract_result(ty: Ty) -> Option<GenericArg> {
-    for arg in ty.walk() {
-        let format = format!("{arg}");
-        if format.starts_with("std::result::Result<") && format.ends_with('>') {
-            return Some(arg);
+/// Resolve the paths of the types listed as Result-like (e.g. `my_crate::Outcome`) to their definitions.
+/// Type aliases are resolved to the type they stand for, as aliases are expanded in the types of calls.
+pub fn resolve_result_types(context: TyCtxt, paths: &[String]) -> Vec<DefId> {
+    let mut res = vec![];
+
+    for path in paths {
+        let Some(def_id) = resolve_type_path(context, path) else {
+            warn!(
+                target: "analyzer::graph",
+                "Could not resolve Result-like type {path}, ignoring it!"
+            );
+            continue;
+        };
+
+        if context.def_kind(def_id) == DefKind::TyAlias {
+            if let Some(adt) = context.type_of(def_id).instantiate_identity().ty_adt_def() {
+                res.push(adt.did());
+            }
+        } else {
+            res.push(def_id);
         }
     }
 
-    None
-}
-
-/// Extract the Result type from any future.
-fn extract_result_from_future<'a>(context: TyCtxt<'a>, ty: Ty<'a>) -> Option<GenericArg<'a>> {
-    for t in ty.walk() {
-        if let Some(typ) = t.as_type() {
-            if let TyKind::Alias(_kind, alias) = typ.kind() {
-                if let TyKind::Coroutine(_def_id, args) =
-                    context.type_of(alias.def_id).instantiate_identity().kind()
-                {
-                    for arg in *args {
-                        let format = format!("{arg}");
-                        if format.starts_with("std::result::Result<") && format.ends_with('>') {
-                            return Some(arg);
-                        }
-                    }
-                }
+    res
+}
+
+/// Resolve the path of a type (e.g. `std::io::Result` or `crate::Outcome`) by walking the modules of its crate.
+fn resolve_type_path(context: TyCtxt, path: &str) -> Option<DefId> {
+    let mut segments = path.split("::");
+    let krate = segments.next()?;
+
+    let mut current = if krate == "crate" || krate == context.crate_name(LOCAL_CRATE).as_str() {
+        CRATE_DEF_ID.to_def_id()
+    } else {
+        context
+            .crates(())
+            .iter()
+            .find(|id| context.crate_name(**id).as_str() == krate)?
+            .as_def_id()
+    };
+
+    for segment in segments {
+        let children = if let Some(local_id) = current.as_local() {
+            context.module_children_local(local_id)
+        } else {
+            context.module_children(current)
+        };
+
+        current = children
+            .iter()
+            .filter(|child| child.ident.as_str() == segment)
+            .find_map(|child| match child.res {
+                Res::Def(
+                    DefKind::Mod
+                    | DefKind::Struct
+                    | DefKind::Enum
+                    | DefKind::Union
+                    | DefKind::TyAlias,
+                    def_id,
+                ) => Some(def_id),
+                _ => None,
+            })?;
+    }
+
+    Some(current)
+}
+
+/// Extract the error from a type listed as Result-like.
+/// This is the error of the Result in one of its fields (e.g. for a newtype), otherwise its last type argument.
+fn get_result_like_error<'tcx>(context: TyCtxt<'tcx>, ty: Ty<'tcx>) -> Option<CallType<'tcx>> {
+    let TyKind::Adt(adt, args) = ty.kind() else {
+        return None;
+    };
+
+    for field in adt.all_fields() {
+        let field_ty = field.ty(context, args);
+        if let TyKind::Adt(field_adt, field_args) = field_ty.kind() {
+            if context.is_diagnostic_item(sym::Result, field_adt.did()) {
+                return Some(get_result_error_type(context, field_args.type_at(1)));
             }
         }
     }
 
-    None
+    args.types()
+        .last()
+        .map(|error| get_result_error_type(context, error))
+}
+
+/// Get the type of a call failing with the error of a Result.
+/// Type-erased errors (i.e. a boxed `dyn Error`) are labeled `Box<dyn Error>`.
+fn get_result_error_type<'tcx>(context: TyCtxt<'tcx>, error: Ty<'tcx>) -> CallType<'tcx> {
+    let mut call_type = CallType::fallible(error, FailureKind::Result);
+
+    if is_type_erased(context, error) {
+        call_type.fixed_label = Some("Box<dyn Error>");
+        call_type.type_erased = true;
+    } else if is_panic_payload(context, error) {
+        call_type.fixed_label = Some("Box<dyn Any> (panic payload)");
+    }
+    call_type.error_family = get_error_family(context, error);
+    call_type.error_category = Some(get_error_category(context, error));
+    call_type.error_enum = error
+        .ty_adt_def()
+        .filter(|adt| adt.is_enum() && adt.did().is_local())
+        .map(|adt| adt.did());
+
+    call_type
+}
+
+/// Get what kind of type an error is, e.g. a string for `String` or `&str`.
+/// Errors behind a reference or in a box are categorized by the type they point to, e.g. `Box<dyn Error>` is a trait object.
+fn get_error_category(context: TyCtxt, error: Ty) -> ErrorCategory {
+    match error.kind() {
+        TyKind::Tuple(types) if types.is_empty() => ErrorCategory::Unit,
+        TyKind::Str => ErrorCategory::String,
+        TyKind::Dynamic(..) => ErrorCategory::TraitObject,
+        TyKind::Param(..) | TyKind::Alias(..) => ErrorCategory::Generic,
+        TyKind::Ref(_region, ty, _mutability) => get_error_category(context, *ty),
+        TyKind::Adt(..) if error.is_box() => get_error_category(context, error.boxed_ty()),
+        TyKind::Adt(adt, _args) if context.lang_items().string() == Some(adt.did()) => {
+            ErrorCategory::String
+        }
+        // Error handling libraries wrap a trait object
+        TyKind::Adt(..) if get_error_family(context, error).is_some() => ErrorCategory::TraitObject,
+        TyKind::Adt(..) => ErrorCategory::Concrete,
+        _ => ErrorCategory::Other,
+    }
+}
+
+/// Get the type of the error a function is declared to return, if it returns a `Result` (also when awaited, for async functions).
+pub fn get_declared_error_type(context: TyCtxt, def_id: DefId) -> Option<CallType> {
+    let ty = get_call_type_using_context(context, def_id);
+    let ty = if context.ty_is_opaque_future(ty) {
+        get_future_output(context, ty, Some(def_id))?
+    } else {
+        ty
+    };
+    is_result(context, ty).then(|| get_result_error_type(context, extract_error_from_result(ty)))
+}
+
+/// Get the error handling library an error type belongs to (e.g. `anyhow::Error`), if any.
+pub fn get_error_family(context: TyCtxt, ty: Ty) -> Option<ErrorFamily> {
+    let TyKind::Adt(adt, _args) = ty.kind() else {
+        return None;
+    };
+
+    match context.crate_name(adt.did().krate).as_str() {
+        "anyhow" => Some(ErrorFamily::Anyhow),
+        "eyre" => Some(ErrorFamily::Eyre),
+        _ => None,
+    }
 }
 
-/// Extract the error from a Result type.
-fn extract_error_from_result(opt: Option<GenericArg>) -> Option<String> {
-    let t = opt?;
-    for arg in t.walk() {
-        let f = format!("{arg}");
-        if format!("{t}").ends_with(&format!(", {f}>")) {
-            return Some(f);
+/// Whether a type is an error, i.e. it implements `std::error::Error` or belongs to an error handling library (e.g. `anyhow::Error`).
+pub fn is_error_type<'tcx>(context: TyCtxt<'tcx>, ty: Ty<'tcx>) -> bool {
+    if get_error_family(context, ty).is_some() {
+        return true;
+    }
+    // All implementations are relevant for e.g. type parameters, so only (concrete) structs and enums are looked up
+    if !matches!(ty.kind(), TyKind::Adt(..)) {
+        return false;
+    }
+    let Some(error_trait) = context.get_diagnostic_item(sym::Error) else {
+        return false;
+    };
+
+    // Blanket implementations (e.g. for `&E` where `E: Error`) only count if the type is an error already
+    let mut implements = false;
+    context.for_each_relevant_impl(error_trait, ty, |impl_id| {
+        if !matches!(
+            context.type_of(impl_id).instantiate_identity().kind(),
+            TyKind::Param(_)
+        ) {
+            implements = true;
         }
+    });
+    implements
+}
+
+/// Whether a type label mentions the type with the given path, as the type itself or one of its type arguments.
+/// The path may be shortened from the front, e.g. `io::Error` matches `std::io::Error` and `Box<std::io::Error>`, but not `MyIoError`.
+pub fn label_mentions_type(label: &str, path: &str) -> bool {
+    let suffix = format!("::{path}");
+    label
+        .split(|c: char| !(c.is_alphanumeric() || c == '_' || c == ':'))
+        .any(|segment| segment == path || segment.ends_with(&suffix))
+}
+
+/// Whether an error type is a boxed `dyn Error` (e.g. `Box<dyn Error + Send + Sync>`), hiding the concrete error.
+pub fn is_type_erased(context: TyCtxt, ty: Ty) -> bool {
+    if !ty.is_box() {
+        return false;
+    }
+
+    if let TyKind::Dynamic(predicates, _region, _kind) = ty.boxed_ty().kind() {
+        predicates
+            .principal_def_id()
+            .is_some_and(|def_id| context.is_diagnostic_item(sym::Error, def_id))
+    } else {
+        false
+    }
+}
+
+/// Whether an error type is a boxed `dyn Any` (e.g. `Box<dyn Any + Send>`), which is how caught panics are returned (e.g. by `catch_unwind`).
+fn is_panic_payload(context: TyCtxt, ty: Ty) -> bool {
+    if !ty.is_box() {
+        return false;
+    }
+
+    if let TyKind::Dynamic(predicates, _region, _kind) = ty.boxed_ty().kind() {
+        predicates
+            .principal_def_id()
+            .is_some_and(|def_id| context.is_diagnostic_item(sym::Any, def_id))
+    } else {
+        false
+    }
+}
+
+/// Get the residual of a type implementing `Try`, e.g. `Result<Infallible, E>` for `Poll<Result<T, E>>`.
+/// This is the type the try operator returns early with.
+pub fn get_try_residual<'tcx>(
+    context: TyCtxt<'tcx>,
+    ty: Ty<'tcx>,
+    caller_id: Option<DefId>,
+) -> Option<Ty<'tcx>> {
+    let try_trait = context.lang_items().try_trait()?;
+    get_associated_type(context, try_trait, "Residual", ty, caller_id)
+}
+
+/// Get the output of a future, e.g. `Result<T, E>` for the future returned by an `async fn` returning `Result<T, E>`.
+fn get_future_output<'tcx>(
+    context: TyCtxt<'tcx>,
+    ty: Ty<'tcx>,
+    caller_id: Option<DefId>,
+) -> Option<Ty<'tcx>> {
+    let future_trait = context.lang_items().future_trait()?;
+    get_associated_type(context, future_trait, "Output", ty, caller_id)
+}
+
+/// Get the output of a boxed future, e.g. `Result<T, E>` for `Pin<Box<dyn Future<Output = Result<T, E>> + Send>>`.
+/// These are returned by methods of `#[async_trait]` traits, and by functions boxing their futures manually.
+fn get_boxed_future_output<'tcx>(context: TyCtxt<'tcx>, ty: Ty<'tcx>) -> Option<Ty<'tcx>> {
+    let future_trait = context.lang_items().future_trait()?;
+
+    // Look through the pointers to the future, e.g. `Pin<Box<dyn Future>>` or `&mut dyn Future`
+    let mut ty = ty;
+    loop {
+        match ty.kind() {
+            TyKind::Adt(adt, args)
+                if adt.is_box() || context.lang_items().pin_type() == Some(adt.did()) =>
+            {
+                ty = args.type_at(0);
+            }
+            TyKind::Ref(_region, inner, _mutability) => ty = *inner,
+            _ => break,
+        }
+    }
+
+    // The output is bound in the trait object, e.g. `dyn Future<Output = T>`
+    let TyKind::Dynamic(predicates, _region, _kind) = ty.kind() else {
+        return None;
+    };
+    if predicates.principal_def_id() != Some(future_trait) {
+        return None;
+    }
+
+    predicates
+        .projection_bounds()
+        .find(|projection| context.parent(projection.skip_binder().def_id) == future_trait)
+        .and_then(|projection| projection.skip_binder().term.ty())
+}
+
+/// Get an associated type of the implementation of a trait for a type, e.g. `<ty as Try>::Residual`.
+/// Returns `None` if the type does not implement the trait.
+fn get_associated_type<'tcx>(
+    context: TyCtxt<'tcx>,
+    trait_id: DefId,
+    name: &str,
+    ty: Ty<'tcx>,
+    caller_id: Option<DefId>,
+) -> Option<Ty<'tcx>> {
+    let associated_type = context.associated_items(trait_id).find_by_name_and_kind(
+        context,
+        Ident::from_str(name),
+        AssocKind::Type,
+        trait_id,
+    )?;
+
+    let param_env = caller_id.map_or(ParamEnv::reveal_all(), |id| {
+        context.param_env_reveal_all_normalized(id)
+    });
+    let projection =
+        Ty::new_projection(context, associated_type.def_id, [context.erase_regions(ty)]);
+
+    // Normalizing fails if the type does not implement the trait
+    let res = context
+        .try_normalize_erasing_regions(param_env, projection)
+        .ok()?;
+    if matches!(res.kind(), TyKind::Alias(..)) {
+        return None;
     }
 
-    None
+    Some(res)
+}
+
+/// Extract the error from the residual of a type implementing `Try`.
+/// Residuals keep the error, and replace the value by an uninhabited type (e.g. `ControlFlow<B, Infallible>`).
+fn get_error_from_residual<'tcx>(context: TyCtxt<'tcx>, residual: Ty<'tcx>) -> CallType<'tcx> {
+    let TyKind::Adt(adt, args) = residual.kind() else {
+        return CallType::fallible(residual, FailureKind::Residual);
+    };
+
+    if context.is_diagnostic_item(sym::Result, adt.did()) {
+        return get_result_error_type(context, args.type_at(1));
+    }
+    if context.is_diagnostic_item(sym::Option, adt.did()) {
+        return CallType::none(residual);
+    }
+
+    let mut errors = args.types().filter(|ty| !is_uninhabited(*ty));
+    match (errors.next(), errors.next()) {
+        // The break value of `Try::branch` is a residual itself, e.g. `ControlFlow<Result<Infallible, E>, T>`
+        (Some(error), None) if is_residual(error) => get_error_from_residual(context, error),
+        (Some(error), None) => CallType::fallible(error, FailureKind::Residual),
+        _ => CallType::fallible(residual, FailureKind::Residual),
+    }
+}
+
+/// Whether a type looks like the residual of a type implementing `Try`, i.e. has an uninhabited type argument.
+fn is_residual(ty: Ty) -> bool {
+    if let TyKind::Adt(_adt, args) = ty.kind() {
+        args.types().any(is_uninhabited)
+    } else {
+        false
+    }
+}
+
+/// Whether a type has no values, e.g. `!` or `Infallible`.
+fn is_uninhabited(ty: Ty) -> bool {
+    match ty.kind() {
+        TyKind::Never => true,
+        TyKind::Adt(adt, _args) => adt.is_enum() && adt.variants().is_empty(),
+        _ => false,
+    }
+}
+
+/// Whether a type is a Result.
+fn is_result(context: TyCtxt, ty: Ty) -> bool {
+    if let TyKind::Adt(adt, _args) = ty.kind() {
+        context.is_diagnostic_item(sym::Result, adt.did())
+    } else {
+        false
+    }
+}
+
+/// Whether a type is an Option.
+fn is_option(context: TyCtxt, ty: Ty) -> bool {
+    if let TyKind::Adt(adt, _args) = ty.kind() {
+        context.is_diagnostic_item(sym::Option, adt.did())
+    } else {
+        false
+    }
+}
+
+/// Extract the Result type from any type, e.g. `Result<T, E>` from `Vec<Result<T, E>>`.
+/// The walk is pre-order, so the outermost Result is found first, e.g. `Result<(), Result<A, B>>`
+/// itself rather than `Result<A, B>`.
+fn extract_result<'tcx>(context: TyCtxt<'tcx>, ty: Ty<'tcx>) -> Option<Ty<'tcx>> {
+    ty.walk()
+        .filter_map(|arg| arg.as_type())
+        .find(|ty| is_result(context, *ty))
+}
+
+/// Extract the error from a Result type, which is always its second generic argument.
+fn extract_error_from_result(result: Ty) -> Ty {
+    let TyKind::Adt(_adt, args) = result.kind() else {
+        unreachable!("Result is not an ADT!");
+    };
+
+    args.type_at(1)
 }
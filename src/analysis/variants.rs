@@ -0,0 +1,158 @@
+use super::create_graph::get_mir;
+use crate::graph::{CallGraph, CallNodeKind};
+use rustc_hir::def::{CtorOf, DefKind};
+use rustc_hir::def_id::DefId;
+use rustc_middle::mir::visit::Visitor;
+use rustc_middle::mir::{AggregateKind, ConstOperand, Location, Rvalue};
+use rustc_middle::ty::{self, TyCtxt};
+use std::collections::{BTreeSet, HashMap};
+
+/// The variants of an error enum a function can return.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct VariantSet {
+    variants: BTreeSet<String>,
+    /// Whether some of the returned errors come from somewhere the variants cannot be determined (e.g. an indirect call).
+    incomplete: bool,
+}
+
+/// Label the edges returning a local error enum with the variants that can be returned, e.g. `MyError::{Io, Parse}`.
+/// The variants a function constructs itself are found in its MIR, and are combined with those of the calls it propagates.
+/// Since this is an approximation, an `…` marks sets that could not be fully determined.
+pub fn attach_error_variants(context: TyCtxt, graph: &mut CallGraph) {
+    // The variants each function constructs itself, for each error enum it is called for
+    let mut sets: HashMap<(usize, DefId), VariantSet> = HashMap::new();
+    for edge in &graph.edges {
+        if let Some(enum_id) = edge.error_enum {
+            add_constructed_variants(context, graph, &mut sets, edge.to, enum_id);
+        }
+    }
+
+    // Add the variants of the propagated calls, until nothing changes anymore
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for edge in &graph.edges {
+            let Some(enum_id) = edge
+                .error_enum
+                .filter(|_id| edge.is_error && edge.propagates)
+            else {
+                continue;
+            };
+
+            add_constructed_variants(context, graph, &mut sets, edge.from, enum_id);
+            add_constructed_variants(context, graph, &mut sets, edge.to, enum_id);
+            let called = sets[&(edge.to, enum_id)].clone();
+            let caller = sets
+                .get_mut(&(edge.from, enum_id))
+                .expect("Variants of caller not collected!");
+
+            let before = caller.clone();
+            caller.variants.extend(called.variants);
+            caller.incomplete |= called.incomplete
+                || matches!(
+                    graph.nodes[edge.to].kind,
+                    CallNodeKind::Indirect(_) | CallNodeKind::Unresolved(_)
+                );
+            changed |= *caller != before;
+        }
+    }
+
+    // Label the edges
+    for edge in &mut graph.edges {
+        let Some(enum_id) = edge.error_enum.filter(|_id| edge.is_error) else {
+            continue;
+        };
+        // Only the bodies of local functions are inspected
+        if !matches!(graph.nodes[edge.to].kind, CallNodeKind::LocalFn(..)) {
+            continue;
+        }
+
+        let set = &sets[&(edge.to, enum_id)];
+        let mut variants: Vec<&str> = set.variants.iter().map(String::as_str).collect();
+        if set.incomplete || variants.is_empty() {
+            variants.push("…");
+        }
+        edge.error_variants = Some(format!(
+            "{}::{{{}}}",
+            context.def_path_str(enum_id),
+            variants.join(", ")
+        ));
+    }
+}
+
+/// Collect the variants of an error enum a function constructs itself, if not done already.
+fn add_constructed_variants(
+    context: TyCtxt,
+    graph: &CallGraph,
+    sets: &mut HashMap<(usize, DefId), VariantSet>,
+    node: usize,
+    enum_id: DefId,
+) {
+    sets.entry((node, enum_id)).or_insert_with(|| {
+        let mut collector = VariantCollector {
+            context,
+            enum_id,
+            variants: BTreeSet::new(),
+        };
+        if let CallNodeKind::LocalFn(def_id, _hir_id) = graph.nodes[node].kind {
+            collector.visit_fn(def_id);
+        }
+
+        VariantSet {
+            variants: collector.variants,
+            incomplete: false,
+        }
+    });
+}
+
+/// Collects the variants of an enum constructed in a function, including the closures defined in it.
+struct VariantCollector<'tcx> {
+    context: TyCtxt<'tcx>,
+    enum_id: DefId,
+    variants: BTreeSet<String>,
+}
+
+impl<'tcx> VariantCollector<'tcx> {
+    /// Visit the MIR of a function (or closure).
+    fn visit_fn(&mut self, def_id: DefId) {
+        if let Some(mir) = get_mir(self.context, def_id) {
+            self.visit_body(mir);
+        }
+    }
+}
+
+impl<'tcx> Visitor<'tcx> for VariantCollector<'tcx> {
+    fn visit_rvalue(&mut self, rvalue: &Rvalue<'tcx>, location: Location) {
+        if let Rvalue::Aggregate(kind, _operands) = rvalue {
+            match **kind {
+                // A variant is constructed, e.g. `MyError::Parse(e)`
+                AggregateKind::Adt(adt_id, variant, ..) if adt_id == self.enum_id => {
+                    let name = self.context.adt_def(adt_id).variant(variant).name;
+                    self.variants.insert(name.to_string());
+                }
+                // A closure is defined, which may construct variants, e.g. `map_err(|e| MyError::Parse(e))`
+                AggregateKind::Closure(closure_id, _args) => self.visit_fn(closure_id),
+                _ => {}
+            }
+        }
+
+        self.super_rvalue(rvalue, location);
+    }
+
+    fn visit_constant(&mut self, constant: &ConstOperand<'tcx>, location: Location) {
+        // A variant constructor is used as a function, e.g. `map_err(MyError::Parse)`
+        if let ty::FnDef(def_id, _args) = constant.const_.ty().kind() {
+            if let DefKind::Ctor(CtorOf::Variant, _kind) = self.context.def_kind(*def_id) {
+                let adt = self
+                    .context
+                    .adt_def(self.context.parent(self.context.parent(*def_id)));
+                if adt.did() == self.enum_id {
+                    let name = adt.variant_with_ctor_id(*def_id).name;
+                    self.variants.insert(name.to_string());
+                }
+            }
+        }
+
+        self.super_constant(constant, location);
+    }
+}
@@ -0,0 +1,176 @@
+use crate::graph::ChainEnd;
+use serde_json::Value;
+use std::fmt;
+
+/// The differences between the chains of a program and those of a baseline, as written using `--chains-json`.
+/// Chains are matched by the paths of the functions they call through and how they end, so they match across unrelated changes.
+#[derive(Debug, Clone, Default)]
+pub struct BaselineComparison {
+    /// The number of chains of the baseline.
+    pub baseline_count: usize,
+    /// The chains that are not in the baseline.
+    pub new: Vec<String>,
+    /// The chains of the baseline that are gone.
+    pub removed: Vec<String>,
+    /// The chains ending worse than in the baseline (e.g. unwrapped instead of handled), with how they ended before.
+    pub degraded: Vec<String>,
+    /// The chains ending better than in the baseline, with how they ended before.
+    pub improved: Vec<String>,
+}
+
+/// A chain as read from JSON, with what identifies it.
+struct ChainRecord {
+    /// The calls of the chain as `caller → callee`, sorted.
+    calls: Vec<String>,
+    termination: ChainEnd,
+    description: String,
+}
+
+/// Compare the chains of a program with those of a baseline, both in the JSON written using `--chains-json`.
+/// Returns an error if either cannot be parsed.
+pub fn compare(baseline: &str, current: &str) -> Result<BaselineComparison, String> {
+    let baseline = parse_chains(baseline)?;
+    let current = parse_chains(current)?;
+
+    let mut comparison = BaselineComparison {
+        baseline_count: baseline.len(),
+        ..BaselineComparison::default()
+    };
+
+    // Chains ending the same way are matched first, the chains calling through the same functions left are changed
+    let mut unmatched: Vec<&ChainRecord> = baseline.iter().collect();
+    let mut changed: Vec<&ChainRecord> = vec![];
+    for chain in &current {
+        let matching = unmatched
+            .iter()
+            .position(|old| old.calls == chain.calls && old.termination == chain.termination);
+        match matching {
+            Some(i) => {
+                unmatched.remove(i);
+            }
+            None => changed.push(chain),
+        }
+    }
+    for chain in changed {
+        let Some(i) = unmatched.iter().position(|old| old.calls == chain.calls) else {
+            comparison.new.push(chain.description.clone());
+            continue;
+        };
+        let old = unmatched.remove(i);
+        let description = format!("{} (was {})", chain.description, old.termination.label());
+        if chain.termination.severity() > old.termination.severity() {
+            comparison.degraded.push(description);
+        } else {
+            comparison.improved.push(description);
+        }
+    }
+    comparison.removed = unmatched
+        .into_iter()
+        .map(|old| old.description.clone())
+        .collect();
+
+    Ok(comparison)
+}
+
+/// Parse the chains written using `--chains-json`.
+/// The functions of the calls are indices in the table of strings, or the strings themselves in chains written before it was added.
+fn parse_chains(json: &str) -> Result<Vec<ChainRecord>, String> {
+    let json: Value = serde_json::from_str(json).map_err(|e| e.to_string())?;
+    let (strings, chains) = match &json {
+        Value::Object(object) => (
+            object["strings"]
+                .as_array()
+                .ok_or("Expected a table of strings")?
+                .as_slice(),
+            object["chains"]
+                .as_array()
+                .ok_or("Expected an array of chains")?,
+        ),
+        _ => (
+            &[][..],
+            json.as_array().ok_or("Expected an array of chains")?,
+        ),
+    };
+    let get_string = |value: &Value| -> String {
+        value
+            .as_u64()
+            .and_then(|i| strings.get(i as usize))
+            .unwrap_or(value)
+            .as_str()
+            .unwrap_or_default()
+            .to_owned()
+    };
+
+    let mut res = vec![];
+    for chain in chains {
+        let termination = chain["termination"]
+            .as_str()
+            .and_then(ChainEnd::from_label)
+            .ok_or("Expected the termination of each chain")?;
+        let hops = chain["hops"]
+            .as_array()
+            .ok_or("Expected the hops of each chain")?;
+
+        // The function where the chain ends calls first, the deepest call is made last
+        let mut calls: Vec<String> = vec![];
+        let mut head = String::new();
+        let mut tail = (String::new(), 0);
+        for hop in hops {
+            let caller = get_string(&hop["caller"]);
+            let callee = get_string(&hop["callee"]);
+            let depth = hop["depth"].as_u64().unwrap_or_default();
+            if depth == 0 {
+                head.clone_from(&caller);
+            }
+            if depth >= tail.1 {
+                tail = (callee.clone(), depth);
+            }
+            calls.push(format!("{caller} → {callee}"));
+        }
+        calls.sort_unstable();
+        calls.dedup();
+
+        res.push(ChainRecord {
+            calls,
+            termination,
+            description: format!("{head} ← {} ({})", tail.0, termination.label()),
+        });
+    }
+    Ok(res)
+}
+
+impl BaselineComparison {
+    /// Whether error handling got worse than in the baseline, i.e. there are new or degraded chains.
+    pub fn is_worse(&self) -> bool {
+        !self.new.is_empty() || !self.degraded.is_empty()
+    }
+}
+
+impl fmt::Display for BaselineComparison {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "Compared to the {} chains of the baseline, there are {} new, {} removed, {} degraded and {} improved chains.",
+            self.baseline_count,
+            self.new.len(),
+            self.removed.len(),
+            self.degraded.len(),
+            self.improved.len()
+        )?;
+        for (title, chains) in [
+            ("New chains:", &self.new),
+            ("Removed chains:", &self.removed),
+            ("Chains ending worse than before:", &self.degraded),
+            ("Chains ending better than before:", &self.improved),
+        ] {
+            if chains.is_empty() {
+                continue;
+            }
+            writeln!(f, "{title}")?;
+            for chain in chains {
+                writeln!(f, "  {chain}")?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,306 @@
+use crate::analysis::{AnalysisOptions, NodeFilter};
+use crate::graph::CallGraph;
+use serde::{Deserialize, Serialize};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+/// The version of the analyzer, cached analyses of other versions are not used.
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// The analysis of a crate, as stored in the cache: its call graph, which the chains are extracted from on every run.
+#[derive(Serialize, Deserialize)]
+struct CachedAnalysis {
+    /// The version of the analyzer that performed the analysis.
+    version: String,
+    call_graph: CallGraph,
+}
+
+/// Get the path the analysis of the crate with the given manifest is cached at, in `target/error-analyzer/` next to the manifest.
+/// The name of the file is a hash of everything the call graph depends on: the version of the analyzer and of cargo,
+/// the options of building the call graph, and the contents of the sources, manifests and lock files in the directory of the package
+/// and in the directories of the other local packages it can depend on (`package_dirs`, e.g. path dependencies).
+/// Changing any of these leads to another path, so the cached analysis is not used.
+pub fn get_cache_path(
+    manifest_path: &Path,
+    package_dirs: &[PathBuf],
+    options: &AnalysisOptions,
+    cargo_version: &str,
+) -> PathBuf {
+    let dir = manifest_path
+        .parent()
+        .expect("Could not get manifest directory!");
+
+    let mut hasher = DefaultHasher::new();
+    VERSION.hash(&mut hasher);
+    cargo_version.hash(&mut hasher);
+
+    get_call_graph_options(options).hash(&mut hasher);
+
+    // The directories of packages can be nested (e.g. a path dependency within the package), so their files are deduplicated
+    let mut files = vec![];
+    get_package_files(dir, &mut files);
+    for package_dir in package_dirs {
+        get_package_files(package_dir, &mut files);
+    }
+    files.sort_unstable();
+    files.dedup();
+    for file in files {
+        file.strip_prefix(dir).unwrap_or(&file).hash(&mut hasher);
+        std::fs::read(&file).unwrap_or_default().hash(&mut hasher);
+    }
+
+    dir.join("target")
+        .join("error-analyzer")
+        .join(format!("{:016x}.json", hasher.finish()))
+}
+
+/// Get the options the call graph depends on, to hash into the path of its cached analysis.
+/// The options of the chains (e.g. `--error-filter`, `--chain` or `--max-chains`), of what is marked on the graphs (e.g. `--heatmap`),
+/// and of printing the analysis are left out, as the chains are extracted from the cached call graph on every run.
+fn get_call_graph_options(options: &AnalysisOptions) -> String {
+    // Each option is named, so a new one has to be put on either side
+    let AnalysisOptions {
+        include_macro_calls,
+        drop_edges,
+        follow_deps,
+        all_functions,
+        entry,
+        result_types,
+        spawn_fns,
+        log_fns,
+        external_threshold,
+        ignore,
+        exclude_paths,
+        include_paths,
+        node_filter,
+        exclude_option_chains: _,
+        chain_types: _,
+        graph: _,
+        max_chain_depth: _,
+        max_chains: _,
+        error_filters: _,
+        chain: _,
+        edge_locations: _,
+        heatmap: _,
+        choke_points: _,
+        score_weights: _,
+        verbose: _,
+        quiet: _,
+        color: _,
+    } = options;
+    let node_filter = node_filter.map(get_node_filter_key);
+    format!(
+        "{:?} {:?}",
+        (
+            include_macro_calls,
+            drop_edges,
+            follow_deps,
+            all_functions,
+            entry,
+            result_types,
+            spawn_fns,
+        ),
+        (
+            log_fns,
+            external_threshold,
+            ignore,
+            exclude_paths,
+            include_paths,
+            node_filter,
+        )
+    )
+}
+
+/// Identify the node filter of a user of the library across runs: its address differs per run, but its offset from this function does not
+/// as long as the executable running both is not rebuilt, which the size and modification time of the executable tell.
+fn get_node_filter_key(node_filter: NodeFilter) -> String {
+    let offset = (node_filter as usize).wrapping_sub(get_node_filter_key as usize);
+    let executable = std::env::current_exe()
+        .and_then(std::fs::metadata)
+        .ok()
+        .map(|metadata| (metadata.len(), metadata.modified().ok()));
+    format!("{offset} {executable:?}")
+}
+
+/// Collect the Rust sources, manifests and lock files within a directory of the package, leaving out the build output and hidden directories (e.g. `.git`).
+/// Other files are left out, as the outputs of the analyzer are usually written next to the manifest.
+fn get_package_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = entry.file_name();
+        if path.is_dir() {
+            if name != "target" && !name.to_string_lossy().starts_with('.') {
+                get_package_files(&path, files);
+            }
+        } else if path.extension().is_some_and(|extension| extension == "rs")
+            || name == "Cargo.toml"
+            || name == "Cargo.lock"
+        {
+            files.push(path);
+        }
+    }
+}
+
+/// Load the call graph of the cached analysis at the given path, if there is one of this version of the analyzer.
+pub fn load(path: &Path) -> Option<CallGraph> {
+    let content = std::fs::read_to_string(path).ok()?;
+    let cached: CachedAnalysis = serde_json::from_str(&content).ok()?;
+    (cached.version == VERSION).then_some(cached.call_graph)
+}
+
+/// Store the call graph of the analysis at the given path, creating the cache directory if needed.
+pub fn store(path: &Path, call_graph: &CallGraph) -> Result<(), String> {
+    #[derive(Serialize)]
+    struct CachedAnalysisRef<'a> {
+        version: &'a str,
+        call_graph: &'a CallGraph,
+    }
+
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    let json = serde_json::to_string(&CachedAnalysisRef {
+        version: VERSION,
+        call_graph,
+    })
+    .map_err(|e| e.to_string())?;
+    std::fs::write(path, json).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::{FilterDecision, GraphKind};
+    use regex::Regex;
+    use rustc_hir::def_id::DefId;
+    use rustc_middle::ty::TyCtxt;
+
+    /// Create a directory within a new temporary directory for the test, with the given files.
+    fn create_dir(test: &str, dir: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir()
+            .join(format!("error-analyzer-{test}-{}", std::process::id()))
+            .join(dir);
+        for (path, content) in files {
+            let path = dir.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn changes_to_path_dependencies_change_the_path() {
+        let test = "path-dependencies";
+        let app = create_dir(
+            test,
+            "app",
+            &[("Cargo.toml", "[package]"), ("src/main.rs", "fn main() {}")],
+        );
+        let common = create_dir(
+            test,
+            "common",
+            &[("Cargo.toml", "[package]"), ("src/lib.rs", "pub fn f() {}")],
+        );
+        let manifest_path = app.join("Cargo.toml");
+        let options = AnalysisOptions::default();
+        let path = get_cache_path(&manifest_path, &[common.clone()], &options, "cargo");
+        assert!(path.starts_with(app.join("target").join("error-analyzer")));
+        // Listing the directory of the package itself as well does not change the path
+        assert_eq!(
+            get_cache_path(
+                &manifest_path,
+                &[app.clone(), common.clone()],
+                &options,
+                "cargo"
+            ),
+            path
+        );
+
+        std::fs::write(common.join("src/lib.rs"), "pub fn f() { g() }").unwrap();
+        let changed = get_cache_path(&manifest_path, &[common.clone()], &options, "cargo");
+        assert_ne!(changed, path);
+        // The build output and files other than sources and manifests are left out
+        std::fs::create_dir_all(common.join("target")).unwrap();
+        std::fs::write(common.join("target/lib.rs"), "").unwrap();
+        std::fs::write(common.join("notes.md"), "").unwrap();
+        assert_eq!(
+            get_cache_path(&manifest_path, &[common], &options, "cargo"),
+            changed
+        );
+
+        std::fs::remove_dir_all(app.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn only_call_graph_options_change_the_path() {
+        let dir = create_dir("options", "app", &[("Cargo.toml", "[package]")]);
+        let manifest_path = dir.join("Cargo.toml");
+        let path =
+            |options: &AnalysisOptions| get_cache_path(&manifest_path, &[], options, "cargo");
+        let default = path(&AnalysisOptions::default());
+
+        // The chains are extracted from the cached call graph, so their options do not change it
+        let chain_options = AnalysisOptions {
+            error_filters: vec![Regex::new("io").unwrap()],
+            chain: Some(2),
+            max_chains: Some(10),
+            max_chain_depth: Some(3),
+            graph: GraphKind::Panic,
+            heatmap: true,
+            edge_locations: true,
+            chain_types: true,
+            choke_points: true,
+            exclude_option_chains: true,
+            verbose: true,
+            quiet: true,
+            ..AnalysisOptions::default()
+        };
+        assert_eq!(path(&chain_options), default);
+
+        let all_functions = AnalysisOptions {
+            all_functions: true,
+            ..AnalysisOptions::default()
+        };
+        assert_ne!(path(&all_functions), default);
+        let ignore = AnalysisOptions {
+            ignore: vec![String::from("cli::*")],
+            ..AnalysisOptions::default()
+        };
+        assert_ne!(path(&ignore), default);
+
+        std::fs::remove_dir_all(dir.parent().unwrap()).unwrap();
+    }
+
+    fn keep(_context: &TyCtxt, _def_id: DefId) -> FilterDecision {
+        FilterDecision::Keep
+    }
+
+    fn skip(_context: &TyCtxt, _def_id: DefId) -> FilterDecision {
+        FilterDecision::SkipNode
+    }
+
+    #[test]
+    fn node_filters_are_identified_by_their_offset() {
+        let key = get_node_filter_key(keep);
+        assert_eq!(get_node_filter_key(keep), key);
+        assert_ne!(get_node_filter_key(skip), key);
+        // The address differs per run, so it is not part of the key
+        assert!(!key.contains(&(keep as usize).to_string()));
+
+        let options = |node_filter| AnalysisOptions {
+            node_filter: Some(node_filter),
+            ..AnalysisOptions::default()
+        };
+        assert_eq!(
+            get_call_graph_options(&options(keep)),
+            get_call_graph_options(&options(keep))
+        );
+        assert_ne!(
+            get_call_graph_options(&options(keep)),
+            get_call_graph_options(&AnalysisOptions::default())
+        );
+    }
+}
@@ -0,0 +1,164 @@
+//! Finding how cargo compiles a crate, from the output of `cargo build -v`.
+//! Cargo prints each rustc invocation it runs as ``Running `rustc --crate-name ..` ``, quoting the arguments
+//! in the style of the platform: single quotes on Linux and macOS, double quotes (escaping `"` as `\"`) on Windows.
+
+use crate::error::AnalyzerError;
+
+/// The target of a package to find the rustc invocation of: its binary, or otherwise its library.
+#[derive(Debug, Clone, Default)]
+pub struct TargetSelector {
+    /// The name of the package, whose library is selected if it has no binary.
+    pub package: String,
+    /// The name of the binary to select, if the manifest declares one. Otherwise it is named after the package.
+    pub bin: Option<String>,
+    /// The directory cargo runs rustc from (the workspace root), relative to the current directory,
+    /// and ending in a separator if not empty. The path of the crate root is relative to it.
+    pub root_dir: String,
+}
+
+/// Get the arguments to run the compiler with from the output of `cargo build -v`,
+/// those of the binary of the package, or otherwise those of its library.
+/// The first argument is the rustc cargo ran, the path of the crate root is made relative to the current directory,
+/// and diagnostics are emitted in the short human-readable format rather than in JSON.
+/// Returns an error if the output does not contain the invocation of either.
+pub fn parse_build_output(
+    build_output: &str,
+    target: &TargetSelector,
+) -> Result<Vec<String>, AnalyzerError> {
+    let bin_name = target
+        .bin
+        .as_deref()
+        .unwrap_or(&target.package)
+        .replace('-', "_");
+    let lib_name = target.package.replace('-', "_");
+
+    let mut lib_args = None;
+    for args in rustc_invocations(build_output) {
+        match (
+            option_value(&args, "--crate-name"),
+            option_value(&args, "--crate-type"),
+        ) {
+            (Some(name), Some("bin")) if name == bin_name => {
+                return Ok(rewrite_args(args, &target.root_dir));
+            }
+            // Library crates are analyzed from their public API, if there is no binary
+            (Some(name), Some("lib")) if name == lib_name => lib_args = Some(args),
+            _ => {}
+        }
+    }
+
+    lib_args
+        .map(|args| rewrite_args(args, &target.root_dir))
+        .ok_or_else(|| AnalyzerError::RustcInvocationNotFound {
+            package: target.package.clone(),
+        })
+}
+
+/// Get the arguments of each rustc invocation in the output of cargo, starting with the rustc that is run.
+/// Environment variables set before it (e.g. using `-vv`, or `set X=..&&` on Windows) and wrappers (e.g. sccache) are left out.
+pub fn rustc_invocations(build_output: &str) -> Vec<Vec<String>> {
+    let mut invocations = vec![];
+    for line in build_output.lines() {
+        let Some((_status, command)) = line.split_once("Running `") else {
+            continue;
+        };
+        let command = command.strip_suffix('`').unwrap_or(command);
+
+        let args = split_args(command);
+        if let Some(rustc) = args.iter().position(|arg| is_rustc(arg)) {
+            invocations.push(args[rustc..].to_vec());
+        }
+    }
+    invocations
+}
+
+/// Split a command as printed by cargo into its arguments, removing the quotes around them.
+/// Both single quotes (Linux and macOS) and double quotes (Windows) are supported, as are escaped quotes (`\"` and `'\''`).
+/// Other backslashes are kept, as these separate the directories of paths on Windows.
+pub fn split_args(command: &str) -> Vec<String> {
+    let mut args = vec![];
+    let mut arg = String::new();
+    // Whether an argument was started, so quoted empty arguments are kept
+    let mut started = false;
+    let mut quote = None;
+
+    let mut chars = command.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            // Single quotes enclose everything literally
+            (Some('\''), '\'') => quote = None,
+            (Some('\''), c) => arg.push(c),
+            (Some('"'), '"') => quote = None,
+            (_, '\\') if matches!(chars.peek(), Some('"' | '\'')) => {
+                arg.push(chars.next().unwrap());
+                started = true;
+            }
+            (Some(_), c) => arg.push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                started = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if started {
+                    args.push(std::mem::take(&mut arg));
+                    started = false;
+                }
+            }
+            (None, c) => {
+                arg.push(c);
+                started = true;
+            }
+        }
+    }
+    if started {
+        args.push(arg);
+    }
+
+    args
+}
+
+/// Prepend the directory rustc is run from to the path of the crate root, if it is relative,
+/// and have diagnostics emitted in the short human-readable format.
+fn rewrite_args(args: Vec<String>, root_dir: &str) -> Vec<String> {
+    let mut crate_root_found = false;
+    args.into_iter()
+        .enumerate()
+        // The JSON diagnostics are read by cargo, the analyzer shows them as they are
+        .filter(|(_index, arg)| !arg.starts_with("--json="))
+        .map(|(index, arg)| {
+            if arg.starts_with("--error-format=") {
+                return String::from("--error-format=short");
+            }
+            // The crate root is the only argument that is a Rust source file, and is not the value of an option
+            if index > 0 && !crate_root_found && arg.ends_with(".rs") && !arg.starts_with('-') {
+                crate_root_found = true;
+                if !is_absolute(&arg) {
+                    return format!("{root_dir}{arg}");
+                }
+            }
+            arg
+        })
+        .collect()
+}
+
+/// Get the value of an option passed as `--option value` (or `--option=value`), if it is passed.
+fn option_value<'a>(args: &'a [String], option: &str) -> Option<&'a str> {
+    args.iter().enumerate().find_map(|(index, arg)| {
+        if arg == option {
+            args.get(index + 1).map(String::as_str)
+        } else {
+            arg.strip_prefix(option)?.strip_prefix('=')
+        }
+    })
+}
+
+/// Whether an argument of a command is the rustc it runs, e.g. `/home/user/.rustup/toolchains/nightly/bin/rustc` or `rustc.exe`.
+fn is_rustc(arg: &str) -> bool {
+    let file_name = arg.rsplit(['/', '\\']).next().unwrap_or(arg);
+    !arg.contains('=') && (file_name == "rustc" || file_name == "rustc.exe")
+}
+
+/// Whether a path is absolute, on either Unix (`/home/..`) or Windows (`C:\..` or `\\server\..`).
+fn is_absolute(path: &str) -> bool {
+    path.starts_with('/') || path.starts_with('\\') || path.get(1..2) == Some(":")
+}
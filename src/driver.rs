@@ -0,0 +1,543 @@
+use crate::analysis::{self, AnalysisOptions};
+use crate::cache;
+use crate::cargo_invocation::{self, TargetSelector};
+use crate::error::AnalyzerError;
+use crate::graph::{CallGraph, ChainGraph};
+use crate::timings::Timings;
+use rustc_driver::Compilation;
+use rustc_interface::interface::Compiler;
+use rustc_interface::Queries;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use toml::Table;
+use tracing::{info, warn};
+
+/// The package to analyze, and how to analyze it.
+#[derive(Debug, Clone, Default)]
+pub struct CrateOptions {
+    /// Path to the manifest of the package, relative to the current directory.
+    pub manifest_path: PathBuf,
+    /// Options for the analysis itself.
+    pub options: AnalysisOptions,
+    /// Whether to use a cached analysis of the package if it is unchanged, and to cache the analysis otherwise.
+    pub use_cache: bool,
+    /// The number of calls an unwrapped error should propagate through to be reported as a compiler diagnostic, if these are emitted.
+    /// Emitting diagnostics needs the compiler, so a cached analysis is not used then.
+    pub diagnostic_depth: Option<usize>,
+}
+
+/// The graphs of an analyzed package, and how they were obtained.
+#[derive(Debug)]
+pub struct AnalysisResult {
+    pub call_graph: CallGraph,
+    pub chain_graph: ChainGraph,
+    /// The path of the cached analysis that was used instead of building and analyzing the package, if any.
+    pub cache_path: Option<PathBuf>,
+    /// How long each stage of the analysis took.
+    pub timings: Timings,
+}
+
+/// Analyze a package: build it using cargo to get the arguments its crate is compiled with,
+/// then run the compiler with these arguments, and extract the call graph and error propagation chains of the crate.
+/// If caching is enabled and the package did not change since it was last analyzed, the cached analysis is returned instead.
+pub fn analyze_crate(crate_options: &CrateOptions) -> Result<AnalysisResult, AnalyzerError> {
+    let relative_manifest_path = crate_options.manifest_path.to_string_lossy();
+    let manifest_path = std::env::current_dir()
+        .map_err(|source| AnalyzerError::ManifestNotFound {
+            path: crate_options.manifest_path.clone(),
+            source,
+        })?
+        .join(&crate_options.manifest_path);
+    let options = &crate_options.options;
+    let mut timings = Timings::default();
+
+    // Skip building and analyzing the package if nothing it depends on changed since it was last analyzed
+    let use_cache = crate_options.use_cache && crate_options.diagnostic_depth.is_none();
+    let mut cache_path = if use_cache {
+        Some(cache::get_cache_path(
+            &manifest_path,
+            &get_local_package_dirs(&manifest_path),
+            options,
+            &cargo_version()?,
+        ))
+    } else {
+        None
+    };
+    if let Some(path) = &cache_path {
+        if let Some(mut call_graph) = timings.time("cache lookup", || cache::load(path)) {
+            info!(
+                target: "analyzer::cache",
+                "Using the cached analysis from {}",
+                path.display()
+            );
+            // The chains are extracted with the options of this run, e.g. only keeping those of an error type
+            let chain_graph = analysis::extract_chains(&mut call_graph, options, &mut timings);
+            return Ok(AnalysisResult {
+                call_graph,
+                chain_graph,
+                cache_path,
+                timings,
+            });
+        }
+    }
+
+    // Extract the compiler arguments from running `cargo build`
+    let mut compiler_args = get_compiler_args(
+        &relative_manifest_path,
+        &manifest_path,
+        !options.follow_deps.is_empty(),
+        &mut timings,
+    )?;
+
+    // Building the package can create its lock file, which the analysis is cached by as well
+    if use_cache {
+        cache_path = Some(cache::get_cache_path(
+            &manifest_path,
+            &get_local_package_dirs(&manifest_path),
+            options,
+            &cargo_version()?,
+        ));
+    }
+
+    // The short error format leaves out the notes of the emitted diagnostics, which show their chains
+    if crate_options.diagnostic_depth.is_some() {
+        for arg in &mut compiler_args {
+            if arg == "--error-format=short" {
+                *arg = String::from("--error-format=human");
+            }
+        }
+    }
+
+    // Run the compiler using the retrieved args, the callback hands the graphs back once it analyzed the crate
+    let mut callback = AnalysisCallback {
+        options: options.clone(),
+        diagnostic_depth: crate_options.diagnostic_depth,
+        cache_path,
+        timings,
+        analysis: None,
+    };
+    let exit_code = run_compiler(compiler_args, &mut callback);
+    info!(target: "analyzer::rustc", "Ran compiler, exit code: {exit_code}");
+
+    let (call_graph, chain_graph) = callback
+        .analysis
+        .unwrap_or(Err(AnalyzerError::CompilerFailed { exit_code }))?;
+
+    Ok(AnalysisResult {
+        call_graph,
+        chain_graph,
+        cache_path: None,
+        timings: callback.timings,
+    })
+}
+
+/// The callback run by the compiler once it parsed the crate, analyzing it using the type context.
+struct AnalysisCallback {
+    options: AnalysisOptions,
+    diagnostic_depth: Option<usize>,
+    /// The path to cache the call graph at, if caching is enabled.
+    cache_path: Option<PathBuf>,
+    /// How long each stage of the analysis took so far.
+    timings: Timings,
+    /// The call graph and chain graph of the crate once it is analyzed, or why it could not be.
+    analysis: Option<Result<(CallGraph, ChainGraph), AnalyzerError>>,
+}
+
+impl rustc_driver::Callbacks for AnalysisCallback {
+    fn after_crate_root_parsing<'tcx>(
+        &mut self,
+        _compiler: &Compiler,
+        queries: &'tcx Queries<'tcx>,
+    ) -> Compilation {
+        // Access type context, the crate is left unanalyzed if it cannot be created
+        let Ok(mut global_context) = queries.global_ctxt() else {
+            return Compilation::Stop;
+        };
+        global_context.enter(|context| {
+            info!(target: "analyzer::rustc", "Analyzing output...");
+            // Analyze the program using the type context
+            let analysis = analysis::build_call_graph(context, &self.options, &mut self.timings)
+                .map(|mut call_graph| {
+                    // Cache the call graph before the chains are extracted, so running again with other chain or output options does not need the compiler
+                    if let Some(path) = &self.cache_path {
+                        if let Err(e) = cache::store(path, &call_graph) {
+                            warn!(target: "analyzer::cache", "Could not cache the analysis: {e}");
+                        }
+                    }
+                    let chain_graph =
+                        analysis::extract_chains(&mut call_graph, &self.options, &mut self.timings);
+                    (call_graph, chain_graph)
+                });
+
+            if let (Ok((call_graph, chain_graph)), Some(depth)) = (&analysis, self.diagnostic_depth)
+            {
+                analysis::emit_diagnostics(context, call_graph, chain_graph, depth);
+            }
+
+            self.analysis = Some(analysis);
+        });
+
+        // No need to compile further
+        Compilation::Stop
+    }
+}
+
+/// Build the package with the given manifest and run the compiler on its crate, handing the type context to `inspect`.
+/// This lets the tests of the analysis drive its steps directly, e.g. to time building the call graph of a fixture.
+#[cfg(all(test, feature = "integration"))]
+pub(crate) fn with_type_context<R: Send>(
+    manifest_path: &Path,
+    inspect: impl for<'tcx> FnOnce(rustc_middle::ty::TyCtxt<'tcx>) -> R + Send,
+) -> Result<R, AnalyzerError> {
+    /// The callback run by the compiler once it parsed the crate, calling `inspect` with the type context.
+    struct InspectCallback<F, R> {
+        inspect: Option<F>,
+        result: Option<R>,
+    }
+
+    impl<F, R> rustc_driver::Callbacks for InspectCallback<F, R>
+    where
+        F: for<'tcx> FnOnce(rustc_middle::ty::TyCtxt<'tcx>) -> R + Send,
+        R: Send,
+    {
+        fn after_crate_root_parsing<'tcx>(
+            &mut self,
+            _compiler: &Compiler,
+            queries: &'tcx Queries<'tcx>,
+        ) -> Compilation {
+            if let (Ok(mut global_context), Some(inspect)) =
+                (queries.global_ctxt(), self.inspect.take())
+            {
+                self.result = Some(global_context.enter(inspect));
+            }
+            Compilation::Stop
+        }
+    }
+
+    // The compiler is run in this process, so the crates are compiled one at a time
+    static COMPILER: std::sync::Mutex<()> = std::sync::Mutex::new(());
+    let _compiler = COMPILER
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+    let compiler_args = get_compiler_args(
+        &manifest_path.to_string_lossy(),
+        &manifest_path.to_path_buf(),
+        false,
+        &mut Timings::default(),
+    )?;
+    let mut callback = InspectCallback {
+        inspect: Some(inspect),
+        result: None,
+    };
+    let exit_code = run_compiler(compiler_args, &mut callback);
+    callback
+        .result
+        .ok_or(AnalyzerError::CompilerFailed { exit_code })
+}
+
+/// Get the compiler arguments used to compile the package by first running `cargo clean` and then `cargo build -v`.
+/// If dependencies are followed, they are built with all their MIR encoded in their metadata.
+fn get_compiler_args(
+    relative_manifest_path: &str,
+    manifest_path: &PathBuf,
+    encode_mir: bool,
+    timings: &mut Timings,
+) -> Result<Vec<String>, AnalyzerError> {
+    info!(
+        target: "analyzer::cargo",
+        "Using {}!",
+        cargo_version()?.trim_end_matches('\n')
+    );
+
+    let (package_name, bin_name) = get_package_name(manifest_path)?;
+    let target = TargetSelector {
+        package: package_name.clone(),
+        bin: bin_name,
+        root_dir: get_root_dir(relative_manifest_path, manifest_path),
+    };
+
+    let (build_output, built) = timings.time("build", || {
+        cargo_clean(manifest_path, &package_name)?;
+        cargo_build_verbose(manifest_path, encode_mir)
+    })?;
+
+    let mut compiler_args = timings.time("argument extraction", || {
+        match cargo_invocation::parse_build_output(&build_output, &target) {
+            // Cargo did not get to compiling the package, e.g. as a dependency could not be resolved
+            Err(AnalyzerError::RustcInvocationNotFound { .. }) if !built => {
+                Err(AnalyzerError::CargoFailed {
+                    command: format!("cargo build --manifest-path {}", manifest_path.display()),
+                    stderr: build_errors(&build_output),
+                })
+            }
+            result => result,
+        }
+    })?;
+
+    // The MIR of the package is read as it was built, without calls being inlined or optimized away
+    compiler_args.push(String::from("-Zmir-opt-level=0"));
+    Ok(compiler_args)
+}
+
+/// Run `cargo clean -p PACKAGE`, where the package name is extracted from the given manifest.
+/// A package that cannot be cleaned (e.g. as it was never built) is built anyway, only failing to run cargo is an error.
+fn cargo_clean(manifest_path: &Path, package_name: &str) -> Result<String, AnalyzerError> {
+    info!(target: "analyzer::cargo", "Cleaning package...");
+    let mut clean_command = create_cargo_command();
+    clean_command.arg("clean");
+    clean_command.arg("-p");
+    clean_command.arg(package_name);
+
+    if let Some(manifest_dir) = manifest_path.parent() {
+        clean_command.current_dir(manifest_dir);
+    }
+
+    let output = clean_command
+        .output()
+        .map_err(|e| AnalyzerError::CargoFailed {
+            command: format!("cargo clean -p {package_name}"),
+            stderr: e.to_string(),
+        })?;
+
+    let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+
+    if output.status.code() != Some(0) {
+        warn!(target: "analyzer::cargo", "Could not clean package!\n{}", stderr.trim_end());
+    }
+
+    Ok(stderr)
+}
+
+/// Read and parse the given manifest.
+pub fn read_manifest(manifest_path: &Path) -> Result<Table, AnalyzerError> {
+    let content = std::fs::read_to_string(manifest_path).map_err(|source| match source.kind() {
+        std::io::ErrorKind::InvalidData => AnalyzerError::ManifestInvalid {
+            path: manifest_path.to_owned(),
+            reason: String::from("it is not valid UTF-8"),
+        },
+        _ => AnalyzerError::ManifestNotFound {
+            path: manifest_path.to_owned(),
+            source,
+        },
+    })?;
+    content
+        .parse::<Table>()
+        .map_err(|e| AnalyzerError::ManifestInvalid {
+            path: manifest_path.to_owned(),
+            reason: e.to_string(),
+        })
+}
+
+/// Extract the package name from the given manifest, and the name of its first binary if it declares any.
+fn get_package_name(manifest_path: &PathBuf) -> Result<(String, Option<String>), AnalyzerError> {
+    let invalid = |reason: &str| AnalyzerError::ManifestInvalid {
+        path: manifest_path.to_owned(),
+        reason: reason.to_owned(),
+    };
+
+    let table = read_manifest(manifest_path)?;
+    let package_name = table
+        .get("package")
+        .and_then(|package| package.get("name"))
+        .and_then(|name| name.as_str())
+        .ok_or_else(|| invalid("no name found in package information"))?
+        .to_owned();
+    if let Some(binaries) = table.get("bin") {
+        let binary_name = binaries
+            .as_array()
+            .and_then(|binaries| binaries.first())
+            .and_then(|binary| binary.get("name"))
+            .and_then(|name| name.as_str())
+            .ok_or_else(|| invalid("no name found in binary information"))?
+            .to_owned();
+        return Ok((package_name, Some(binary_name)));
+    }
+
+    Ok((package_name, None))
+}
+
+/// Get the directory cargo runs rustc from, relative to the current directory: the root of the workspace of the package.
+/// Falls back to the directory of the package if the workspace cannot be located.
+fn get_root_dir(relative_manifest_path: &str, manifest_path: &Path) -> String {
+    let package_dir = Path::new(relative_manifest_path.trim_end_matches("Cargo.toml"));
+    let mut root_dir = package_dir.to_path_buf();
+
+    // The path of the package within its workspace, e.g. `crates/app`, which is empty if it is not part of one
+    let in_workspace = locate_workspace(manifest_path).and_then(|workspace_manifest| {
+        let package_dir = manifest_path.parent()?;
+        let workspace_dir = workspace_manifest.parent()?;
+        package_dir
+            .strip_prefix(workspace_dir)
+            .ok()
+            .map(Path::to_path_buf)
+    });
+    for _component in in_workspace.iter().flat_map(|path| path.components()) {
+        if root_dir.file_name().is_some() {
+            root_dir.pop();
+        } else {
+            root_dir.push("..");
+        }
+    }
+
+    let mut root_dir = root_dir.to_string_lossy().into_owned();
+    if !root_dir.is_empty() && !root_dir.ends_with(std::path::MAIN_SEPARATOR) {
+        root_dir.push(std::path::MAIN_SEPARATOR);
+    }
+    root_dir
+}
+
+/// Run `cargo locate-project --workspace`, getting the manifest of the workspace of the package, if it can be located.
+fn locate_workspace(manifest_path: &Path) -> Option<PathBuf> {
+    let mut locate_command = create_cargo_command();
+    locate_command.arg("locate-project");
+    locate_command.arg("--workspace");
+    locate_command.arg("--message-format");
+    locate_command.arg("plain");
+    locate_command.arg("--manifest-path");
+    locate_command.arg(manifest_path.as_os_str());
+
+    let output = locate_command.output().ok()?;
+    if output.status.code() != Some(0) {
+        return None;
+    }
+
+    let workspace_manifest = String::from_utf8_lossy(&output.stdout);
+    Some(PathBuf::from(workspace_manifest.trim_end()))
+}
+
+/// Run `cargo metadata`, getting the directories of the local packages the package can depend on:
+/// its path dependencies and the members of its workspace, along with the root of the workspace (which has its lock file).
+/// Only the dependencies already known to cargo are resolved, so this is empty if cargo would need to fetch them first.
+fn get_local_package_dirs(manifest_path: &Path) -> Vec<PathBuf> {
+    let mut metadata_command = create_cargo_command();
+    metadata_command.arg("metadata");
+    metadata_command.arg("--format-version");
+    metadata_command.arg("1");
+    metadata_command.arg("--offline");
+    metadata_command.arg("--manifest-path");
+    metadata_command.arg(manifest_path.as_os_str());
+
+    let Ok(output) = metadata_command.output() else {
+        return vec![];
+    };
+    if output.status.code() != Some(0) {
+        warn!(
+            target: "analyzer::cache",
+            "Could not get the path dependencies of the package, so changes to these are not noticed!\n{}",
+            String::from_utf8_lossy(&output.stderr).trim_end()
+        );
+        return vec![];
+    }
+    let Ok(metadata) = serde_json::from_slice::<serde_json::Value>(&output.stdout) else {
+        return vec![];
+    };
+
+    // Packages from a registry or git repository have a source, and are pinned by the lock file
+    let packages = metadata["packages"].as_array().into_iter().flatten();
+    let mut dirs: Vec<PathBuf> = packages
+        .filter(|package| package["source"].is_null())
+        .filter_map(|package| Path::new(package["manifest_path"].as_str()?).parent())
+        .map(Path::to_path_buf)
+        .collect();
+    if let Some(workspace_root) = metadata["workspace_root"].as_str() {
+        dirs.push(PathBuf::from(workspace_root));
+    }
+    dirs
+}
+
+/// Create a new cargo command.
+fn create_cargo_command() -> Command {
+    Command::new("cargo")
+}
+
+/// Run `cargo --version`.
+fn cargo_version() -> Result<String, AnalyzerError> {
+    let mut version_command = create_cargo_command();
+    version_command.arg("--version");
+
+    let output = version_command
+        .output()
+        .map_err(|e| AnalyzerError::CargoFailed {
+            command: String::from("cargo --version"),
+            stderr: e.to_string(),
+        })?;
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Run `cargo build -v` on the given manifest, returning its output and whether the package was built.
+/// If `encode_mir` is set, the MIR of all functions is kept in the metadata of the built crates, so dependencies can be followed.
+fn cargo_build_verbose(
+    manifest_path: &Path,
+    encode_mir: bool,
+) -> Result<(String, bool), AnalyzerError> {
+    // TODO: interrupt build as to not compile the program twice
+    info!(target: "analyzer::cargo", "Building package...");
+    let mut build_command = create_cargo_command();
+    build_command.arg("build");
+    build_command.arg("-v");
+    build_command.arg("--manifest-path");
+    build_command.arg(manifest_path.as_os_str());
+
+    if encode_mir {
+        // By default only the MIR of generic and inline functions is available
+        let mut flags = std::env::var("RUSTFLAGS").unwrap_or_default();
+        flags.push_str(" -Zalways-encode-mir");
+        build_command.env("RUSTFLAGS", flags.trim());
+    }
+
+    let output = build_command
+        .output()
+        .map_err(|e| AnalyzerError::CargoFailed {
+            command: format!("cargo build --manifest-path {}", manifest_path.display()),
+            stderr: e.to_string(),
+        })?;
+
+    let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+
+    let built = output.status.code() == Some(0);
+    if !built {
+        warn!(
+            target: "analyzer::cargo",
+            "Could not (fully) build package!\n\n{}\nTrying to continue...",
+            build_errors(&stderr)
+        );
+    }
+
+    Ok((stderr, built))
+}
+
+/// Get the errors from the output of `cargo build`, one per line.
+fn build_errors(build_output: &str) -> String {
+    let mut errors = String::new();
+    for line in build_output.split('\n') {
+        if line.starts_with("error") {
+            errors.push_str(line);
+            errors.push('\n');
+        }
+    }
+    errors
+}
+
+/// Run a compiler with the provided arguments and callbacks.
+/// Returns the exit code of the compiler.
+fn run_compiler(args: Vec<String>, callbacks: &mut (dyn rustc_driver::Callbacks + Send)) -> i32 {
+    info!(target: "analyzer::rustc", "Running compiler...");
+
+    // Install a panic hook that will print the ICE message on unexpected panics while the compiler runs,
+    // the panic hook of the caller is restored afterwards, as the analyzer can be used as a library
+    let caller_hook = std::panic::take_hook();
+    let using_internal_features =
+        rustc_driver::install_ice_hook(rustc_driver::DEFAULT_BUG_REPORT_URL, |_| ());
+
+    // Invoke compiler, and return the exit code
+    let exit_code = rustc_driver::catch_with_exit_code(move || {
+        rustc_driver::RunCompiler::new(&args, callbacks)
+            .set_using_internal_features(using_internal_features)
+            .run()
+    });
+
+    std::panic::set_hook(caller_hook);
+    exit_code
+}
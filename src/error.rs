@@ -0,0 +1,110 @@
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+/// An error ending a run of the analyzer, e.g. as the package to analyze cannot be found or built.
+#[derive(Debug)]
+pub enum AnalyzerError {
+    /// The manifest of the package cannot be read, e.g. as the path does not point to one.
+    ManifestNotFound { path: PathBuf, source: io::Error },
+    /// The manifest of the package (or its analyzer configuration) cannot be parsed, or misses the package name.
+    ManifestInvalid { path: PathBuf, reason: String },
+    /// Running cargo failed, with what it wrote to stderr (or why it could not be run).
+    CargoFailed { command: String, stderr: String },
+    /// The output of `cargo build` does not contain the rustc invocation of the binary or library of the package.
+    RustcInvocationNotFound { package: String },
+    /// The crate is a binary, but has no `main` function to analyze it from (e.g. as it is `#![no_main]`), and no other entry function is given.
+    EntryFnMissing { crate_name: String },
+    /// The entry function given using `--entry` is not a local function, with the paths of the local functions it may have meant.
+    EntryFnNotFound {
+        path: String,
+        candidates: Vec<String>,
+    },
+    /// The compiler stopped before the crate could be analyzed, e.g. as it cannot be parsed.
+    CompilerFailed { exit_code: i32 },
+    /// The graph could not be written to its output path, nor to the temporary directory.
+    OutputWrite { path: PathBuf, source: io::Error },
+}
+
+impl AnalyzerError {
+    /// What can likely be done about this error, to print along with it.
+    pub fn hint(&self) -> &'static str {
+        match self {
+            AnalyzerError::ManifestNotFound { .. } => {
+                "pass the path to the Cargo.toml of the package to analyze, relative to the current directory"
+            }
+            AnalyzerError::ManifestInvalid { .. } => {
+                "check that the manifest is valid TOML with a [package] name, e.g. using `cargo metadata`"
+            }
+            AnalyzerError::CargoFailed { .. } => {
+                "check that cargo is installed and that `cargo build` works for the package"
+            }
+            AnalyzerError::RustcInvocationNotFound { .. } => {
+                "the package needs a binary or library target, procedural macro crates cannot be analyzed"
+            }
+            AnalyzerError::EntryFnMissing { .. } => {
+                "add a `main` function to the binary, pass the function to analyze it from using `--entry` (e.g. the reset handler of firmware), or turn it into a library to analyze its public API"
+            }
+            AnalyzerError::EntryFnNotFound { .. } => {
+                "pass the path of a local function to `--entry`, e.g. `run` or `cli::run`"
+            }
+            AnalyzerError::CompilerFailed { .. } => {
+                "fix the errors the compiler reported above, the analysis needs the crate to parse"
+            }
+            AnalyzerError::OutputWrite { .. } => {
+                "check that the directory of the output path exists and is writable"
+            }
+        }
+    }
+}
+
+impl fmt::Display for AnalyzerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AnalyzerError::ManifestNotFound { path, source } => {
+                write!(f, "Could not read manifest {}: {source}", path.display())
+            }
+            AnalyzerError::ManifestInvalid { path, reason } => {
+                write!(f, "Invalid manifest {}: {reason}", path.display())
+            }
+            AnalyzerError::CargoFailed { command, stderr } => {
+                writeln!(f, "`{command}` failed:")?;
+                write!(f, "{}", stderr.trim_end())
+            }
+            AnalyzerError::RustcInvocationNotFound { package } => write!(
+                f,
+                "Could not find how cargo compiles the binary or library of package {package}"
+            ),
+            AnalyzerError::EntryFnMissing { crate_name } => {
+                write!(f, "Binary crate {crate_name} has no main function")
+            }
+            AnalyzerError::EntryFnNotFound { path, candidates } => {
+                write!(f, "Entry function {path} is not a local function")?;
+                if !candidates.is_empty() {
+                    write!(f, ", candidates:")?;
+                    for candidate in candidates {
+                        write!(f, "\n  {candidate}")?;
+                    }
+                }
+                Ok(())
+            }
+            AnalyzerError::CompilerFailed { exit_code } => write!(
+                f,
+                "The compiler exited with code {exit_code} before the crate could be analyzed"
+            ),
+            AnalyzerError::OutputWrite { path, source } => {
+                write!(f, "Could not write output to {}: {source}", path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for AnalyzerError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AnalyzerError::ManifestNotFound { source, .. }
+            | AnalyzerError::OutputWrite { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
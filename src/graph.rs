@@ -1,69 +1,418 @@
+use crate::interner::{Interner, Symbol};
+use crate::schema::{self, StablePaths, VersionedGraph};
+use crate::stats::ChainStats;
 use dot::{Edges, Id, Kind, LabelText, Nodes, Style};
 use rustc_hir::def_id::DefId;
 use rustc_hir::HirId;
+use rustc_middle::mir::BasicBlock;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::borrow::Cow;
 use std::cmp::PartialEq;
+use std::io::{self, Write};
 
-#[derive(Debug, Clone)]
+/// The graph of the calls of the analyzed crate, with the errors they carry.
+/// It is (de)serialized in a versioned schema (see `schema`), where the ids of the compiler are stable paths.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(remote = "Self")]
 pub struct CallGraph {
     pub nodes: Vec<CallNode>,
     pub edges: Vec<CallEdge>,
+    /// The indices of the edges leaving each node, kept up to date when adding nodes and edges.
+    outgoing: Vec<Vec<usize>>,
+    /// The indices of the edges reaching each node, kept up to date when adding nodes and edges.
+    incoming: Vec<Vec<usize>>,
     pub crate_name: String,
+    pub filtered_macro_calls: usize,
+    pub unreachable_fns: Option<usize>,
+    /// The number of functions of dependencies that were aggregated into a node per dependency, as their calls carry no errors.
+    pub aggregated_fns: usize,
+    /// The functions left out by the filters (e.g. `--exclude-path`), whose calls are left out as well unless only the node is skipped.
+    pub filtered_fns: Vec<String>,
+    /// The node of the entry point of the program (e.g. `main`), if any.
+    pub entry_node: Option<usize>,
+    /// The node of the program exit, the caller of the entry point, if the entry point returns anything (e.g. a `Result` or an `ExitCode`).
+    pub exit_node: Option<usize>,
+    /// The return type of the entry point, e.g. `std::result::Result<(), MyError>` or `std::process::ExitCode`.
+    pub entry_return_ty: Option<String>,
+    /// Whether the entry point returns a `Result`, whose error the runtime prints when exiting the program.
+    pub entry_returns_result: bool,
+    pub discarded_calls: Vec<String>,
+    /// The locations of calls that could not be resolved or typed, as type information is unavailable.
+    pub unresolved_calls: Vec<String>,
+    /// The public functions returning an error callers cannot match on (e.g. a `String`), with that error type.
+    pub opaque_error_fns: Vec<(String, String)>,
+    /// The error type this graph is restricted to, if it is the subgraph of a single error type.
+    pub subgraph_ty: Option<String>,
+    /// Per origin of errors with the errors leaving it, the functions all of its errors pass through from the origin up, if choke points are found.
+    pub choke_points: Vec<(String, Vec<String>)>,
+    /// The labels of the nodes and the types of the edges, which are shared by many of them.
+    pub strings: Interner,
+    /// The stable paths of the functions and calls, which the ids of the compiler are (de)serialized as.
+    #[serde(skip)]
+    pub(crate) stable_paths: StablePaths,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CallNode {
     id: usize,
-    pub label: String,
-    pub kind: CallNodeKind,
+    pub label: Symbol,
+    pub(crate) kind: CallNodeKind,
     pub panics: bool,
+    /// Whether this function calls a function that can panic.
+    pub may_panic: bool,
+    /// The followed dependency this node belongs to, used to cluster its nodes.
+    pub cluster: Option<String>,
+    /// The full path of the called function, if its label is shortened (e.g. for trait methods).
+    pub path: Option<String>,
+    /// Whether this function constructs an error itself (e.g. `Err(..)`), rather than only passing errors on.
+    pub error_origin: bool,
+    /// The expressions constructing the errors of this function, e.g. `MyError::Io` or `std::io::Error::new`.
+    pub error_constructors: Vec<String>,
+    /// The types this function downcasts type-erased errors to, e.g. `SqlxError` for `error.downcast_ref::<SqlxError>()`.
+    pub downcasts: Vec<Downcast>,
+    /// The number of distinct chains passing through this function, if counted for the heatmap.
+    pub chain_count: usize,
+    /// Whether all errors of an origin pass through this function, if choke points are found.
+    pub choke_point: bool,
+    /// Whether this function can be called from outside of the crate, i.e. is part of the public API of a library.
+    pub public: bool,
+    /// Whether this function is declared to return a `Result`, but can never return an error.
+    pub infallible: bool,
+    /// Whether this function is an `async fn`.
+    pub is_async: bool,
+    /// Whether this function is an `unsafe fn`.
+    pub is_unsafe: bool,
+    /// The declared visibility of this function, if it is a function item (rather than e.g. a closure or an indirect call).
+    pub visibility: Option<Visibility>,
+    /// Whether this function is a test, i.e. has `#[test]` or is within a module under `#[cfg(test)]`.
+    pub is_test: bool,
+    /// Whether this function has `#[track_caller]`, so its panics are reported at the location of its caller.
+    pub track_caller: bool,
+    /// Whether this is the node of an item evaluated at compile-time (e.g. an associated const or a static) rather than of a function,
+    /// whose calls are made in a const context.
+    pub const_context: bool,
 }
 
-#[derive(Debug, Clone)]
-pub enum CallNodeKind {
-    LocalFn(DefId, HirId),
-    NonLocalFn(DefId),
+/// The declared visibility of a function.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Visibility {
+    /// Visible everywhere, i.e. `pub`.
+    Public,
+    /// Visible outside of its module, but not outside of its crate, e.g. `pub(crate)` or `pub(super)`.
+    Crate,
+    /// Visible only within its module.
+    Private,
 }
 
-#[derive(Debug, Clone)]
+/// An attribute of a function that the style of its node in the call graph can depend on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeAttribute {
+    Async,
+    Unsafe,
+    Test,
+    Visibility(Visibility),
+}
+
+/// The names of the attributes of the functions, as written in the manifest.
+pub const NODE_ATTRIBUTE_NAMES: [&str; 6] =
+    ["async", "unsafe", "test", "pub", "pub(crate)", "private"];
+
+/// The dot styles of the nodes of the call graph by the attributes of their functions (e.g. dashed for unsafe functions),
+/// as configured in the `[package.metadata.error-analyzer.node-styles]` table of the manifest.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeStyles {
+    styles: Vec<(NodeAttribute, String)>,
+}
+
+/// The id of a call, by which its location and type are looked up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum CallId {
+    /// A call in the analyzed crate, by its call expression (or the expression making an implicit call, e.g. an operator).
+    Hir(HirId),
+    /// A call in a function of a followed dependency, which has no HIR, by that function and the block of its MIR ending in the call.
+    Mir(DefId, BasicBlock),
+}
+
+impl CallId {
+    /// Get the id of the call expression, if the call is made in the analyzed crate.
+    pub(crate) fn hir_id(self) -> Option<HirId> {
+        match self {
+            CallId::Hir(hir_id) => Some(hir_id),
+            CallId::Mir(_def_id, _block) => None,
+        }
+    }
+}
+
+/// Orders the calls of the analyzed crate first, `DefId`s having no order of their own.
+impl Ord for CallId {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        match (self, other) {
+            (CallId::Hir(a), CallId::Hir(b)) => a.cmp(b),
+            (CallId::Hir(_a), CallId::Mir(..)) => std::cmp::Ordering::Less,
+            (CallId::Mir(..), CallId::Hir(_b)) => std::cmp::Ordering::Greater,
+            (CallId::Mir(a, a_block), CallId::Mir(b, b_block)) => {
+                (a.krate, a.index, a_block).cmp(&(b.krate, b.index, b_block))
+            }
+        }
+    }
+}
+
+impl PartialOrd for CallId {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) enum CallNodeKind {
+    LocalFn(
+        #[serde(with = "crate::schema::def_id")] DefId,
+        #[serde(with = "crate::schema::hir_id")] HirId,
+    ),
+    NonLocalFn(#[serde(with = "crate::schema::def_id")] DefId),
+    ForeignFn(#[serde(with = "crate::schema::def_id")] DefId),
+    Indirect(String),
+    /// A call whose callee could not be determined, as type information is unavailable (e.g. due to compilation errors).
+    Unresolved(String),
+    /// The functions of a dependency whose calls carry no errors, aggregated into a single node, by the name of the dependency.
+    Aggregated(String),
+    /// The exit of the program, receiving what the entry point returns (e.g. its error, printed by the runtime).
+    ProcessExit,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CallEdge {
     pub from: usize,
     pub to: usize,
-    pub call_id: HirId,
-    pub ty: Option<String>,
+    #[serde(with = "crate::schema::call_id")]
+    pub(crate) call_id: CallId,
+    pub ty: Option<Symbol>,
     pub propagates: bool,
+    pub propagates_locally: bool,
     pub is_error: bool,
+    /// Whether the error is the error of a `Result` or the `None` of an `Option`, if `is_error` is set.
+    pub failure_kind: FailureKind,
+    pub const_evaluated: bool,
+    pub is_unsafe_call: bool,
+    pub is_recursive: bool,
+    /// Whether the result of the call must be used beyond being a `Result`, i.e. the called function or the type it returns has `#[must_use]`.
+    pub must_use: bool,
+    pub handling: Handling,
+    pub expect_message: Option<String>,
+    #[serde(with = "crate::schema::option_hir_id")]
+    pub(crate) map_err_id: Option<HirId>,
+    pub mapped_ty: Option<Symbol>,
+    pub implicit: Option<ImplicitCall>,
+    /// Whether the error is a boxed `dyn Error`, hiding its concrete type.
+    pub type_erased: bool,
+    /// Whether the type is only approximate, as only the generic signature of the called function is known (e.g. `Result<T, E>`).
+    pub type_approximate: bool,
+    /// The concrete error types that are boxed into the `dyn Error`, if they are known.
+    pub origin_ty: Option<String>,
+    /// The error handling library the error type belongs to (e.g. `anyhow`), if any.
+    pub error_family: Option<ErrorFamily>,
+    /// What kind of type the error is (e.g. a string), if it is the error of a `Result`.
+    pub error_category: Option<ErrorCategory>,
+    /// The error type, if it is an enum defined in the analyzed crate.
+    #[serde(with = "crate::schema::option_def_id")]
+    pub(crate) error_enum: Option<DefId>,
+    /// The variants of the error enum that can be returned by this call, e.g. `MyError::{Io, Parse}`.
+    pub error_variants: Option<String>,
+    /// The kinds of the `std::io::Error`s that can be returned by this call, e.g. `NotFound, PermissionDenied`, or `io` if these are not known.
+    pub io_error_kinds: Option<String>,
+    /// The message of the context added to the error by the caller (e.g. using `anyhow::Context`), if it is a string literal.
+    pub context_message: Option<String>,
+    /// Whether panics of the called function are caught by the caller (e.g. using `catch_unwind`).
+    pub catches_panic: bool,
+    /// How the caller converts between a `Result` and an `Option` (e.g. using `ok`), if it does.
+    pub conversion: Option<FailureConversion>,
+    /// The location of the call, e.g. `src/main.rs:12:5`.
+    pub location: Option<String>,
+    /// Whether losing the error of this call is accepted, so the chains ending here are not reported.
+    pub suppressed: bool,
+}
+
+/// How a caller deals with the result of a call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Handling {
+    /// The result is forwarded to the caller's caller.
+    Propagated,
+    /// The error is dealt with (e.g. by matching on the result).
+    Handled,
+    /// The error is only logged by the branch dealing with it (e.g. `Err(e) => log::error!("{e}")`), which does not act on it.
+    LoggedAndDropped,
+    /// The error ends the process without unwinding (e.g. `Err(e) => std::process::exit(1)`).
+    ProcessExit,
+    /// The error causes a panic (e.g. by unwrapping the result).
+    Unwrapped,
+    /// The result is thrown away (e.g. `let _ = f();`).
+    Discarded,
+    /// None of the above could be detected.
+    Unknown,
+}
+
+/// The kind of type a call can fail with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FailureKind {
+    /// The call returns a `Result`, and fails with its error.
+    Result,
+    /// The call returns an `Option`, and fails with `None`.
+    Option,
+    /// The call returns another type implementing `Try` (e.g. `ControlFlow`), and fails with its residual.
+    Residual,
 }
 
-impl<'a> dot::Labeller<'a, CallNode, CallEdge> for CallGraph {
+/// A conversion between the kinds of failure of a call, changing what kind of failure flows onward.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FailureConversion {
+    /// `Result::ok`, which drops the error.
+    Ok,
+    /// `Result::err`, which turns the error into a value.
+    Err,
+    /// `Option::ok_or` or `Option::ok_or_else`, which creates an error for `None`.
+    OkOr,
+}
+
+/// The error handling library an error type belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ErrorFamily {
+    /// `anyhow::Error`.
+    Anyhow,
+    /// `eyre::Report`.
+    Eyre,
+}
+
+/// The downcast of a type-erased error to a concrete type, e.g. `error.downcast_ref::<SqlxError>()`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Downcast {
+    /// The type the error is downcast to, e.g. `SqlxError`.
+    pub ty: String,
+    /// The error handling library of the downcast error (e.g. `anyhow::Error`), or `None` for a `dyn Error`.
+    pub family: Option<ErrorFamily>,
+}
+
+/// What kind of type an error is, telling how well callers can match on it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum ErrorCategory {
+    /// A concrete enum or struct, e.g. `std::io::Error` or a local error enum.
+    Concrete,
+    /// A trait object hiding the concrete error, e.g. `Box<dyn Error>`, or an error handling library wrapping one (e.g. `anyhow::Error`).
+    TraitObject,
+    /// A string, e.g. `String` or `&str`.
+    String,
+    /// The unit type `()`, carrying no information at all.
+    Unit,
+    /// A generic type parameter of the caller.
+    Generic,
+    /// Any other type, e.g. an integer or a tuple.
+    Other,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ImplicitCall {
+    Operator,
+    Deref,
+    Drop,
+    Iterator,
+    From,
+    Spawn,
+    Collect,
+    /// The program exit receiving what the entry point returns.
+    ProcessExit,
+}
+
+impl<'a> dot::Labeller<'a, &'a CallNode, CallEdge> for CallGraph {
     fn graph_id(&self) -> Id<'a> {
         let mut name: String = self.crate_name.clone();
         name.retain(|e| e.is_ascii_alphanumeric() || e == '_');
         Id::new(format!("error_propagation_{name}")).unwrap()
     }
 
-    fn node_id(&self, n: &CallNode) -> Id<'a> {
+    fn node_id(&self, n: &&'a CallNode) -> Id<'a> {
         Id::new(format!("n{:?}", n.id)).unwrap()
     }
 
-    fn node_label(&self, n: &CallNode) -> LabelText<'a> {
-        LabelText::label(n.label.clone())
+    fn node_label(&self, n: &&'a CallNode) -> LabelText<'a> {
+        LabelText::label(self.strings.resolve(n.label).to_owned())
     }
 
     fn edge_label(&self, e: &CallEdge) -> LabelText<'a> {
-        LabelText::label(e.ty.clone().unwrap_or(String::from("unknown")))
+        // Drops never return anything interesting
+        if e.implicit == Some(ImplicitCall::Drop) {
+            return LabelText::label("drop");
+        }
+
+        let mut label = self.type_label(e).unwrap_or(String::from("unknown"));
+        if e.type_approximate {
+            label.push_str(" (approximate)");
+        }
+        if self.nodes[e.from].const_context {
+            label.push_str(" (const context)");
+        } else if e.const_evaluated {
+            label.push_str(" (const-evaluated)");
+        }
+        if e.is_unsafe_call {
+            label.push_str(" (unsafe)");
+        }
+        if e.is_recursive {
+            label.push_str(" (recursive)");
+        }
+        if let Some(handling) = e.handling_label() {
+            label.push_str(&format!(" ({handling})"));
+        }
+        if e.propagates_locally {
+            label.push_str(" (propagates to try block)");
+        }
+        if let Some(implicit) = e.implicit {
+            label.push_str(&format!(" ({})", implicit.label()));
+        }
+        LabelText::label(label)
     }
 
-    fn node_color(&'a self, n: &CallNode) -> Option<LabelText<'a>> {
+    fn node_shape(&'a self, n: &&'a CallNode) -> Option<LabelText<'a>> {
+        match n.kind {
+            CallNodeKind::ForeignFn(_def_id) => Some(LabelText::label("box")),
+            CallNodeKind::Aggregated(_) => Some(LabelText::label("folder")),
+            CallNodeKind::ProcessExit => Some(LabelText::label("doubleoctagon")),
+            _ => None,
+        }
+    }
+
+    fn node_style(&'a self, n: &&'a CallNode) -> Style {
+        if n.error_origin {
+            Style::Bold
+        } else {
+            Style::None
+        }
+    }
+
+    fn node_color(&'a self, n: &&'a CallNode) -> Option<LabelText<'a>> {
         if n.panics {
             Some(LabelText::label("red"))
+        } else if n.may_panic {
+            Some(LabelText::label("pink"))
+        } else if let CallNodeKind::ForeignFn(_def_id) = n.kind {
+            Some(LabelText::label("brown"))
         } else {
             None
         }
     }
 
     fn edge_color(&'a self, e: &CallEdge) -> Option<LabelText<'a>> {
-        if e.is_error && e.propagates {
+        if e.implicit == Some(ImplicitCall::Drop) {
+            Some(LabelText::label("gray"))
+        } else if e.is_error && self.nodes[e.to].infallible {
+            // The error of an infallible function is never returned
+            Some(LabelText::label("gray"))
+        } else if e.is_error && e.handling == Handling::Handled {
+            Some(LabelText::label("green"))
+        } else if e.is_error && e.handling == Handling::LoggedAndDropped {
+            Some(LabelText::label("gold"))
+        } else if e.is_error && e.handling == Handling::ProcessExit {
+            Some(LabelText::label("firebrick"))
+        } else if e.is_error && e.handling == Handling::Discarded {
+            Some(LabelText::label("orange"))
+        } else if e.is_error && e.propagates {
             Some(LabelText::label("purple"))
         } else if e.is_error {
             Some(LabelText::label("red"))
@@ -75,7 +424,14 @@ impl<'a> dot::Labeller<'a, CallNode, CallEdge> for CallGraph {
     }
 
     fn edge_style(&'a self, e: &CallEdge) -> Style {
-        if e.is_error || e.propagates {
+        if e.implicit == Some(ImplicitCall::Drop) {
+            Style::Dotted
+        } else if e.is_recursive {
+            Style::Bold
+        } else if e.implicit.is_some() || (self.subgraph_ty.is_some() && e.type_erased) {
+            // In the subgraph of an error type, type-erased calls carry that type hidden in a `dyn Error`
+            Style::Dashed
+        } else if e.is_error || e.propagates {
             Style::None
         } else {
             Style::Dotted
@@ -87,98 +443,197 @@ impl<'a> dot::Labeller<'a, CallNode, CallEdge> for CallGraph {
     }
 }
 
-impl<'a> dot::GraphWalk<'a, CallNode, CallEdge> for CallGraph {
-    fn nodes(&'a self) -> Nodes<'a, CallNode> {
-        let mut nodes = vec![];
-        for edge in &self.edges {
-            if !nodes.contains(&self.nodes[edge.from]) {
-                nodes.push(self.nodes[edge.from].clone());
-            }
-            if !nodes.contains(&self.nodes[edge.to]) {
-                nodes.push(self.nodes[edge.to].clone());
-            }
-        }
-        Cow::Owned(nodes)
+impl<'a> dot::GraphWalk<'a, &'a CallNode, CallEdge> for CallGraph {
+    fn nodes(&'a self) -> Nodes<'a, &'a CallNode> {
+        Cow::Owned(get_connected_nodes(
+            &self.nodes,
+            self.edges.iter().map(|edge| (edge.from, edge.to)),
+        ))
     }
 
     fn edges(&'a self) -> Edges<'a, CallEdge> {
-        Cow::Owned(self.edges.clone())
+        Cow::Borrowed(&self.edges)
     }
 
-    fn source(&'a self, edge: &CallEdge) -> CallNode {
-        self.nodes[edge.from].clone()
+    fn source(&'a self, edge: &CallEdge) -> &'a CallNode {
+        &self.nodes[edge.from]
     }
 
-    fn target(&'a self, edge: &CallEdge) -> CallNode {
-        self.nodes[edge.to].clone()
+    fn target(&'a self, edge: &CallEdge) -> &'a CallNode {
+        &self.nodes[edge.to]
     }
 }
 
-#[derive(Debug, Clone)]
+/// The graph of the error propagation chains of the analyzed crate.
+/// It is (de)serialized in a versioned schema (see `schema`), as is the call graph.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(remote = "Self")]
 pub struct ChainGraph {
     pub nodes: Vec<ChainNode>,
     pub edges: Vec<ChainEdge>,
     pub crate_name: String,
+    /// The chains shown in this graph.
+    pub chains: Vec<Chain>,
+    /// The statistics of the chains.
+    pub stats: ChainStats,
+    /// The labels of the nodes and edges, which are shared by many of them.
+    pub strings: Interner,
+    /// The stable paths of the functions and calls of the chains, which the ids of the compiler are (de)serialized as.
+    #[serde(skip)]
+    pub(crate) stable_paths: StablePaths,
 }
 
-#[derive(Debug, Clone)]
+/// An error propagation chain, from the call where the error stops propagating down to the calls where it originates.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Chain {
+    /// The number of the chain, i.e. its position among the chains of the program.
+    pub index: usize,
+    /// The calls of the chain, the call where the chain ends comes last.
+    pub calls: Vec<CallEdge>,
+    /// The number of chained function calls of the longest error path.
+    pub depth: usize,
+    /// The expressions constructing the errors of the chain, e.g. `MyError::Io` or `<MyError as From<io::Error>>::from`.
+    pub constructors: Vec<String>,
+    /// The index of the chain whose propagation path contains that of this chain, if any.
+    /// This chain is then shown as part of that chain, and counted once.
+    pub merged_into: Option<usize>,
+    /// How the chain ends.
+    pub termination: ChainEnd,
+    /// The nodes of the call graph past which the chain was not followed, as it reached the maximum depth.
+    pub truncated_at: Vec<usize>,
+    /// Whether losing the error of the chain is accepted, so it is counted apart and not reported.
+    pub suppressed: bool,
+}
+
+/// How an error propagation chain ends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum ChainEnd {
+    /// The error is returned from the entry point (e.g. `main`), ending the program.
+    ReturnedFromMain,
+    /// The error causes a panic (e.g. by unwrapping it).
+    Unwrapped,
+    /// The error is dealt with (e.g. by matching on it).
+    Handled,
+    /// The error is only logged, without acting on it.
+    LoggedAndDropped,
+    /// The error ends the process without unwinding (e.g. using `std::process::exit`).
+    ProcessExit,
+    /// The error is thrown away.
+    Discarded,
+    /// The error is returned to code outside of the analyzed crate (e.g. by a closure passed to a dependency, or a function that is not called).
+    LeavesCrate,
+    /// None of the above could be detected (e.g. the error is stored).
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChainNode {
     id: usize,
-    label: String,
+    label: Symbol,
+    error_origin: bool,
+    /// How the chains ending in this node end, if any do.
+    termination: Option<ChainEnd>,
+    /// Whether the chains through this node were not followed past it, as they reached the maximum depth.
+    truncated: bool,
+    /// The number of the chain this node is shown as part of, used to cluster its nodes.
+    chain: Option<usize>,
+    /// Whether all errors of an origin pass through the function of this node.
+    choke_point: bool,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChainEdge {
     from: usize,
     to: usize,
-    label: Option<String>,
+    label: Option<Symbol>,
+    /// The location of the call, e.g. `src/main.rs:12:5`.
+    pub location: Option<String>,
 }
 
-impl<'a> dot::Labeller<'a, ChainNode, ChainEdge> for ChainGraph {
+impl<'a> dot::Labeller<'a, &'a ChainNode, ChainEdge> for ChainGraph {
     fn graph_id(&'a self) -> Id<'a> {
         let mut name: String = self.crate_name.clone();
         name.retain(|e| e.is_ascii_alphanumeric() || e == '_');
         Id::new(format!("error_propagation_{name}_chains")).unwrap()
     }
 
-    fn node_id(&'a self, n: &ChainNode) -> Id<'a> {
+    fn node_id(&'a self, n: &&'a ChainNode) -> Id<'a> {
         Id::new(format!("n{:?}", n.id)).unwrap()
     }
 
-    fn node_label(&self, n: &ChainNode) -> LabelText<'a> {
-        LabelText::label(n.label.clone())
+    fn node_label(&self, n: &&'a ChainNode) -> LabelText<'a> {
+        let label = self.strings.resolve(n.label);
+        if n.truncated {
+            LabelText::label(format!("{label}\n(truncated)"))
+        } else {
+            LabelText::label(label.to_owned())
+        }
     }
 
     fn edge_label(&self, e: &ChainEdge) -> LabelText<'a> {
-        LabelText::label(e.label.clone().unwrap_or(String::from("unknown")))
+        let label = e
+            .label
+            .map_or("unknown", |label| self.strings.resolve(label));
+        LabelText::label(label.to_owned())
     }
-}
 
-impl<'a> dot::GraphWalk<'a, ChainNode, ChainEdge> for ChainGraph {
-    fn nodes(&'a self) -> Nodes<'a, ChainNode> {
-        let mut nodes = vec![];
-        for edge in &self.edges {
-            if !nodes.contains(&self.nodes[edge.from]) {
-                nodes.push(self.nodes[edge.from].clone());
-            }
-            if !nodes.contains(&self.nodes[edge.to]) {
-                nodes.push(self.nodes[edge.to].clone());
-            }
+    fn node_shape(&'a self, n: &&'a ChainNode) -> Option<LabelText<'a>> {
+        // The program exit, where the errors returned from the entry point end
+        (n.termination == Some(ChainEnd::ReturnedFromMain))
+            .then(|| LabelText::label("doubleoctagon"))
+    }
+
+    fn node_color(&'a self, n: &&'a ChainNode) -> Option<LabelText<'a>> {
+        n.termination
+            .map(|termination| LabelText::label(termination.color()))
+    }
+
+    fn node_style(&'a self, n: &&'a ChainNode) -> Style {
+        if n.error_origin {
+            Style::Bold
+        } else if n.truncated {
+            Style::Dashed
+        } else {
+            Style::None
         }
-        Cow::Owned(nodes)
+    }
+}
+
+impl<'a> dot::GraphWalk<'a, &'a ChainNode, ChainEdge> for ChainGraph {
+    fn nodes(&'a self) -> Nodes<'a, &'a ChainNode> {
+        Cow::Owned(get_connected_nodes(
+            &self.nodes,
+            self.edges.iter().map(|edge| (edge.from, edge.to)),
+        ))
     }
 
     fn edges(&'a self) -> Edges<'a, ChainEdge> {
-        Cow::Owned(self.edges.clone())
+        Cow::Borrowed(&self.edges)
     }
 
-    fn source(&'a self, edge: &ChainEdge) -> ChainNode {
-        self.nodes[edge.to].clone()
+    fn source(&'a self, edge: &ChainEdge) -> &'a ChainNode {
+        &self.nodes[edge.to]
     }
 
-    fn target(&'a self, edge: &ChainEdge) -> ChainNode {
-        self.nodes[edge.from].clone()
+    fn target(&'a self, edge: &ChainEdge) -> &'a ChainNode {
+        &self.nodes[edge.from]
+    }
+}
+
+/// Get the nodes with at least one edge, in the order they are first connected by the given edges (as pairs of node indices).
+/// The nodes are borrowed, so rendering a graph does not copy its nodes.
+fn get_connected_nodes<N>(nodes: &[N], edges: impl Iterator<Item = (usize, usize)>) -> Vec<&N> {
+    let mut connected = vec![false; nodes.len()];
+    let mut res = vec![];
+    for (from, to) in edges {
+        for node in [from, to] {
+            if !connected[node] {
+                connected[node] = true;
+                res.push(&nodes[node]);
+            }
+        }
     }
+    res
 }
 
 impl CallGraph {
@@ -187,25 +642,124 @@ impl CallGraph {
         CallGraph {
             nodes: Vec::new(),
             edges: Vec::new(),
+            outgoing: Vec::new(),
+            incoming: Vec::new(),
             crate_name,
+            filtered_macro_calls: 0,
+            unreachable_fns: None,
+            aggregated_fns: 0,
+            filtered_fns: Vec::new(),
+            entry_node: None,
+            exit_node: None,
+            entry_return_ty: None,
+            entry_returns_result: false,
+            discarded_calls: Vec::new(),
+            unresolved_calls: Vec::new(),
+            opaque_error_fns: Vec::new(),
+            subgraph_ty: None,
+            choke_points: Vec::new(),
+            strings: Interner::default(),
+            stable_paths: StablePaths::default(),
         }
     }
 
+    /// Load a graph saved using `save`, if it is of the schema version this version of the analyzer reads.
+    /// The loaded graph is unresolved: its ids can only be compared, and its functions are identified by their stable paths.
+    #[cfg(feature = "serde")]
+    pub fn load(path: &std::path::Path) -> Result<Self, String> {
+        schema::load(path)
+    }
+
+    /// Save this graph as JSON, along with its schema version.
+    #[cfg(feature = "serde")]
+    pub fn save(&self, path: &std::path::Path) -> Result<(), String> {
+        schema::save(self, path)
+    }
+
+    /// Whether the ids of the functions and calls are those of the compilation this graph was analyzed in,
+    /// rather than given when deserializing it.
+    pub fn is_resolved(&self) -> bool {
+        self.stable_paths.is_resolved()
+    }
+
+    /// Get the stable path of the function of a node, with the disambiguators of e.g. its impl block or closure (e.g. `app::{impl#0}::read`).
+    /// Unlike its label, it identifies the function across analyses, e.g. to match the nodes of two graphs.
+    pub fn stable_path(&self, node: usize) -> Option<&str> {
+        self.nodes[node]
+            .kind
+            .def_id()
+            .and_then(|def_id| self.stable_paths.get(def_id))
+    }
+
     /// Add a node to this graph, returning its id.
-    pub fn add_node(&mut self, label: &str, node_kind: CallNodeKind) -> usize {
+    pub(crate) fn add_node(&mut self, label: &str, node_kind: CallNodeKind) -> usize {
+        let label = self.strings.intern(label);
         let node = CallNode::new(self.nodes.len(), label, node_kind);
         let id = node.id();
         self.nodes.push(node);
+        self.outgoing.push(Vec::new());
+        self.incoming.push(Vec::new());
         id
     }
 
     /// Add an edge between two nodes to this graph.
     pub fn add_edge(&mut self, edge: CallEdge) {
+        self.outgoing[edge.from].push(self.edges.len());
+        self.incoming[edge.to].push(self.edges.len());
         self.edges.push(edge);
     }
 
+    /// Replace each group of nodes by a single new node with the given label and kind, moving their edges to it.
+    /// The nodes that are kept get new ids, in the same order, so any ids held outside of this graph are no longer valid.
+    pub(crate) fn merge_nodes(&mut self, groups: Vec<(Vec<usize>, String, CallNodeKind)>) {
+        let mut merged = vec![false; self.nodes.len()];
+        for (nodes, _label, _kind) in &groups {
+            for node in nodes {
+                merged[*node] = true;
+            }
+        }
+
+        // The new id of each node, where the merged nodes get the id of the node of their group
+        let mut ids = vec![0; self.nodes.len()];
+        let mut nodes: Vec<CallNode> = vec![];
+        for mut node in std::mem::take(&mut self.nodes) {
+            if merged[node.id] {
+                continue;
+            }
+            ids[node.id] = nodes.len();
+            node.id = nodes.len();
+            nodes.push(node);
+        }
+        for (group, label, kind) in groups {
+            let id = nodes.len();
+            for node in group {
+                ids[node] = id;
+            }
+            nodes.push(CallNode::new(id, self.strings.intern(&label), kind));
+        }
+        self.nodes = nodes;
+
+        for edge in &mut self.edges {
+            edge.from = ids[edge.from];
+            edge.to = ids[edge.to];
+        }
+        self.entry_node = self.entry_node.map(|node| ids[node]);
+        self.exit_node = self.exit_node.map(|node| ids[node]);
+        self.index_edges();
+    }
+
+    /// Rebuild the indices of the edges leaving and reaching each node, after the edges are replaced.
+    fn index_edges(&mut self) {
+        self.outgoing = vec![Vec::new(); self.nodes.len()];
+        self.incoming = vec![Vec::new(); self.nodes.len()];
+        for (i, edge) in self.edges.iter().enumerate() {
+            self.outgoing[edge.from].push(i);
+            self.incoming[edge.to].push(i);
+        }
+    }
+
     /// Find a node of `LocalFn` kind.
-    pub fn find_local_fn_node(&self, id: HirId) -> Option<CallNode> {
+    pub(crate) fn find_local_fn_node(&self, id: HirId) -> Option<CallNode> {
         for node in &self.nodes {
             if let CallNodeKind::LocalFn(_def_id, hir_id) = node.kind {
                 if hir_id == id {
@@ -218,9 +772,25 @@ impl CallGraph {
     }
 
     /// Find a node of `NonLocalFn` kind.
-    pub fn find_non_local_fn_node(&self, id: DefId) -> Option<CallNode> {
+    /// Trait methods get a node per kind of type they are called on, so the label has to match as well.
+    pub(crate) fn find_non_local_fn_node(&self, id: DefId, label: &str) -> Option<CallNode> {
+        // A label that was never interned belongs to no node
+        let label = self.strings.get(label)?;
         for node in &self.nodes {
             if let CallNodeKind::NonLocalFn(def_id) = node.kind {
+                if def_id == id && node.label == label {
+                    return Some(node.clone());
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Find a node of `ForeignFn` kind.
+    pub(crate) fn find_foreign_fn_node(&self, id: DefId) -> Option<CallNode> {
+        for node in &self.nodes {
+            if let CallNodeKind::ForeignFn(def_id) = node.kind {
                 if def_id == id {
                     return Some(node.clone());
                 }
@@ -230,36 +800,233 @@ impl CallGraph {
         None
     }
 
-    pub fn get_outgoing_edges(&self, node_id: usize) -> Vec<&CallEdge> {
-        let mut res = vec![];
+    /// Find a node of `Indirect` kind.
+    pub fn find_indirect_node(&self, signature: &str) -> Option<CallNode> {
+        for node in &self.nodes {
+            if let CallNodeKind::Indirect(sig) = &node.kind {
+                if sig == signature {
+                    return Some(node.clone());
+                }
+            }
+        }
+
+        None
+    }
 
-        for edge in &self.edges {
-            if edge.from == node_id {
-                res.push(edge);
+    /// Find a node of `Unresolved` kind.
+    pub fn find_unresolved_node(&self, name: &str) -> Option<CallNode> {
+        for node in &self.nodes {
+            if let CallNodeKind::Unresolved(n) = &node.kind {
+                if n == name {
+                    return Some(node.clone());
+                }
             }
         }
 
+        None
+    }
+
+    /// Get the label of a node.
+    pub fn label(&self, node: usize) -> &str {
+        self.strings.resolve(self.nodes[node].label)
+    }
+
+    /// Get the full path of the function of a node, i.e. its label unless it is shortened.
+    pub fn full_path(&self, node: usize) -> String {
+        let node = &self.nodes[node];
+        node.path
+            .clone()
+            .unwrap_or_else(|| self.strings.resolve(node.label).to_owned())
+    }
+
+    /// Get the type returned by a call, if known.
+    pub fn ty(&self, edge: &CallEdge) -> Option<&str> {
+        edge.ty.map(|ty| self.strings.resolve(ty))
+    }
+
+    /// Get the type the error of a call is converted to using `map_err`, if it is.
+    pub fn mapped_ty(&self, edge: &CallEdge) -> Option<&str> {
+        edge.mapped_ty.map(|ty| self.strings.resolve(ty))
+    }
+
+    /// Get the type of a call, including the type its error is converted to using `map_err` (e.g. `io::Error → MyError`).
+    pub fn type_label(&self, edge: &CallEdge) -> Option<String> {
+        let mut ty = edge
+            .error_variants
+            .clone()
+            .or(self.ty(edge).map(String::from))?;
+        if let Some(kinds) = &edge.io_error_kinds {
+            ty.push_str(&format!(" ({kinds})"));
+        }
+        if let Some(origin_ty) = &edge.origin_ty {
+            ty.push_str(&format!(" (from {origin_ty})"));
+        }
+        if let Some(message) = &edge.context_message {
+            ty.push_str(&format!(" (context \"{message}\")"));
+        }
+        if let Some(conversion) = edge.conversion {
+            ty.push_str(&format!(" ({})", conversion.label()));
+        }
+
+        if let Some(mapped_ty) = self.mapped_ty(edge) {
+            Some(format!("{ty} → {mapped_ty}"))
+        } else {
+            Some(ty)
+        }
+    }
+
+    /// Get a copy of this graph with only the provided edges (by index), the nodes keep their ids.
+    /// Only the nodes of these edges are rendered, as for any graph.
+    pub fn with_edges(&self, edges: &[usize]) -> CallGraph {
+        let mut res = self.clone();
+        res.edges = edges.iter().map(|edge| self.edges[*edge].clone()).collect();
+        res.index_edges();
         res
     }
 
-    /// Convert this graph to dot representation.
-    pub fn to_dot(&self) -> String {
-        let mut buf = Vec::new();
+    /// Get the indices of the edges reaching a node, in the order they were added.
+    pub fn get_incoming_edge_ids(&self, node_id: usize) -> &[usize] {
+        &self.incoming[node_id]
+    }
+
+    /// Get the edges reaching a node, in the order they were added.
+    pub fn get_incoming_edges(&self, node_id: usize) -> Vec<&CallEdge> {
+        self.incoming[node_id]
+            .iter()
+            .map(|edge| &self.edges[*edge])
+            .collect()
+    }
 
-        dot::render(self, &mut buf).unwrap();
+    /// Get the edges leaving a node, in the order they were added.
+    pub fn get_outgoing_edges(&self, node_id: usize) -> Vec<&CallEdge> {
+        self.outgoing[node_id]
+            .iter()
+            .map(|edge| &self.edges[*edge])
+            .collect()
+    }
+
+    /// Write this graph in dot representation, streaming it to the writer rather than building it in memory.
+    /// Nodes of followed dependencies are put in a cluster per dependency, and unsafe functions are dashed.
+    pub fn write_dot<W: Write>(&self, w: W) -> io::Result<()> {
+        self.write_dot_with_styles(w, &NodeStyles::default())
+    }
+
+    /// Write this graph in dot representation like `write_dot`, styling the nodes by the attributes of their functions.
+    pub fn write_dot_with_styles<W: Write>(&self, w: W, styles: &NodeStyles) -> io::Result<()> {
+        // Only the nodes of edges are rendered, so leave out the others
+        let rendered: Vec<&CallNode> = self
+            .nodes
+            .iter()
+            .filter(|node| !self.outgoing[node.id].is_empty() || !self.incoming[node.id].is_empty())
+            .collect();
+
+        // The dot crate does not support subgraphs, so add them before the closing brace
+        let mut extra = String::new();
+        let mut clusters: Vec<&String> = vec![];
+        for node in &rendered {
+            if let Some(cluster) = &node.cluster {
+                if !clusters.contains(&cluster) {
+                    clusters.push(cluster);
+                }
+            }
+        }
+        for cluster in clusters {
+            extra.push_str(&format!(
+                "    subgraph cluster_{cluster} {{\n        label=\"{cluster}\";\n"
+            ));
+            for node in &rendered {
+                if node.cluster.as_ref() == Some(cluster) {
+                    extra.push_str(&format!("        n{};\n", node.id));
+                }
+            }
+            extra.push_str("    }\n");
+        }
+
+        // The dot crate does not support tooltips either, so show the full paths of shortened labels the same way
+        extra.extend(rendered.iter().filter_map(|node| {
+            node.path.as_ref().map(|path| {
+                format!(
+                    "    n{}[tooltip=\"{}\"];\n",
+                    node.id,
+                    path.replace('"', "\\\"")
+                )
+            })
+        }));
+
+        // Nor fill colors, so color the nodes in chains from white to red by the number of chains passing through them, relative to the most
+        let max_count = rendered
+            .iter()
+            .map(|node| node.chain_count)
+            .max()
+            .unwrap_or(0);
+        // Nor multiple styles, so the styles of the attributes of the functions are added the same way
+        for node in &rendered {
+            let mut style = styles.of(node);
+            let mut fill = String::new();
+            if node.chain_count > 0 {
+                let shade = 255 - node.chain_count * 255 / max_count;
+                style.insert(0, "filled");
+                fill = format!("[fillcolor=\"#ff{shade:02x}{shade:02x}\"]");
+            }
+            if style.is_empty() {
+                continue;
+            }
+            // Setting the style of a node replaces its own, so error origins are kept bold
+            if node.error_origin {
+                style.push("bold");
+            }
+            extra.push_str(&format!(
+                "    n{}[style=\"{}\"]{fill};\n",
+                node.id,
+                style.join(",")
+            ));
+        }
+
+        // Nor double borders, so add them to the functions all errors of an origin pass through
+        for node in rendered.iter().filter(|node| node.choke_point) {
+            extra.push_str(&format!("    n{}[peripheries=2];\n", node.id));
+        }
 
-        String::from_utf8(buf).unwrap()
+        let mut writer = DotWriter::new(w, |_line| None);
+        dot::render(self, &mut writer)?;
+        writer.finish(&extra)
     }
 }
 
 impl CallNode {
     /// Create a new node.
-    fn new(node_id: usize, label: &str, node_type: CallNodeKind) -> Self {
+    fn new(node_id: usize, label: Symbol, node_type: CallNodeKind) -> Self {
         CallNode {
             id: node_id,
-            label: String::from(label),
+            label,
             kind: node_type,
             panics: false,
+            may_panic: false,
+            cluster: None,
+            path: None,
+            error_origin: false,
+            error_constructors: Vec::new(),
+            downcasts: Vec::new(),
+            chain_count: 0,
+            choke_point: false,
+            public: false,
+            infallible: false,
+            is_async: false,
+            is_unsafe: false,
+            visibility: None,
+            is_test: false,
+            track_caller: false,
+            const_context: false,
+        }
+    }
+
+    /// Whether the function of this node has the given attribute.
+    pub fn has_attribute(&self, attribute: NodeAttribute) -> bool {
+        match attribute {
+            NodeAttribute::Async => self.is_async,
+            NodeAttribute::Unsafe => self.is_unsafe,
+            NodeAttribute::Test => self.is_test,
+            NodeAttribute::Visibility(visibility) => self.visibility == Some(visibility),
         }
     }
 
@@ -271,34 +1038,270 @@ impl CallNode {
 
 impl CallNodeKind {
     /// Get a new `LocalFn`.
-    pub fn local_fn(def_id: DefId, hir_id: HirId) -> Self {
+    pub(crate) fn local_fn(def_id: DefId, hir_id: HirId) -> Self {
         CallNodeKind::LocalFn(def_id, hir_id)
     }
 
     /// Get a new `NonLocalFn`.
-    pub fn non_local_fn(id: DefId) -> Self {
+    pub(crate) fn non_local_fn(id: DefId) -> Self {
         CallNodeKind::NonLocalFn(id)
     }
 
-    /// Extract the `DefId` from this node.
-    pub fn def_id(&self) -> DefId {
+    /// Get a new `ForeignFn`.
+    pub(crate) fn foreign_fn(id: DefId) -> Self {
+        CallNodeKind::ForeignFn(id)
+    }
+
+    /// Get a new `Indirect`.
+    pub(crate) fn indirect(signature: String) -> Self {
+        CallNodeKind::Indirect(signature)
+    }
+
+    /// Get a new `Unresolved`.
+    pub(crate) fn unresolved(name: String) -> Self {
+        CallNodeKind::Unresolved(name)
+    }
+
+    /// Extract the `DefId` from this node, indirect and unresolved calls and aggregated functions have none.
+    pub(crate) fn def_id(&self) -> Option<DefId> {
         match self {
-            CallNodeKind::LocalFn(def_id, _hir_id) => *def_id,
-            CallNodeKind::NonLocalFn(def_id) => *def_id,
+            CallNodeKind::LocalFn(def_id, _hir_id) => Some(*def_id),
+            CallNodeKind::NonLocalFn(def_id) | CallNodeKind::ForeignFn(def_id) => Some(*def_id),
+            CallNodeKind::Indirect(_)
+            | CallNodeKind::Unresolved(_)
+            | CallNodeKind::Aggregated(_)
+            | CallNodeKind::ProcessExit => None,
         }
     }
 }
 
 impl CallEdge {
     /// Create a new edge.
-    pub fn new(from: usize, to: usize, call_id: HirId, propagates: bool) -> Self {
+    pub(crate) fn new(from: usize, to: usize, call_id: CallId, propagates: bool) -> Self {
         CallEdge {
             from,
             to,
             call_id,
             ty: None,
             propagates,
+            propagates_locally: false,
             is_error: false,
+            failure_kind: FailureKind::Result,
+            const_evaluated: false,
+            is_unsafe_call: false,
+            is_recursive: false,
+            must_use: false,
+            handling: Handling::Unknown,
+            expect_message: None,
+            map_err_id: None,
+            mapped_ty: None,
+            implicit: None,
+            type_erased: false,
+            type_approximate: false,
+            origin_ty: None,
+            error_family: None,
+            error_category: None,
+            error_enum: None,
+            error_variants: None,
+            io_error_kinds: None,
+            context_message: None,
+            catches_panic: false,
+            conversion: None,
+            location: None,
+            suppressed: false,
+        }
+    }
+
+    /// Get the label describing how the result of this call is handled, including the message of an `expect` call.
+    pub fn handling_label(&self) -> Option<String> {
+        match (self.handling, &self.expect_message) {
+            (Handling::Unwrapped, Some(message)) => Some(format!("expect \"{message}\"")),
+            (handling, _message) => handling.label().map(String::from),
+        }
+    }
+}
+
+impl Handling {
+    /// Get the label describing how a chain ends in this kind of handling, propagation does not end a chain.
+    pub fn label(self) -> Option<&'static str> {
+        match self {
+            Handling::Handled => Some("handled"),
+            Handling::LoggedAndDropped => Some("logged and dropped"),
+            Handling::ProcessExit => Some("handled by exiting"),
+            Handling::Unwrapped => Some("unwrapped"),
+            Handling::Discarded => Some("discarded"),
+            Handling::Propagated | Handling::Unknown => None,
+        }
+    }
+}
+
+impl ErrorCategory {
+    /// Get the label describing errors of this category.
+    pub fn label(self) -> &'static str {
+        match self {
+            ErrorCategory::Concrete => "concrete",
+            ErrorCategory::TraitObject => "trait object",
+            ErrorCategory::String => "string",
+            ErrorCategory::Unit => "unit",
+            ErrorCategory::Generic => "generic",
+            ErrorCategory::Other => "other",
+        }
+    }
+
+    /// Whether callers cannot match on errors of this category, as they are a string, unit or trait object.
+    pub fn is_opaque(self) -> bool {
+        matches!(
+            self,
+            ErrorCategory::String | ErrorCategory::Unit | ErrorCategory::TraitObject
+        )
+    }
+}
+
+impl ChainEnd {
+    /// Get the label describing how a chain ends in this way.
+    pub fn label(self) -> &'static str {
+        match self {
+            ChainEnd::ReturnedFromMain => "returned from main",
+            ChainEnd::Unwrapped => "unwrapped",
+            ChainEnd::Handled => "handled",
+            ChainEnd::LoggedAndDropped => "logged and dropped",
+            ChainEnd::ProcessExit => "handled by exiting",
+            ChainEnd::Discarded => "discarded",
+            ChainEnd::LeavesCrate => "returned outside of the analyzed crate",
+            ChainEnd::Unknown => "used otherwise",
+        }
+    }
+
+    /// Get the way a chain ends from its label, e.g. as read back from JSON.
+    pub fn from_label(label: &str) -> Option<Self> {
+        [
+            ChainEnd::ReturnedFromMain,
+            ChainEnd::Unwrapped,
+            ChainEnd::Handled,
+            ChainEnd::LoggedAndDropped,
+            ChainEnd::ProcessExit,
+            ChainEnd::Discarded,
+            ChainEnd::LeavesCrate,
+            ChainEnd::Unknown,
+        ]
+        .into_iter()
+        .find(|termination| termination.label() == label)
+    }
+
+    /// Get how badly the error is dealt with when a chain ends in this way, from handled (0) to unwrapped (3).
+    pub fn severity(self) -> usize {
+        match self {
+            ChainEnd::Handled => 0,
+            ChainEnd::ReturnedFromMain
+            | ChainEnd::LoggedAndDropped
+            | ChainEnd::ProcessExit
+            | ChainEnd::LeavesCrate
+            | ChainEnd::Unknown => 1,
+            ChainEnd::Discarded => 2,
+            ChainEnd::Unwrapped => 3,
+        }
+    }
+
+    /// Get the color of the node where a chain ends in this way.
+    pub fn color(self) -> &'static str {
+        match self {
+            ChainEnd::ReturnedFromMain => "red",
+            ChainEnd::Unwrapped => "purple",
+            ChainEnd::Handled => "green",
+            ChainEnd::LoggedAndDropped => "gold",
+            ChainEnd::ProcessExit => "firebrick",
+            ChainEnd::Discarded => "orange",
+            ChainEnd::LeavesCrate => "blue",
+            ChainEnd::Unknown => "gray",
+        }
+    }
+}
+
+impl Visibility {
+    /// Get the label of this visibility, i.e. `pub`, `pub(crate)` or `private`.
+    pub fn label(self) -> &'static str {
+        match self {
+            Visibility::Public => "pub",
+            Visibility::Crate => "pub(crate)",
+            Visibility::Private => "private",
+        }
+    }
+}
+
+impl NodeAttribute {
+    /// Get an attribute from its name, as written in the manifest.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "async" => Some(NodeAttribute::Async),
+            "unsafe" => Some(NodeAttribute::Unsafe),
+            "test" => Some(NodeAttribute::Test),
+            "pub" => Some(NodeAttribute::Visibility(Visibility::Public)),
+            "pub(crate)" => Some(NodeAttribute::Visibility(Visibility::Crate)),
+            "private" => Some(NodeAttribute::Visibility(Visibility::Private)),
+            _ => None,
+        }
+    }
+}
+
+impl Default for NodeStyles {
+    /// Unsafe functions are dashed.
+    fn default() -> Self {
+        NodeStyles {
+            styles: vec![(NodeAttribute::Unsafe, String::from("dashed"))],
+        }
+    }
+}
+
+impl NodeStyles {
+    /// Set the dot style of the functions with an attribute, given by its name (e.g. `unsafe`), replacing its default.
+    /// An empty style leaves these functions unstyled.
+    pub fn set(&mut self, name: &str, style: &str) -> Result<(), String> {
+        let Some(attribute) = NodeAttribute::from_name(name) else {
+            return Err(format!(
+                "Unknown function attribute {name}, use one of: {}",
+                NODE_ATTRIBUTE_NAMES.join(", ")
+            ));
+        };
+        self.styles.retain(|(other, _style)| *other != attribute);
+        if !style.is_empty() {
+            self.styles.push((attribute, style.to_owned()));
+        }
+        Ok(())
+    }
+
+    /// Get the styles of a node, in the order they were set.
+    fn of(&self, node: &CallNode) -> Vec<&str> {
+        self.styles
+            .iter()
+            .filter(|(attribute, _style)| node.has_attribute(*attribute))
+            .map(|(_attribute, style)| style.as_str())
+            .collect()
+    }
+}
+
+impl FailureConversion {
+    /// Get the label describing this kind of conversion.
+    pub fn label(self) -> &'static str {
+        match self {
+            FailureConversion::Ok => "error dropped via .ok()",
+            FailureConversion::Err => "Result→Option via err",
+            FailureConversion::OkOr => "Option→Result via ok_or",
+        }
+    }
+}
+
+impl ImplicitCall {
+    /// Get the label describing this kind of implicit call.
+    pub fn label(self) -> &'static str {
+        match self {
+            ImplicitCall::Operator => "operator",
+            ImplicitCall::Deref => "deref",
+            ImplicitCall::Drop => "drop",
+            ImplicitCall::Iterator => "for loop",
+            ImplicitCall::From => "via From",
+            ImplicitCall::Spawn => "spawn",
+            ImplicitCall::Collect => "via iterator",
+            ImplicitCall::ProcessExit => "process exit",
         }
     }
 }
@@ -316,6 +1319,11 @@ impl PartialEq for CallNodeKind {
                 def_id1 == def_id2 && hir_id1 == hir_id2
             }
             (CallNodeKind::NonLocalFn(id1), CallNodeKind::NonLocalFn(id2)) => id1 == id2,
+            (CallNodeKind::ForeignFn(id1), CallNodeKind::ForeignFn(id2)) => id1 == id2,
+            (CallNodeKind::Indirect(sig1), CallNodeKind::Indirect(sig2)) => sig1 == sig2,
+            (CallNodeKind::Unresolved(name1), CallNodeKind::Unresolved(name2)) => name1 == name2,
+            (CallNodeKind::Aggregated(name1), CallNodeKind::Aggregated(name2)) => name1 == name2,
+            (CallNodeKind::ProcessExit, CallNodeKind::ProcessExit) => true,
             _ => false,
         }
     }
@@ -334,42 +1342,239 @@ impl ChainGraph {
             nodes: Vec::new(),
             edges: Vec::new(),
             crate_name,
+            chains: Vec::new(),
+            stats: ChainStats::default(),
+            strings: Interner::default(),
+            stable_paths: StablePaths::default(),
         }
     }
 
-    pub fn add_node(&mut self, label: String) -> usize {
+    /// Load a graph saved using `save`, if it is of the schema version this version of the analyzer reads.
+    #[cfg(feature = "serde")]
+    pub fn load(path: &std::path::Path) -> Result<Self, String> {
+        schema::load(path)
+    }
+
+    /// Save this graph as JSON, along with its schema version.
+    #[cfg(feature = "serde")]
+    pub fn save(&self, path: &std::path::Path) -> Result<(), String> {
+        schema::save(self, path)
+    }
+
+    pub fn add_node(&mut self, label: &str) -> usize {
         let id = self.nodes.len();
+        let label = self.strings.intern(label);
 
         self.nodes.push(ChainNode::new(id, label));
 
         id
     }
 
-    pub fn add_edge(&mut self, from: usize, to: usize, label: Option<String>) {
-        self.edges.push(ChainEdge::new(from, to, label));
+    pub fn add_edge(
+        &mut self,
+        from: usize,
+        to: usize,
+        label: Option<&str>,
+        location: Option<String>,
+    ) {
+        let label = label.map(|label| self.strings.intern(label));
+        self.edges.push(ChainEdge::new(from, to, label, location));
+    }
+
+    /// Mark a node as the function where a chain ends, and how it ends.
+    /// Nodes where several chains end keep the first way.
+    pub fn mark_termination(&mut self, node: usize, termination: ChainEnd) {
+        self.nodes[node].termination.get_or_insert(termination);
+    }
+
+    /// Mark a node as a function past which chains were not followed.
+    pub fn mark_truncated(&mut self, node: usize) {
+        self.nodes[node].truncated = true;
+    }
+
+    /// Mark a node as a function all errors of an origin pass through.
+    pub fn mark_choke_point(&mut self, node: usize) {
+        self.nodes[node].choke_point = true;
+    }
+
+    /// Set the number of the chain a node is shown as part of.
+    pub fn set_chain(&mut self, node: usize, chain: usize) {
+        self.nodes[node].chain = Some(chain);
+    }
+
+    /// Mark a node as a function where errors originate.
+    pub fn mark_error_origin(&mut self, node: usize) {
+        self.nodes[node].error_origin = true;
+    }
+
+    /// Write this graph in dot representation, streaming it to the writer rather than building it in memory.
+    /// The nodes of each chain are put in a cluster, labeled with the number of the chain, its depth and how it ends.
+    pub fn write_dot<W: Write>(&self, w: W) -> io::Result<()> {
+        // The dot crate does not support subgraphs, so add them before the closing brace
+        let mut extra = String::new();
+        for chain in &self.chains {
+            let nodes: Vec<&ChainNode> = self
+                .nodes
+                .iter()
+                .filter(|node| node.chain == Some(chain.index))
+                .collect();
+            if nodes.is_empty() {
+                continue;
+            }
+
+            // The chains merged into this chain end here as well
+            let mut endings: Vec<ChainEnd> = self
+                .chains
+                .iter()
+                .filter(|other| {
+                    other.index == chain.index
+                        || other
+                            .merged_into
+                            .is_some_and(|i| self.chains[i].index == chain.index)
+                })
+                .map(|other| other.termination)
+                .collect();
+            endings.sort_unstable();
+            endings.dedup();
+            let endings: Vec<&str> = endings.iter().map(|ending| ending.label()).collect();
+
+            extra.push_str(&format!(
+                "    subgraph cluster_chain_{} {{\n        label=\"chain #{} (depth {}, ends: {})\";\n",
+                chain.index,
+                chain.index,
+                chain.depth,
+                endings.join(", ")
+            ));
+            for node in nodes {
+                extra.push_str(&format!("        n{};\n", node.id));
+            }
+            extra.push_str("    }\n");
+        }
+
+        // Nor double borders, so add them to the functions all errors of an origin pass through
+        for node in self.nodes.iter().filter(|node| node.choke_point) {
+            extra.push_str(&format!("    n{}[peripheries=2];\n", node.id));
+        }
+
+        // The dot crate does not support tooltips either, so add the locations of the calls to the edges it renders, in order
+        let mut edges = self.edges.iter();
+        let mut writer = DotWriter::new(w, |line| {
+            let location = if line.contains(" -> ") {
+                edges.next().and_then(|edge| edge.location.as_ref())
+            } else {
+                None
+            };
+            let statement = line.strip_suffix(';')?;
+            location.map(|location| format!("{statement}[tooltip=\"{location}\"];"))
+        });
+        dot::render(self, &mut writer)?;
+        writer.finish(&extra)
+    }
+}
+
+/// Undo the escaping of the symbols used in labels, i.e. the arrows of converted errors and the ellipses of incomplete variant sets.
+fn unescape_symbols(dot: &str) -> String {
+    dot.replace("\\u{2192}", "→").replace("\\u{2026}", "…")
+}
+
+/// A writer passing the dot rendered by the dot crate on line by line, so graphs are never held in memory as a whole.
+/// The escaping of symbols is undone, and each line can be replaced (e.g. to add attributes the crate does not support).
+/// The closing brace is held back, so the statements the crate does not support (e.g. subgraphs) can be added before it.
+struct DotWriter<W: Write, F: FnMut(&str) -> Option<String>> {
+    inner: W,
+    /// The part of the current line written so far.
+    line: Vec<u8>,
+    /// Whether the closing brace was held back.
+    closed: bool,
+    /// Get the replacement of a line, if it is replaced.
+    replace_line: F,
+}
+
+impl<W: Write, F: FnMut(&str) -> Option<String>> DotWriter<W, F> {
+    /// Create a new writer, writing to the provided writer.
+    fn new(inner: W, replace_line: F) -> Self {
+        DotWriter {
+            inner,
+            line: Vec::new(),
+            closed: false,
+            replace_line,
+        }
+    }
+
+    /// Write a complete line, without its line break.
+    fn write_line(&mut self, line: &[u8]) -> io::Result<()> {
+        // The dot crate escapes non-ASCII characters, which graphviz does not understand
+        let line = unescape_symbols(&String::from_utf8_lossy(line));
+        if self.closed {
+            self.inner.write_all(b"}\n")?;
+            self.closed = false;
+        }
+        if line == "}" {
+            self.closed = true;
+            return Ok(());
+        }
+
+        let line = (self.replace_line)(&line).unwrap_or(line);
+        self.inner.write_all(line.as_bytes())?;
+        self.inner.write_all(b"\n")
     }
 
-    /// Convert this graph to dot representation.
-    pub fn to_dot(&self) -> String {
-        let mut buf = Vec::new();
+    /// Write the statements to add before the closing brace, and the brace itself.
+    fn finish(mut self, extra: &str) -> io::Result<()> {
+        if !self.line.is_empty() {
+            let line = std::mem::take(&mut self.line);
+            self.write_line(&line)?;
+        }
+        self.inner.write_all(extra.as_bytes())?;
+        if self.closed {
+            self.inner.write_all(b"}\n")?;
+        }
+        self.inner.flush()
+    }
+}
 
-        dot::render(self, &mut buf).unwrap();
+impl<W: Write, F: FnMut(&str) -> Option<String>> Write for DotWriter<W, F> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut rest = buf;
+        while let Some(end) = rest.iter().position(|byte| *byte == b'\n') {
+            let mut line = std::mem::take(&mut self.line);
+            line.extend_from_slice(&rest[..end]);
+            self.write_line(&line)?;
+            rest = &rest[end + 1..];
+        }
+        self.line.extend_from_slice(rest);
+        Ok(buf.len())
+    }
 
-        String::from_utf8(buf).unwrap()
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
     }
 }
 
 impl ChainNode {
     /// Create a new node.
-    fn new(id: usize, label: String) -> Self {
-        ChainNode { id, label }
+    fn new(id: usize, label: Symbol) -> Self {
+        ChainNode {
+            id,
+            label,
+            error_origin: false,
+            termination: None,
+            truncated: false,
+            chain: None,
+            choke_point: false,
+        }
     }
 }
 
 impl ChainEdge {
     /// Create a new edge.
-    pub fn new(from: usize, to: usize, label: Option<String>) -> Self {
-        ChainEdge { from, to, label }
+    pub fn new(from: usize, to: usize, label: Option<Symbol>, location: Option<String>) -> Self {
+        ChainEdge {
+            from,
+            to,
+            label,
+            location,
+        }
     }
 }
 
@@ -384,3 +1589,212 @@ impl PartialEq for ChainEdge {
         self.to == other.to && self.from == other.from
     }
 }
+
+impl VersionedGraph for CallGraph {
+    fn serialize_fields<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        CallGraph::serialize(self, serializer)
+    }
+
+    fn deserialize_fields<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        CallGraph::deserialize(deserializer)
+    }
+}
+
+impl Serialize for CallGraph {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        schema::serialize(self, &self.stable_paths, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for CallGraph {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (mut graph, stable_paths): (CallGraph, _) = schema::deserialize(deserializer)?;
+        graph.stable_paths = stable_paths;
+        Ok(graph)
+    }
+}
+
+impl VersionedGraph for ChainGraph {
+    fn serialize_fields<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ChainGraph::serialize(self, serializer)
+    }
+
+    fn deserialize_fields<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        ChainGraph::deserialize(deserializer)
+    }
+}
+
+impl Serialize for ChainGraph {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        schema::serialize(self, &self.stable_paths, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ChainGraph {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let (mut graph, stable_paths): (ChainGraph, _) = schema::deserialize(deserializer)?;
+        graph.stable_paths = stable_paths;
+        Ok(graph)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use rustc_hir::def_id::{DefIndex, LocalDefId, LOCAL_CRATE};
+    use rustc_hir::hir_id::{ItemLocalId, OwnerId};
+
+    /// Get the id of the local function of a node, numbered by the node.
+    fn local_def_id(node: usize) -> LocalDefId {
+        LocalDefId {
+            local_def_index: DefIndex::from_usize(node),
+        }
+    }
+
+    /// Create a graph with a local function per label.
+    pub(crate) fn graph(functions: &[&str]) -> CallGraph {
+        let mut graph = CallGraph::new(String::from("test"));
+        for function in functions {
+            add_function(&mut graph, function);
+        }
+        graph
+    }
+
+    /// Add a local function to a graph, returning its node.
+    pub(crate) fn add_function(graph: &mut CallGraph, label: &str) -> usize {
+        let node = graph.nodes.len();
+        let def_id = DefId {
+            krate: LOCAL_CRATE,
+            index: DefIndex::from_usize(node),
+        };
+        let hir_id = HirId::make_owner(local_def_id(node));
+        graph.add_node(label, CallNodeKind::local_fn(def_id, hir_id))
+    }
+
+    /// Add a call between the functions with the given labels, returning its index.
+    /// Each call gets its own id, as if it is a separate call expression.
+    pub(crate) fn add_call(
+        graph: &mut CallGraph,
+        from: &str,
+        to: &str,
+        is_error: bool,
+        propagates: bool,
+    ) -> usize {
+        let node = |label: &str| {
+            (0..graph.nodes.len())
+                .find(|node| graph.label(*node) == label)
+                .unwrap_or_else(|| panic!("No function {label}"))
+        };
+        let (from, to) = (node(from), node(to));
+        let call_id = CallId::Hir(HirId {
+            owner: OwnerId {
+                def_id: local_def_id(from),
+            },
+            local_id: ItemLocalId::from_usize(graph.edges.len() + 1),
+        });
+        let mut edge = CallEdge::new(from, to, call_id, propagates);
+        edge.is_error = is_error;
+        graph.add_edge(edge);
+        graph.edges.len() - 1
+    }
+
+    /// Assert that the edges leaving and reaching each node are those of `edges`, in their order.
+    fn assert_indexed(graph: &CallGraph) {
+        assert_eq!(graph.outgoing.len(), graph.nodes.len());
+        assert_eq!(graph.incoming.len(), graph.nodes.len());
+        for node in 0..graph.nodes.len() {
+            let outgoing: Vec<usize> = (0..graph.edges.len())
+                .filter(|edge| graph.edges[*edge].from == node)
+                .collect();
+            let incoming: Vec<usize> = (0..graph.edges.len())
+                .filter(|edge| graph.edges[*edge].to == node)
+                .collect();
+            assert_eq!(graph.outgoing[node], outgoing, "Edges leaving {node}");
+            assert_eq!(
+                graph.get_incoming_edge_ids(node),
+                incoming,
+                "Edges reaching {node}"
+            );
+            assert_eq!(graph.get_outgoing_edges(node).len(), outgoing.len());
+            assert_eq!(graph.get_incoming_edges(node).len(), incoming.len());
+        }
+    }
+
+    /// `main` calls `run` twice, which calls `load` and `parse`, both calling `read`.
+    fn example() -> CallGraph {
+        let mut graph = graph(&["main", "run", "load", "parse", "read"]);
+        add_call(&mut graph, "main", "run", true, false);
+        add_call(&mut graph, "run", "load", true, true);
+        add_call(&mut graph, "run", "parse", true, true);
+        add_call(&mut graph, "load", "read", true, true);
+        add_call(&mut graph, "main", "run", false, false);
+        add_call(&mut graph, "parse", "read", true, true);
+        graph
+    }
+
+    #[test]
+    fn added_edges_are_indexed() {
+        let mut graph = example();
+        assert_indexed(&graph);
+        assert_eq!(graph.get_incoming_edge_ids(1), [0, 4]);
+
+        // Nodes added later get an empty index, which their edges are added to
+        let log = add_function(&mut graph, "log");
+        assert!(graph.get_incoming_edges(log).is_empty());
+        add_call(&mut graph, "read", "log", false, false);
+        add_call(&mut graph, "log", "log", false, false);
+        assert_indexed(&graph);
+        assert_eq!(graph.get_incoming_edge_ids(log), [6, 7]);
+    }
+
+    #[test]
+    fn merged_nodes_are_reindexed() {
+        let mut graph = example();
+        graph.entry_node = Some(0);
+        let kind = graph.nodes[2].kind.clone();
+        graph.merge_nodes(vec![(vec![2, 3], String::from("load or parse"), kind)]);
+        assert_indexed(&graph);
+
+        // The nodes that are kept are renumbered in order, the merged node comes last
+        let labels: Vec<&str> = (0..graph.nodes.len())
+            .map(|node| graph.label(node))
+            .collect();
+        assert_eq!(labels, ["main", "run", "read", "load or parse"]);
+        assert_eq!(graph.entry_node, Some(0));
+        assert_eq!(graph.get_incoming_edge_ids(2), [3, 5]);
+        assert_eq!(graph.outgoing[3], [3, 5]);
+        assert_eq!(graph.get_incoming_edge_ids(3), [1, 2]);
+    }
+
+    #[test]
+    fn copies_with_fewer_edges_are_reindexed() {
+        let graph = example();
+        let copy = graph.with_edges(&[5, 0]);
+        assert_indexed(&copy);
+        assert_eq!(copy.nodes.len(), graph.nodes.len());
+        assert_eq!(copy.get_incoming_edge_ids(4), [0]);
+        assert_eq!(copy.outgoing[0], [1]);
+        assert!(copy.get_incoming_edges(2).is_empty());
+        // The graph itself is left as it is
+        assert_indexed(&graph);
+        assert_eq!(graph.get_incoming_edge_ids(4), [3, 5]);
+    }
+
+    #[test]
+    fn rendered_nodes_are_borrowed() {
+        let mut graph = example();
+        add_function(&mut graph, "unused");
+        let nodes = dot::GraphWalk::nodes(&graph);
+        assert!(matches!(nodes, Cow::Owned(_)));
+
+        // The connected nodes in the order their edges connect them, without the unused function
+        let ids: Vec<usize> = nodes.iter().map(|node| node.id).collect();
+        assert_eq!(ids, [0, 1, 2, 3, 4]);
+        for node in nodes.iter() {
+            assert!(std::ptr::eq(*node, &graph.nodes[node.id]));
+        }
+        let edges = dot::GraphWalk::edges(&graph);
+        assert!(matches!(edges, Cow::Borrowed(_)));
+        assert!(std::ptr::eq(edges.as_ptr(), graph.edges.as_ptr()));
+    }
+}
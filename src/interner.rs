@@ -0,0 +1,118 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// A string stored once in an `Interner`, e.g. a label or error type shared by many nodes and edges of a graph.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Symbol(u32);
+
+/// The strings of a graph, each stored once and referred to by its `Symbol`.
+/// It is (de)serialized as its table of strings, in which each symbol is an index.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(from = "Vec<String>", into = "Vec<String>")]
+pub struct Interner {
+    strings: Vec<Arc<str>>,
+    symbols: HashMap<Arc<str>, Symbol>,
+}
+
+impl Interner {
+    /// Get the symbol of a string, storing the string if it was not interned before.
+    pub fn intern(&mut self, string: &str) -> Symbol {
+        if let Some(symbol) = self.symbols.get(string) {
+            return *symbol;
+        }
+
+        let symbol = Symbol(self.strings.len() as u32);
+        let string: Arc<str> = Arc::from(string);
+        self.strings.push(string.clone());
+        self.symbols.insert(string, symbol);
+        symbol
+    }
+
+    /// Get the string of a symbol of this interner.
+    pub fn resolve(&self, symbol: Symbol) -> &str {
+        &self.strings[symbol.0 as usize]
+    }
+
+    /// Get the symbol of a string, if it was interned.
+    pub fn get(&self, string: &str) -> Option<Symbol> {
+        self.symbols.get(string).copied()
+    }
+}
+
+impl From<Vec<String>> for Interner {
+    fn from(strings: Vec<String>) -> Self {
+        let mut interner = Interner::default();
+        for string in strings {
+            interner.intern(&string);
+        }
+        interner
+    }
+}
+
+impl From<Interner> for Vec<String> {
+    fn from(interner: Interner) -> Self {
+        interner
+            .strings
+            .iter()
+            .map(|string| string.to_string())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_string_is_interned_once() {
+        let mut interner = Interner::default();
+        let io = interner.intern("std::io::Error");
+        let parse = interner.intern("std::num::ParseIntError");
+        assert_eq!(interner.intern("std::io::Error"), io);
+        assert_ne!(io, parse);
+        assert_eq!(interner.strings.len(), 2);
+        assert_eq!(interner.symbols.len(), 2);
+        // The table and the lookup share the string
+        assert!(Arc::ptr_eq(
+            &interner.strings[0],
+            interner.symbols.get_key_value("std::io::Error").unwrap().0
+        ));
+    }
+
+    #[test]
+    fn symbols_resolve_to_their_string() {
+        let mut interner = Interner::default();
+        let symbols: Vec<Symbol> = ["main", "run", "main", ""]
+            .iter()
+            .map(|string| interner.intern(string))
+            .collect();
+        assert_eq!(symbols[0], symbols[2]);
+        let strings: Vec<&str> = symbols
+            .iter()
+            .map(|symbol| interner.resolve(*symbol))
+            .collect();
+        assert_eq!(strings, ["main", "run", "main", ""]);
+        assert_eq!(interner.get("run"), Some(symbols[1]));
+        assert_eq!(interner.get("load"), None);
+    }
+
+    #[test]
+    fn table_keeps_the_symbols() {
+        let mut interner = Interner::default();
+        let run = interner.intern("run");
+        interner.intern("main");
+        let table: Vec<String> = interner.clone().into();
+        assert_eq!(table, ["run", "main"]);
+
+        // Duplicates in a table are interned once, keeping the symbols of the strings before them
+        let interner = Interner::from(vec![
+            String::from("run"),
+            String::from("main"),
+            String::from("run"),
+        ]);
+        assert_eq!(interner.get("run"), Some(run));
+        assert_eq!(interner.strings.len(), 2);
+    }
+}
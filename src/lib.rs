@@ -0,0 +1,39 @@
+#![feature(rustc_private)]
+
+//! Static analysis of the error handling of a Rust package: the errors each function call carries,
+//! and the chains along which these errors are propagated until they are handled, unwrapped, or reach the program exit.
+//! The package is analyzed using `analyze_crate`, which runs the compiler on it using `rustc_private`.
+
+pub mod analysis;
+pub mod baseline;
+mod cache;
+pub mod cargo_invocation;
+mod driver;
+pub mod error;
+pub mod graph;
+pub mod interner;
+pub mod logging;
+pub mod progress;
+pub mod query;
+pub mod report;
+pub mod sarif;
+pub mod schema;
+pub mod scoring;
+pub mod stats;
+pub mod summary;
+pub mod timings;
+
+extern crate rustc_ast;
+extern crate rustc_data_structures;
+extern crate rustc_driver;
+extern crate rustc_hir;
+extern crate rustc_interface;
+extern crate rustc_middle;
+extern crate rustc_parse;
+extern crate rustc_session;
+extern crate rustc_span;
+
+pub use analysis::AnalysisOptions;
+pub use driver::{analyze_crate, read_manifest, AnalysisResult, CrateOptions};
+pub use error::AnalyzerError;
+pub use graph::{CallGraph, ChainGraph};
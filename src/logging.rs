@@ -0,0 +1,82 @@
+//! The messages of the analyzer (e.g. `Building package...` or the warnings about calls that could not be resolved) are emitted using `tracing`,
+//! so users of the library can filter and capture them by installing their own subscriber.
+//! Their targets tell the part of the analysis they come from: `analyzer::cargo` (building the package), `analyzer::rustc` (running the compiler),
+//! `analyzer::cache`, `analyzer::graph` (building and typing the call graph) and `analyzer::chains` (extracting the chains).
+//! The stages of the analysis (as timed by `--timings`) are `analyzer::timings` spans, which the messages of the stage are emitted in.
+//!
+//! The analyzer itself installs the subscriber of `init`, which prints the messages like the analyzer always has, filtered by `ERROR_ANALYZER_LOG`.
+
+use std::fmt;
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::writer::MakeWriterExt;
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::EnvFilter;
+
+/// The environment variable filtering the messages of the analyzer, in the syntax of `RUST_LOG`, e.g. `analyzer::graph=debug,analyzer::cargo=warn`.
+pub const LOG_ENV: &str = "ERROR_ANALYZER_LOG";
+
+/// Get the filter of the messages set by `ERROR_ANALYZER_LOG`, showing the messages of the info level and above if it is not set.
+pub fn env_filter() -> EnvFilter {
+    EnvFilter::try_from_env(LOG_ENV).unwrap_or_else(|_| EnvFilter::new("info"))
+}
+
+/// Install the subscriber of the analyzer, printing the messages filtered by `ERROR_ANALYZER_LOG` using `MessageFormat`.
+/// Informational messages are printed to stdout, and warnings and errors to stderr.
+/// Nothing is installed if a subscriber already is, e.g. by the user of the library.
+pub fn init() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(env_filter())
+        .with_writer(
+            std::io::stderr
+                .with_max_level(Level::WARN)
+                .or_else(std::io::stdout),
+        )
+        .with_ansi(false)
+        .event_format(MessageFormat)
+        .try_init();
+}
+
+/// The format of the messages printed by the analyzer: informational messages as they are, warnings and errors prefixed by `Warning:` and `Error:`,
+/// and debug and trace messages with their level, target and the stages they are emitted in, e.g. `DEBUG analyzer::graph [stage=edge typing]: ...`.
+pub struct MessageFormat;
+
+impl<S, N> FormatEvent<S, N> for MessageFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        context: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let metadata = event.metadata();
+        match *metadata.level() {
+            Level::INFO => {}
+            Level::WARN => write!(writer, "Warning: ")?,
+            Level::ERROR => write!(writer, "Error: ")?,
+            level => {
+                write!(writer, "{level} {}", metadata.target())?;
+                let stages: Vec<String> = context
+                    .event_scope()
+                    .into_iter()
+                    .flat_map(|scope| scope.from_root())
+                    .map(|span| {
+                        span.extensions()
+                            .get::<tracing_subscriber::fmt::FormattedFields<N>>()
+                            .map_or_else(|| span.name().to_owned(), |fields| fields.to_string())
+                    })
+                    .collect();
+                if !stages.is_empty() {
+                    write!(writer, " [{}]", stages.join(", "))?;
+                }
+                write!(writer, ": ")?;
+            }
+        }
+        context.format_fields(writer.by_ref(), event)?;
+        writeln!(writer)
+    }
+}
@@ -1,23 +1,22 @@
 #![feature(rustc_private)]
 
-mod analysis;
-mod graph;
-
 extern crate rustc_driver;
-extern crate rustc_hir;
-extern crate rustc_interface;
-extern crate rustc_middle;
-extern crate rustc_parse;
 extern crate rustc_session;
 
-use rustc_driver::Compilation;
-use rustc_interface::interface::Compiler;
-use rustc_interface::Queries;
+use regex::Regex;
+use static_error_analyzer::analysis::{self, AnalysisOptions, GraphKind};
+use static_error_analyzer::graph::{CallGraph, ChainGraph, NodeStyles};
+use static_error_analyzer::scoring::ScoreWeights;
+use static_error_analyzer::summary::{ChokePoints, ColorChoice, Heatmap, Summary};
+use static_error_analyzer::timings::Timings;
+use static_error_analyzer::{analyze_crate, read_manifest, AnalyzerError, CrateOptions};
+use static_error_analyzer::{baseline, logging, query, report, sarif};
+use std::fs::File;
+use std::io::{BufWriter, Write};
 use std::path::{Path, PathBuf};
-use std::process::Command;
 use toml::Table;
 
-/// Entry point, first sets up the compiler, and then runs it using the provided arguments.
+/// Entry point, first extracts the arguments, then analyzes the package using them and writes the requested outputs.
 fn main() {
     // Create a wrapper around an DiagCtxt that is used for early error emissions.
     let early_dcx =
@@ -28,333 +27,885 @@ fn main() {
         .unwrap_or_else(|_| std::process::exit(rustc_driver::EXIT_FAILURE));
 
     // Extract the arguments
-    let (relative_manifest_path, relative_output_path, remove_redundant) = extract_arguments(&args);
-
-    let manifest_path = get_manifest_path(&relative_manifest_path);
-    let output_path = get_output_path(&relative_output_path);
-
-    // Extract the compiler arguments from running `cargo build`
-    let compiler_args = get_compiler_args(&relative_manifest_path, &manifest_path)
-        .expect("Could not get arguments from cargo build!");
+    let mut arguments = extract_arguments(&args);
+
+    let manifest_path = get_manifest_path(&arguments.manifest_path);
+    let config = read_manifest(&manifest_path)
+        .map(|manifest| get_analyzer_config(&manifest))
+        .unwrap_or_else(|e| fail(e));
+    let invalid_config = |reason| AnalyzerError::ManifestInvalid {
+        path: manifest_path.clone(),
+        reason,
+    };
+    arguments.options.ignore =
+        get_ignored_functions(&config).unwrap_or_else(|e| fail(invalid_config(e)));
+    arguments.options.score_weights =
+        get_score_weights(&config).unwrap_or_else(|e| fail(invalid_config(e)));
+    let node_styles = get_node_styles(&config).unwrap_or_else(|e| fail(invalid_config(e)));
+    let output_path = get_output_path(&arguments.output_path);
 
     // Enable CTRL + C
     rustc_driver::install_ctrlc_handler();
 
-    // Install a panic hook that will print the ICE message on unexpected panics.
-    let using_internal_features =
-        rustc_driver::install_ice_hook(rustc_driver::DEFAULT_BUG_REPORT_URL, |_| ());
-
     // This allows tools to enable rust logging without having to magically match rustc’s tracing crate version.
     rustc_driver::init_rustc_env_logger(&early_dcx);
+    // The messages of the analyzer itself are filtered by ERROR_ANALYZER_LOG instead
+    logging::init();
+
+    let crate_options = CrateOptions {
+        manifest_path: PathBuf::from(&arguments.manifest_path),
+        options: arguments.options,
+        use_cache: !arguments.no_cache,
+        diagnostic_depth: arguments.diagnostic_depth,
+    };
+
+    // Build and analyze the package, or use its cached analysis
+    let result = analyze_crate(&crate_options).unwrap_or_else(|e| fail(e));
+
+    if crate_options.options.choke_points {
+        println!();
+        print!(
+            "{}",
+            ChokePoints {
+                graph: &result.call_graph
+            }
+        );
+    }
 
-    // Run the compiler using the retrieved args.
-    let exit_code = run_compiler(
-        compiler_args,
-        &mut AnalysisCallback(output_path, remove_redundant),
-        using_internal_features,
-    );
-
-    println!("Ran compiler, exit code: {exit_code}");
-}
+    // The summary of the error chains, the panic graph has statistics of its own
+    println!();
+    match &result.chain_graph.stats.panics {
+        Some(panics) => print!("{panics}"),
+        None => print!(
+            "{}",
+            Summary::new(&result.chain_graph.stats, crate_options.options.color)
+        ),
+    }
+    println!();
 
-/// Extract the needed arguments from the provided arguments
-fn extract_arguments(args: &[String]) -> (String, String, bool) {
-    if args.len() < 3 {
-        eprintln!("Usage:");
-        eprintln!("static-result-analyzer.exe input output [--call]");
-        eprintln!();
-        eprintln!("Both the input and output path should be relative.");
-        eprintln!(
-            "The call flag will output the call graph instead of the error chain graph if set."
+    if crate_options.options.heatmap {
+        print!(
+            "{}",
+            Heatmap {
+                graph: &result.call_graph
+            }
         );
-        std::process::exit(rustc_driver::EXIT_FAILURE);
     }
 
-    (
-        args.get(1).unwrap().clone(),
-        args.get(2).unwrap().clone(),
-        !args.get(3).is_some_and(|arg| arg == "--call"),
-    )
-}
+    let mut outputs = Outputs {
+        output_path,
+        remove_redundant: arguments.remove_redundant,
+        error_subgraph: arguments.error_subgraph,
+        stats_path: arguments.stats_path.as_deref().map(get_output_path),
+        exit_chains_path: arguments.exit_chains_path.as_deref().map(get_output_path),
+        path_query: arguments.path_query,
+        path_dot_path: arguments.path_dot_path.as_deref().map(get_output_path),
+        report_path: arguments.report_path.as_deref().map(get_output_path),
+        sarif_path: arguments.sarif_path.as_deref().map(get_output_path),
+        sarif_rules: arguments.sarif_rules,
+        chains_path: arguments.chains_path.as_deref().map(get_output_path),
+        baseline_path: arguments.baseline_path.as_deref().map(get_output_path),
+        fail_on_new_chains: arguments.fail_on_new_chains,
+        worse_than_baseline: false,
+        deny_lib_unwrap: arguments.deny_lib_unwrap,
+        denied_lints_found: false,
+        timings: result.timings,
+        print_timings: arguments.timings,
+        options: crate_options.options,
+        node_styles,
+    };
+    if let Err(e) = outputs.write_outputs(&result.call_graph, &result.chain_graph) {
+        fail(e);
+    }
 
-/// Get the full path to the manifest.
-fn get_output_path(output_path: &str) -> PathBuf {
-    std::env::current_dir().unwrap().join(output_path)
+    // Error handling got worse than in the baseline or a denied lint was found, which should fail e.g. CI
+    if outputs.worse_than_baseline || outputs.denied_lints_found {
+        std::process::exit(rustc_driver::EXIT_FAILURE);
+    }
 }
 
-/// Get the full path to the manifest.
-fn get_manifest_path(cargo_path: &str) -> PathBuf {
-    std::env::current_dir().unwrap().join(cargo_path)
+/// Print an error ending the run, with a hint on what can be done about it, and exit.
+fn fail(error: AnalyzerError) -> ! {
+    eprintln!();
+    eprintln!("Error: {error}");
+    eprintln!("Hint: {}", error.hint());
+    std::process::exit(rustc_driver::EXIT_FAILURE)
 }
 
-/// Get the compiler arguments used to compile the package by first running `cargo clean` and then `cargo build -vv`.
-fn get_compiler_args(relative_manifest_path: &str, manifest_path: &PathBuf) -> Option<Vec<String>> {
-    println!("Using {}!", cargo_version().trim_end_matches('\n'));
-
-    let (package_name, bin_name) = get_package_name(manifest_path);
-
-    cargo_clean(manifest_path, &package_name);
-
-    let build_output = cargo_build_verbose(manifest_path);
-
-    let command = get_rustc_invocation(&build_output, &package_name, bin_name)?;
-
-    Some(split_args(relative_manifest_path, &command))
+/// The arguments the analyzer was invoked with.
+struct Arguments {
+    /// Relative path to the manifest of the package to analyze.
+    manifest_path: String,
+    /// Relative path to the file the graph is written to.
+    output_path: String,
+    /// Whether to output the chain graph instead of the full call graph.
+    remove_redundant: bool,
+    /// The error type whose subgraph of the call graph to output instead, if any.
+    error_subgraph: Option<String>,
+    /// Relative path to the file the chain statistics are written to in JSON, if any.
+    stats_path: Option<String>,
+    /// Relative path to the file the graph of the chains reaching the program exit is written to, if any.
+    exit_chains_path: Option<String>,
+    /// The two functions to find the error propagation paths between, if any.
+    path_query: Option<(String, String)>,
+    /// Relative path to the file the graph of the paths found between the two functions is written to, if any.
+    path_dot_path: Option<String>,
+    /// Relative path to the file the Markdown report is written to, if any.
+    report_path: Option<String>,
+    /// Relative path to the file the chains losing their error are written to in SARIF, if any.
+    sarif_path: Option<String>,
+    /// The rules of the SARIF results, with their levels.
+    sarif_rules: Vec<sarif::Rule>,
+    /// Relative path to the file the chains are written to in JSON, if any.
+    chains_path: Option<String>,
+    /// The number of calls an unwrapped error should propagate through to be reported as a compiler diagnostic, if these are emitted.
+    diagnostic_depth: Option<usize>,
+    /// Relative path to the chains of a previous analysis (as written using `--chains-json`) to compare the chains with, if any.
+    baseline_path: Option<String>,
+    /// Whether to fail if there are new or degraded chains compared to the baseline.
+    fail_on_new_chains: bool,
+    /// Whether to fail if errors are unwrapped in functions reachable from the public API of a library.
+    deny_lib_unwrap: bool,
+    /// Whether to analyze the package even if a cached analysis of it can be used.
+    no_cache: bool,
+    /// Whether to print how long each stage of the run takes.
+    timings: bool,
+    /// Options for the analysis itself.
+    options: AnalysisOptions,
 }
 
-/// Split up individual arguments from the command.
-fn split_args(relative_manifest_path: &str, command: &str) -> Vec<String> {
-    let mut res = vec![];
-    let mut temp = String::new();
-
-    // Split on ' '
-    for arg in command.split(' ') {
-        let mut arg = arg.to_owned();
-
-        // If this is the path to main.rs, prepend the relative path to the manifest, stripping away Cargo.toml
-        if arg.contains("main.rs") {
-            let mut new_arg = String::from(relative_manifest_path.trim_end_matches("Cargo.toml"));
-            new_arg.push_str(&arg);
-            arg = new_arg;
-        }
+/// Extract the needed arguments from the provided arguments
+fn extract_arguments(args: &[String]) -> Arguments {
+    if args.len() < 3 {
+        print_usage();
+        std::process::exit(rustc_driver::EXIT_FAILURE);
+    }
 
-        // Leave ' ' when enclosed in '"', removing the enclosing '"'
-        if arg.starts_with('"') && arg.ends_with('"') {
-            temp.push_str(
-                arg.strip_prefix('"')
-                    .expect("Could not remove '\"' from start of string!")
-                    .strip_suffix('"')
-                    .expect("Could not remove '\"' from end of string!"),
-            );
-            res.push(temp);
-            temp = String::new();
-        } else if arg.ends_with('"') {
-            temp.push_str(
-                arg.strip_suffix('"')
-                    .expect("Could not remove '\"' from end of string!"),
-            );
-            res.push(temp);
-            temp = String::new();
-        } else if arg.starts_with('"') {
-            temp.push_str(
-                arg.strip_prefix('"')
-                    .expect("Could not remove '\"' from start of string!"),
-            );
-            temp.push(' ');
-        } else if !temp.is_empty() {
-            temp.push_str(&arg);
-            temp.push(' ');
-        } else {
-            res.push(arg);
+    let mut arguments = Arguments {
+        manifest_path: args[1].clone(),
+        output_path: args[2].clone(),
+        remove_redundant: true,
+        error_subgraph: None,
+        stats_path: None,
+        exit_chains_path: None,
+        path_query: None,
+        path_dot_path: None,
+        report_path: None,
+        sarif_path: None,
+        sarif_rules: sarif::get_rules(&[]).unwrap(),
+        chains_path: None,
+        diagnostic_depth: None,
+        baseline_path: None,
+        fail_on_new_chains: false,
+        deny_lib_unwrap: false,
+        no_cache: false,
+        timings: false,
+        options: AnalysisOptions::default(),
+    };
+
+    let mut rest = args[3..].iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "--call" => arguments.remove_redundant = false,
+            "--include-macro-calls" => arguments.options.include_macro_calls = true,
+            "--drop-edges" => arguments.options.drop_edges = true,
+            "--all-functions" => arguments.options.all_functions = true,
+            "--exclude-option-chains" => arguments.options.exclude_option_chains = true,
+            "--chain-types" => arguments.options.chain_types = true,
+            "--edge-locations" => arguments.options.edge_locations = true,
+            "--heatmap" => arguments.options.heatmap = true,
+            "--choke-points" => arguments.options.choke_points = true,
+            "--verbose" => arguments.options.verbose = true,
+            "--quiet" => arguments.options.quiet = true,
+            "--color" => {
+                arguments.options.color = parse_color(arg, rest.next().map(String::as_str));
+            }
+            color if color.starts_with("--color=") => {
+                arguments.options.color = parse_color("--color", color.strip_prefix("--color="));
+            }
+            "--timings" => arguments.timings = true,
+            "--result-types" => {
+                let Some(paths) = rest.next() else {
+                    eprintln!("Missing type paths after {arg}");
+                    eprintln!();
+                    print_usage();
+                    std::process::exit(rustc_driver::EXIT_FAILURE);
+                };
+                arguments.options.result_types = paths
+                    .split(',')
+                    .map(|path| path.trim().to_owned())
+                    .filter(|path| !path.is_empty())
+                    .collect();
+            }
+            "--spawn-fns" => {
+                let Some(paths) = rest.next() else {
+                    eprintln!("Missing function paths after {arg}");
+                    eprintln!();
+                    print_usage();
+                    std::process::exit(rustc_driver::EXIT_FAILURE);
+                };
+                arguments.options.spawn_fns = paths
+                    .split(',')
+                    .map(|path| path.trim().to_owned())
+                    .filter(|path| !path.is_empty())
+                    .collect();
+            }
+            "--log-fns" => {
+                let Some(paths) = rest.next() else {
+                    eprintln!("Missing function paths after {arg}");
+                    eprintln!();
+                    print_usage();
+                    std::process::exit(rustc_driver::EXIT_FAILURE);
+                };
+                arguments.options.log_fns = paths
+                    .split(',')
+                    .map(|path| path.trim().to_owned())
+                    .filter(|path| !path.is_empty())
+                    .collect();
+            }
+            "--follow-deps" => {
+                let Some(crates) = rest.next() else {
+                    eprintln!("Missing crate names after {arg}");
+                    eprintln!();
+                    print_usage();
+                    std::process::exit(rustc_driver::EXIT_FAILURE);
+                };
+                // Crate names use underscores where package names may use dashes
+                arguments.options.follow_deps = crates
+                    .split(',')
+                    .map(|name| name.trim().replace('-', "_"))
+                    .filter(|name| !name.is_empty())
+                    .collect();
+            }
+            "--exclude-path" | "--include-path" => {
+                let Some(path) = rest.next() else {
+                    eprintln!("Missing module path after {arg}");
+                    eprintln!();
+                    print_usage();
+                    std::process::exit(rustc_driver::EXIT_FAILURE);
+                };
+                let paths = if arg == "--exclude-path" {
+                    &mut arguments.options.exclude_paths
+                } else {
+                    &mut arguments.options.include_paths
+                };
+                paths.push(path.trim().to_owned());
+            }
+            "--entry" => {
+                let Some(path) = rest.next() else {
+                    eprintln!("Missing function path after {arg}");
+                    eprintln!();
+                    print_usage();
+                    std::process::exit(rustc_driver::EXIT_FAILURE);
+                };
+                arguments.options.entry = Some(path.trim().to_owned());
+            }
+            "--error-subgraph" => {
+                let Some(ty) = rest.next() else {
+                    eprintln!("Missing type path after {arg}");
+                    eprintln!();
+                    print_usage();
+                    std::process::exit(rustc_driver::EXIT_FAILURE);
+                };
+                arguments.error_subgraph = Some(ty.clone());
+            }
+            "--stats-json" => {
+                let Some(path) = rest.next() else {
+                    eprintln!("Missing path after {arg}");
+                    eprintln!();
+                    print_usage();
+                    std::process::exit(rustc_driver::EXIT_FAILURE);
+                };
+                arguments.stats_path = Some(path.clone());
+            }
+            "--emit-diagnostics" => {
+                arguments.diagnostic_depth = arguments
+                    .diagnostic_depth
+                    .or(Some(DEFAULT_DIAGNOSTIC_DEPTH));
+            }
+            "--diagnostic-depth" => {
+                let Some(depth) = rest.next().and_then(|depth| depth.parse::<usize>().ok()) else {
+                    eprintln!("Expected a number after {arg}");
+                    eprintln!();
+                    print_usage();
+                    std::process::exit(rustc_driver::EXIT_FAILURE);
+                };
+                arguments.diagnostic_depth = Some(depth);
+            }
+            "--fail-on-new-chains" => arguments.fail_on_new_chains = true,
+            "--no-cache" => arguments.no_cache = true,
+            "--deny" => {
+                let Some(lint) = rest.next() else {
+                    eprintln!("Missing lint after {arg}");
+                    eprintln!();
+                    print_usage();
+                    std::process::exit(rustc_driver::EXIT_FAILURE);
+                };
+                match lint.as_str() {
+                    "lib-unwrap" => arguments.deny_lib_unwrap = true,
+                    _ => {
+                        eprintln!(
+                            "Unknown lint {lint}, use one of: {}",
+                            analysis::LINTS.join(", ")
+                        );
+                        eprintln!();
+                        print_usage();
+                        std::process::exit(rustc_driver::EXIT_FAILURE);
+                    }
+                }
+            }
+            "--baseline" => {
+                let Some(path) = rest.next() else {
+                    eprintln!("Missing path after {arg}");
+                    eprintln!();
+                    print_usage();
+                    std::process::exit(rustc_driver::EXIT_FAILURE);
+                };
+                arguments.baseline_path = Some(path.clone());
+            }
+            "--chains-json" => {
+                let Some(path) = rest.next() else {
+                    eprintln!("Missing path after {arg}");
+                    eprintln!();
+                    print_usage();
+                    std::process::exit(rustc_driver::EXIT_FAILURE);
+                };
+                arguments.chains_path = Some(path.clone());
+            }
+            "--exit-chains" => {
+                let Some(path) = rest.next() else {
+                    eprintln!("Missing path after {arg}");
+                    eprintln!();
+                    print_usage();
+                    std::process::exit(rustc_driver::EXIT_FAILURE);
+                };
+                arguments.exit_chains_path = Some(path.clone());
+            }
+            "--path" => {
+                let (Some(from), Some(to)) = (rest.next(), rest.next()) else {
+                    eprintln!("Expected two function paths after {arg}");
+                    eprintln!();
+                    print_usage();
+                    std::process::exit(rustc_driver::EXIT_FAILURE);
+                };
+                arguments.path_query = Some((from.clone(), to.clone()));
+            }
+            "--path-dot" => {
+                let Some(path) = rest.next() else {
+                    eprintln!("Missing path after {arg}");
+                    eprintln!();
+                    print_usage();
+                    std::process::exit(rustc_driver::EXIT_FAILURE);
+                };
+                arguments.path_dot_path = Some(path.clone());
+            }
+            "--report" => {
+                let (Some("md"), Some(path)) = (rest.next().map(String::as_str), rest.next())
+                else {
+                    eprintln!("Expected md and a path after {arg}");
+                    eprintln!();
+                    print_usage();
+                    std::process::exit(rustc_driver::EXIT_FAILURE);
+                };
+                arguments.report_path = Some(path.clone());
+            }
+            "--format" => {
+                let (Some("sarif"), Some(path)) = (rest.next().map(String::as_str), rest.next())
+                else {
+                    eprintln!("Expected sarif and a path after {arg}");
+                    eprintln!();
+                    print_usage();
+                    std::process::exit(rustc_driver::EXIT_FAILURE);
+                };
+                arguments.sarif_path = Some(path.clone());
+            }
+            "--sarif-levels" => {
+                let Some(levels) = rest.next() else {
+                    eprintln!("Missing levels after {arg}");
+                    eprintln!();
+                    print_usage();
+                    std::process::exit(rustc_driver::EXIT_FAILURE);
+                };
+                let levels: Vec<(String, String)> = levels
+                    .split(',')
+                    .filter_map(|level| level.split_once('='))
+                    .map(|(rule, level)| (rule.trim().to_owned(), level.trim().to_owned()))
+                    .collect();
+                match sarif::get_rules(&levels) {
+                    Ok(rules) => arguments.sarif_rules = rules,
+                    Err(e) => {
+                        eprintln!("Invalid levels after {arg}: {e}");
+                        std::process::exit(rustc_driver::EXIT_FAILURE);
+                    }
+                }
+            }
+            "--error-filter" => {
+                let Some(pattern) = rest.next() else {
+                    eprintln!("Missing pattern after {arg}");
+                    eprintln!();
+                    print_usage();
+                    std::process::exit(rustc_driver::EXIT_FAILURE);
+                };
+                match Regex::new(pattern) {
+                    Ok(filter) => arguments.options.error_filters.push(filter),
+                    Err(e) => {
+                        eprintln!("Invalid pattern after {arg}: {e}");
+                        std::process::exit(rustc_driver::EXIT_FAILURE);
+                    }
+                }
+            }
+            "--chain" => {
+                let Some(chain) = rest.next().and_then(|chain| chain.parse::<usize>().ok()) else {
+                    eprintln!("Expected a chain number after {arg}");
+                    eprintln!();
+                    print_usage();
+                    std::process::exit(rustc_driver::EXIT_FAILURE);
+                };
+                arguments.options.chain = Some(chain);
+            }
+            "--max-chain-depth" | "--max-chains" | "--external-threshold" => {
+                let Some(limit) = rest.next().and_then(|limit| limit.parse::<usize>().ok()) else {
+                    eprintln!("Expected a number after {arg}");
+                    eprintln!();
+                    print_usage();
+                    std::process::exit(rustc_driver::EXIT_FAILURE);
+                };
+                match arg.as_str() {
+                    "--max-chain-depth" => arguments.options.max_chain_depth = Some(limit),
+                    "--max-chains" => arguments.options.max_chains = Some(limit),
+                    _ => arguments.options.external_threshold = Some(limit),
+                }
+            }
+            "--graph" => {
+                arguments.options.graph = match rest.next().map(String::as_str) {
+                    Some("error") => GraphKind::Error,
+                    Some("panic") => GraphKind::Panic,
+                    _ => {
+                        eprintln!("Expected error or panic after {arg}");
+                        eprintln!();
+                        print_usage();
+                        std::process::exit(rustc_driver::EXIT_FAILURE);
+                    }
+                };
+            }
+            _ => {
+                eprintln!("Unknown argument: {arg}");
+                eprintln!();
+                print_usage();
+                std::process::exit(rustc_driver::EXIT_FAILURE);
+            }
         }
     }
 
-    // Overwrite error format args
-    for i in 0..res.len() {
-        if i >= res.len() {
-            break;
-        }
-        res[i] = res[i].replace("\\\"", "\"");
-        if res[i].starts_with("--error-format=") {
-            res[i] = String::from("--error-format=short");
-        }
-        if res[i].starts_with("--json=") {
-            res.remove(i);
+    arguments
+}
+
+/// Parse the choice of coloring the summary given after the color flag, printing the usage and exiting if it is missing or unknown.
+fn parse_color(arg: &str, value: Option<&str>) -> ColorChoice {
+    match value.and_then(ColorChoice::from_label) {
+        Some(color) => color,
+        None => {
+            eprintln!("Expected auto, always or never after {arg}");
+            eprintln!();
+            print_usage();
+            std::process::exit(rustc_driver::EXIT_FAILURE);
         }
     }
-
-    res
 }
 
-/// Run `cargo clean -p PACKAGE`, where the package name is extracted from the given manifest.
-fn cargo_clean(manifest_path: &PathBuf, package_name: &str) -> String {
-    println!("Cleaning package...");
-    let mut clean_command = create_cargo_command();
-    clean_command.arg("clean");
-    clean_command.arg("-p");
-    clean_command.arg(package_name);
-
-    clean_command.current_dir(
-        manifest_path
-            .parent()
-            .expect("Could not get manifest directory!"),
+/// The number of calls an unwrapped error should propagate through to be reported as a compiler diagnostic, if not provided.
+/// Errors unwrapped right where they are returned are common and deliberate, so only those coming from further away are reported.
+const DEFAULT_DIAGNOSTIC_DEPTH: usize = 1;
+
+/// Print how the analyzer should be invoked.
+fn print_usage() {
+    eprintln!("Usage:");
+    eprintln!(
+        "static-result-analyzer.exe input output [--call] [--include-macro-calls] [--drop-edges] [--all-functions] [--entry path::to::function] [--exclude-option-chains] [--chain-types] [--edge-locations] [--heatmap] [--choke-points] [--follow-deps crate1,crate2] [--exclude-path module::path].. [--include-path module::path].. [--result-types path::To::Type,..] [--spawn-fns path::to::spawn,..] [--log-fns path::to::log,..] [--graph error|panic] [--max-chain-depth N] [--max-chains M] [--external-threshold N] [--error-filter regex].. [--error-subgraph path::To::Error] [--stats-json path] [--chains-json path] [--baseline path] [--fail-on-new-chains] [--deny lib-unwrap] [--exit-chains path] [--chain i] [--emit-diagnostics] [--diagnostic-depth N] [--path from to] [--path-dot path] [--report md path] [--format sarif path] [--sarif-levels rule=level,..] [--verbose] [--quiet] [--color auto|always|never] [--timings] [--no-cache]"
     );
+    eprintln!();
+    eprintln!("Both the input and output path should be relative.");
+    eprintln!("The call flag will output the call graph instead of the error chain graph if set.");
+    eprintln!("The include macro calls flag will keep calls expanded from external macros (e.g. println!) if set.");
+    eprintln!("The drop edges flag will add edges for implicit calls to local Drop implementations if set.");
+    eprintln!("The all functions flag will analyze every local function, not just those reachable from main, if set.");
+    eprintln!("The entry flag will analyze the package from the given local function (e.g. cli::run) instead of from main or its public API, e.g. for #![no_main] binaries.");
+    eprintln!("The exclude option chains flag will leave chains propagating None out of the chain graph if set.");
+    eprintln!("The chain types flag will add the error types each function receives and returns to its label in the chain graph if set.");
+    eprintln!("The edge locations flag will add the location (file:line:column) of each call to its label in the chain graph if set.");
+    eprintln!("The heatmap flag will fill the nodes of the call graph from white to red by the number of chains passing through them if set.");
+    eprintln!("The choke points flag will list the functions all errors of each origin pass through, and give them a double border in the graph, if set.");
+    eprintln!("The follow deps flag will continue the analysis into the listed dependencies using their MIR.");
+    eprintln!("The exclude path flag will leave the functions in the given module (e.g. generated or vendor::*) out of the call graph without exploring them, and can be passed multiple times.");
+    eprintln!("The include path flag will restrict the local functions in the call graph to those in the given module, still exploring the calls of the others, and can be passed multiple times. Included paths are kept within excluded modules.");
+    eprintln!("The result types flag will treat the listed types (e.g. newtypes around a Result) as carrying a Result.");
+    eprintln!("The spawn fns flag will treat the listed functions as spawning a task, besides tokio::spawn, async_std::task::spawn and std::thread::spawn.");
+    eprintln!("The log fns flag will treat the listed macros and functions as logging errors, besides those of log and tracing and eprintln, so errors only logged by them are counted as logged and dropped.");
+    eprintln!("The graph flag selects whether the chain graph shows error propagation chains (default) or panic chains.");
+    eprintln!("The max chain depth flag stops following chains past the given number of chained calls (default 100).");
+    eprintln!("The max chains flag stops extracting chains past the given number, keeping the deepest ones (default 10000).");
+    eprintln!("The external threshold flag aggregates the functions of a dependency whose calls carry no errors into one node, once there are more than the given number of them (default 25).");
+    eprintln!("The error filter flag keeps only the chains with an error type matching the given regex, and can be passed multiple times to keep the chains matching any of them.");
+    eprintln!("The error subgraph flag will output the part of the call graph carrying the given error type instead, including the type-erased errors boxing it.");
+    eprintln!("The stats json flag will write the chain statistics (e.g. the deepest and largest chains) to the given relative path in JSON.");
+    eprintln!("The chains json flag will write the chains, with the details of each of their calls, to the given relative path in JSON.");
+    eprintln!("The baseline flag will compare the chains with those written to the given relative path by the chains json flag before, listing the new, removed and changed chains.");
+    eprintln!("The fail on new chains flag will make the analyzer fail if there are new chains or chains ending worse than in the baseline, if set.");
+    eprintln!("The deny flag will make the analyzer fail if the given lint is found, listing its findings. The lib-unwrap lint finds errors unwrapped in functions reachable from the public API of a library.");
+    eprintln!("The exit chains flag will write a graph of only the chains whose error is returned from main to the given relative path.");
+    eprintln!("The chain flag will only show the chain with the given number (as shown in the summary and graph) in the chain graph.");
+    eprintln!("The emit diagnostics flag will report the unwrapped errors as compiler warnings and the discarded errors as notes, along with the calls they propagate through, if set.");
+    eprintln!("The diagnostic depth flag emits diagnostics for the unwrapped errors propagated through more than the given number of calls only (default 1).");
+    eprintln!("The path flag will list the paths through which errors propagate between the two given functions (e.g. db::fetch and main), in both directions.");
+    eprintln!("The path dot flag will write a graph of only the calls on these paths to the given relative path.");
+    eprintln!("The format flag will write the chains ending in an unwrap, a discarded error or a dropped task to the given relative path in SARIF, e.g. for GitHub code scanning.");
+    eprintln!("The sarif levels flag sets the level (error, warning, note or none) of the results per rule, e.g. unwrap-terminated-chain=error.");
+    eprintln!("The report flag will write a Markdown summary of the analysis (e.g. to paste into a pull request) to the given relative path.");
+    eprintln!("The verbose flag will print how long the steps of the analysis take if set.");
+    eprintln!("The quiet flag will leave out the progress of building the call graph, typing the calls and extracting the chains, if set.");
+    eprintln!("The color flag selects whether the summary is colored: auto (default) colors it if the output is a terminal and NO_COLOR is not set, always or never. It can also be given as --color=never.");
+    eprintln!("The timings flag will print how long each stage of the run (e.g. building the package or extracting the chains) takes as a table, and add these to the statistics, if set.");
+    eprintln!("The no cache flag will analyze the package even if a cached analysis of it (in target/error-analyzer) can be used, if set.");
+}
 
-    let output = clean_command.output().expect("Could not clean!");
+/// Get the configuration of the analyzer, from the `[package.metadata.error-analyzer]` table of the manifest, if any.
+fn get_analyzer_config(manifest: &Table) -> Table {
+    manifest
+        .get("package")
+        .and_then(|package| package.get("metadata"))
+        .and_then(|metadata| metadata.get("error-analyzer"))
+        .and_then(|config| config.as_table())
+        .cloned()
+        .unwrap_or_default()
+}
 
-    let stderr = String::from_utf8(output.stderr).expect("Invalid UTF8!");
+/// Get the functions whose unwrapped or discarded errors are accepted, from `ignore` in the configuration.
+fn get_ignored_functions(config: &Table) -> Result<Vec<String>, String> {
+    let Some(ignore) = config.get("ignore") else {
+        return Ok(vec![]);
+    };
+
+    ignore
+        .as_array()
+        .ok_or("'ignore' is not an array")?
+        .iter()
+        .map(|path| {
+            path.as_str().map(str::to_owned).ok_or(String::from(
+                "'ignore' contains a value that is not a string",
+            ))
+        })
+        .collect()
+}
 
-    if output.status.code() != Some(0) {
-        eprintln!("Could not clean package!");
-        println!("{:?}", stderr);
+/// Get the weights of the severity score of the chains, from the `weights` table in the configuration.
+/// Weights that are not configured keep their default.
+fn get_score_weights(config: &Table) -> Result<ScoreWeights, String> {
+    let mut weights = ScoreWeights::default();
+    let Some(configured) = config.get("weights") else {
+        return Ok(weights);
+    };
+
+    let configured = configured.as_table().ok_or("'weights' is not a table")?;
+    for (name, weight) in configured {
+        // Whole numbers are parsed as integers
+        let Some(weight) = weight
+            .as_float()
+            .or_else(|| weight.as_integer().map(|weight| weight as f64))
+        else {
+            return Err(format!("the weight {name} is not a number"));
+        };
+        weights.set(name, weight)?;
     }
-
-    stderr
+    Ok(weights)
 }
 
-/// Extract the package name from the given manifest.
-fn get_package_name(manifest_path: &PathBuf) -> (String, Option<String>) {
-    let file = std::fs::read(manifest_path).expect("Could not read manifest!");
-    let content = String::from_utf8(file).expect("Invalid UTF8!");
-    let table = content
-        .parse::<Table>()
-        .expect("Could not parse manifest as TOML!");
-    let package_table = table["package"]
+/// Get the dot styles of the nodes of the call graph by the attributes of their functions, from the `node-styles` table in the configuration.
+/// Attributes that are not configured keep their default style.
+fn get_node_styles(config: &Table) -> Result<NodeStyles, String> {
+    let mut styles = NodeStyles::default();
+    let Some(configured) = config.get("node-styles") else {
+        return Ok(styles);
+    };
+
+    let configured = configured
         .as_table()
-        .expect("'package' is not a table!");
-    let package_name = package_table["name"]
-        .as_str()
-        .expect("No name found in package information!")
-        .to_owned();
-    if table.contains_key("bin") {
-        let binary_table = table["bin"]
-            .as_array()
-            .expect("'bin' is not an array!")
-            .get(0)
-            .expect("'bin' contains no values!")
-            .as_table()
-            .expect("'bin' is not a table!");
-        let binary_name = binary_table["name"]
-            .as_str()
-            .expect("No name found in binary information!")
-            .to_owned();
-        return (package_name, Some(binary_name));
+        .ok_or("'node-styles' is not a table")?;
+    for (name, style) in configured {
+        let Some(style) = style.as_str() else {
+            return Err(format!("the style of {name} is not a string"));
+        };
+        styles.set(name, style)?;
     }
-
-    (package_name, None)
+    Ok(styles)
 }
 
-/// Create a new cargo command.
-fn create_cargo_command() -> Command {
-    let command = Command::new("cargo");
-
-    command
+/// Get the full path to the manifest.
+fn get_output_path(output_path: &str) -> PathBuf {
+    std::env::current_dir().unwrap().join(output_path)
 }
 
-/// Run `cargo --version`.
-fn cargo_version() -> String {
-    let mut version_command = create_cargo_command();
-    version_command.arg("--version");
-
-    let output = version_command
-        .output()
-        .expect("Could not get cargo version!");
+/// Get the full path to the manifest.
+fn get_manifest_path(cargo_path: &str) -> PathBuf {
+    std::env::current_dir().unwrap().join(cargo_path)
+}
 
-    let stdout = String::from_utf8(output.stdout).expect("Invalid UTF8!");
+/// Write a graph in dot representation to a file, buffering the writes as the graph is streamed.
+fn write_dot(
+    path: &Path,
+    write: impl FnOnce(&mut BufWriter<File>) -> std::io::Result<()>,
+) -> std::io::Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    write(&mut file)?;
+    file.flush()
+}
 
-    stdout
+/// The outputs of the analyzer, written once the package is analyzed.
+struct Outputs {
+    output_path: PathBuf,
+    remove_redundant: bool,
+    error_subgraph: Option<String>,
+    stats_path: Option<PathBuf>,
+    exit_chains_path: Option<PathBuf>,
+    path_query: Option<(String, String)>,
+    path_dot_path: Option<PathBuf>,
+    report_path: Option<PathBuf>,
+    sarif_path: Option<PathBuf>,
+    sarif_rules: Vec<sarif::Rule>,
+    chains_path: Option<PathBuf>,
+    baseline_path: Option<PathBuf>,
+    fail_on_new_chains: bool,
+    /// Whether there are new or degraded chains compared to the baseline, and the analyzer should fail because of it.
+    worse_than_baseline: bool,
+    deny_lib_unwrap: bool,
+    /// Whether a denied lint was found, and the analyzer should fail because of it.
+    denied_lints_found: bool,
+    /// How long each stage of the run took so far.
+    timings: Timings,
+    /// Whether to print the timings, and add them to the statistics.
+    print_timings: bool,
+    options: AnalysisOptions,
+    /// The styles of the nodes of the call graph by the attributes of their functions.
+    node_styles: NodeStyles,
 }
 
-/// Run `cargo build -v` on the given manifest.
-fn cargo_build_verbose(manifest_path: &Path) -> String {
-    // TODO: interrupt build as to not compile the program twice
-    println!("Building package...");
-    let mut build_command = create_cargo_command();
-    build_command.arg("build");
-    build_command.arg("-v");
-    build_command.arg("--manifest-path");
-    build_command.arg(manifest_path.as_os_str());
+impl Outputs {
+    /// Write the graph and the other requested outputs of an analysis, and print the results asked for (e.g. the paths between two functions).
+    /// Returns an error if the graph could not be written, after writing the other outputs.
+    fn write_outputs(
+        &mut self,
+        call_graph: &CallGraph,
+        chain_graph: &ChainGraph,
+    ) -> Result<(), AnalyzerError> {
+        let stage = self.timings.start("export");
+
+        let subgraph = self.error_subgraph.as_ref().map(|ty| {
+            let subgraph = analysis::error_subgraph(call_graph, ty);
+            if subgraph.edges.is_empty() {
+                eprintln!("Warning: no call carries errors of type {ty}");
+            }
+            subgraph
+        });
 
-    let output = build_command.output().expect("Could not build!");
+        if let Some(stats_path) = &self.stats_path {
+            let timings = self.print_timings.then_some(&self.timings);
+            match std::fs::write(stats_path, chain_graph.stats.to_json(timings)) {
+                Ok(()) => println!("Wrote statistics to {}", stats_path.display()),
+                Err(e) => {
+                    eprintln!("Could not write statistics!");
+                    eprintln!("{e}");
+                    eprintln!();
+                }
+            }
+        }
 
-    let stderr = String::from_utf8(output.stderr).expect("Invalid UTF8!");
+        if let Some(chains_path) = &self.chains_path {
+            match std::fs::write(chains_path, analysis::chains_json(call_graph, chain_graph)) {
+                Ok(()) => println!("Wrote chains to {}", chains_path.display()),
+                Err(e) => {
+                    eprintln!("Could not write chains!");
+                    eprintln!("{e}");
+                    eprintln!();
+                }
+            }
+        }
 
-    if output.status.code() != Some(0) {
-        eprintln!("Could not (fully) build package!");
-        eprintln!();
-        for line in stderr.split('\n') {
-            if line.starts_with("error") {
-                eprintln!("{}", line);
+        if let Some(baseline_path) = &self.baseline_path {
+            let comparison = std::fs::read_to_string(baseline_path)
+                .map_err(|e| e.to_string())
+                .and_then(|baseline| {
+                    baseline::compare(&baseline, &analysis::chains_json(call_graph, chain_graph))
+                });
+            match comparison {
+                Ok(comparison) => {
+                    println!();
+                    print!("{comparison}");
+                    println!();
+                    self.worse_than_baseline = self.fail_on_new_chains && comparison.is_worse();
+                }
+                Err(e) => {
+                    eprintln!("Could not compare with the baseline!");
+                    eprintln!("{e}");
+                    eprintln!();
+                    self.worse_than_baseline = self.fail_on_new_chains;
+                }
             }
         }
-        eprintln!();
-        eprintln!("Trying to continue...");
-    }
 
-    stderr
-}
+        if self.deny_lib_unwrap {
+            let unwraps = analysis::lib_unwraps(call_graph, chain_graph);
+            if !unwraps.is_empty() {
+                eprintln!();
+                eprintln!(
+                    "Denied: {} errors are unwrapped in functions reachable from the public API:",
+                    unwraps.len()
+                );
+                for unwrap in &unwraps {
+                    eprintln!("  {unwrap}");
+                }
+                eprintln!();
+                self.denied_lints_found = true;
+            }
+        }
 
-/// Gets the rustc invocation command from the output of `cargo build -vv`.
-fn get_rustc_invocation(
-    build_output: &str,
-    package_name: &str,
-    bin_name: Option<String>,
-) -> Option<String> {
-    let name = bin_name
-        .unwrap_or(package_name.to_owned())
-        .replace('-', "_");
-    for line in build_output.split('\n') {
-        for part in line.split('`') {
-            for command in part.split("&& ") {
-                if command.contains("rustc")
-                    && command.contains("--crate-type bin")
-                    && !command.contains("build.rs")
-                    && command.contains("main.rs")
-                    && command.contains(&format!("--crate-name {name}"))
-                {
-                    return Some(String::from(command));
+        if let Some(exit_chains_path) = &self.exit_chains_path {
+            let exit_graph = analysis::exit_chains(call_graph, chain_graph, &self.options);
+            match write_dot(exit_chains_path, |w| exit_graph.write_dot(w)) {
+                Ok(()) => println!("Wrote exit chains to {}", exit_chains_path.display()),
+                Err(e) => {
+                    eprintln!("Could not write exit chains!");
+                    eprintln!("{e}");
+                    eprintln!();
                 }
             }
         }
-    }
 
-    None
-}
+        if let Some(sarif_path) = &self.sarif_path {
+            let summaries = analysis::chain_summaries(call_graph, chain_graph, &self.options);
+            match std::fs::write(sarif_path, sarif::to_sarif(&summaries, &self.sarif_rules)) {
+                Ok(()) => println!("Wrote SARIF results to {}", sarif_path.display()),
+                Err(e) => {
+                    eprintln!("Could not write SARIF results!");
+                    eprintln!("{e}");
+                    eprintln!();
+                }
+            }
+        }
 
-/// Run a compiler with the provided arguments and callbacks.
-/// Returns the exit code of the compiler.
-fn run_compiler(
-    args: Vec<String>,
-    callbacks: &mut (dyn rustc_driver::Callbacks + Send),
-    using_internal_features: std::sync::Arc<std::sync::atomic::AtomicBool>,
-) -> i32 {
-    println!("Running compiler...");
-
-    // Invoke compiler, and return the exit code
-    rustc_driver::catch_with_exit_code(move || {
-        rustc_driver::RunCompiler::new(&args, callbacks)
-            .set_using_internal_features(using_internal_features)
-            .run()
-    })
-}
+        if let Some((from, to)) = &self.path_query {
+            match query::find_error_paths(call_graph, from, to) {
+                Ok(paths) => {
+                    println!();
+                    print!("{}", paths.display(call_graph));
+                    println!();
+
+                    if let Some(path_dot_path) = &self.path_dot_path {
+                        let path_graph = paths.to_graph(call_graph);
+                        match write_dot(path_dot_path, |w| {
+                            path_graph.write_dot_with_styles(w, &self.node_styles)
+                        }) {
+                            Ok(()) => println!("Wrote paths to {}", path_dot_path.display()),
+                            Err(e) => {
+                                eprintln!("Could not write paths!");
+                                eprintln!("{e}");
+                                eprintln!();
+                            }
+                        }
+                    }
+                }
+                Err(e) => {
+                    eprintln!("Could not find the paths between {from} and {to}!");
+                    eprintln!("{e}");
+                    eprintln!();
+                }
+            }
+        }
 
-struct AnalysisCallback(PathBuf, bool);
+        println!("Writing graph...");
 
-impl rustc_driver::Callbacks for AnalysisCallback {
-    fn after_crate_root_parsing<'tcx>(
-        &mut self,
-        _compiler: &Compiler,
-        queries: &'tcx Queries<'tcx>,
-    ) -> Compilation {
-        // Access type context
-        queries.global_ctxt().unwrap().enter(|context| {
-            println!("Analyzing output...");
-            // Analyze the program using the type context
-            let (call_graph, chain_graph) = analysis::analyze(context);
-
-            let dot = if self.1 {
-                chain_graph.to_dot()
+        let write_graph = |w: &mut BufWriter<File>| {
+            if let Some(subgraph) = &subgraph {
+                subgraph.write_dot_with_styles(w, &self.node_styles)
+            } else if self.remove_redundant {
+                chain_graph.write_dot(w)
             } else {
-                call_graph.to_dot()
-            };
+                call_graph.write_dot_with_styles(w, &self.node_styles)
+            }
+        };
+        let mut output_error = None;
+        match write_dot(&self.output_path, write_graph) {
+            Ok(()) => {
+                println!("Done!");
+                println!("Wrote to {}", &self.output_path.display());
+            }
+            Err(e) => {
+                eprintln!("Could not write output!");
+                eprintln!("{e}");
+                eprintln!();
+
+                // The graph can be huge, so it is written to a temporary file rather than printed
+                let temp_path = std::env::temp_dir().join(
+                    self.output_path
+                        .file_name()
+                        .unwrap_or(std::ffi::OsStr::new("graph.dot")),
+                );
+                match write_dot(&temp_path, write_graph) {
+                    Ok(()) => println!("Wrote to {} instead", temp_path.display()),
+                    Err(e) => {
+                        eprintln!("Could not write output to {} either!", temp_path.display());
+                        eprintln!("{e}");
+                        eprintln!();
+                    }
+                }
 
-            println!("Writing graph...");
+                // The output is not where it was asked for, even if the temporary file was written
+                output_error = Some(AnalyzerError::OutputWrite {
+                    path: self.output_path.clone(),
+                    source: e,
+                });
+            }
+        }
 
-            match std::fs::write(&self.0, dot.clone()) {
-                Ok(()) => {
-                    println!("Done!");
-                    println!("Wrote to {}", &self.0.display());
-                }
+        // The report links the graphs, so it is written once they are
+        if let Some(report_path) = &self.report_path {
+            let title = if self.error_subgraph.is_some() {
+                "Error subgraph"
+            } else if self.remove_redundant {
+                "Chain graph"
+            } else {
+                "Call graph"
+            };
+            let mut graphs = vec![(title, self.output_path.clone())];
+            graphs.extend(
+                self.exit_chains_path
+                    .clone()
+                    .map(|path| ("Exit chains", path)),
+            );
+            graphs.extend(self.path_dot_path.clone().map(|path| ("Error paths", path)));
+            let report = report::to_markdown(
+                &chain_graph.stats,
+                &call_graph.crate_name,
+                &graphs,
+                report_path,
+            );
+            match std::fs::write(report_path, report) {
+                Ok(()) => println!("Wrote report to {}", report_path.display()),
                 Err(e) => {
-                    eprintln!("Could not write output!");
+                    eprintln!("Could not write report!");
                     eprintln!("{e}");
                     eprintln!();
-                    println!("{dot}");
                 }
             }
-        });
+        }
 
-        // No need to compile further
-        Compilation::Stop
+        self.timings.finish(stage);
+        if self.print_timings {
+            println!();
+            print!("{}", self.timings);
+        }
+
+        match output_error {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
     }
 }
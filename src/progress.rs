@@ -0,0 +1,82 @@
+use std::io::{IsTerminal, Write};
+use std::time::{Duration, Instant};
+
+/// How often the progress is updated in place, if stderr is a terminal.
+const TERMINAL_INTERVAL: Duration = Duration::from_millis(100);
+/// How often the progress is printed as a line, if stderr is not a terminal (e.g. in CI logs).
+const PLAIN_INTERVAL: Duration = Duration::from_secs(10);
+
+/// The progress of the phase of the analysis that is running, printed to stderr so a long analysis can be told apart from a stuck one.
+/// It is updated in place if stderr is a terminal, and otherwise printed as a line every few seconds.
+pub struct Progress {
+    /// Whether to print the progress at all, i.e. not using `--quiet`.
+    enabled: bool,
+    /// Whether stderr is a terminal, so the progress can be updated in place.
+    terminal: bool,
+    /// The name of the running phase, e.g. `Building the call graph`.
+    phase: &'static str,
+    /// When the progress was last printed, or the phase started.
+    last_printed: Instant,
+    /// Whether a line of progress is shown that should be cleared once the phase finishes.
+    shown: bool,
+}
+
+impl Progress {
+    /// Create the progress of an analysis, printed unless `quiet` is set.
+    pub fn new(quiet: bool) -> Self {
+        Progress {
+            enabled: !quiet,
+            terminal: std::io::stderr().is_terminal(),
+            phase: "",
+            last_printed: Instant::now(),
+            shown: false,
+        }
+    }
+
+    /// Start a phase of the analysis, finishing the previous one.
+    pub fn start(&mut self, phase: &'static str) {
+        self.finish();
+        self.phase = phase;
+        self.last_printed = Instant::now();
+    }
+
+    /// Report the progress of the running phase, e.g. `120 functions explored, 450 calls added`.
+    /// The message is only created if it is time to print it again.
+    pub fn update(&mut self, message: impl FnOnce() -> String) {
+        let interval = if self.terminal {
+            TERMINAL_INTERVAL
+        } else {
+            PLAIN_INTERVAL
+        };
+        if !self.enabled || self.last_printed.elapsed() < interval {
+            return;
+        }
+        self.last_printed = Instant::now();
+
+        let mut stderr = std::io::stderr().lock();
+        if self.terminal {
+            // Return to the start of the line and clear it, to overwrite the previous update
+            let _ = write!(stderr, "\r\x1b[2K{}: {}", self.phase, message());
+            let _ = stderr.flush();
+            self.shown = true;
+        } else {
+            let _ = writeln!(stderr, "{}: {}", self.phase, message());
+        }
+    }
+
+    /// Finish the running phase, clearing its progress from the terminal so the output after it starts on a clean line.
+    pub fn finish(&mut self) {
+        if self.shown {
+            let mut stderr = std::io::stderr().lock();
+            let _ = write!(stderr, "\r\x1b[2K");
+            let _ = stderr.flush();
+            self.shown = false;
+        }
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
@@ -0,0 +1,364 @@
+use crate::graph::{CallEdge, CallGraph};
+use std::fmt;
+
+/// The maximum number of paths listed per direction, as the number of paths can grow exponentially with the size of the graph.
+const MAX_PATHS: usize = 100;
+
+/// The paths through which errors propagate between two functions, in both directions.
+#[derive(Debug, Clone)]
+pub struct PathQuery {
+    /// The label of the first function.
+    pub from: String,
+    /// The label of the second function.
+    pub to: String,
+    /// The paths through which errors of the first function reach the second, as the indices of their calls, from the first function up.
+    pub forward: Vec<Vec<usize>>,
+    /// The paths through which errors of the second function reach the first, likewise.
+    pub backward: Vec<Vec<usize>>,
+}
+
+/// Find the paths through which errors propagate between two functions, given by (part of) their path, e.g. `db::fetch`.
+/// Errors reach a function through a call returning an error, which each function in between propagates.
+/// Returns an error with suggestions if a name matches no function or several functions.
+pub fn find_error_paths(graph: &CallGraph, from: &str, to: &str) -> Result<PathQuery, String> {
+    let from_node = resolve_function(graph, from)?;
+    let to_node = resolve_function(graph, to)?;
+
+    Ok(PathQuery {
+        from: graph.label(from_node).to_owned(),
+        to: graph.label(to_node).to_owned(),
+        forward: get_error_paths(graph, from_node, to_node),
+        backward: get_error_paths(graph, to_node, from_node),
+    })
+}
+
+/// Get the node of the function with the provided name, which matches its label or full path exactly, or ends it (e.g. `fetch` for `db::fetch`).
+fn resolve_function(graph: &CallGraph, name: &str) -> Result<usize, String> {
+    let paths: Vec<(usize, Vec<&str>)> = graph
+        .nodes
+        .iter()
+        .map(|node| {
+            let mut paths = vec![graph.strings.resolve(node.label)];
+            paths.extend(node.path.as_deref());
+            (node.id(), paths)
+        })
+        .collect();
+
+    let exact: Vec<usize> = paths
+        .iter()
+        .filter(|(_node, paths)| paths.contains(&name))
+        .map(|(node, _paths)| *node)
+        .collect();
+    let matches = if exact.is_empty() {
+        let suffix = format!("::{name}");
+        paths
+            .iter()
+            .filter(|(_node, paths)| paths.iter().any(|path| path.ends_with(&suffix)))
+            .map(|(node, _paths)| *node)
+            .collect()
+    } else {
+        exact
+    };
+
+    match matches.as_slice() {
+        [node] => Ok(*node),
+        [] => {
+            // Suggest the functions sharing the last segment of the name, or containing it otherwise
+            let last = name.rsplit("::").next().unwrap_or(name);
+            let suggestions: Vec<&str> = graph
+                .nodes
+                .iter()
+                .map(|node| graph.strings.resolve(node.label))
+                .filter(|label| label.contains(last))
+                .collect();
+            if suggestions.is_empty() {
+                Err(format!("No function matches {name}"))
+            } else {
+                Err(format!(
+                    "No function matches {name}, did you mean one of: {}?",
+                    suggestions.join(", ")
+                ))
+            }
+        }
+        _ => {
+            let labels: Vec<&str> = matches.iter().map(|node| graph.label(*node)).collect();
+            Err(format!(
+                "Several functions match {name}, use one of: {}",
+                labels.join(", ")
+            ))
+        }
+    }
+}
+
+/// Get the paths through which errors of the `from` function reach the `to` function, as the indices of their calls.
+/// Each call returns an error to its caller, which propagates it further if it is not `to` itself.
+fn get_error_paths(graph: &CallGraph, from: usize, to: usize) -> Vec<Vec<usize>> {
+    let mut res = vec![];
+    if from != to {
+        explore_error_paths(graph, from, to, &mut vec![from], &mut vec![], &mut res);
+    }
+    res
+}
+
+/// Extend the current path (of nodes and calls) with the callers receiving the errors of its last node.
+fn explore_error_paths(
+    graph: &CallGraph,
+    node: usize,
+    to: usize,
+    nodes: &mut Vec<usize>,
+    calls: &mut Vec<usize>,
+    res: &mut Vec<Vec<usize>>,
+) {
+    for &i in graph.get_incoming_edge_ids(node) {
+        if res.len() >= MAX_PATHS {
+            return;
+        }
+        let call = &graph.edges[i];
+        if !call.is_error || nodes.contains(&call.from) {
+            continue;
+        }
+
+        calls.push(i);
+        if call.from == to {
+            res.push(calls.clone());
+        } else if call.propagates {
+            nodes.push(call.from);
+            explore_error_paths(graph, call.from, to, nodes, calls, res);
+            nodes.pop();
+        }
+        calls.pop();
+    }
+}
+
+impl PathQuery {
+    /// Get a graph of only the calls of the paths found, in both directions.
+    pub fn to_graph(&self, graph: &CallGraph) -> CallGraph {
+        let mut calls: Vec<usize> = vec![];
+        for call in self.forward.iter().chain(&self.backward).flatten() {
+            if !calls.contains(call) {
+                calls.push(*call);
+            }
+        }
+        graph.with_edges(&calls)
+    }
+
+    /// Format the paths of a direction as an indented list, e.g. `fetch` followed by `→ lookup (propagated, src/db.rs:12:5)`.
+    fn write_paths(
+        &self,
+        f: &mut fmt::Formatter,
+        graph: &CallGraph,
+        from: &str,
+        to: &str,
+        paths: &[Vec<usize>],
+    ) -> fmt::Result {
+        if paths.is_empty() {
+            return writeln!(f, "Errors of {from} do not reach {to}.");
+        }
+
+        let limit = if paths.len() >= MAX_PATHS {
+            format!(" (only the first {MAX_PATHS} are listed)")
+        } else {
+            String::new()
+        };
+        writeln!(
+            f,
+            "Errors of {from} reach {to} through {} paths{limit}:",
+            paths.len()
+        )?;
+        for path in paths {
+            writeln!(f, "  {from}")?;
+            for (depth, call) in path.iter().enumerate() {
+                let call = &graph.edges[*call];
+                writeln!(
+                    f,
+                    "  {}→ {} ({})",
+                    "  ".repeat(depth + 1),
+                    graph.label(call.from),
+                    describe_call(graph, call)
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Get a value that displays the paths found in both directions, using the graph they were found in.
+    pub fn display<'a>(&'a self, graph: &'a CallGraph) -> impl fmt::Display + 'a {
+        PathQueryDisplay { query: self, graph }
+    }
+}
+
+/// Describe a call on a path, i.e. the error it returns, how the caller deals with it, and where it is made.
+fn describe_call(graph: &CallGraph, call: &CallEdge) -> String {
+    let mut res = graph.type_label(call).unwrap_or(String::from("unknown"));
+    if let Some(handling) = call.handling_label() {
+        res.push_str(&format!(", {handling}"));
+    }
+    if let Some(location) = &call.location {
+        res.push_str(&format!(", {location}"));
+    }
+    res
+}
+
+/// Displays the paths of a query, using the graph they were found in.
+struct PathQueryDisplay<'a> {
+    query: &'a PathQuery,
+    graph: &'a CallGraph,
+}
+
+impl fmt::Display for PathQueryDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let query = self.query;
+        query.write_paths(f, self.graph, &query.from, &query.to, &query.forward)?;
+        query.write_paths(f, self.graph, &query.to, &query.from, &query.backward)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::tests::{add_call, graph};
+
+    /// Get the labels of the callers along each path, from the first function up.
+    fn callers(graph: &CallGraph, paths: &[Vec<usize>]) -> Vec<Vec<String>> {
+        paths
+            .iter()
+            .map(|path| {
+                path.iter()
+                    .map(|call| graph.label(graph.edges[*call].from).to_owned())
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// `read` returns errors that `load` and `parse` propagate to `run`, which `main` handles.
+    /// `cache` handles the errors of `read` itself, and `load` calls `run` again.
+    fn example() -> CallGraph {
+        let mut graph = graph(&["main", "run", "load", "parse", "read", "cache", "log"]);
+        add_call(&mut graph, "main", "run", true, false);
+        add_call(&mut graph, "run", "load", true, true);
+        add_call(&mut graph, "run", "parse", true, true);
+        add_call(&mut graph, "load", "read", true, true);
+        add_call(&mut graph, "parse", "read", true, true);
+        add_call(&mut graph, "load", "run", true, true);
+        add_call(&mut graph, "main", "cache", true, false);
+        add_call(&mut graph, "cache", "read", true, false);
+        add_call(&mut graph, "run", "log", false, false);
+        graph
+    }
+
+    #[test]
+    fn paths_are_found_in_the_direction_errors_propagate() {
+        let graph = example();
+        let query = find_error_paths(&graph, "read", "main").unwrap();
+        assert_eq!(query.from, "read");
+        assert_eq!(query.to, "main");
+        assert_eq!(
+            callers(&graph, &query.forward),
+            [["load", "run", "main"], ["parse", "run", "main"]]
+        );
+        assert!(query.backward.is_empty());
+
+        let query = find_error_paths(&graph, "main", "read").unwrap();
+        assert!(query.forward.is_empty());
+        assert_eq!(query.backward.len(), 2);
+    }
+
+    #[test]
+    fn cycles_are_not_followed() {
+        let graph = example();
+        // `run` propagates its errors to `load`, which is already on the path from `read`
+        let query = find_error_paths(&graph, "read", "run").unwrap();
+        assert_eq!(
+            callers(&graph, &query.forward),
+            [["load", "run"], ["parse", "run"]]
+        );
+        // Both propagate the errors of each other
+        let query = find_error_paths(&graph, "load", "run").unwrap();
+        assert_eq!(callers(&graph, &query.forward), [["run"]]);
+        assert_eq!(callers(&graph, &query.backward), [["load"]]);
+    }
+
+    #[test]
+    fn calls_not_propagating_end_their_path() {
+        let graph = example();
+        // `cache` handles the errors of `read`, so they do not reach `main` through it
+        let query = find_error_paths(&graph, "read", "cache").unwrap();
+        assert_eq!(callers(&graph, &query.forward), [["cache"]]);
+        let query = find_error_paths(&graph, "read", "main").unwrap();
+        assert!(!callers(&graph, &query.forward)
+            .iter()
+            .any(|path| path[0] == "cache"));
+        // Calls that carry no errors are no paths at all
+        let query = find_error_paths(&graph, "log", "run").unwrap();
+        assert!(query.forward.is_empty());
+    }
+
+    #[test]
+    fn functions_are_resolved_by_label_path_or_suffix() {
+        let mut graph = graph(&["main", "read", "read"]);
+        graph.nodes[1].path = Some(String::from("app::db::read"));
+        graph.nodes[2].path = Some(String::from("app::fs::read"));
+
+        assert_eq!(resolve_function(&graph, "main"), Ok(0));
+        assert_eq!(resolve_function(&graph, "app::fs::read"), Ok(2));
+        assert_eq!(resolve_function(&graph, "db::read"), Ok(1));
+        assert_eq!(
+            resolve_function(&graph, "read"),
+            Err(String::from(
+                "Several functions match read, use one of: read, read"
+            ))
+        );
+    }
+
+    #[test]
+    fn unknown_functions_get_suggestions() {
+        let graph = example();
+        assert_eq!(
+            resolve_function(&graph, "db::rea"),
+            Err(String::from(
+                "No function matches db::rea, did you mean one of: read?"
+            ))
+        );
+        assert_eq!(
+            resolve_function(&graph, "fetch"),
+            Err(String::from("No function matches fetch"))
+        );
+        assert!(find_error_paths(&graph, "main", "fetch").is_err());
+    }
+
+    #[test]
+    fn paths_are_capped() {
+        let labels: Vec<String> = (0..MAX_PATHS + 10).map(|i| format!("via{i}")).collect();
+        let mut functions = vec!["main", "read"];
+        functions.extend(labels.iter().map(String::as_str));
+        let mut graph = graph(&functions);
+        for label in &labels {
+            add_call(&mut graph, "main", label, true, false);
+            add_call(&mut graph, label, "read", true, true);
+        }
+
+        let query = find_error_paths(&graph, "read", "main").unwrap();
+        assert_eq!(query.forward.len(), MAX_PATHS);
+        let text = query.display(&graph).to_string();
+        assert!(text.starts_with(&format!(
+            "Errors of read reach main through {MAX_PATHS} paths (only the first {MAX_PATHS} are listed):"
+        )));
+    }
+
+    #[test]
+    fn paths_are_displayed_with_their_calls() {
+        let mut graph = example();
+        graph.edges[0].handling = crate::graph::Handling::Handled;
+        graph.edges[1].location = Some(String::from("src/main.rs:12:5"));
+        let query = find_error_paths(&graph, "load", "main").unwrap();
+        assert_eq!(
+            query.display(&graph).to_string(),
+            "Errors of load reach main through 1 paths:\n  \
+             load\n    \
+             → run (unknown, src/main.rs:12:5)\n      \
+             → main (unknown, handled)\n\
+             Errors of main do not reach load.\n"
+        );
+        assert_eq!(query.to_graph(&graph).edges.len(), 2);
+    }
+}
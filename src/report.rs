@@ -0,0 +1,199 @@
+use crate::stats::{ChainStats, ChainSummary, FunctionSummary};
+use std::path::{Path, PathBuf};
+
+/// Convert the statistics of the chains of a crate to a Markdown report, e.g. to paste into a pull request.
+/// The graphs are linked as (title, path) if they were written, relative to the report where possible.
+pub fn to_markdown(
+    stats: &ChainStats,
+    crate_name: &str,
+    graphs: &[(&str, PathBuf)],
+    report_path: &Path,
+) -> String {
+    let mut res = format!("# Error propagation in `{crate_name}`\n\n");
+
+    res.push_str("## Summary\n\n");
+    res.push_str(&format!(
+        "- **{}** error propagation chains ({} before deduplication)\n",
+        stats.count, stats.raw_count
+    ));
+    if let Some(unfiltered_count) = stats.unfiltered_count {
+        res.push_str(&format!(
+            "- {} of the {unfiltered_count} chains match the error filter\n",
+            stats.filtered_count
+        ));
+    }
+    if stats.merged_count > 0 {
+        res.push_str(&format!(
+            "- {} chains share their propagation path with another chain\n",
+            stats.merged_count
+        ));
+    }
+    if stats.truncated_count > 0 {
+        res.push_str(&format!(
+            "- {} chains were truncated at the maximum depth of {} calls\n",
+            stats.truncated_count, stats.depth_limit
+        ));
+    }
+    if stats.left_out_count > 0 {
+        res.push_str(&format!(
+            "- {} chains were left out, as only the {} deepest chains are kept\n",
+            stats.left_out_count, stats.chain_limit
+        ));
+    }
+    res.push_str(&format!(
+        "- Longest error path: **{}** chained function calls\n",
+        stats.max_depth
+    ));
+    res.push_str(&format!(
+        "- Biggest chain: **{}** function calls, {:.1} on average\n",
+        stats.max_size,
+        if stats.count > 0 {
+            stats.average_size()
+        } else {
+            0.0
+        }
+    ));
+    res.push_str(&format!(
+        "- {} chains propagate `None`\n",
+        stats.option_count
+    ));
+    res.push_str(&format!(
+        "- {} of the {} calls in the chains return a type-erased error (`Box<dyn Error>`), {} an anyhow or eyre error\n",
+        stats.type_erased_count, stats.total_size, stats.error_family_count
+    ));
+    res.push_str(&format!(
+        "- {} recursive error propagation cycles\n",
+        stats.recursive_count
+    ));
+    res.push_str(&format!(
+        "- {} errors dropped using `.ok()`, {} lost in spawned tasks, {} discarded\n",
+        stats.ok_discard_count,
+        stats.lost_to_tasks_count,
+        stats.discarded_calls.len()
+    ));
+
+    if !stats.endings.is_empty() || stats.suppressed_count > 0 {
+        res.push_str("\n## How the chains end\n\n");
+        res.push_str("| the error is | chains |\n| --- | ---: |\n");
+        for (termination, n) in &stats.endings {
+            res.push_str(&format!("| {} | {n} |\n", termination.label()));
+        }
+        if stats.suppressed_count > 0 {
+            res.push_str(&format!("| suppressed | {} |\n", stats.suppressed_count));
+        }
+    }
+
+    if let Some(entry_ty) = &stats.entry_return_ty {
+        res.push_str("\n## Errors reaching the program exit\n\n");
+        if !stats.entry_returns_result {
+            res.push_str(&format!(
+                "No chains reach the program exit, as the entry point returns `{entry_ty}`.\n"
+            ));
+        } else if stats.exit_chains.is_empty() {
+            res.push_str(&format!(
+                "No chains reach the program exit, though the entry point returns `{entry_ty}`.\n"
+            ));
+        } else {
+            res.push_str(&format!(
+                "The entry point returns `{entry_ty}`, so these errors are printed when the program exits:\n\n"
+            ));
+            for summary in &stats.exit_chains {
+                res.push_str(&format!("- {}\n", format_summary(summary)));
+            }
+        }
+    }
+
+    if !stats.functions.is_empty() {
+        res.push_str("\n## How the functions deal with errors\n\n");
+        res.push_str(&format_function_table(&stats.functions));
+    }
+
+    // The largest chains are often among the deepest, these are only listed once
+    let mut top_chains: Vec<&ChainSummary> = vec![];
+    for summary in stats.deepest_chains.iter().chain(&stats.largest_chains) {
+        if !top_chains.iter().any(|other| other.index == summary.index) {
+            top_chains.push(summary);
+        }
+    }
+    if !top_chains.is_empty() {
+        res.push_str("\n## Top chains\n\n");
+        res.push_str(
+            "The chains with the longest error paths and the most calls, with the path from where each ends to where its error originates.\n",
+        );
+        for summary in top_chains {
+            res.push_str(&format!("\n{}\n\n", format_summary(summary)));
+            res.push_str(&format_hops(summary));
+        }
+    }
+
+    let graphs: Vec<&(&str, PathBuf)> = graphs
+        .iter()
+        .filter(|(_title, path)| path.exists())
+        .collect();
+    if !graphs.is_empty() {
+        res.push_str("\n## Graphs\n\n");
+        let base = report_path.parent().unwrap_or(Path::new(""));
+        for (title, path) in graphs {
+            let link = path.strip_prefix(base).unwrap_or(path);
+            res.push_str(&format!("- [{title}]({})\n", link.display()));
+        }
+    }
+
+    res
+}
+
+/// Format the summary of a chain on a line, e.g. ``**#2** `main` ← `parse` (depth 3, 4 calls): handled, originates at `src/main.rs:4:5` ``.
+fn format_summary(summary: &ChainSummary) -> String {
+    let mut res = format!(
+        "**#{}** `{}` ← `{}` (depth {}, {} calls): {}",
+        summary.index,
+        summary.head,
+        summary.tail,
+        summary.depth,
+        summary.size,
+        summary.termination.label()
+    );
+    if let Some(location) = &summary.location {
+        res.push_str(&format!(", originates at `{location}`"));
+    }
+    res
+}
+
+/// Format the path of a chain as a nested list, starting at its head, with the function, error type and location of each call.
+fn format_hops(summary: &ChainSummary) -> String {
+    let mut res = format!("- `{}`\n", summary.head);
+    for (depth, hop) in summary.hops.iter().enumerate() {
+        res.push_str(&format!("{}- `{}`", "  ".repeat(depth + 1), hop.function));
+        if let Some(ty) = &hop.ty {
+            res.push_str(&format!(" — `{ty}`"));
+        }
+        if let Some(location) = &hop.location {
+            res.push_str(&format!(" — `{location}`"));
+        }
+        res.push('\n');
+    }
+    res
+}
+
+/// Format the summaries of the functions as a Markdown table, with a row per function.
+fn format_function_table(functions: &[FunctionSummary]) -> String {
+    let mut res = String::from(
+        "| function | fallible | propagated | handled | logged | unwrapped | discarded | returns Result | deepest chain |\n",
+    );
+    res.push_str("| --- | ---: | ---: | ---: | ---: | ---: | ---: | --- | ---: |\n");
+    for summary in functions {
+        res.push_str(&format!(
+            "| `{}` | {} | {} | {} | {} | {} | {} | {} | {} |\n",
+            summary.function,
+            summary.fallible_calls,
+            summary.propagated,
+            summary.handled,
+            summary.logged,
+            summary.unwrapped,
+            summary.discarded,
+            if summary.returns_result { "yes" } else { "no" },
+            summary.deepest_chain
+        ));
+    }
+    res
+}
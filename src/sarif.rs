@@ -0,0 +1,175 @@
+use crate::graph::ChainEnd;
+use crate::stats::ChainSummary;
+
+/// The levels a rule can report its results at, where `none` leaves them out.
+pub const LEVELS: [&str; 4] = ["error", "warning", "note", "none"];
+
+/// A kind of finding, for the chains ending in a way that loses their error.
+#[derive(Debug, Clone)]
+pub struct Rule {
+    /// The id of the rule, e.g. `error-analyzer/unwrap-terminated-chain`.
+    pub id: &'static str,
+    /// What the results of the rule mean.
+    pub description: &'static str,
+    /// The level the results are reported at, one of the `LEVELS`.
+    pub level: String,
+}
+
+/// Get the rules with their default levels, overridden by the provided (rule, level) pairs.
+/// The rules can be given without the `error-analyzer/` prefix, e.g. `unwrap-terminated-chain=error`.
+/// Returns an error if a rule or level does not exist.
+pub fn get_rules(levels: &[(String, String)]) -> Result<Vec<Rule>, String> {
+    let mut rules = vec![
+        Rule {
+            id: "error-analyzer/unwrap-terminated-chain",
+            description: "An error propagated up to this call is unwrapped, causing a panic.",
+            level: String::from("warning"),
+        },
+        Rule {
+            id: "error-analyzer/discarded-error-chain",
+            description: "An error propagated up to this call is thrown away.",
+            level: String::from("warning"),
+        },
+        Rule {
+            id: "error-analyzer/discarded-must-use",
+            description:
+                "An error returned by this call to a #[must_use] function is thrown away, along with the result it must be used for.",
+            level: String::from("error"),
+        },
+        Rule {
+            id: "error-analyzer/error-lost-in-task",
+            description:
+                "An error returned by this spawned task is lost, as its handle is dropped.",
+            level: String::from("error"),
+        },
+    ];
+
+    for (name, level) in levels {
+        if !LEVELS.contains(&level.as_str()) {
+            return Err(format!(
+                "Unknown level {level}, use one of: {}",
+                LEVELS.join(", ")
+            ));
+        }
+        let Some(rule) = rules
+            .iter_mut()
+            .find(|rule| rule.id == name || rule.id.strip_prefix("error-analyzer/") == Some(name))
+        else {
+            let ids: Vec<&str> = rules.iter().map(|rule| rule.id).collect();
+            return Err(format!(
+                "Unknown rule {name}, use one of: {}",
+                ids.join(", ")
+            ));
+        };
+        rule.level.clone_from(level);
+    }
+
+    Ok(rules)
+}
+
+/// Get the rule the ending of a chain falls under, if it loses the error and this is not accepted.
+fn get_rule<'a>(rules: &'a [Rule], summary: &ChainSummary) -> Option<&'a Rule> {
+    let id = if summary.suppressed {
+        return None;
+    } else if summary.lost_in_task {
+        "error-analyzer/error-lost-in-task"
+    } else if summary.termination == ChainEnd::Unwrapped {
+        "error-analyzer/unwrap-terminated-chain"
+    } else if summary.termination == ChainEnd::Discarded && summary.must_use {
+        "error-analyzer/discarded-must-use"
+    } else if summary.termination == ChainEnd::Discarded {
+        "error-analyzer/discarded-error-chain"
+    } else {
+        return None;
+    };
+    rules.iter().find(|rule| rule.id == id)
+}
+
+/// Convert the chains ending in a way that loses their error to SARIF, with a result at the call where each ends.
+/// The locations are relative to the root of the analyzed crate (`%SRCROOT%`).
+pub fn to_sarif(summaries: &[ChainSummary], rules: &[Rule]) -> String {
+    let mut results = vec![];
+    for summary in summaries {
+        let Some(rule) = get_rule(rules, summary).filter(|rule| rule.level != "none") else {
+            continue;
+        };
+        // Results need a location to annotate
+        let Some((file, line, column)) = summary.end_location.as_deref().and_then(parse_location)
+        else {
+            continue;
+        };
+
+        results.push(serde_json::json!({
+            "ruleId": rule.id,
+            "level": rule.level,
+            "message": { "text": get_message(summary) },
+            "properties": { "severity": summary.score },
+            "locations": [{
+                "physicalLocation": {
+                    "artifactLocation": { "uri": file.replace('\\', "/"), "uriBaseId": "%SRCROOT%" },
+                    "region": { "startLine": line, "startColumn": column },
+                },
+            }],
+        }));
+    }
+
+    let rules: Vec<serde_json::Value> = rules
+        .iter()
+        .map(|rule| {
+            serde_json::json!({
+                "id": rule.id,
+                "shortDescription": { "text": rule.description },
+                "defaultConfiguration": { "level": rule.level },
+            })
+        })
+        .collect();
+    let sarif = serde_json::json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "static-result-analyzer",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules,
+                },
+            },
+            "results": results,
+        }],
+    });
+
+    serde_json::to_string_pretty(&sarif).unwrap()
+}
+
+/// Describe the chain of a result, e.g. `The error of chain #2 is unwrapped in main, after propagating through 2 chained calls: main → parse (ParseError) → read (std::io::Error)`.
+fn get_message(summary: &ChainSummary) -> String {
+    let mut path = vec![summary.head.clone()];
+    for hop in &summary.hops {
+        match &hop.ty {
+            Some(ty) => path.push(format!("{} ({ty})", hop.function)),
+            None => path.push(hop.function.clone()),
+        }
+    }
+
+    let ending = if summary.lost_in_task {
+        "lost in a spawned task"
+    } else {
+        summary.termination.label()
+    };
+    format!(
+        "The error of chain #{} is {ending} in {}, after propagating through {} chained calls: {}",
+        summary.index,
+        summary.head,
+        summary.depth,
+        path.join(" → ")
+    )
+}
+
+/// Split a location into its file, line and column, e.g. `src/main.rs:12:5`.
+fn parse_location(location: &str) -> Option<(&str, usize, usize)> {
+    let mut parts = location.rsplitn(3, ':');
+    let column = parts.next()?.parse().ok()?;
+    let line = parts.next()?.parse().ok()?;
+    let file = parts.next()?;
+    Some((file, line, column))
+}
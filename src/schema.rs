@@ -0,0 +1,394 @@
+//! The schema the graphs are (de)serialized in. It is versioned, so graphs written by another version of the analyzer are rejected
+//! with an explanation, rather than misread.
+//!
+//! The ids the compiler gives to functions and calls (`DefId` and `HirId`) are only meaningful within the compilation they come from,
+//! so these are (de)serialized as stable strings: the path of the definition with the disambiguators of e.g. its impl block or closure
+//! (e.g. `app::{impl#0}::read`), and for a `HirId` the path of its owner followed by its index within it (e.g. `app::main@12`).
+//! A deserialized graph is unresolved: it gets ids of its own for these paths, which can be compared (e.g. to diff graphs),
+//! but not used with a `TyCtxt`.
+
+use crate::graph::{CallGraph, CallId, CallNodeKind};
+use rustc_hir::def_id::{DefId, DefIndex, LOCAL_CRATE};
+use rustc_hir::hir_id::{HirId, ItemLocalId, OwnerId};
+use rustc_middle::ty::TyCtxt;
+use serde::de::{self, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde::ser::{self, SerializeStruct};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+use std::marker::PhantomData;
+
+/// The version of the schema the graphs are serialized in, which changes whenever a graph of an older version cannot be read.
+pub const SCHEMA_VERSION: u32 = 12;
+
+/// The fields of the versioned representation of a graph, the version coming first so it is checked before the graph is read.
+const FIELDS: &[&str] = &["schema_version", "graph"];
+
+thread_local! {
+    /// The stable paths of the graph being (de)serialized on this thread, if any.
+    static CURRENT_PATHS: RefCell<Option<StablePaths>> = const { RefCell::new(None) };
+}
+
+/// The stable paths of the definitions a graph refers to, which the ids of the compiler are (de)serialized as.
+#[derive(Debug, Clone, Default)]
+pub struct StablePaths {
+    paths: HashMap<DefId, String>,
+    /// The ids given to the paths of a deserialized graph.
+    ids: HashMap<String, DefId>,
+    /// Whether the ids are those of the compilation the graph was analyzed in, rather than given when deserializing it.
+    resolved: bool,
+}
+
+impl StablePaths {
+    /// Get the stable paths of all definitions the call graph refers to: the functions of its nodes,
+    /// and the owners of the calls and error enums of its edges. The calls of the chains are those of the call graph.
+    pub(crate) fn collect(context: TyCtxt, call_graph: &CallGraph) -> Self {
+        let mut paths = StablePaths {
+            resolved: true,
+            ..StablePaths::default()
+        };
+
+        for node in &call_graph.nodes {
+            if let CallNodeKind::LocalFn(_def_id, hir_id) = node.kind {
+                paths.add(context, hir_id.owner.to_def_id());
+            }
+            if let Some(def_id) = node.kind.def_id() {
+                paths.add(context, def_id);
+            }
+        }
+        for edge in &call_graph.edges {
+            match edge.call_id {
+                CallId::Hir(hir_id) => paths.add(context, hir_id.owner.to_def_id()),
+                CallId::Mir(def_id, _block) => paths.add(context, def_id),
+            }
+            if let Some(map_err_id) = edge.map_err_id {
+                paths.add(context, map_err_id.owner.to_def_id());
+            }
+            if let Some(error_enum) = edge.error_enum {
+                paths.add(context, error_enum);
+            }
+        }
+
+        paths
+    }
+
+    /// Add the stable path of a definition, e.g. `app::{impl#0}::read`, if it was not added before.
+    fn add(&mut self, context: TyCtxt, def_id: DefId) {
+        self.paths.entry(def_id).or_insert_with(|| {
+            format!(
+                "{}{}",
+                context.crate_name(def_id.krate),
+                context.def_path(def_id).to_string_no_crate_verbose()
+            )
+        });
+    }
+
+    /// Get the stable path of a definition, if the graph refers to it.
+    pub(crate) fn get(&self, def_id: DefId) -> Option<&str> {
+        self.paths.get(&def_id).map(String::as_str)
+    }
+
+    /// Whether the ids are those of the compilation the graph was analyzed in, rather than given when deserializing it.
+    pub(crate) fn is_resolved(&self) -> bool {
+        self.resolved
+    }
+
+    /// Get the id of a path of a deserialized graph, giving it a new one if it has none yet.
+    /// These ids all belong to the local crate, so the owners of `HirId`s are local as well.
+    fn id_of(&mut self, path: String) -> DefId {
+        if let Some(def_id) = self.ids.get(&path) {
+            return *def_id;
+        }
+
+        let def_id = DefId {
+            krate: LOCAL_CRATE,
+            index: DefIndex::from_usize(self.ids.len()),
+        };
+        self.ids.insert(path.clone(), def_id);
+        self.paths.insert(def_id, path);
+        def_id
+    }
+}
+
+/// Check whether a graph of the given schema version can be read by this version of the analyzer.
+pub fn check_version(version: u32) -> Result<(), String> {
+    match version.cmp(&SCHEMA_VERSION) {
+        Ordering::Equal => Ok(()),
+        Ordering::Greater => Err(format!(
+            "The graph has schema version {version}, which is newer than the version this analyzer reads ({SCHEMA_VERSION}), update the analyzer to read it"
+        )),
+        Ordering::Less => Err(format!(
+            "The graph has schema version {version}, which is older than the version this analyzer reads ({SCHEMA_VERSION}), analyze the crate again to update it"
+        )),
+    }
+}
+
+/// A graph (de)serialized along with its schema version, using the implementation derived for its fields.
+pub(crate) trait VersionedGraph: Sized {
+    fn serialize_fields<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error>;
+    fn deserialize_fields<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error>;
+}
+
+/// Serialize a graph along with its schema version, writing the ids it refers to as their stable paths.
+pub(crate) fn serialize<G: VersionedGraph, S: Serializer>(
+    graph: &G,
+    paths: &StablePaths,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    struct Fields<'a, G>(&'a G);
+
+    impl<G: VersionedGraph> Serialize for Fields<'_, G> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            self.0.serialize_fields(serializer)
+        }
+    }
+
+    let (result, _paths) = with_paths(paths.clone(), || {
+        let mut state = serializer.serialize_struct("Graph", FIELDS.len())?;
+        state.serialize_field("schema_version", &SCHEMA_VERSION)?;
+        state.serialize_field("graph", &Fields(graph))?;
+        state.end()
+    });
+    result
+}
+
+/// Deserialize a graph, checking its schema version first, and giving the paths it refers to ids of their own.
+/// Returns the graph along with its (unresolved) stable paths.
+pub(crate) fn deserialize<'de, G: VersionedGraph, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<(G, StablePaths), D::Error> {
+    struct Fields<G>(G);
+
+    impl<'de, G: VersionedGraph> Deserialize<'de> for Fields<G> {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            G::deserialize_fields(deserializer).map(Fields)
+        }
+    }
+
+    struct GraphVisitor<G>(PhantomData<G>);
+
+    impl<'de, G: VersionedGraph> Visitor<'de> for GraphVisitor<G> {
+        type Value = G;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a graph with its schema version")
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<G, A::Error> {
+            let version: u32 = seq
+                .next_element()?
+                .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+            check_version(version).map_err(de::Error::custom)?;
+            let Fields(graph) = seq
+                .next_element()?
+                .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+            Ok(graph)
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<G, A::Error> {
+            let mut version = None;
+            let mut graph = None;
+            while let Some(key) = map.next_key::<String>()? {
+                match key.as_str() {
+                    "schema_version" => {
+                        let schema_version: u32 = map.next_value()?;
+                        check_version(schema_version).map_err(de::Error::custom)?;
+                        version = Some(schema_version);
+                    }
+                    "graph" => {
+                        // The graph of another version may not be readable, so its version should be known first
+                        if version.is_none() {
+                            return Err(de::Error::missing_field("schema_version"));
+                        }
+                        let Fields(fields) = map.next_value()?;
+                        graph = Some(fields);
+                    }
+                    _ => {
+                        map.next_value::<IgnoredAny>()?;
+                    }
+                }
+            }
+            graph.ok_or_else(|| de::Error::missing_field("graph"))
+        }
+    }
+
+    let (graph, paths) = with_paths(StablePaths::default(), || {
+        deserializer.deserialize_struct("Graph", FIELDS, GraphVisitor(PhantomData))
+    });
+    Ok((graph?, paths))
+}
+
+/// Load a graph from a JSON file, as written by `save`.
+#[cfg(feature = "serde")]
+pub(crate) fn load<G: serde::de::DeserializeOwned>(path: &std::path::Path) -> Result<G, String> {
+    let json = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    serde_json::from_str(&json).map_err(|e| e.to_string())
+}
+
+/// Save a graph to a JSON file, which `load` reads.
+#[cfg(feature = "serde")]
+pub(crate) fn save<G: Serialize>(graph: &G, path: &std::path::Path) -> Result<(), String> {
+    let json = serde_json::to_string(graph).map_err(|e| e.to_string())?;
+    std::fs::write(path, json).map_err(|e| e.to_string())
+}
+
+/// Run a (de)serialization with the given stable paths, returning its result along with the paths,
+/// to which the paths of a deserialized graph are added.
+fn with_paths<T>(paths: StablePaths, f: impl FnOnce() -> T) -> (T, StablePaths) {
+    // Graphs may be (de)serialized within another, e.g. in the cache, so the outer paths are restored after
+    let outer = CURRENT_PATHS.with(|current| current.replace(Some(paths)));
+    let result = f();
+    let paths = CURRENT_PATHS.with(|current| current.replace(outer));
+    (result, paths.unwrap_or_default())
+}
+
+/// Get the stable path of a definition of the graph being serialized.
+fn path_of<E: ser::Error>(def_id: DefId) -> Result<String, E> {
+    CURRENT_PATHS.with(|current| {
+        current
+            .borrow()
+            .as_ref()
+            .ok_or_else(|| E::custom("ids can only be serialized as part of a graph"))?
+            .get(def_id)
+            .map(str::to_owned)
+            .ok_or_else(|| E::custom(format!("the graph has no stable path for {def_id:?}")))
+    })
+}
+
+/// Get the id of a path of the graph being deserialized.
+fn id_of<E: de::Error>(path: String) -> Result<DefId, E> {
+    CURRENT_PATHS.with(|current| {
+        current
+            .borrow_mut()
+            .as_mut()
+            .map(|paths| paths.id_of(path))
+            .ok_or_else(|| E::custom("ids can only be deserialized as part of a graph"))
+    })
+}
+
+/// (De)serialize a `DefId` as the stable path of its definition, e.g. `app::{impl#0}::read`.
+pub(crate) mod def_id {
+    use rustc_hir::def_id::DefId;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(id: &DefId, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&super::path_of(*id)?)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<DefId, D::Error> {
+        super::id_of(String::deserialize(deserializer)?)
+    }
+}
+
+/// (De)serialize an optional `DefId`, as with `def_id`.
+pub(crate) mod option_def_id {
+    use rustc_hir::def_id::DefId;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(id: &Option<DefId>, serializer: S) -> Result<S::Ok, S::Error> {
+        id.map(super::path_of::<S::Error>)
+            .transpose()?
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<DefId>, D::Error> {
+        Option::<String>::deserialize(deserializer)?
+            .map(super::id_of)
+            .transpose()
+    }
+}
+
+/// (De)serialize a `HirId` as the stable path of its owner followed by its index within it, e.g. `app::main@12`.
+pub(crate) mod hir_id {
+    use rustc_hir::HirId;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(id: &HirId, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&super::hir_id_path(*id)?)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<HirId, D::Error> {
+        super::hir_id_of(String::deserialize(deserializer)?)
+    }
+}
+
+/// (De)serialize an optional `HirId`, as with `hir_id`.
+pub(crate) mod option_hir_id {
+    use rustc_hir::HirId;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(id: &Option<HirId>, serializer: S) -> Result<S::Ok, S::Error> {
+        id.map(super::hir_id_path::<S::Error>)
+            .transpose()?
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<HirId>, D::Error> {
+        Option::<String>::deserialize(deserializer)?
+            .map(super::hir_id_of)
+            .transpose()
+    }
+}
+
+/// (De)serialize the id of a call: that of a call expression as with `hir_id`, and a call in the MIR of a followed dependency
+/// as the stable path of its function followed by the block ending in the call, e.g. `dep::read@bb3`.
+pub(crate) mod call_id {
+    use crate::graph::CallId;
+    use rustc_middle::mir::BasicBlock;
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(id: &CallId, serializer: S) -> Result<S::Ok, S::Error> {
+        let path = match *id {
+            CallId::Hir(hir_id) => super::hir_id_path::<S::Error>(hir_id)?,
+            CallId::Mir(def_id, block) => {
+                let function = super::path_of::<S::Error>(def_id)?;
+                format!("{function}@bb{}", block.as_u32())
+            }
+        };
+        serializer.serialize_str(&path)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<CallId, D::Error> {
+        let path = String::deserialize(deserializer)?;
+        match path.rsplit_once("@bb") {
+            Some((function, block)) => {
+                let block: u32 = block.parse().map_err(|_e| {
+                    <D::Error as de::Error>::custom(format!("{path} does not end in a block"))
+                })?;
+                let function = super::id_of::<D::Error>(function.to_owned())?;
+                Ok(CallId::Mir(function, BasicBlock::from_u32(block)))
+            }
+            None => super::hir_id_of(path).map(CallId::Hir),
+        }
+    }
+}
+
+/// Get the stable representation of a `HirId` of the graph being serialized, e.g. `app::main@12`.
+fn hir_id_path<E: ser::Error>(id: HirId) -> Result<String, E> {
+    let owner = path_of::<E>(id.owner.to_def_id())?;
+    Ok(format!("{owner}@{}", id.local_id.as_u32()))
+}
+
+/// Get the `HirId` of its stable representation in the graph being deserialized.
+fn hir_id_of<E: de::Error>(path: String) -> Result<HirId, E> {
+    let Some((owner, local_id)) = path.rsplit_once('@') else {
+        return Err(E::custom(format!("{path} is not of the form owner@index")));
+    };
+    let local_id: u32 = local_id
+        .parse()
+        .map_err(|_e| E::custom(format!("{path} does not end in an index")))?;
+    let owner = id_of::<E>(owner.to_owned())?;
+
+    Ok(HirId {
+        owner: OwnerId {
+            def_id: owner.expect_local(),
+        },
+        local_id: ItemLocalId::from_u32(local_id),
+    })
+}
@@ -0,0 +1,262 @@
+use crate::graph::ChainEnd;
+use crate::stats::ChainSummary;
+
+/// The weights of the parts of the severity score of a chain, as configured in the `[package.metadata.error-analyzer.weights]` table of the manifest.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScoreWeights {
+    /// The weight per chained function call of the longest error path.
+    pub depth: f64,
+    /// The weight of a chain whose error is unwrapped.
+    pub unwrapped: f64,
+    /// The weight of a chain whose error is discarded.
+    pub discarded: f64,
+    /// The weight added to a discarded chain whose error is discarded at a call to a `#[must_use]` function.
+    pub must_use: f64,
+    /// The weight of a chain whose error is not dealt with in the analyzed crate, i.e. returned from main, leaving the crate or ending in an unknown way.
+    pub unhandled: f64,
+    /// The weight of a chain whose error is handled.
+    pub handled: f64,
+    /// The weight of a chain whose error is only logged, without acting on it.
+    pub logged: f64,
+    /// The weight of a chain whose error ends the process.
+    pub exited: f64,
+    /// The weight per call on the path of the chain into another module.
+    pub module_crossing: f64,
+    /// The weight of a chain whose error is boxed into a `dyn Error` along the way, hiding its concrete type.
+    pub type_erased: f64,
+}
+
+/// The names of the weights, as written in the manifest.
+pub const WEIGHT_NAMES: [&str; 10] = [
+    "depth",
+    "unwrapped",
+    "discarded",
+    "must_use",
+    "unhandled",
+    "handled",
+    "logged",
+    "exited",
+    "module_crossing",
+    "type_erased",
+];
+
+impl Default for ScoreWeights {
+    fn default() -> Self {
+        ScoreWeights {
+            depth: 1.0,
+            unwrapped: 10.0,
+            discarded: 8.0,
+            must_use: 4.0,
+            unhandled: 3.0,
+            handled: 0.0,
+            logged: 5.0,
+            exited: 4.0,
+            module_crossing: 1.0,
+            type_erased: 3.0,
+        }
+    }
+}
+
+impl ScoreWeights {
+    /// Set the weight with the given name, one of the `WEIGHT_NAMES`.
+    /// Returns an error if the weight does not exist.
+    pub fn set(&mut self, name: &str, weight: f64) -> Result<(), String> {
+        let field = match name {
+            "depth" => &mut self.depth,
+            "unwrapped" => &mut self.unwrapped,
+            "discarded" => &mut self.discarded,
+            "must_use" => &mut self.must_use,
+            "unhandled" => &mut self.unhandled,
+            "handled" => &mut self.handled,
+            "logged" => &mut self.logged,
+            "exited" => &mut self.exited,
+            "module_crossing" => &mut self.module_crossing,
+            "type_erased" => &mut self.type_erased,
+            _ => {
+                return Err(format!(
+                    "Unknown weight {name}, use one of: {}",
+                    WEIGHT_NAMES.join(", ")
+                ))
+            }
+        };
+        *field = weight;
+        Ok(())
+    }
+
+    /// Get the weight of the way a chain ends.
+    fn termination(&self, termination: ChainEnd) -> f64 {
+        match termination {
+            ChainEnd::Unwrapped => self.unwrapped,
+            ChainEnd::Discarded => self.discarded,
+            ChainEnd::Handled => self.handled,
+            ChainEnd::LoggedAndDropped => self.logged,
+            ChainEnd::ProcessExit => self.exited,
+            ChainEnd::ReturnedFromMain | ChainEnd::LeavesCrate | ChainEnd::Unknown => {
+                self.unhandled
+            }
+        }
+    }
+}
+
+/// Score how severe a chain is, where a deep chain losing its error after crossing several modules and erasing its type scores highest.
+/// The score is the weighted sum of its depth, the way it ends, the number of module boundaries its path crosses,
+/// whether its error type is erased and whether it is discarded at a call to a `#[must_use]` function.
+pub fn score(summary: &ChainSummary, weights: &ScoreWeights) -> f64 {
+    let mut score = summary.depth as f64 * weights.depth + weights.termination(summary.termination);
+    score += get_module_crossings(summary) as f64 * weights.module_crossing;
+    if summary.type_erased {
+        score += weights.type_erased;
+    }
+    if summary.must_use {
+        score += weights.must_use;
+    }
+    score
+}
+
+/// Count the calls on the path of a chain to a function in another module than the caller, e.g. from `cli::run` to `config::load`.
+fn get_module_crossings(summary: &ChainSummary) -> usize {
+    let mut crossings = 0;
+    let mut module = get_module(&summary.head);
+    for hop in &summary.hops {
+        let next = get_module(&hop.function);
+        if next != module {
+            crossings += 1;
+        }
+        module = next;
+    }
+    crossings
+}
+
+/// Get the module of a function from its path, e.g. `config` for `config::load`, or the crate root for `main`.
+fn get_module(path: &str) -> &str {
+    // Leave out generic arguments, these can contain paths of their own
+    let path = path.split('<').next().unwrap_or(path);
+    path.rsplit_once("::").map_or("", |(module, _name)| module)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stats::ChainHop;
+
+    /// Create the summary of a chain from `head` through the called `hops`, without a location or type.
+    fn summary(head: &str, hops: &[&str], termination: ChainEnd) -> ChainSummary {
+        ChainSummary {
+            index: 0,
+            head: head.to_string(),
+            tail: hops.last().unwrap_or(&head).to_string(),
+            functions: std::iter::once(head)
+                .chain(hops.iter().copied())
+                .map(String::from)
+                .collect(),
+            depth: hops.len(),
+            size: hops.len(),
+            termination,
+            location: None,
+            hops: hops
+                .iter()
+                .map(|function| ChainHop {
+                    function: function.to_string(),
+                    ty: None,
+                    location: None,
+                })
+                .collect(),
+            end_location: None,
+            lost_in_task: false,
+            suppressed: false,
+            type_erased: false,
+            downcasts: Vec::new(),
+            must_use: false,
+            score: 0.0,
+        }
+    }
+
+    #[test]
+    fn score_is_the_weighted_sum_for_each_termination() {
+        let weights = ScoreWeights::default();
+        let expected = [
+            (ChainEnd::ReturnedFromMain, weights.unhandled),
+            (ChainEnd::Unwrapped, weights.unwrapped),
+            (ChainEnd::Handled, weights.handled),
+            (ChainEnd::LoggedAndDropped, weights.logged),
+            (ChainEnd::ProcessExit, weights.exited),
+            (ChainEnd::Discarded, weights.discarded),
+            (ChainEnd::LeavesCrate, weights.unhandled),
+            (ChainEnd::Unknown, weights.unhandled),
+        ];
+        for (termination, weight) in expected {
+            let chain = summary("main", &["parse", "read"], termination);
+            assert_eq!(
+                score(&chain, &weights),
+                2.0 * weights.depth + weight,
+                "{termination:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn score_adds_type_erasure_and_must_use() {
+        let weights = ScoreWeights::default();
+        let mut chain = summary("main", &["parse"], ChainEnd::Discarded);
+        chain.type_erased = true;
+        chain.must_use = true;
+        assert_eq!(
+            score(&chain, &weights),
+            weights.depth + weights.discarded + weights.type_erased + weights.must_use
+        );
+    }
+
+    #[test]
+    fn score_counts_module_crossings() {
+        let weights = ScoreWeights {
+            module_crossing: 10.0,
+            ..ScoreWeights::default()
+        };
+        let chain = summary(
+            "cli::run",
+            &["config::load", "config::parse", "io::read"],
+            ChainEnd::Handled,
+        );
+        assert_eq!(get_module_crossings(&chain), 2);
+        assert_eq!(score(&chain, &weights), 3.0 * weights.depth + 20.0);
+    }
+
+    #[test]
+    fn module_crossings_ignore_paths_in_generic_arguments() {
+        let chain = summary(
+            "a::run",
+            &["a::f<b::T>", "a::g<c::U, d::V>"],
+            ChainEnd::Handled,
+        );
+        assert_eq!(get_module_crossings(&chain), 0);
+        let chain = summary("main", &["a::f<b::T>", "b::g"], ChainEnd::Handled);
+        assert_eq!(get_module_crossings(&chain), 2);
+    }
+
+    #[test]
+    fn module_of_a_root_function_is_the_crate_root() {
+        assert_eq!(get_module("main"), "");
+        assert_eq!(get_module("a::b::f"), "a::b");
+        assert_eq!(get_module("f<a::T>"), "");
+    }
+
+    #[test]
+    fn set_changes_a_named_weight() {
+        let mut weights = ScoreWeights::default();
+        for name in WEIGHT_NAMES {
+            weights.set(name, 42.0).unwrap();
+        }
+        assert_eq!(weights.depth, 42.0);
+        assert_eq!(weights.type_erased, 42.0);
+        assert_eq!(weights.termination(ChainEnd::Unknown), 42.0);
+    }
+
+    #[test]
+    fn set_rejects_an_unknown_weight() {
+        let mut weights = ScoreWeights::default();
+        let error = weights.set("severity", 1.0).unwrap_err();
+        assert!(error.starts_with("Unknown weight severity"), "{error}");
+        assert!(error.contains("module_crossing"), "{error}");
+        assert_eq!(weights, ScoreWeights::default());
+    }
+}
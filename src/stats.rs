@@ -0,0 +1,381 @@
+use crate::graph::{ChainEnd, ErrorCategory};
+use crate::summary::Summary;
+use crate::timings::Timings;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// The statistics of the error propagation chains of a program, as printed in the summary.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ChainStats {
+    /// The number of chains, where chains sharing their propagation path with another chain are counted as part of it.
+    pub count: usize,
+    /// The number of chains before the chains formed by the same calls were deduplicated.
+    pub raw_count: usize,
+    /// The number of chains before the chains not matching the error filters were left out, if any filters are provided.
+    pub unfiltered_count: Option<usize>,
+    /// The number of chains matching the error filters.
+    pub filtered_count: usize,
+    /// The number of chains sharing their propagation path with another chain.
+    pub merged_count: usize,
+    /// The number of chains that were not followed past the maximum depth.
+    pub truncated_count: usize,
+    /// The maximum number of chained calls followed.
+    pub depth_limit: usize,
+    /// The number of chains left out, as the maximum number of chains was reached.
+    pub left_out_count: usize,
+    /// The maximum number of chains kept.
+    pub chain_limit: usize,
+    /// The number of chains propagating `None`.
+    pub option_count: usize,
+    /// The number of calls of the largest chain.
+    pub max_size: usize,
+    /// The number of chained calls of the longest error path.
+    pub max_depth: usize,
+    /// The number of calls of all chains together, including the chains sharing their propagation path with another chain.
+    pub total_size: usize,
+    /// The number of chains per number of chained calls of their longest error path, starting at one.
+    pub depth_histogram: Vec<usize>,
+    /// The number of chains per number of calls, starting at one.
+    pub size_histogram: Vec<usize>,
+    /// The chains with the longest error paths, deepest first, leaving out the suppressed chains.
+    pub deepest_chains: Vec<ChainSummary>,
+    /// The chains with the most calls, largest first, leaving out the suppressed chains.
+    pub largest_chains: Vec<ChainSummary>,
+    /// The chains with the highest severity score, most severe first, leaving out the suppressed chains.
+    pub most_severe_chains: Vec<ChainSummary>,
+    /// The number of calls in the chains returning a type-erased error (`Box<dyn Error>`).
+    pub type_erased_count: usize,
+    /// The number of calls in the chains returning an anyhow or eyre error.
+    pub error_family_count: usize,
+    /// The number of chains per way they end, leaving out the suppressed chains.
+    pub endings: Vec<(ChainEnd, usize)>,
+    /// The number of chains whose way of losing their error is accepted, i.e. configured to be ignored or allowed by a comment.
+    pub suppressed_count: usize,
+    /// The return type of the entry point, if any.
+    pub entry_return_ty: Option<String>,
+    /// Whether the entry point returns a `Result`, so errors can reach the program exit.
+    pub entry_returns_result: bool,
+    /// The chains whose error is returned from the entry point.
+    pub exit_chains: Vec<ChainSummary>,
+    /// The number of recursive error propagation cycles.
+    pub recursive_count: usize,
+    /// The number of errors dropped by converting them using `.ok()`.
+    pub ok_discard_count: usize,
+    /// The number of errors lost in spawned tasks whose handle is dropped, leaving out where this is accepted.
+    pub lost_to_tasks_count: usize,
+    /// The number of errors discarded at calls to `#[must_use]` functions, leaving out where this is accepted.
+    pub must_use_discard_count: usize,
+    /// The number of calls expanded from external macros that were left out.
+    pub filtered_macro_calls: usize,
+    /// The number of functions of dependencies aggregated into a node per dependency, as their calls carry no errors.
+    pub aggregated_fns: usize,
+    /// The functions left out of the call graph by the filters (e.g. `--exclude-path`).
+    pub filtered_fns: Vec<String>,
+    /// The locations where errors are discarded, leaving out where this is accepted.
+    pub discarded_calls: Vec<String>,
+    /// The number of fallible calls per kind of error type they return, e.g. strings.
+    pub error_categories: Vec<(ErrorCategory, usize)>,
+    /// The public functions returning an error callers cannot match on (a string, unit or trait object), with that error type.
+    pub opaque_error_fns: Vec<(String, String)>,
+    /// The functions declared to return a `Result` that can never return an error.
+    pub infallible_fns: Vec<String>,
+    /// The number of functions not reachable from the entry point, if all functions are analyzed.
+    pub unreachable_fns: Option<usize>,
+    /// The functions where most errors originate, and the number of chains originating in each.
+    pub origins: Vec<(String, usize)>,
+    /// How each local function making fallible calls deals with their errors, most unhandled errors first.
+    pub functions: Vec<FunctionSummary>,
+    /// The functions where chains end that convert their error between types, and those types in order.
+    pub conversions: Vec<(String, Vec<String>)>,
+    /// The error type changes without a conversion, as the called function, its error type, the caller and its error type.
+    pub unexplained: Vec<(String, String, String, String)>,
+    /// The statistics of the panics, if the chains are those of panics rather than errors (`--graph panic`).
+    pub panics: Option<PanicStats>,
+}
+
+/// The statistics of the panics of a program, as printed instead of the summary if the chains are those of panics.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PanicStats {
+    /// The number of functions that panic themselves.
+    pub count: usize,
+    /// The number of chained calls of the longest panic path.
+    pub max_depth: usize,
+    /// The number of functions that can panic, themselves or through the functions they call.
+    pub panicking_count: usize,
+    /// The number of calls catching the panics of the called function.
+    pub caught_count: usize,
+}
+
+/// The endpoints of a chain and its measures, used to report the most notable chains.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChainSummary {
+    /// The number of the chain.
+    pub index: usize,
+    /// The path of the function where the chain ends.
+    pub head: String,
+    /// The path of the function where the errors of the chain originate.
+    pub tail: String,
+    /// The paths of the functions of the chain, from the head in the order they are called.
+    pub functions: Vec<String>,
+    /// The number of chained function calls of the longest error path.
+    pub depth: usize,
+    /// The number of calls of the chain.
+    pub size: usize,
+    /// How the chain ends.
+    pub termination: ChainEnd,
+    /// The location of the call returning the error to the tail, if known.
+    pub location: Option<String>,
+    /// The calls of the path from the head to the tail, in the order they are called.
+    pub hops: Vec<ChainHop>,
+    /// The location of the call where the chain ends, if known.
+    pub end_location: Option<String>,
+    /// Whether the error is lost in a spawned task whose handle is dropped.
+    pub lost_in_task: bool,
+    /// Whether losing the error of the chain is accepted.
+    pub suppressed: bool,
+    /// Whether the error is boxed into a `dyn Error` on the path of the chain, hiding its concrete type.
+    pub type_erased: bool,
+    /// The concrete types the type-erased error is downcast to by the functions of the chain, e.g. `SqlxError`.
+    pub downcasts: Vec<String>,
+    /// Whether the error is discarded at a call to a `#[must_use]` function, whose result is meant to be looked at beyond its error.
+    pub must_use: bool,
+    /// How severe the chain is, higher is worse.
+    pub score: f64,
+}
+
+/// A call on the path of a chain.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChainHop {
+    /// The path of the called function.
+    pub function: String,
+    /// The error type returned by the call, if known.
+    pub ty: Option<String>,
+    /// The location of the call, if known.
+    pub location: Option<String>,
+}
+
+/// How a function deals with the errors of the fallible calls it makes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FunctionSummary {
+    /// The path of the function.
+    pub function: String,
+    /// The number of calls the function makes that can fail.
+    pub fallible_calls: usize,
+    /// The number of calls whose error is propagated.
+    pub propagated: usize,
+    /// The number of calls whose error is dealt with.
+    pub handled: usize,
+    /// The number of calls whose error is only logged.
+    pub logged: usize,
+    /// The number of calls whose error ends the process.
+    pub exited: usize,
+    /// The number of calls whose error causes a panic.
+    pub unwrapped: usize,
+    /// The number of calls whose error is thrown away.
+    pub discarded: usize,
+    /// Whether the function returns a `Result` itself.
+    pub returns_result: bool,
+    /// The number of chained calls of the longest error path below the function.
+    pub deepest_chain: usize,
+}
+
+impl ChainStats {
+    /// The average number of calls of a chain, where chains sharing their propagation path with another chain are counted as part of it.
+    /// Zero if there are no chains.
+    pub fn average_size(&self) -> f64 {
+        if self.count == 0 {
+            return 0.0;
+        }
+        let total: usize = self
+            .size_histogram
+            .iter()
+            .enumerate()
+            .map(|(i, n)| (i + 1) * n)
+            .sum();
+        (total as f64) / (self.count as f64)
+    }
+
+    /// Convert these statistics to their JSON representation, with how long each stage of the run took if given.
+    pub fn to_json(&self, timings: Option<&Timings>) -> String {
+        let summaries = |summaries: &[ChainSummary]| {
+            summaries
+                .iter()
+                .map(ChainSummary::to_json)
+                .collect::<Vec<_>>()
+        };
+        let endings: serde_json::Map<String, serde_json::Value> = self
+            .endings
+            .iter()
+            .map(|(termination, n)| (termination.label().to_owned(), serde_json::json!(n)))
+            .collect();
+        let error_categories: serde_json::Map<String, serde_json::Value> = self
+            .error_categories
+            .iter()
+            .map(|(category, n)| (category.label().to_owned(), serde_json::json!(n)))
+            .collect();
+        let opaque_error_fns: Vec<serde_json::Value> = self
+            .opaque_error_fns
+            .iter()
+            .map(|(function, ty)| serde_json::json!({ "function": function, "type": ty }))
+            .collect();
+        let origins: Vec<serde_json::Value> = self
+            .origins
+            .iter()
+            .map(|(function, n)| serde_json::json!({ "function": function, "chains": n }))
+            .collect();
+        let functions: Vec<serde_json::Value> = self
+            .functions
+            .iter()
+            .map(FunctionSummary::to_json)
+            .collect();
+
+        let mut stats = serde_json::json!({
+            "chains": self.count,
+            "chains_before_deduplication": self.raw_count,
+            "chains_before_filtering": self.unfiltered_count,
+            "merged_chains": self.merged_count,
+            "truncated_chains": self.truncated_count,
+            "left_out_chains": self.left_out_count,
+            "option_chains": self.option_count,
+            "max_size": self.max_size,
+            "max_depth": self.max_depth,
+            "average_size": self.average_size(),
+            "depth_histogram": self.depth_histogram,
+            "size_histogram": self.size_histogram,
+            "deepest_chains": summaries(&self.deepest_chains),
+            "largest_chains": summaries(&self.largest_chains),
+            "most_severe_chains": summaries(&self.most_severe_chains),
+            "type_erased_calls": self.type_erased_count,
+            "error_family_calls": self.error_family_count,
+            "endings": endings,
+            "suppressed_chains": self.suppressed_count,
+            "entry_return_type": self.entry_return_ty,
+            "exit_chains": summaries(&self.exit_chains),
+            "recursive_cycles": self.recursive_count,
+            "ok_discarded_errors": self.ok_discard_count,
+            "errors_lost_to_tasks": self.lost_to_tasks_count,
+            "discarded_must_use_errors": self.must_use_discard_count,
+            "filtered_macro_calls": self.filtered_macro_calls,
+            "aggregated_functions": self.aggregated_fns,
+            "filtered_functions": self.filtered_fns,
+            "discarded_calls": self.discarded_calls,
+            "error_categories": error_categories,
+            "opaque_error_functions": opaque_error_fns,
+            "infallible_functions": self.infallible_fns,
+            "unreachable_functions": self.unreachable_fns,
+            "origins": origins,
+            "functions": functions,
+        });
+        if let Some(timings) = timings {
+            stats["timings"] = timings.to_json();
+        }
+
+        serde_json::to_string_pretty(&stats).unwrap()
+    }
+}
+
+/// Formats the statistics as the plain text summary, see `Summary` for the summary printed to the terminal.
+impl fmt::Display for ChainStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        Summary::plain(self).fmt(f)
+    }
+}
+
+impl ChainSummary {
+    /// Convert this summary to its JSON representation.
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "index": self.index,
+            "head": self.head,
+            "tail": self.tail,
+            "functions": self.functions,
+            "depth": self.depth,
+            "size": self.size,
+            "termination": self.termination.label(),
+            "location": self.location,
+            "end_location": self.end_location,
+            "lost_in_task": self.lost_in_task,
+            "suppressed": self.suppressed,
+            "type_erased": self.type_erased,
+            "downcasts": self.downcasts,
+            "must_use": self.must_use,
+            "score": self.score,
+            "hops": self.hops.iter().map(|hop| serde_json::json!({
+                "function": hop.function,
+                "type": hop.ty,
+                "location": hop.location,
+            })).collect::<Vec<_>>(),
+        })
+    }
+}
+
+/// Formats the summary for the terminal, e.g. `#2 main ← parse (depth 3, 4 calls): handled, originates at src/main.rs:4`.
+impl fmt::Display for ChainSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "#{} {} ← {} (depth {}, {} calls): {}",
+            self.index,
+            self.head,
+            self.tail,
+            self.depth,
+            self.size,
+            self.termination.label()
+        )?;
+        if !self.downcasts.is_empty() {
+            write!(f, ", downcasts to {}", self.downcasts.join(", "))?;
+        }
+        if let Some(location) = &self.location {
+            write!(f, ", originates at {location}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for PanicStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "There are {} functions that panic themselves in this program.",
+            self.count
+        )?;
+        writeln!(
+            f,
+            "The longest panic path consists of {} chained function calls.",
+            self.max_depth
+        )?;
+        writeln!(
+            f,
+            "There are {} functions that can panic in this program.",
+            self.panicking_count
+        )?;
+        writeln!(
+            f,
+            "{} calls catch the panics of the called function.",
+            self.caught_count
+        )
+    }
+}
+
+impl FunctionSummary {
+    /// The number of calls whose error is not dealt with, i.e. is unwrapped or discarded.
+    pub fn unhandled(&self) -> usize {
+        self.unwrapped + self.discarded
+    }
+
+    /// Convert this summary to its JSON representation.
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "function": self.function,
+            "fallible_calls": self.fallible_calls,
+            "propagated": self.propagated,
+            "handled": self.handled,
+            "logged": self.logged,
+            "exited": self.exited,
+            "unwrapped": self.unwrapped,
+            "discarded": self.discarded,
+            "returns_result": self.returns_result,
+            "deepest_chain": self.deepest_chain,
+        })
+    }
+}
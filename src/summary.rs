@@ -0,0 +1,607 @@
+use crate::graph::{CallGraph, ChainEnd};
+use crate::stats::{ChainStats, FunctionSummary};
+use std::collections::HashMap;
+use std::fmt;
+use std::io::IsTerminal;
+
+/// The widest bar of a histogram, in characters.
+const HISTOGRAM_WIDTH: usize = 40;
+/// The width of the terminal the summary is printed to, if `COLUMNS` does not tell.
+const DEFAULT_WIDTH: usize = 120;
+/// The narrowest the function column of the table is truncated to, however narrow the terminal.
+const MIN_FUNCTION_WIDTH: usize = 24;
+
+/// Whether to color the summary printed to the terminal (`--color`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// Color the summary if stdout is a terminal and `NO_COLOR` is not set.
+    #[default]
+    Auto,
+    /// Always color the summary, even if `NO_COLOR` is set.
+    Always,
+    /// Never color the summary.
+    Never,
+}
+
+impl ColorChoice {
+    /// Get the choice from its label, i.e. `auto`, `always` or `never`.
+    pub fn from_label(label: &str) -> Option<Self> {
+        match label {
+            "auto" => Some(ColorChoice::Auto),
+            "always" => Some(ColorChoice::Always),
+            "never" => Some(ColorChoice::Never),
+            _ => None,
+        }
+    }
+
+    /// Whether the summary is colored, following <https://no-color.org> if not chosen explicitly.
+    pub fn colors(self) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                std::env::var_os("NO_COLOR").map_or(true, |value| value.is_empty())
+                    && std::io::stdout().is_terminal()
+            }
+        }
+    }
+}
+
+/// The way a part of the summary is emphasized when it is colored.
+#[derive(Debug, Clone, Copy)]
+enum Style {
+    /// The heading of a list or table, in bold.
+    Heading,
+    /// A count of errors that are lost or cause a panic, in red.
+    Bad,
+    /// A count of errors that are dealt with, in green.
+    Good,
+}
+
+impl Style {
+    /// Get the ANSI escape code of the style.
+    fn code(self) -> &'static str {
+        match self {
+            Style::Heading => "1",
+            Style::Bad => "31",
+            Style::Good => "32",
+        }
+    }
+
+    /// Get the style of a count of chains ending in the given way, if it is emphasized.
+    fn of_ending(termination: ChainEnd) -> Option<Self> {
+        match termination {
+            ChainEnd::Handled => Some(Style::Good),
+            ChainEnd::Unwrapped | ChainEnd::Discarded => Some(Style::Bad),
+            ChainEnd::ReturnedFromMain
+            | ChainEnd::LoggedAndDropped
+            | ChainEnd::ProcessExit
+            | ChainEnd::LeavesCrate
+            | ChainEnd::Unknown => None,
+        }
+    }
+}
+
+/// The summary of the chain statistics printed to the terminal after the analysis.
+/// Without colors or a width it is plain text, as also written by the `Display` implementation of `ChainStats`.
+pub struct Summary<'a> {
+    /// The statistics to summarize.
+    pub stats: &'a ChainStats,
+    /// Whether to color the counts of lost (red) and handled (green) errors, and the headings.
+    pub colors: bool,
+    /// The width of the terminal the table of the functions is fit in, by truncating their paths, if any.
+    pub width: Option<usize>,
+}
+
+impl<'a> Summary<'a> {
+    /// Create the summary of the statistics to print to stdout, colored by the given choice.
+    /// If stdout is a terminal, the table of the functions is fit in its width (`COLUMNS`, or 120 characters).
+    pub fn new(stats: &'a ChainStats, color: ColorChoice) -> Self {
+        let width = std::io::stdout().is_terminal().then(|| {
+            std::env::var("COLUMNS")
+                .ok()
+                .and_then(|columns| columns.parse().ok())
+                .unwrap_or(DEFAULT_WIDTH)
+        });
+        Summary {
+            stats,
+            colors: color.colors(),
+            width,
+        }
+    }
+
+    /// Create the plain text summary of the statistics.
+    pub fn plain(stats: &'a ChainStats) -> Self {
+        Summary {
+            stats,
+            colors: false,
+            width: None,
+        }
+    }
+
+    /// Emphasize a part of the summary in the given style, if it is colored.
+    fn paint(&self, text: impl fmt::Display, style: Style) -> String {
+        if self.colors {
+            format!("\x1b[{}m{text}\x1b[0m", style.code())
+        } else {
+            text.to_string()
+        }
+    }
+
+    /// Emphasize a count in the given style if it is not zero, so the counts of problems that do not occur do not stand out.
+    fn paint_count(&self, n: usize, style: Style) -> String {
+        if n > 0 {
+            self.paint(n, style)
+        } else {
+            n.to_string()
+        }
+    }
+
+    /// Write the heading of a list or table.
+    fn write_heading(&self, f: &mut fmt::Formatter, heading: &str) -> fmt::Result {
+        writeln!(f, "{}", self.paint(heading, Style::Heading))
+    }
+
+    /// Write the summaries of the functions as a table, with a row per function and the columns aligned.
+    /// The unwrapped and discarded errors are red and the handled ones green, and the paths of the functions are truncated to fit the width.
+    fn write_function_table(
+        &self,
+        f: &mut fmt::Formatter,
+        functions: &[FunctionSummary],
+    ) -> fmt::Result {
+        let header = [
+            "function",
+            "fallible",
+            "propagated",
+            "handled",
+            "logged",
+            "unwrapped",
+            "discarded",
+            "returns Result",
+            "deepest chain",
+        ];
+        let styles = [
+            None,
+            None,
+            None,
+            Some(Style::Good),
+            None,
+            Some(Style::Bad),
+            Some(Style::Bad),
+            None,
+            None,
+        ];
+        let mut rows: Vec<[String; 9]> = functions
+            .iter()
+            .map(|summary| {
+                [
+                    summary.function.clone(),
+                    summary.fallible_calls.to_string(),
+                    summary.propagated.to_string(),
+                    summary.handled.to_string(),
+                    summary.logged.to_string(),
+                    summary.unwrapped.to_string(),
+                    summary.discarded.to_string(),
+                    String::from(if summary.returns_result { "yes" } else { "no" }),
+                    summary.deepest_chain.to_string(),
+                ]
+            })
+            .collect();
+
+        // Each column is as wide as its widest cell
+        let column_width = |rows: &[[String; 9]], i: usize| {
+            rows.iter()
+                .map(|row| row[i].chars().count())
+                .chain([header[i].len()])
+                .max()
+                .unwrap_or(0)
+        };
+        let mut widths: Vec<usize> = (0..header.len()).map(|i| column_width(&rows, i)).collect();
+
+        // The functions are truncated to the space the other columns leave, indented and separated by ` | `
+        if let Some(width) = self.width {
+            let others: usize = widths[1..].iter().map(|width| width + 3).sum();
+            let function_width = width.saturating_sub(2 + others).max(MIN_FUNCTION_WIDTH);
+            if widths[0] > function_width {
+                for row in &mut rows {
+                    row[0] = truncate_path(&row[0], function_width);
+                }
+                widths[0] = column_width(&rows, 0);
+            }
+        }
+
+        let format_row = |cells: Vec<&str>, painted: bool| {
+            let cells: Vec<String> = cells
+                .into_iter()
+                .zip(&widths)
+                .zip(styles)
+                .map(|((cell, width), style)| {
+                    let padding = " ".repeat(width.saturating_sub(cell.chars().count()));
+                    match style {
+                        Some(style) if painted && cell != "0" => {
+                            format!("{}{padding}", self.paint(cell, style))
+                        }
+                        _ => format!("{cell}{padding}"),
+                    }
+                })
+                .collect();
+            format!("  {}", cells.join(" | ").trim_end())
+        };
+
+        writeln!(
+            f,
+            "{}",
+            self.paint(format_row(header.to_vec(), false), Style::Heading)
+        )?;
+        for row in &rows {
+            writeln!(
+                f,
+                "{}",
+                format_row(row.iter().map(String::as_str).collect(), true)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// The scale of the heatmap coloring the call graph (`--heatmap`): the function the most chains pass through, which is shown in full red.
+pub struct Heatmap<'a> {
+    /// The call graph whose nodes have their chain counts attached.
+    pub graph: &'a CallGraph,
+}
+
+impl fmt::Display for Heatmap<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Ties are broken by the order of the nodes, i.e. the functions closest to the entry point first
+        let hottest = self
+            .graph
+            .nodes
+            .iter()
+            .rev()
+            .max_by_key(|node| node.chain_count)
+            .filter(|node| node.chain_count > 0);
+        match hottest {
+            Some(node) => writeln!(
+                f,
+                "Heatmap: {} chains pass through {}, the most of all functions, which is shown in full red.",
+                node.chain_count,
+                self.graph.strings.resolve(node.label)
+            ),
+            None => writeln!(f, "Heatmap: no chains pass through any function."),
+        }
+    }
+}
+
+/// The choke points of the call graph (`--choke-points`): the functions all errors of an origin pass through,
+/// ranked by the number of origins, followed by those of each origin.
+pub struct ChokePoints<'a> {
+    /// The call graph whose choke points were found.
+    pub graph: &'a CallGraph,
+}
+
+impl fmt::Display for ChokePoints<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for (_origin, dominators) in &self.graph.choke_points {
+            for function in dominators {
+                *counts.entry(function).or_default() += 1;
+            }
+        }
+        let mut ranked: Vec<(&str, usize)> = counts.into_iter().collect();
+        ranked.sort_unstable_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then(a.cmp(b)));
+
+        if ranked.is_empty() {
+            writeln!(
+                f,
+                "No choke points: the errors of each origin are dealt with by different functions."
+            )?;
+        } else {
+            writeln!(
+                f,
+                "Choke points, the functions all errors of an origin pass through:"
+            )?;
+            for (function, count) in ranked {
+                writeln!(f, "  {function}: {count} origins")?;
+            }
+        }
+        writeln!(f, "Per origin, the functions all of its errors pass through, up to where they are dealt with:")?;
+        for (origin, dominators) in &self.graph.choke_points {
+            if dominators.is_empty() {
+                writeln!(f, "  {origin}: none")?;
+            } else {
+                writeln!(f, "  {origin} → {}", dominators.join(" → "))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Truncate the path of a function to at most the given number of characters, keeping its tail, which tells the functions apart.
+/// Leading segments are replaced by `…`, e.g. `app::db::pool::Connection::fetch` becomes `…::Connection::fetch`,
+/// and if the last segment alone is too long, it is cut off at its start.
+pub fn truncate_path(path: &str, width: usize) -> String {
+    if path.chars().count() <= width {
+        return path.to_owned();
+    }
+
+    let mut tail = String::new();
+    for segment in path.rsplit("::") {
+        let longer = if tail.is_empty() {
+            segment.to_owned()
+        } else {
+            format!("{segment}::{tail}")
+        };
+        // The tail is preceded by `…::`
+        if longer.chars().count() + 3 > width {
+            break;
+        }
+        tail = longer;
+    }
+    if !tail.is_empty() {
+        return format!("…::{tail}");
+    }
+
+    let skipped = path.chars().count() + 1 - width;
+    format!("…{}", path.chars().skip(skipped).collect::<String>())
+}
+
+/// Write a histogram as a bar chart, with a row per value that occurs, e.g. `  3 | ### 3`.
+fn write_histogram(f: &mut fmt::Formatter, histogram: &[usize]) -> fmt::Result {
+    let max = histogram.iter().copied().max().unwrap_or(0).max(1);
+    let label_width = histogram.len().to_string().len();
+    for (i, n) in histogram.iter().enumerate() {
+        if *n == 0 {
+            continue;
+        }
+        // Values with chains always get a bar, however small
+        let width = (n * HISTOGRAM_WIDTH).div_ceil(max);
+        writeln!(f, "  {:>label_width$} | {} {n}", i + 1, "#".repeat(width))?;
+    }
+    Ok(())
+}
+
+impl fmt::Display for Summary<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let stats = self.stats;
+        writeln!(
+            f,
+            "There are {} error propagation chains in this program ({} before deduplication).",
+            self.paint(stats.count, Style::Heading),
+            stats.raw_count
+        )?;
+        if let Some(unfiltered_count) = stats.unfiltered_count {
+            writeln!(
+                f,
+                "{} of the {unfiltered_count} chains match the error filter.",
+                stats.filtered_count
+            )?;
+        }
+        if stats.merged_count > 0 {
+            writeln!(f, "{} chains share their propagation path with another chain, and are shown as part of it.", stats.merged_count)?;
+        }
+        if stats.truncated_count > 0 {
+            writeln!(f, "{} chains were truncated at the maximum depth of {} calls, their truncated functions are marked.", stats.truncated_count, stats.depth_limit)?;
+        }
+        if stats.left_out_count > 0 {
+            writeln!(
+                f,
+                "{} chains were left out, as only the {} deepest chains are kept.",
+                stats.left_out_count, stats.chain_limit
+            )?;
+        }
+        writeln!(f, "{} of these chains propagate None.", stats.option_count)?;
+        writeln!(
+            f,
+            "The biggest chain consists of {} function calls.",
+            stats.max_size
+        )?;
+        writeln!(
+            f,
+            "The longest error path consists of {} chained function calls.",
+            stats.max_depth
+        )?;
+        writeln!(
+            f,
+            "The average chain consists of {} function calls.",
+            stats.average_size()
+        )?;
+        if stats.count > 0 {
+            self.write_heading(f, "Chains per number of chained function calls:")?;
+            write_histogram(f, &stats.depth_histogram)?;
+            self.write_heading(f, "Chains per number of function calls:")?;
+            write_histogram(f, &stats.size_histogram)?;
+        }
+        if !stats.deepest_chains.is_empty() {
+            self.write_heading(f, "The chains with the longest error paths:")?;
+            for summary in &stats.deepest_chains {
+                writeln!(f, "  {summary}")?;
+            }
+            self.write_heading(f, "The chains with the most function calls:")?;
+            for summary in &stats.largest_chains {
+                writeln!(f, "  {summary}")?;
+            }
+        }
+        if !stats.most_severe_chains.is_empty() {
+            self.write_heading(f, "The most severe chains:")?;
+            for summary in &stats.most_severe_chains {
+                writeln!(
+                    f,
+                    "  severity {}: {summary}",
+                    self.paint(format!("{:.1}", summary.score), Style::Bad)
+                )?;
+            }
+        }
+        writeln!(
+            f,
+            "{} of the {} calls in these chains return a type-erased error (Box<dyn Error>).",
+            stats.type_erased_count, stats.total_size
+        )?;
+        writeln!(
+            f,
+            "{} of the {} calls in these chains return an anyhow or eyre error.",
+            stats.error_family_count, stats.total_size
+        )?;
+        for (termination, n) in &stats.endings {
+            let n = match Style::of_ending(*termination) {
+                Some(style) => self.paint_count(*n, style),
+                None => n.to_string(),
+            };
+            writeln!(
+                f,
+                "{n} chains end in an error that is {}.",
+                termination.label()
+            )?;
+        }
+        if stats.suppressed_count > 0 {
+            writeln!(
+                f,
+                "{} chains end in an error whose loss is accepted, and are suppressed.",
+                stats.suppressed_count
+            )?;
+        }
+        if let Some(entry_ty) = &stats.entry_return_ty {
+            if stats.entry_returns_result {
+                writeln!(
+                    f,
+                    "{} chains reach the program exit, as the entry point returns {entry_ty}:",
+                    stats.exit_chains.len()
+                )?;
+                for summary in &stats.exit_chains {
+                    writeln!(f, "  {summary}")?;
+                }
+            } else {
+                writeln!(
+                    f,
+                    "No chains reach the program exit, as the entry point returns {entry_ty}."
+                )?;
+            }
+        }
+        writeln!(
+            f,
+            "There are {} recursive error propagation cycles in this program.",
+            stats.recursive_count
+        )?;
+        writeln!(
+            f,
+            "{} errors are dropped by converting them to an Option using .ok().",
+            self.paint_count(stats.ok_discard_count, Style::Bad)
+        )?;
+        writeln!(
+            f,
+            "{} errors are lost in spawned tasks whose handle is dropped.",
+            self.paint_count(stats.lost_to_tasks_count, Style::Bad)
+        )?;
+        if stats.must_use_discard_count > 0 {
+            writeln!(
+                f,
+                "{} errors are discarded at calls to #[must_use] functions.",
+                self.paint(stats.must_use_discard_count, Style::Bad)
+            )?;
+        }
+        writeln!(
+            f,
+            "{} calls expanded from external macros were left out.",
+            stats.filtered_macro_calls
+        )?;
+        if stats.aggregated_fns > 0 {
+            writeln!(
+                f,
+                "{} functions of dependencies whose calls carry no errors were aggregated into a node per dependency.",
+                stats.aggregated_fns
+            )?;
+        }
+        if !stats.filtered_fns.is_empty() {
+            writeln!(
+                f,
+                "{} functions were left out by the filters, along with the calls only they make.",
+                stats.filtered_fns.len()
+            )?;
+        }
+        if !stats.discarded_calls.is_empty() {
+            writeln!(
+                f,
+                "{} errors were discarded at:",
+                self.paint(stats.discarded_calls.len(), Style::Bad)
+            )?;
+            for location in &stats.discarded_calls {
+                writeln!(f, "  {location}")?;
+            }
+        }
+        if !stats.error_categories.is_empty() {
+            let categories: Vec<String> = stats
+                .error_categories
+                .iter()
+                .map(|(category, n)| format!("{n} {}", category.label()))
+                .collect();
+            writeln!(
+                f,
+                "The errors of the fallible calls are of these kinds: {}.",
+                categories.join(", ")
+            )?;
+        }
+        if !stats.opaque_error_fns.is_empty() {
+            writeln!(
+                f,
+                "{} public functions return an error their callers cannot match on:",
+                stats.opaque_error_fns.len()
+            )?;
+            for (function, ty) in &stats.opaque_error_fns {
+                writeln!(f, "  {function}: {ty}")?;
+            }
+        }
+        if !stats.infallible_fns.is_empty() {
+            writeln!(
+                f,
+                "{} functions return a Result but can never return an error, and could return their value directly:",
+                stats.infallible_fns.len()
+            )?;
+            for function in &stats.infallible_fns {
+                writeln!(f, "  {function}")?;
+            }
+        }
+        if let Some(unreachable) = stats.unreachable_fns {
+            writeln!(
+                f,
+                "{unreachable} functions were not reachable from the entry point."
+            )?;
+        }
+        if !stats.origins.is_empty() {
+            self.write_heading(f, "The functions where most errors originate:")?;
+            for (function, n) in &stats.origins {
+                writeln!(f, "  {function}: {n} chains")?;
+            }
+        }
+        if !stats.functions.is_empty() {
+            self.write_heading(
+                f,
+                "How the functions making fallible calls deal with their errors:",
+            )?;
+            self.write_function_table(f, &stats.functions)?;
+        }
+        if !stats.conversions.is_empty() {
+            writeln!(
+                f,
+                "{} chains convert their error between types:",
+                stats.conversions.len()
+            )?;
+            for (end, types) in &stats.conversions {
+                writeln!(f, "  {end}: {}", types.join(" → "))?;
+            }
+        }
+        if !stats.unexplained.is_empty() {
+            writeln!(
+                f,
+                "{} error type changes have no corresponding From or map_err conversion:",
+                stats.unexplained.len()
+            )?;
+            for (called, from_ty, caller, to_ty) in &stats.unexplained {
+                writeln!(
+                    f,
+                    "  {called} returns {from_ty}, which {caller} returns as {to_ty}"
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
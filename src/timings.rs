@@ -0,0 +1,129 @@
+use std::fmt;
+use std::time::{Duration, Instant};
+use tracing::span::EnteredSpan;
+use tracing::{debug, info_span};
+
+/// A stage of a run of the analyzer (e.g. building the package or extracting the chains), with how long it took.
+#[derive(Debug, Clone)]
+pub struct Stage {
+    /// The name of the stage, e.g. `graph construction`.
+    pub name: &'static str,
+    /// How long the stage took.
+    pub duration: Duration,
+    /// What the stage produced, e.g. the number of functions and calls of the call graph, if noted.
+    pub detail: Option<String>,
+}
+
+/// A stage that is running, to be finished using `Timings::finish`.
+pub struct RunningStage {
+    name: &'static str,
+    start: Instant,
+    /// The span of the stage, the messages of the analysis emitted while it runs are emitted in.
+    span: EnteredSpan,
+}
+
+impl RunningStage {
+    /// How long the stage has been running, e.g. to report a part of it.
+    pub fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+/// How long each stage of a run of the analyzer took, in the order they ran.
+/// These are printed as a table using `--timings`, and included in the statistics written using `--stats-json`.
+#[derive(Debug, Clone, Default)]
+pub struct Timings {
+    stages: Vec<Stage>,
+}
+
+impl Timings {
+    /// Run a stage, noting how long it takes under the given name.
+    pub fn time<T>(&mut self, name: &'static str, stage: impl FnOnce() -> T) -> T {
+        let running = self.start(name);
+        let result = stage();
+        self.finish(running);
+        result
+    }
+
+    /// Start a stage spanning more than a single call, noting how long it takes under the given name once finished.
+    /// The stage is a `tracing` span with the target `analyzer::timings`, which is entered until the stage is finished.
+    pub fn start(&self, name: &'static str) -> RunningStage {
+        RunningStage {
+            name,
+            start: Instant::now(),
+            span: info_span!(target: "analyzer::timings", "stage", stage = %name).entered(),
+        }
+    }
+
+    /// Finish a running stage, returning how long it took.
+    pub fn finish(&mut self, stage: RunningStage) -> Duration {
+        let duration = stage.start.elapsed();
+        debug!(target: "analyzer::timings", "{} took {duration:.2?}", stage.name);
+        stage.span.exit();
+        self.stages.push(Stage {
+            name: stage.name,
+            duration,
+            detail: None,
+        });
+        duration
+    }
+
+    /// Note what the last stage produced, e.g. the number of chains extracted.
+    pub fn note(&mut self, detail: String) {
+        if let Some(stage) = self.stages.last_mut() {
+            stage.detail = Some(detail);
+        }
+    }
+
+    /// The stages timed so far.
+    pub fn stages(&self) -> &[Stage] {
+        &self.stages
+    }
+
+    /// How long the stages timed so far took together.
+    pub fn total(&self) -> Duration {
+        self.stages.iter().map(|stage| stage.duration).sum()
+    }
+
+    /// Convert these timings to their JSON representation, with the duration of each stage in milliseconds.
+    pub fn to_json(&self) -> serde_json::Value {
+        let stages: Vec<serde_json::Value> = self
+            .stages
+            .iter()
+            .map(|stage| {
+                serde_json::json!({
+                    "stage": stage.name,
+                    "ms": stage.duration.as_secs_f64() * 1000.0,
+                    "detail": stage.detail,
+                })
+            })
+            .collect();
+
+        serde_json::json!({
+            "stages": stages,
+            "total_ms": self.total().as_secs_f64() * 1000.0,
+        })
+    }
+}
+
+impl fmt::Display for Timings {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let width = self
+            .stages
+            .iter()
+            .map(|stage| stage.name.len())
+            .max()
+            .unwrap_or(0)
+            .max("total".len());
+
+        writeln!(f, "How long each stage took:")?;
+        for stage in &self.stages {
+            write!(f, "  {:width$} | {:>10.2?}", stage.name, stage.duration)?;
+            if let Some(detail) = &stage.detail {
+                write!(f, " | {detail}")?;
+            }
+            writeln!(f)?;
+        }
+        writeln!(f, "  {:width$} | {:>10.2?}", "total", self.total())
+    }
+}
@@ -69,10 +69,13 @@ goto after_call
 
 
 :after_call
+set flags=
+set /p flags=Enter any additional flags, e.g. --include-macro-calls (default - none): 
+
 :: Run the analyzer
 echo Building and running analyzer!
 
-cargo +%toolchain% run -- %input% %output% %call%
+cargo +%toolchain% run -- %input% %output% %call% %flags%
 
 
 :: Check whether the toolchain was installed specifically for this, and ask whether to remove it again if it was
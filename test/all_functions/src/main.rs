@@ -0,0 +1,27 @@
+use std::num::ParseIntError;
+
+trait Source {
+    fn read(&self) -> Result<u32, ParseIntError>;
+}
+
+struct Text(String);
+
+impl Source for Text {
+    fn read(&self) -> Result<u32, ParseIntError> {
+        parse(&self.0)
+    }
+}
+
+fn parse(value: &str) -> Result<u32, ParseIntError> {
+    value.parse()
+}
+
+#[allow(dead_code)]
+fn unused() -> Result<u32, ParseIntError> {
+    Ok(parse("1")? * 2)
+}
+
+fn main() {
+    let source: Box<dyn Source> = Box::new(Text(String::from("1")));
+    println!("{:?}", source.read());
+}
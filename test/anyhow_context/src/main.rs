@@ -0,0 +1,27 @@
+use anyhow::{bail, ensure, Context, Result};
+
+fn read_config(path: &str) -> Result<String> {
+    let text = std::fs::read_to_string(path).context("failed to read the config file")?;
+    ensure!(!text.is_empty(), "the config file is empty");
+    Ok(text)
+}
+
+fn parse_port(text: &str) -> Result<u16> {
+    if text.starts_with('-') {
+        bail!("the port is negative");
+    }
+    let port = text.trim().parse().with_context(|| "the port is not a number")?;
+    Ok(port)
+}
+
+fn load() -> Result<u16> {
+    let text = read_config("port.txt").context("failed to load the config")?;
+    let port = parse_port(&text).context("failed to load the config")?;
+    Ok(port)
+}
+
+fn main() {
+    if let Err(error) = load() {
+        eprintln!("{error:?}");
+    }
+}
@@ -0,0 +1,69 @@
+use std::future::Future;
+use std::pin::Pin;
+
+#[derive(Debug)]
+struct FetchError;
+
+type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// What `#[async_trait]` expands a trait with async methods to.
+trait Fetcher {
+    fn fetch<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<u32, FetchError>>;
+
+    fn count<'a>(&'a self) -> Pin<Box<dyn Future<Output = Option<u32>> + 'a>>;
+}
+
+struct Remote;
+
+impl Fetcher for Remote {
+    fn fetch<'a>(&'a self, key: &'a str) -> BoxFuture<'a, Result<u32, FetchError>> {
+        Box::pin(async move {
+            if key.is_empty() {
+                Err(FetchError)
+            } else {
+                Ok(1)
+            }
+        })
+    }
+
+    fn count<'a>(&'a self) -> Pin<Box<dyn Future<Output = Option<u32>> + 'a>> {
+        Box::pin(async move { Some(1) })
+    }
+}
+
+/// A manually boxed future, not behind a trait.
+fn load(path: &str) -> Box<dyn Future<Output = Result<String, FetchError>> + Unpin + '_> {
+    Box::new(std::future::ready(if path.is_empty() {
+        Err(FetchError)
+    } else {
+        Ok(String::from(path))
+    }))
+}
+
+/// A boxed future that cannot fail.
+fn ticks() -> Pin<Box<dyn Future<Output = u64>>> {
+    Box::pin(async { 1 })
+}
+
+async fn fetch_twice(fetcher: &impl Fetcher) -> Result<u32, FetchError> {
+    let first = fetcher.fetch("first").await?;
+    let second = fetcher.fetch("second").await?;
+    Ok(first + second)
+}
+
+async fn total(fetcher: &impl Fetcher) -> Option<u32> {
+    let count = fetcher.count().await?;
+    let _ = ticks().await;
+    Some(count)
+}
+
+async fn run() -> Result<(), FetchError> {
+    let _ = load("config").await?;
+    let _ = fetch_twice(&Remote).await?;
+    let _ = total(&Remote).await;
+    Ok(())
+}
+
+fn main() {
+    let _ = run();
+}
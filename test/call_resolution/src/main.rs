@@ -0,0 +1,64 @@
+use std::num::ParseIntError;
+
+macro_rules! checked {
+    ($e:expr) => {
+        validate($e)
+    };
+}
+
+struct Request {
+    port: u16,
+    retries: u32,
+}
+
+struct RequestBuilder {
+    port: Option<String>,
+    retries: Option<String>,
+}
+
+impl RequestBuilder {
+    fn new() -> Self {
+        RequestBuilder {
+            port: None,
+            retries: None,
+        }
+    }
+
+    fn port(mut self, port: &str) -> Self {
+        self.port = Some(String::from(port));
+        self
+    }
+
+    fn retries(mut self, retries: &str) -> Self {
+        self.retries = Some(String::from(retries));
+        self
+    }
+
+    fn build(self) -> Result<Request, ParseIntError> {
+        Ok(Request {
+            port: self.port.unwrap_or_default().parse()?,
+            retries: self.retries.unwrap_or_default().parse()?,
+        })
+    }
+}
+
+fn validate(value: u32) -> Result<u32, String> {
+    if value > 10 {
+        Err(String::from("too many retries"))
+    } else {
+        Ok(value)
+    }
+}
+
+fn run() -> Result<u16, ParseIntError> {
+    let request = RequestBuilder::new()
+        .port("8080")
+        .retries("3")
+        .build()?;
+    println!("{:?}", checked!(request.retries));
+    Ok(request.port)
+}
+
+fn main() {
+    println!("{:?}", run());
+}
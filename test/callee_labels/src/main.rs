@@ -0,0 +1,43 @@
+use std::fmt::Write as _;
+use std::io::{self, Read, Write};
+
+fn main() {
+    let _ = run();
+}
+
+fn run() -> io::Result<()> {
+    // A plain function
+    let contents = std::fs::read_to_string("input.txt")?;
+
+    // Inherent methods
+    let number: u32 = contents.trim().parse().map_err(io::Error::other)?;
+    let mut buffer = Vec::with_capacity(number as usize);
+
+    // Trait methods called on concrete types
+    let mut file = std::fs::File::open("input.txt")?;
+    file.read_to_end(&mut buffer)?;
+    io::stdout().flush()?;
+
+    // Trait methods called on trait objects
+    let mut output: Box<dyn Write> = Box::new(io::stderr());
+    output.write_all(&buffer)?;
+    output.flush()?;
+    write_to(&mut io::sink())?;
+
+    // Trait methods called through a generic parameter
+    read_from(&mut file)?;
+
+    let mut message = String::new();
+    let _ = write!(message, "{number}");
+    Ok(())
+}
+
+fn write_to(output: &mut dyn Write) -> io::Result<()> {
+    output.write_all(b"done")?;
+    Write::flush(output)
+}
+
+fn read_from<R: Read>(input: &mut R) -> io::Result<usize> {
+    let mut buffer = String::new();
+    input.read_to_string(&mut buffer)
+}
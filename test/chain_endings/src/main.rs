@@ -0,0 +1,52 @@
+#[derive(Debug)]
+struct CheckError;
+
+fn check(value: u32) -> Result<u32, CheckError> {
+    if value > 10 {
+        Err(CheckError)
+    } else {
+        Ok(value)
+    }
+}
+
+fn checked_twice(value: u32) -> Result<u32, CheckError> {
+    let value = check(value)?;
+    check(value * 2)
+}
+
+fn handled(value: u32) -> u32 {
+    match checked_twice(value) {
+        Ok(value) => value,
+        Err(_) => 0,
+    }
+}
+
+fn unwrapped(value: u32) -> u32 {
+    checked_twice(value).unwrap()
+}
+
+fn discarded(value: u32) {
+    let _ = check(value);
+}
+
+fn stored(values: &[u32]) -> Vec<Result<u32, CheckError>> {
+    let mut results = Vec::new();
+    for value in values {
+        results.push(check(*value));
+    }
+    results
+}
+
+fn check_all(values: &[u32]) -> Option<CheckError> {
+    values.iter().try_for_each(|value| check(*value).map(|_| ())).err()
+}
+
+fn main() -> Result<(), CheckError> {
+    println!("{}", handled(1));
+    println!("{}", unwrapped(2));
+    discarded(3);
+    println!("{}", stored(&[4, 5]).len());
+    println!("{:?}", check_all(&[6, 7]));
+    checked_twice(8)?;
+    Ok(())
+}
@@ -0,0 +1,39 @@
+use std::num::ParseIntError;
+
+fn parse(input: &str) -> Result<i32, ParseIntError> {
+    input.parse()
+}
+
+fn level_one(input: &str) -> Result<i32, ParseIntError> {
+    Ok(parse(input)? + 1)
+}
+
+fn level_two(input: &str) -> Result<i32, ParseIntError> {
+    Ok(level_one(input)? * 2)
+}
+
+fn level_three(input: &str) -> Result<i32, ParseIntError> {
+    Ok(level_two(input)? - 3)
+}
+
+fn level_four(input: &str) -> Result<i32, ParseIntError> {
+    Ok(level_three(input)? / 4)
+}
+
+fn shallow(input: &str) -> Result<i32, ParseIntError> {
+    parse(input)
+}
+
+fn main() {
+    // A deep chain, truncated by --max-chain-depth 4
+    if let Err(e) = level_four("12") {
+        println!("deep: {e}");
+    }
+
+    // Shallow chains, left out before the deep chain by --max-chains 1
+    let _ = shallow("1");
+    match shallow("2") {
+        Ok(n) => println!("{n}"),
+        Err(e) => println!("shallow: {e}"),
+    }
+}
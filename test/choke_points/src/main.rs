@@ -0,0 +1,60 @@
+#[derive(Debug)]
+struct QueryError;
+
+// All errors of `query` pass through `fetch`, whichever of the two paths they take to `load`
+fn query() -> Result<u32, QueryError> {
+    Err(QueryError)
+}
+
+fn fetch() -> Result<u32, QueryError> {
+    let value = query()?;
+    Ok(value)
+}
+
+fn fetch_cached() -> Result<u32, QueryError> {
+    let value = fetch()?;
+    Ok(value)
+}
+
+fn fetch_fresh() -> Result<u32, QueryError> {
+    let value = fetch()?;
+    Ok(value)
+}
+
+fn load(cached: bool) -> Result<u32, QueryError> {
+    if cached {
+        fetch_cached()
+    } else {
+        fetch_fresh()
+    }
+}
+
+#[derive(Debug)]
+struct ParseError;
+
+// The errors of `parse` are dealt with by two different callers, so none of them is a choke point
+fn parse() -> Result<u32, ParseError> {
+    Err(ParseError)
+}
+
+fn parse_or_default() -> u32 {
+    parse().unwrap_or(0)
+}
+
+fn parse_or_panic() -> u32 {
+    parse().unwrap()
+}
+
+fn main() {
+    match load(true) {
+        Ok(value) => println!("{value}"),
+        Err(e) => println!("{e:?}"),
+    }
+    println!("{}", parse_or_default() + parse_or_panic());
+}
+
+// Not reachable from main, so only part of the graph with --all-functions, where it adds a second root above `fetch`
+#[allow(dead_code)]
+fn refresh() {
+    let _ = fetch_fresh();
+}
@@ -0,0 +1,35 @@
+use std::num::ParseIntError;
+
+/// The closure propagates the error out of itself, and `collect` into the caller.
+fn double_all(values: &[&str]) -> Result<Vec<u32>, ParseIntError> {
+    values
+        .iter()
+        .map(|value| {
+            let value: u32 = value.trim().parse()?;
+            Ok(value * 2)
+        })
+        .collect()
+}
+
+/// The closure propagates the error out of itself, and `try_fold` into the caller.
+fn sum(values: &[&str]) -> Result<u32, ParseIntError> {
+    values
+        .iter()
+        .try_fold(0, |total, value| Ok(total + value.parse::<u32>()?))
+}
+
+/// The closure drops the error, leaving out the invalid values.
+fn sum_valid(values: &[&str]) -> u32 {
+    values
+        .iter()
+        .filter_map(|value| value.parse::<u32>().ok())
+        .sum()
+}
+
+fn main() {
+    let values = ["1", "2", "x"];
+    let doubled = double_all(&values).unwrap();
+    let total = sum(&values).expect("the values should be numbers");
+    let valid = sum_valid(&values);
+    println!("{doubled:?} {total} {valid}");
+}
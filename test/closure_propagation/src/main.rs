@@ -0,0 +1,23 @@
+use std::num::ParseIntError;
+
+fn parse(value: &str) -> Result<u32, ParseIntError> {
+    value.parse()
+}
+
+fn doubled(values: &[&str]) -> Vec<Result<u32, ParseIntError>> {
+    values
+        .iter()
+        .map(|value| {
+            let number = parse(value)?;
+            Ok(number * 2)
+        })
+        .collect()
+}
+
+fn parsed(values: &[&str]) -> Vec<Result<u32, ParseIntError>> {
+    values.iter().map(|value| parse(value)).collect()
+}
+
+fn main() {
+    println!("{:?} {:?}", doubled(&["1", "2"]), parsed(&["3"]));
+}
@@ -0,0 +1,36 @@
+use std::num::ParseIntError;
+
+#[derive(Debug)]
+struct ValidationError;
+
+fn validate(n: i32) -> Result<i32, ValidationError> {
+    if n < 0 {
+        Err(ValidationError)
+    } else {
+        Ok(n)
+    }
+}
+
+fn parse_all(input: &[&str]) -> Result<Vec<i32>, ParseIntError> {
+    let numbers = input.iter().map(|s| s.parse::<i32>()).collect::<Result<Vec<_>, _>>()?;
+    Ok(numbers)
+}
+
+fn parse_path(input: &[&str]) -> Result<Vec<u8>, ParseIntError> {
+    let numbers: Vec<u8> = input.iter().copied().map(str::parse).collect::<Result<_, _>>()?;
+    Ok(numbers)
+}
+
+fn validate_all(input: Vec<i32>) -> Result<Vec<i32>, ValidationError> {
+    input
+        .into_iter()
+        .filter(|n| *n != 0)
+        .map(validate)
+        .collect()
+}
+
+fn main() {
+    let _ = parse_all(&["1", "2"]);
+    let _ = parse_path(&["1", "2"]);
+    let _ = validate_all(vec![1, -1]);
+}
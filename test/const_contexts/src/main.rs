@@ -0,0 +1,48 @@
+#[derive(Debug)]
+struct ConfigError;
+
+const fn parse_port(port: u32) -> Result<u16, ConfigError> {
+    if port > u16::MAX as u32 {
+        Err(ConfigError)
+    } else {
+        Ok(port as u16)
+    }
+}
+
+const fn buffer_len() -> usize {
+    16
+}
+
+const fn retries() -> usize {
+    3
+}
+
+fn connect<const N: usize>(port: u16) -> Result<[u8; N], ConfigError> {
+    if port == 0 {
+        return Err(ConfigError);
+    }
+    Ok([0; N])
+}
+
+fn run() -> Result<(), ConfigError> {
+    // Const initializer calling a function that is fallible at runtime
+    const PORT: u16 = match parse_port(8080) {
+        Ok(port) => port,
+        Err(_) => 80,
+    };
+    static BACKUP_PORT: u16 = match parse_port(8081) {
+        Ok(port) => port,
+        Err(_) => 81,
+    };
+
+    // Array length and const generic argument
+    let _buffer = [0u8; buffer_len()];
+    connect::<{ retries() }>(PORT)?;
+    connect::<{ retries() }>(BACKUP_PORT)?;
+
+    Ok(())
+}
+
+fn main() -> Result<(), ConfigError> {
+    run()
+}
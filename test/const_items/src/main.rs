@@ -0,0 +1,46 @@
+#[derive(Debug)]
+struct ConfigError;
+
+struct Config {
+    port: u16,
+}
+
+const fn parse_port(port: u32) -> Result<u16, ConfigError> {
+    if port > u16::MAX as u32 {
+        Err(ConfigError)
+    } else {
+        Ok(port as u16)
+    }
+}
+
+const fn level_base() -> isize {
+    10
+}
+
+impl Config {
+    // Associated const, evaluated at compile-time
+    const DEFAULT: Config = Config {
+        port: match parse_port(8080) {
+            Ok(port) => port,
+            Err(_) => 80,
+        },
+    };
+}
+
+// Module-level static
+static FALLBACK_PORT: u16 = match parse_port(8081) {
+    Ok(port) => port,
+    Err(_) => 81,
+};
+
+// Enum discriminant expressions
+#[allow(dead_code)]
+enum Level {
+    Low = level_base(),
+    High = level_base() * 2,
+}
+
+fn main() {
+    println!("{} {}", Config::DEFAULT.port, FALLBACK_PORT);
+    println!("{}", Level::High as isize);
+}
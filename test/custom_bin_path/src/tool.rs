@@ -0,0 +1,9 @@
+use std::num::ParseIntError;
+
+fn parse(input: &str) -> Result<i32, ParseIntError> {
+    input.parse()
+}
+
+fn main() {
+    parse("42").unwrap();
+}
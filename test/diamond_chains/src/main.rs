@@ -0,0 +1,46 @@
+#[derive(Debug)]
+struct StoreError;
+
+fn fetch(key: u32) -> Result<u32, StoreError> {
+    if key == 0 {
+        Err(StoreError)
+    } else {
+        Ok(key)
+    }
+}
+
+fn lookup(key: u32) -> Result<u32, StoreError> {
+    let value = fetch(key)?;
+    Ok(value)
+}
+
+fn short_path(key: u32) -> Result<u32, StoreError> {
+    lookup(key)
+}
+
+fn validated(key: u32) -> Result<u32, StoreError> {
+    let value = lookup(key)?;
+    Ok(value + 1)
+}
+
+fn long_path(key: u32) -> Result<u32, StoreError> {
+    validated(key + 1)
+}
+
+fn both(key: u32) -> Result<u32, StoreError> {
+    let short = short_path(key)?;
+    let long = long_path(key)?;
+    Ok(short + long)
+}
+
+fn cached(key: u32) -> u32 {
+    lookup(key).unwrap_or_default()
+}
+
+fn main() {
+    if let Err(error) = both(1) {
+        println!("{error:?}");
+    }
+    let _ = both(2);
+    println!("{}", cached(3));
+}
@@ -0,0 +1,26 @@
+use std::num::ParseIntError;
+
+fn parse(value: &str) -> Result<u16, ParseIntError> {
+    value.trim().parse()
+}
+
+async fn result() -> Result<(), ParseIntError> {
+    parse("1")?;
+    Ok(())
+}
+
+async fn run() {
+    result().await;
+}
+
+fn log(message: &str) {
+    println!("{message}");
+}
+
+#[allow(unused_must_use)]
+fn main() {
+    parse("x");
+    let _ = parse("y");
+    log("done");
+    let _future = run();
+}
@@ -0,0 +1,61 @@
+use anyhow::Context;
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug)]
+struct SqlxError;
+
+impl fmt::Display for SqlxError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "the query failed")
+    }
+}
+
+impl Error for SqlxError {}
+
+#[derive(Debug)]
+struct ParseError;
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "the row could not be parsed")
+    }
+}
+
+impl Error for ParseError {}
+
+fn query() -> Result<u32, SqlxError> {
+    Err(SqlxError)
+}
+
+fn parse(row: &str) -> Result<u32, ParseError> {
+    row.parse().map_err(|_| ParseError)
+}
+
+fn fetch() -> anyhow::Result<u32> {
+    let count = query().context("failed to fetch the count")?;
+    Ok(count)
+}
+
+fn load(row: &str) -> Result<u32, Box<dyn Error>> {
+    Ok(parse(row)?)
+}
+
+fn main() {
+    // The top-level handler regains the concrete error of the query
+    if let Err(error) = fetch() {
+        if let Some(sqlx) = error.downcast_ref::<SqlxError>() {
+            println!("Database error: {sqlx}");
+        } else {
+            println!("{error:?}");
+        }
+    }
+
+    match load("x") {
+        Ok(row) => println!("{row}"),
+        Err(error) => match error.downcast::<ParseError>() {
+            Ok(parse) => println!("Invalid row: {parse}"),
+            Err(other) => println!("{other}"),
+        },
+    }
+}
@@ -0,0 +1,31 @@
+use std::io::Write;
+
+struct Logger {
+    out: Vec<u8>,
+}
+
+impl Drop for Logger {
+    fn drop(&mut self) {
+        flush(&mut self.out).unwrap();
+    }
+}
+
+struct Session {
+    logger: Logger,
+}
+
+fn flush(out: &mut Vec<u8>) -> std::io::Result<()> {
+    out.flush()
+}
+
+fn run() -> Result<(), std::io::Error> {
+    let session = Session {
+        logger: Logger { out: vec![] },
+    };
+    session.logger.out.clone().write_all(b"hello")?;
+    Ok(())
+}
+
+fn main() {
+    run().unwrap();
+}
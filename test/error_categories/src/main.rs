@@ -0,0 +1,71 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+fn main() {
+    let _ = api::read();
+    let _ = api::parse("1");
+    let _ = api::check(1);
+    let _ = api::load();
+    let _ = api::name();
+    let _ = internal::parse("2");
+    let _ = convert::<io::Error>();
+}
+
+pub mod api {
+    use super::*;
+
+    /// Concrete: callers can match on it.
+    pub fn read() -> Result<String, io::Error> {
+        std::fs::read_to_string("input.txt")
+    }
+
+    /// String: flagged, as it is public.
+    pub fn parse(input: &str) -> Result<u32, String> {
+        input.parse().map_err(|_e| String::from("not a number"))
+    }
+
+    /// Unit: flagged, as it is public.
+    pub fn check(n: u32) -> Result<(), ()> {
+        if n > 0 {
+            Ok(())
+        } else {
+            Err(())
+        }
+    }
+
+    /// Trait object: flagged, as it is public.
+    pub fn load() -> Result<String, Box<dyn Error>> {
+        Ok(read()?)
+    }
+
+    /// String slice: flagged, as it is public.
+    pub fn name() -> Result<&'static str, &'static str> {
+        Err("unnamed")
+    }
+}
+
+mod internal {
+    /// String, but not public: not flagged.
+    pub(crate) fn parse(input: &str) -> Result<u32, String> {
+        input.parse().map_err(|_e| String::from("not a number"))
+    }
+}
+
+/// Generic: the error type is a parameter.
+fn convert<E: From<io::Error>>() -> Result<String, E> {
+    Ok(generic_read::<E>()?)
+}
+
+fn generic_read<E: From<io::Error>>() -> Result<String, E> {
+    Ok(std::fs::read_to_string("input.txt")?)
+}
+
+#[derive(Debug)]
+pub struct MyError;
+
+impl fmt::Display for MyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "my error")
+    }
+}
@@ -0,0 +1,67 @@
+use std::io;
+use std::num::ParseIntError;
+
+#[derive(Debug)]
+struct ParseError {
+    line: usize,
+}
+
+impl ParseError {
+    fn new(line: usize) -> Self {
+        ParseError { line }
+    }
+}
+
+#[derive(Debug)]
+enum MyError {
+    Io(io::Error),
+    Parse(ParseError),
+    Number(ParseIntError),
+}
+
+impl From<ParseIntError> for MyError {
+    fn from(error: ParseIntError) -> Self {
+        MyError::Number(error)
+    }
+}
+
+fn read_config(path: &str) -> Result<String, MyError> {
+    if path.is_empty() {
+        return Err(MyError::Io(io::Error::new(io::ErrorKind::Other, "empty path")));
+    }
+    Ok(String::from(path))
+}
+
+fn parse_line(line: &str, number: usize) -> Result<u32, MyError> {
+    if line.starts_with('#') {
+        return Err(MyError::Parse(ParseError::new(number)));
+    }
+    let value = line.parse::<u32>()?;
+    Ok(value)
+}
+
+fn parse_count(line: &str) -> Result<u32, MyError> {
+    line.trim().parse::<u32>().map_err(|e| e.into())
+}
+
+fn convert(line: &str) -> Result<u32, MyError> {
+    match line.parse::<u32>() {
+        Ok(value) => Ok(value),
+        Err(error) => Err(error.into()),
+    }
+}
+
+fn load(path: &str) -> Result<u32, MyError> {
+    let config = read_config(path)?;
+    let first = parse_line(&config, 1)?;
+    let count = parse_count(&config)?;
+    Ok(first + count + convert(&config)?)
+}
+
+fn main() {
+    match load("config.txt") {
+        Ok(value) => println!("{value}"),
+        Err(MyError::Parse(error)) => println!("line {}", error.line),
+        Err(error) => println!("{error:?}"),
+    }
+}
@@ -0,0 +1,41 @@
+use std::fs;
+use std::io;
+use std::num::ParseIntError;
+
+#[derive(Debug)]
+enum AppError {
+    Io(io::Error),
+    Parse(String),
+}
+
+impl From<io::Error> for AppError {
+    fn from(e: io::Error) -> Self {
+        AppError::Io(e)
+    }
+}
+
+/// Converts the error using `From`, through `?`.
+fn load(path: &str) -> Result<String, AppError> {
+    Ok(fs::read_to_string(path)?)
+}
+
+/// Converts the error using `map_err`.
+fn parse(content: &str) -> Result<u32, AppError> {
+    content
+        .trim()
+        .parse()
+        .map_err(|e: ParseIntError| AppError::Parse(e.to_string()))
+}
+
+fn run() -> Result<u32, AppError> {
+    let content = load("count.txt")?;
+    let count = parse(&content)?;
+    Ok(count)
+}
+
+fn main() {
+    match run() {
+        Ok(count) => println!("{count}"),
+        Err(e) => eprintln!("{e:?}"),
+    }
+}
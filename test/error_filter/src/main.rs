@@ -0,0 +1,35 @@
+use std::fs;
+use std::io;
+use std::num::ParseIntError;
+
+fn read_config(path: &str) -> Result<String, io::Error> {
+    fs::read_to_string(path)
+}
+
+fn load_config(path: &str) -> Result<String, io::Error> {
+    let config = read_config(path)?;
+    Ok(config.trim().to_owned())
+}
+
+fn parse_port(input: &str) -> Result<u16, ParseIntError> {
+    input.trim().parse()
+}
+
+fn read_port(input: &str) -> Result<u16, ParseIntError> {
+    let port = parse_port(input)?;
+    Ok(port + 1)
+}
+
+fn main() {
+    // Kept by --error-filter io::Error
+    match load_config("config.toml") {
+        Ok(config) => println!("{config}"),
+        Err(e) => println!("no config: {e}"),
+    }
+
+    // Kept by --error-filter ParseIntError
+    match read_port("8080") {
+        Ok(port) => println!("{port}"),
+        Err(e) => println!("no port: {e}"),
+    }
+}
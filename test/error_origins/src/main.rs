@@ -0,0 +1,50 @@
+use std::io;
+
+#[derive(Debug)]
+enum AppError {
+    Io(io::Error),
+    Empty,
+}
+
+impl From<io::Error> for AppError {
+    fn from(error: io::Error) -> Self {
+        AppError::Io(error)
+    }
+}
+
+fn check_length(input: &str) -> Result<&str, AppError> {
+    if input.is_empty() {
+        return Err(AppError::Empty);
+    }
+    Ok(input)
+}
+
+fn open_device(name: &str) -> io::Result<u32> {
+    if name.starts_with('/') {
+        Ok(1)
+    } else {
+        Err(io::Error::new(io::ErrorKind::NotFound, "no such device"))
+    }
+}
+
+fn first_word(input: &str) -> Option<&str> {
+    input.split_whitespace().next()
+}
+
+fn forward(input: &str) -> Result<u32, AppError> {
+    let name = check_length(input)?;
+    Ok(open_device(name)?)
+}
+
+fn device_of(input: &str) -> Result<u32, AppError> {
+    let word = first_word(input).ok_or(AppError::Empty)?;
+    forward(word)
+}
+
+fn main() {
+    let _ = forward("/dev/null");
+    let _ = device_of("dev");
+    if let Err(error) = device_of("") {
+        println!("{error:?}");
+    }
+}
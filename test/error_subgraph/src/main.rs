@@ -0,0 +1,37 @@
+use std::error::Error;
+use std::fs;
+use std::io;
+
+#[derive(Debug)]
+struct ConfigError;
+
+fn read(path: &str) -> Result<String, io::Error> {
+    fs::read_to_string(path)
+}
+
+fn read_config() -> Result<String, ConfigError> {
+    read("config.toml").map_err(|_e| ConfigError)
+}
+
+fn read_boxed() -> Result<String, Box<dyn Error>> {
+    let input = read("input.txt")?;
+    Ok(input)
+}
+
+fn parse() -> Result<u32, std::num::ParseIntError> {
+    "1".parse()
+}
+
+fn run() -> Result<(), Box<dyn Error>> {
+    read_boxed()?;
+    parse()?;
+    Ok(())
+}
+
+fn main() {
+    let _ = read_config();
+    let _ = read("other.txt");
+    if let Err(e) = run() {
+        println!("{e}");
+    }
+}
@@ -0,0 +1,45 @@
+use std::num::ParseIntError;
+
+#[derive(Debug, thiserror::Error)]
+enum ConfigError {
+    #[error("could not read the config")]
+    Io(#[from] std::io::Error),
+    #[error("invalid port")]
+    Parse(ParseIntError),
+    #[error("the port is reserved")]
+    Reserved,
+    #[error("{0}")]
+    Other(String),
+}
+
+fn read(path: &str) -> Result<String, ConfigError> {
+    // `#[from]` conversion by the try operator
+    let text = std::fs::read_to_string(path)?;
+    Ok(text)
+}
+
+fn parse(text: &str) -> Result<u16, ConfigError> {
+    // Variant constructor used as a function
+    let port = text.trim().parse().map_err(ConfigError::Parse)?;
+    if port < 1024 {
+        return Err(ConfigError::Reserved);
+    }
+    Ok(port)
+}
+
+fn port(path: &str) -> Result<u16, ConfigError> {
+    let text = read(path)?;
+    parse(&text)
+}
+
+fn fallback(error: ConfigError) -> Result<u16, ConfigError> {
+    // Only returns the error it is given
+    Err(error)
+}
+
+fn main() {
+    match port("port.txt").or_else(fallback) {
+        Ok(port) => println!("{port}"),
+        Err(error) => println!("{}", ConfigError::Other(error.to_string())),
+    }
+}
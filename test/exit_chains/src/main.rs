@@ -0,0 +1,43 @@
+use std::fmt;
+use std::fs;
+use std::io;
+
+#[derive(Debug)]
+enum MyError {
+    Io(io::Error),
+    Empty,
+}
+
+impl fmt::Display for MyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+impl From<io::Error> for MyError {
+    fn from(e: io::Error) -> Self {
+        MyError::Io(e)
+    }
+}
+
+fn read_input(path: &str) -> Result<String, MyError> {
+    let input = fs::read_to_string(path)?;
+    if input.is_empty() {
+        return Err(MyError::Empty);
+    }
+    Ok(input)
+}
+
+fn optional_input(path: &str) -> String {
+    // Handled, so it does not reach the program exit
+    read_input(path).unwrap_or_default()
+}
+
+fn main() -> Result<(), MyError> {
+    println!("{}", optional_input("optional.txt"));
+
+    // Both reach the program exit, and are printed by the runtime
+    let input = read_input("input.txt")?;
+    fs::write("output.txt", input)?;
+    Ok(())
+}
@@ -0,0 +1,70 @@
+// Calls more than 25 functions of `core` whose calls carry no errors, which are aggregated into a single node,
+// while the fallible functions of `core` (e.g. `str::parse`) stay individual, so the chains still go through them
+
+#[derive(Debug)]
+enum InputError {
+    Empty,
+    Parse(std::num::ParseIntError),
+}
+
+impl From<std::num::ParseIntError> for InputError {
+    fn from(e: std::num::ParseIntError) -> Self {
+        InputError::Parse(e)
+    }
+}
+
+fn parse(input: &str) -> Result<u32, InputError> {
+    let trimmed = input.trim();
+    if trimmed.is_empty() {
+        return Err(InputError::Empty);
+    }
+    Ok(trimmed.parse::<u32>()?)
+}
+
+fn describe(n: u32) -> u64 {
+    let mut total = u64::from(n.count_ones());
+    total += u64::from(n.leading_zeros());
+    total += u64::from(n.trailing_zeros());
+    total += u64::from(n.rotate_left(3));
+    total += u64::from(n.rotate_right(3));
+    total += u64::from(n.swap_bytes());
+    total += u64::from(n.reverse_bits());
+    total += u64::from(n.wrapping_add(1));
+    total += u64::from(n.wrapping_sub(1));
+    total += u64::from(n.wrapping_mul(3));
+    total += u64::from(n.saturating_add(1));
+    total += u64::from(n.saturating_sub(1));
+    total += u64::from(n.saturating_mul(3));
+    total += u64::from(n.pow(2));
+    total += u64::from(n.is_power_of_two());
+    total += u64::from(n.next_power_of_two());
+    total += u64::from(n.min(10));
+    total += u64::from(n.max(10));
+    total += u64::from(n.clamp(1, 10));
+    total += u64::from(n.abs_diff(7));
+    total += u64::from(n.to_be());
+    total += u64::from(n.to_le());
+    total += u64::from(n.count_zeros());
+    total += u64::from(n.leading_ones());
+    total += u64::from(n.trailing_ones());
+    total += u64::from(n.div_euclid(2));
+    total += u64::from(n.rem_euclid(2));
+    total += u64::from(n.is_multiple_of_checked());
+    total
+}
+
+trait MultipleOf {
+    fn is_multiple_of_checked(self) -> bool;
+}
+
+impl MultipleOf for u32 {
+    fn is_multiple_of_checked(self) -> bool {
+        self.checked_rem(3).is_some_and(|rem| rem == 0)
+    }
+}
+
+fn main() -> Result<(), InputError> {
+    let n = parse("42")?;
+    println!("{}", describe(n));
+    Ok(())
+}
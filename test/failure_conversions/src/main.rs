@@ -0,0 +1,42 @@
+use std::collections::HashMap;
+use std::num::ParseIntError;
+
+#[derive(Debug)]
+enum ConfigError {
+    Missing(&'static str),
+    Invalid(ParseIntError),
+}
+
+fn parse_port(value: &str) -> Result<u16, ParseIntError> {
+    value.parse()
+}
+
+fn lookup<'a>(config: &'a HashMap<&str, &str>, key: &str) -> Option<&'a &'a str> {
+    config.get(key)
+}
+
+fn optional_port(value: &str) -> Option<u16> {
+    parse_port(value).ok()
+}
+
+fn port_error(value: &str) -> Option<ParseIntError> {
+    parse_port(value).err()
+}
+
+fn required_value(config: &HashMap<&str, &str>) -> Result<String, ConfigError> {
+    let value = lookup(config, "port").ok_or(ConfigError::Missing("port"))?;
+    Ok(value.to_string())
+}
+
+fn required_port(config: &HashMap<&str, &str>) -> Result<u16, ConfigError> {
+    let value = lookup(config, "port").ok_or_else(|| ConfigError::Missing("port"))?;
+    parse_port(value).map_err(ConfigError::Invalid)
+}
+
+fn main() {
+    let config = HashMap::from([("port", "80")]);
+    let _ = optional_port("80");
+    let _ = port_error("x");
+    let _ = required_value(&config);
+    let _ = required_port(&config);
+}
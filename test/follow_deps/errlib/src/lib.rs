@@ -0,0 +1,9 @@
+use std::num::ParseIntError;
+
+fn parse(value: &str) -> Result<u32, ParseIntError> {
+    value.trim().parse()
+}
+
+pub fn sum(a: &str, b: &str) -> Result<u32, ParseIntError> {
+    Ok(parse(a)? + parse(b)?)
+}
@@ -0,0 +1,9 @@
+use std::num::ParseIntError;
+
+fn run() -> Result<u32, ParseIntError> {
+    errlib::sum("1", "2")
+}
+
+fn main() {
+    println!("{:?}", run());
+}
@@ -0,0 +1,51 @@
+use std::num::ParseIntError;
+
+struct Lines {
+    lines: Vec<String>,
+}
+
+struct Numbers {
+    lines: std::vec::IntoIter<String>,
+}
+
+impl IntoIterator for Lines {
+    type Item = Result<u32, ParseIntError>;
+    type IntoIter = Numbers;
+
+    fn into_iter(self) -> Numbers {
+        Numbers {
+            lines: self.lines.into_iter(),
+        }
+    }
+}
+
+impl Iterator for Numbers {
+    type Item = Result<u32, ParseIntError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let line = self.lines.next()?;
+        Some(parse(&line))
+    }
+}
+
+fn parse(line: &str) -> Result<u32, ParseIntError> {
+    line.trim().parse()
+}
+
+fn read() -> Result<Lines, ParseIntError> {
+    Ok(Lines {
+        lines: vec![String::from("1"), String::from("2")],
+    })
+}
+
+fn sum() -> Result<u32, ParseIntError> {
+    let mut total = 0;
+    for number in read()? {
+        total += number?;
+    }
+    Ok(total)
+}
+
+fn main() {
+    println!("{:?}", sum());
+}
@@ -0,0 +1,24 @@
+use std::ffi::CString;
+use std::os::raw::{c_char, c_int};
+
+extern "C" {
+    // Returns a negative value on failure, rather than a Result
+    fn atoi(input: *const c_char) -> c_int;
+}
+
+#[derive(Debug)]
+struct ParseError;
+
+fn parse(input: &str) -> Result<i32, ParseError> {
+    let input = CString::new(input).map_err(|_| ParseError)?;
+    let value = unsafe { atoi(input.as_ptr()) };
+    if value < 0 {
+        return Err(ParseError);
+    }
+    Ok(value)
+}
+
+fn main() -> Result<(), ParseError> {
+    parse("42")?;
+    Ok(())
+}
@@ -0,0 +1,36 @@
+use std::num::ParseIntError;
+
+#[derive(Debug)]
+struct AppError {
+    message: String,
+}
+
+impl From<ParseIntError> for AppError {
+    fn from(error: ParseIntError) -> Self {
+        AppError {
+            message: describe(&error),
+        }
+    }
+}
+
+fn describe(error: &ParseIntError) -> String {
+    format!("invalid number: {error}")
+}
+
+fn parse(value: &str) -> Result<u16, ParseIntError> {
+    value.trim().parse()
+}
+
+fn port() -> Result<u16, AppError> {
+    let port = parse("8080")?;
+    Ok(port)
+}
+
+fn read() -> Result<String, Box<dyn std::error::Error>> {
+    let text = std::fs::read_to_string("port.txt")?;
+    Ok(text)
+}
+
+fn main() {
+    println!("{:?} {:?}", port(), read());
+}
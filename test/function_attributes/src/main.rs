@@ -0,0 +1,40 @@
+use std::num::ParseIntError;
+
+mod config {
+    use std::num::ParseIntError;
+
+    pub fn load(input: &str) -> Result<u32, ParseIntError> {
+        let value = parse(input)?;
+        Ok(value + defaults::offset()?)
+    }
+
+    fn parse(input: &str) -> Result<u32, ParseIntError> {
+        input.parse()
+    }
+
+    pub(crate) mod defaults {
+        use std::num::ParseIntError;
+
+        pub(crate) fn offset() -> Result<u32, ParseIntError> {
+            "1".parse()
+        }
+    }
+}
+
+/// Reads a value from a raw pointer, so the caller has to uphold its validity.
+unsafe fn read_raw(value: *const u32) -> u32 {
+    *value
+}
+
+async fn fetch(input: &str) -> Result<u32, ParseIntError> {
+    input.parse()
+}
+
+fn main() -> Result<(), ParseIntError> {
+    let value = config::load("41")?;
+    let read = unsafe { read_raw(&value) };
+    // The future is never polled, creating it is the call
+    drop(fetch("1"));
+    println!("{read}");
+    Ok(())
+}
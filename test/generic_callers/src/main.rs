@@ -0,0 +1,22 @@
+use std::str::FromStr;
+
+/// Parse the first input as any type that can be parsed.
+fn parse_first<T: FromStr>(inputs: &[&str]) -> Result<T, T::Err> {
+    inputs[0].trim().parse()
+}
+
+/// Parse all inputs, stopping at the first that cannot be parsed.
+fn parse_all<T: FromStr>(inputs: &[&str]) -> Result<Vec<T>, T::Err> {
+    let mut values = Vec::new();
+    for input in inputs {
+        values.push(input.parse::<T>()?);
+    }
+    Ok(values)
+}
+
+fn main() {
+    let inputs = ["1", "2"];
+    let first: u32 = parse_first(&inputs).unwrap();
+    let all: Vec<f64> = parse_all(&inputs).unwrap_or_default();
+    println!("{first} {all:?}");
+}
@@ -0,0 +1,34 @@
+use std::num::ParseIntError;
+
+fn parse(value: &str) -> Result<u16, ParseIntError> {
+    value.trim().parse()
+}
+
+fn port() -> Result<u16, ParseIntError> {
+    let port = parse("8080")?;
+    Ok(port)
+}
+
+fn retries() -> u32 {
+    match parse("3") {
+        Ok(retries) => u32::from(retries),
+        Err(error) => {
+            eprintln!("{error}");
+            0
+        }
+    }
+}
+
+fn timeout() -> u16 {
+    if let Err(error) = parse("x") {
+        eprintln!("{error}");
+    }
+    parse("30").unwrap_or_else(|_error| 10)
+}
+
+fn main() {
+    match port() {
+        Ok(port) => println!("{port} {} {}", retries(), timeout()),
+        Err(error) => eprintln!("{error}"),
+    }
+}
@@ -0,0 +1,39 @@
+#[derive(Debug)]
+struct MyError;
+
+struct Handlers {
+    on_run: fn() -> Result<(), MyError>,
+}
+
+fn helper() -> Result<(), MyError> {
+    Err(MyError)
+}
+
+fn pick() -> fn() -> Result<(), MyError> {
+    helper
+}
+
+fn run() -> Result<(), MyError> {
+    // Function item stored in a variable, should resolve to `helper`
+    let f = helper;
+    f()?;
+
+    // Function pointer, should be an indirect call
+    let g: fn() -> Result<(), MyError> = pick();
+    g()?;
+
+    // Function pointer stored in a field, should be an indirect call
+    let handlers = Handlers { on_run: pick() };
+    (handlers.on_run)()?;
+
+    // Closure stored in a variable, should be an indirect call
+    let c = |fail: bool| if fail { Err(MyError) } else { Ok(()) };
+    c(true)?;
+
+    Ok(())
+}
+
+fn main() -> Result<(), MyError> {
+    run()?;
+    Ok(())
+}
@@ -0,0 +1,71 @@
+use std::fs;
+use std::io;
+
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("{e}");
+    }
+    let _ = validate(3).unwrap();
+    let _ = stored().unwrap();
+}
+
+/// Fallible: propagates the error of a fallible call.
+fn run() -> Result<(), io::Error> {
+    let config = load()?;
+    let _version = version()?;
+    let _port = parse_port(&config)?;
+    check(Ok(()))?;
+    let _even = is_even(4)?;
+    Ok(())
+}
+
+/// Fallible: passes on the error it receives.
+fn check(res: Result<(), io::Error>) -> Result<(), io::Error> {
+    res
+}
+
+/// Infallible, though recursive: only propagates the errors of itself and another infallible function.
+fn is_even(n: u32) -> Result<bool, io::Error> {
+    if n == 0 {
+        return Ok(true);
+    }
+    is_odd(n - 1)
+}
+
+/// Infallible, though recursive.
+fn is_odd(n: u32) -> Result<bool, io::Error> {
+    if n == 0 {
+        return Ok(false);
+    }
+    is_even(n - 1)
+}
+
+/// Fallible: calls a function of the standard library that can fail.
+fn load() -> Result<String, io::Error> {
+    fs::read_to_string("config.toml")
+}
+
+/// Infallible: never constructs an error.
+fn version() -> Result<u32, io::Error> {
+    Ok(1)
+}
+
+/// Transitively infallible: only propagates the errors of an infallible function.
+fn parse_port(_config: &str) -> Result<u16, io::Error> {
+    let version = version()?;
+    Ok(8000 + version as u16)
+}
+
+/// Fallible: constructs an error itself.
+fn validate(n: u32) -> Result<u32, String> {
+    if n > 2 {
+        return Err(String::from("too big"));
+    }
+    Ok(n)
+}
+
+/// Fallible: returns a result it did not construct.
+fn stored() -> Result<u32, String> {
+    let results = vec![validate(1), validate(5)];
+    results.into_iter().next().unwrap_or(Ok(0))
+}
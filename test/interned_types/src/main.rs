@@ -0,0 +1,19 @@
+// Two calls returning the same error type, from different functions, forming two chains
+// Writing the chains using `--chains-json` stores `std::io::Error` once in the table of strings, which both calls refer to
+
+use std::fs;
+use std::io;
+
+fn read_config() -> Result<String, io::Error> {
+    fs::read_to_string("config.toml")
+}
+
+fn read_data() -> Result<String, io::Error> {
+    fs::read_to_string("data.csv")
+}
+
+fn main() {
+    let config = read_config().unwrap();
+    let data = read_data().unwrap();
+    println!("{config}{data}");
+}
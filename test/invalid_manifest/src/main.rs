@@ -0,0 +1,3 @@
+fn main() {
+    println!("The manifest of this package cannot be parsed");
+}
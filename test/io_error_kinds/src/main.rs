@@ -0,0 +1,38 @@
+use std::fs::{self, File};
+use std::io::{self, ErrorKind, Read};
+
+fn open_config(path: &str) -> io::Result<File> {
+    File::open(path)
+}
+
+fn read_header(path: &str) -> io::Result<[u8; 4]> {
+    let mut header = [0; 4];
+    open_config(path)?.read_exact(&mut header)?;
+    Ok(header)
+}
+
+fn check_name(name: &str) -> io::Result<()> {
+    if name.is_empty() {
+        return Err(io::Error::new(ErrorKind::InvalidInput, "empty name"));
+    }
+    let kind = ErrorKind::Unsupported;
+    if name.starts_with('.') {
+        return Err(kind.into());
+    }
+    Ok(())
+}
+
+fn load(name: &str) -> io::Result<String> {
+    check_name(name)?;
+    fs::read_to_string(name)
+}
+
+fn last_error() -> io::Result<()> {
+    Err(io::Error::last_os_error())
+}
+
+fn main() {
+    println!("{:?}", read_header("header.bin"));
+    println!("{:?}", load("config.toml"));
+    println!("{:?}", last_error());
+}
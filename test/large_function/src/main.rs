@@ -0,0 +1,49 @@
+// A single function making 5000 calls, to measure how long building its call graph takes using `--verbose`
+
+#[derive(Debug)]
+struct StepError;
+
+struct Counter {
+    count: u32,
+}
+
+impl Counter {
+    fn add(&mut self, n: u32) -> Result<u32, StepError> {
+        self.count = self.count.checked_add(n).ok_or(StepError)?;
+        Ok(self.count)
+    }
+}
+
+fn step(n: u32) -> Result<u32, StepError> {
+    if n > 100 {
+        return Err(StepError);
+    }
+    Ok(n + 1)
+}
+
+macro_rules! five {
+    ($($t:tt)*) => {
+        $($t)* $($t)* $($t)* $($t)* $($t)*
+    };
+}
+
+macro_rules! ten {
+    ($($t:tt)*) => {
+        five! { $($t)* }
+        five! { $($t)* }
+    };
+}
+
+// Each repetition makes five calls: `step`, `?`, `add`, `?` and `parse`
+fn run(counter: &mut Counter) -> Result<(), StepError> {
+    ten! { ten! { ten! {
+        counter.add(step(1)?)?;
+        let _ = "1".parse::<u32>();
+    } } }
+    Ok(())
+}
+
+fn main() -> Result<(), StepError> {
+    let mut counter = Counter { count: 0 };
+    run(&mut counter)
+}
@@ -0,0 +1,42 @@
+use std::num::ParseIntError;
+
+#[derive(Debug)]
+enum ConfigError {
+    Missing,
+    Invalid(ParseIntError),
+}
+
+fn lookup(key: &str) -> Option<String> {
+    if key == "port" {
+        Some(String::from("8080"))
+    } else {
+        None
+    }
+}
+
+fn parse(value: &str) -> Result<u16, ParseIntError> {
+    value.parse()
+}
+
+fn report(key: &str) -> ConfigError {
+    eprintln!("{key} is missing");
+    ConfigError::Missing
+}
+
+fn port() -> Result<u16, ConfigError> {
+    let Some(value) = lookup("port") else {
+        return Err(report("port"));
+    };
+    let Ok(port) = parse(&value) else {
+        return Err(report("port"));
+    };
+    Ok(port)
+}
+
+fn checked_port() -> Result<u16, ConfigError> {
+    parse("80").map_err(ConfigError::Invalid)
+}
+
+fn main() {
+    println!("{:?} {:?}", port(), checked_port());
+}
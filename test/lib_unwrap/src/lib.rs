@@ -0,0 +1,47 @@
+use std::fs;
+use std::io;
+
+/// Reaches an unwrap through a private function: flagged.
+pub fn load(path: &str) -> String {
+    config::parse(path)
+}
+
+/// Propagates the error to the caller: not flagged.
+pub fn try_load(path: &str) -> Result<String, io::Error> {
+    read(path)
+}
+
+pub struct Settings;
+
+impl Settings {
+    /// Reaches the same unwrap from a public method: listed as another entry point.
+    pub fn new() -> Self {
+        let _ = config::parse("settings.toml");
+        Settings
+    }
+}
+
+mod config {
+    pub fn parse(path: &str) -> String {
+        super::read(path).unwrap()
+    }
+
+    /// Not reachable from the public API: not flagged.
+    #[allow(dead_code)]
+    fn unused() -> String {
+        super::read("unused.toml").unwrap()
+    }
+}
+
+fn read(path: &str) -> Result<String, io::Error> {
+    fs::read_to_string(path)
+}
+
+#[cfg(test)]
+mod tests {
+    /// Only compiled for tests: not flagged.
+    #[test]
+    fn reads() {
+        super::read("test.toml").unwrap();
+    }
+}
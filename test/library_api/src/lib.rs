@@ -0,0 +1,43 @@
+use std::collections::HashMap;
+use std::num::ParseIntError;
+
+/// A parsed set of `key=value` pairs.
+pub struct Settings {
+    values: HashMap<String, u32>,
+}
+
+impl Settings {
+    /// Propagates the error of parsing a value to the caller.
+    pub fn parse(input: &str) -> Result<Self, ParseIntError> {
+        let mut values = HashMap::new();
+        for line in input.lines() {
+            let (key, value) = split(line);
+            values.insert(key.to_owned(), parse_value(value)?);
+        }
+        Ok(Settings { values })
+    }
+
+    /// Unwraps the error of parsing the default value.
+    pub fn get(&self, key: &str) -> u32 {
+        match self.values.get(key) {
+            Some(value) => *value,
+            None => parse_value("0").unwrap(),
+        }
+    }
+}
+
+/// Handles the error, leaving out invalid values.
+pub fn parse_lenient(input: &str) -> Vec<u32> {
+    input
+        .split(',')
+        .filter_map(|value| parse_value(value).ok())
+        .collect()
+}
+
+fn split(line: &str) -> (&str, &str) {
+    line.split_once('=').unwrap_or((line, ""))
+}
+
+fn parse_value(value: &str) -> Result<u32, ParseIntError> {
+    value.trim().parse()
+}
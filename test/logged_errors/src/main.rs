@@ -0,0 +1,53 @@
+use std::fs;
+
+#[derive(Debug)]
+struct ConfigError;
+
+fn read_config() -> Result<String, ConfigError> {
+    fs::read_to_string("config.toml").map_err(|_| ConfigError)
+}
+
+fn load_cache() -> Result<Vec<u8>, ConfigError> {
+    fs::read("cache.bin").map_err(|_| ConfigError)
+}
+
+fn connect() -> Result<u16, ConfigError> {
+    Ok(8080)
+}
+
+fn sync() -> Result<(), ConfigError> {
+    Ok(())
+}
+
+fn report(error: &ConfigError) {
+    eprintln!("Reporting {error:?}");
+}
+
+fn main() {
+    // Only logged by the Err arm
+    match read_config() {
+        Ok(config) => println!("{config}"),
+        Err(e) => eprintln!("Could not read the config: {e:?}"),
+    }
+
+    // Only logged by the branch taken on an error, using a function passed with --log-fns
+    if let Err(e) = load_cache() {
+        report(&e);
+    }
+
+    // Logged by the else branch
+    if let Ok(port) = connect() {
+        println!("{port}");
+    } else {
+        eprintln!("Could not connect");
+    }
+
+    // Acted upon after logging, so handled
+    match sync() {
+        Ok(()) => {}
+        Err(e) => {
+            eprintln!("Could not sync: {e:?}");
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,35 @@
+#[derive(Debug)]
+struct ReadError;
+
+fn read(name: &str) -> Result<Vec<u8>, ReadError> {
+    if name.is_empty() {
+        Err(ReadError)
+    } else {
+        Ok(vec![1, 2, 3])
+    }
+}
+
+// Calls from local macros are kept
+macro_rules! read_all {
+    ($($name:expr),*) => {
+        {
+            let mut total = 0;
+            $(total += read($name)?.len();)*
+            total
+        }
+    };
+}
+
+fn run() -> Result<usize, ReadError> {
+    // The formatting machinery behind `println!` is left out by default,
+    // but the user-written call inside of it is not
+    println!("{}", read("a")?.len());
+
+    let total = read_all!("b", "c");
+    Ok(total)
+}
+
+fn main() -> Result<(), ReadError> {
+    run()?;
+    Ok(())
+}
@@ -0,0 +1,31 @@
+use std::num::ParseIntError;
+
+#[derive(Debug)]
+enum AppError {
+    Io(std::io::Error),
+    Parse(ParseIntError),
+}
+
+impl From<ParseIntError> for AppError {
+    fn from(error: ParseIntError) -> Self {
+        AppError::Parse(error)
+    }
+}
+
+fn read() -> Result<String, std::io::Error> {
+    std::fs::read_to_string("port.txt")
+}
+
+fn parse(value: &str) -> Result<u16, ParseIntError> {
+    value.trim().parse()
+}
+
+fn port() -> Result<u16, AppError> {
+    let text = read().map_err(AppError::Io)?;
+    let port = parse(&text).map_err(|error| AppError::from(error))?;
+    Ok(port)
+}
+
+fn main() {
+    println!("{:?}", port());
+}
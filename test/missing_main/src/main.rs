@@ -0,0 +1,11 @@
+use std::num::ParseIntError;
+
+// This binary has no main function, so it cannot be analyzed from its entry point
+
+fn parse(input: &str) -> Result<i32, ParseIntError> {
+    input.parse()
+}
+
+pub fn run() {
+    parse("42").unwrap();
+}
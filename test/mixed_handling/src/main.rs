@@ -0,0 +1,67 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::num::ParseIntError;
+
+#[derive(Debug)]
+enum ConfigError {
+    Io(io::Error),
+    Parse(ParseIntError),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Io(e) => write!(f, "could not read the config: {e}"),
+            ConfigError::Parse(e) => write!(f, "could not parse the config: {e}"),
+        }
+    }
+}
+
+impl From<io::Error> for ConfigError {
+    fn from(e: io::Error) -> Self {
+        ConfigError::Io(e)
+    }
+}
+
+impl From<ParseIntError> for ConfigError {
+    fn from(e: ParseIntError) -> Self {
+        ConfigError::Parse(e)
+    }
+}
+
+/// Propagates both errors, converted into the config error.
+fn read_limit(path: &str) -> Result<u32, ConfigError> {
+    let content = fs::read_to_string(path)?;
+    let limit = content.trim().parse()?;
+    Ok(limit)
+}
+
+/// Handles the error, falling back to a default.
+fn limit_or_default(path: &str) -> u32 {
+    match read_limit(path) {
+        Ok(limit) => limit,
+        Err(e) => {
+            eprintln!("{e}");
+            10
+        }
+    }
+}
+
+/// Discards the error.
+fn touch(path: &str) {
+    let _ = fs::write(path, "");
+}
+
+/// Unwraps the error.
+fn retries() -> u32 {
+    "3".parse().unwrap()
+}
+
+fn main() -> Result<(), ConfigError> {
+    touch("limit.txt");
+    let limit = limit_or_default("limit.txt");
+    let strict = read_limit("strict.txt")?;
+    println!("{limit} {strict} {}", retries());
+    Ok(())
+}
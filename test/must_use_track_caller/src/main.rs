@@ -0,0 +1,34 @@
+use std::num::ParseIntError;
+
+/// A connection, which is closed as soon as it is dropped.
+#[must_use]
+struct Connection {
+    port: u32,
+}
+
+#[must_use = "the written value is only saved once flushed"]
+fn write(input: &str) -> Result<u32, ParseIntError> {
+    input.parse()
+}
+
+fn log(input: &str) -> Result<u32, ParseIntError> {
+    input.parse()
+}
+
+fn connect(input: &str) -> Result<Connection, ParseIntError> {
+    let port = input.parse()?;
+    Ok(Connection { port })
+}
+
+#[track_caller]
+fn port_of(input: &str) -> u32 {
+    input.parse().expect("Invalid port")
+}
+
+fn main() {
+    let _ = write("1");
+    let _ = log("2");
+    let _ = connect("3");
+
+    println!("{}", port_of("4"));
+}
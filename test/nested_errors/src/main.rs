@@ -0,0 +1,68 @@
+use std::collections::HashMap;
+
+#[derive(Debug)]
+struct CodedError<C, M> {
+    code: C,
+    message: M,
+}
+
+fn main() {
+    let _ = outer_only();
+    let _ = tuple_error();
+    let _ = map_error();
+    let _ = generic_error();
+    let _ = wrapped();
+}
+
+/// The error is `Result<u8, String>`, not `String`.
+fn nested() -> Result<(), Result<u8, String>> {
+    Err(Ok(1))
+}
+
+fn outer_only() -> Result<(), Result<u8, String>> {
+    nested()?;
+    Ok(())
+}
+
+fn tuple() -> Result<u32, (u8, String)> {
+    Err((1, String::from("tuple")))
+}
+
+fn tuple_error() -> Result<u32, (u8, String)> {
+    let value = tuple()?;
+    Ok(value + 1)
+}
+
+fn map() -> Result<u32, HashMap<String, Vec<u32>>> {
+    Err(HashMap::new())
+}
+
+fn map_error() -> Result<u32, HashMap<String, Vec<u32>>> {
+    let value = map()?;
+    Ok(value)
+}
+
+fn coded() -> Result<u32, CodedError<u16, &'static str>> {
+    Err(CodedError {
+        code: 404,
+        message: "not found",
+    })
+}
+
+fn generic_error() -> Result<u32, CodedError<u16, &'static str>> {
+    let value = coded()?;
+    let _ = (value, CodedError { code: 0u16, message: "" }.code);
+    Ok(value)
+}
+
+/// The Result is found inside the Option.
+fn optional() -> Option<Result<u32, (String, Result<u8, u16>)>> {
+    Some(Err((String::new(), Ok(0))))
+}
+
+fn wrapped() -> Result<u32, (String, Result<u8, u16>)> {
+    match optional() {
+        Some(result) => result,
+        None => Ok(0),
+    }
+}
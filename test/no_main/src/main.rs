@@ -0,0 +1,39 @@
+//! A binary without a `main` function, as firmware is: its startup code calls the reset handler instead.
+#![no_main]
+
+use std::num::ParseIntError;
+
+#[derive(Debug)]
+enum BootError {
+    Config(ParseIntError),
+}
+
+impl From<ParseIntError> for BootError {
+    fn from(e: ParseIntError) -> Self {
+        BootError::Config(e)
+    }
+}
+
+fn read_clock(config: &str) -> Result<u32, BootError> {
+    Ok(config.parse()?)
+}
+
+fn init() -> Result<u32, BootError> {
+    let clock = read_clock("48000000")?;
+    Ok(clock / 1000)
+}
+
+/// Called by the startup code once memory is set up.
+#[no_mangle]
+pub extern "C" fn reset_handler() -> i32 {
+    match init() {
+        Ok(ticks) => ticks as i32,
+        Err(_e) => -1,
+    }
+}
+
+/// Stands in for the startup code, which links to the reset handler.
+#[no_mangle]
+pub extern "C" fn main(_argc: i32, _argv: *const *const u8) -> i32 {
+    reset_handler()
+}
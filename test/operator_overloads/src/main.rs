@@ -0,0 +1,55 @@
+use std::ops::{Add, Deref, Index};
+
+#[derive(Debug)]
+struct OverflowError;
+
+fn checked_add(a: u8, b: u8) -> Result<u8, OverflowError> {
+    a.checked_add(b).ok_or(OverflowError)
+}
+
+#[derive(Clone, Copy)]
+struct Small(u8);
+
+impl Add for Small {
+    type Output = Small;
+
+    fn add(self, other: Small) -> Small {
+        // Panics on overflow
+        Small(checked_add(self.0, other.0).unwrap())
+    }
+}
+
+struct Table {
+    values: Vec<u8>,
+}
+
+impl Index<usize> for Table {
+    type Output = u8;
+
+    fn index(&self, index: usize) -> &u8 {
+        if index >= self.values.len() {
+            panic!("index out of bounds");
+        }
+        &self.values[index]
+    }
+}
+
+struct Wrapper(Table);
+
+impl Deref for Wrapper {
+    type Target = Table;
+
+    fn deref(&self) -> &Table {
+        &self.0
+    }
+}
+
+fn main() {
+    let sum = Small(200) + Small(100);
+    let table = Wrapper(Table {
+        values: vec![sum.0],
+    });
+    // Index on a local type, through auto-deref of a local smart pointer
+    println!("{}", table[0]);
+    println!("{}", table.values.len());
+}
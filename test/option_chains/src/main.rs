@@ -0,0 +1,30 @@
+struct User {
+    manager: Option<u32>,
+}
+
+fn find_user(id: u32) -> Option<User> {
+    (id < 10).then_some(User {
+        manager: id.checked_sub(1),
+    })
+}
+
+/// Propagates `None` if there is no such user, or it has no manager.
+fn manager_of(id: u32) -> Option<u32> {
+    let user = find_user(id)?;
+    user.manager
+}
+
+/// Propagates `None` further up.
+fn manager_name(id: u32) -> Option<String> {
+    let manager = manager_of(id)?;
+    Some(format!("user {manager}"))
+}
+
+fn main() {
+    match manager_name(3) {
+        Some(name) => println!("{name}"),
+        None => println!("no manager"),
+    }
+    let manager = manager_of(5).unwrap();
+    println!("{manager}");
+}
@@ -0,0 +1,29 @@
+use std::collections::HashMap;
+
+fn lookup(map: &HashMap<&str, u32>, key: &str) -> Option<u32> {
+    let value = map.get(key)?;
+    Some(*value)
+}
+
+fn double(map: &HashMap<&str, u32>, key: &str) -> Option<u32> {
+    let value = lookup(map, key)?;
+    value.checked_mul(2)
+}
+
+fn quadruple(map: &HashMap<&str, u32>, key: &str) -> Option<u32> {
+    let value = double(map, key)?;
+    double_value(value)
+}
+
+fn double_value(value: u32) -> Option<u32> {
+    value.checked_mul(2)
+}
+
+fn main() {
+    let mut map = HashMap::new();
+    map.insert("a", 1);
+    match quadruple(&map, "a") {
+        Some(value) => println!("{value}"),
+        None => println!("missing"),
+    }
+}
@@ -0,0 +1,37 @@
+use std::panic::{self, AssertUnwindSafe};
+use std::thread;
+
+fn main() {
+    let _ = guarded("1");
+    let _ = in_thread();
+    let _ = in_named_thread();
+    unguarded("2");
+}
+
+fn parse(input: &str) -> u32 {
+    input.parse().unwrap()
+}
+
+/// Panics of the closure are caught, and returned as an error instead.
+fn guarded(input: &str) -> Result<u32, Box<dyn std::any::Any + Send>> {
+    panic::catch_unwind(AssertUnwindSafe(|| parse(input)))
+}
+
+/// Panics of the thread end the thread, and are returned by `join`.
+fn in_thread() -> u32 {
+    let handle = thread::spawn(|| parse("3"));
+    handle.join().unwrap_or(0)
+}
+
+fn in_named_thread() -> thread::Result<u32> {
+    let handle = thread::Builder::new()
+        .name(String::from("worker"))
+        .spawn(|| parse("4"))
+        .expect("could not spawn thread");
+    handle.join()
+}
+
+/// Panics here reach main.
+fn unguarded(input: &str) {
+    let _ = parse(input);
+}
@@ -0,0 +1,34 @@
+fn main() {
+    if std::env::args().count() > 3 {
+        panic!("too many arguments");
+    }
+    let _ = caller();
+    checked(1);
+    fine();
+    guarded();
+}
+
+fn caller() -> Result<u32, String> {
+    todo_later()?;
+    Ok(1)
+}
+
+fn todo_later() -> Result<u32, String> {
+    todo!()
+}
+
+fn checked(x: u32) {
+    assert_eq!(x, 1);
+}
+
+fn fine() -> u32 {
+    1
+}
+
+fn guarded() -> bool {
+    std::panic::catch_unwind(explode).is_err()
+}
+
+fn explode() {
+    unreachable!()
+}
@@ -0,0 +1,48 @@
+use std::num::ParseIntError;
+
+/// Stands in for generated code, which is excluded.
+mod generated {
+    use std::num::ParseIntError;
+
+    pub fn parse_all() -> Result<u32, ParseIntError> {
+        let first = parse_one("1")?;
+        let second = helpers::only_from_generated()?;
+        Ok(first + second)
+    }
+
+    fn parse_one(input: &str) -> Result<u32, ParseIntError> {
+        input.parse()
+    }
+
+    pub mod helpers {
+        use std::num::ParseIntError;
+
+        pub fn only_from_generated() -> Result<u32, ParseIntError> {
+            "2".parse()
+        }
+    }
+}
+
+/// Stands in for a vendored tree.
+mod vendor {
+    use std::num::ParseIntError;
+
+    pub fn checksum(data: &str) -> Result<u32, ParseIntError> {
+        data.trim().parse()
+    }
+}
+
+mod app {
+    use std::num::ParseIntError;
+
+    pub fn run() -> Result<u32, ParseIntError> {
+        let parsed = crate::generated::parse_all()?;
+        let sum = crate::vendor::checksum(" 3 ")?;
+        Ok(parsed + sum)
+    }
+}
+
+fn main() -> Result<(), ParseIntError> {
+    println!("{}", app::run()?);
+    Ok(())
+}
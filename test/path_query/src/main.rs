@@ -0,0 +1,41 @@
+mod db {
+    #[derive(Debug)]
+    pub struct DbError;
+
+    pub fn connect() -> Result<(), DbError> {
+        Err(DbError)
+    }
+
+    pub fn fetch() -> Result<u32, DbError> {
+        connect()?;
+        Ok(1)
+    }
+}
+
+mod cache {
+    #[derive(Debug)]
+    pub struct CacheError;
+
+    pub fn fetch() -> Result<u32, CacheError> {
+        Err(CacheError)
+    }
+}
+
+fn load() -> Result<u32, db::DbError> {
+    let value = db::fetch()?;
+    Ok(value)
+}
+
+fn load_twice() -> Result<u32, db::DbError> {
+    let first = db::fetch()?;
+    let second = load()?;
+    Ok(first + second)
+}
+
+fn main() {
+    let _ = cache::fetch();
+    match load_twice() {
+        Ok(value) => println!("{value}"),
+        Err(e) => println!("{e:?}"),
+    }
+}
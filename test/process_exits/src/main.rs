@@ -0,0 +1,52 @@
+use std::process;
+
+#[derive(Debug)]
+struct CliError;
+
+fn parse_args() -> Result<String, CliError> {
+    std::env::args().nth(1).ok_or(CliError)
+}
+
+fn open_input(path: &str) -> Result<Vec<u8>, CliError> {
+    std::fs::read(path).map_err(|_| CliError)
+}
+
+fn lock() -> Result<(), CliError> {
+    Ok(())
+}
+
+fn validate(input: &[u8]) -> Result<usize, CliError> {
+    if input.is_empty() {
+        Err(CliError)
+    } else {
+        Ok(input.len())
+    }
+}
+
+fn main() {
+    // The Err arm prints the error and exits
+    let path = match parse_args() {
+        Ok(path) => path,
+        Err(e) => {
+            eprintln!("Usage: cli <path> ({e:?})");
+            process::exit(2);
+        }
+    };
+
+    // The else block of a let-else exits
+    let Ok(input) = open_input(&path) else {
+        process::exit(1)
+    };
+
+    // The branch taken on an error aborts
+    if let Err(_) = lock() {
+        process::abort();
+    }
+
+    // Only one of the branches taken on an error exits
+    match validate(&input) {
+        Ok(len) => println!("{len}"),
+        Err(CliError) if input.len() > 1 => process::exit(3),
+        Err(e) => println!("{e:?}"),
+    }
+}
@@ -0,0 +1,42 @@
+use std::num::ParseIntError;
+
+struct Config {
+    port: String,
+}
+
+trait Validate {
+    fn validate(&self) -> Result<u16, ParseIntError>;
+}
+
+impl Config {
+    fn port(&self) -> Result<u16, ParseIntError> {
+        self.port.parse()
+    }
+}
+
+impl Validate for Config {
+    fn validate(&self) -> Result<u16, ParseIntError> {
+        let port = self.port()?;
+        Ok(port)
+    }
+}
+
+fn main() {
+    let config = Config {
+        port: String::from("8080"),
+    };
+    let _ = method_calls(&config);
+    let _ = path_calls(&config);
+}
+
+/// Calls written as method calls.
+fn method_calls(config: &Config) -> Result<u16, ParseIntError> {
+    config.port()?;
+    config.validate()
+}
+
+/// The same calls, written using (fully-qualified) paths.
+fn path_calls(config: &Config) -> Result<u16, ParseIntError> {
+    Config::port(config)?;
+    <Config as Validate>::validate(config)
+}
@@ -0,0 +1,30 @@
+use std::num::ParseIntError;
+
+fn parse(value: &str) -> Result<u32, ParseIntError> {
+    value.parse()
+}
+
+fn sum(values: &[&str]) -> Result<u32, ParseIntError> {
+    match values.split_first() {
+        Some((first, rest)) => Ok(parse(first)? + sum(rest)?),
+        None => Ok(0),
+    }
+}
+
+fn even(n: u32) -> Result<bool, ParseIntError> {
+    if n == 0 {
+        return Ok(true);
+    }
+    odd(n - 1)
+}
+
+fn odd(n: u32) -> Result<bool, ParseIntError> {
+    if n == 0 {
+        return parse("x").map(|_| false);
+    }
+    even(n - 1)
+}
+
+fn main() {
+    println!("{:?} {:?}", sum(&["1", "2"]), even(4));
+}
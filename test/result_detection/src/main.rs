@@ -0,0 +1,32 @@
+use core::result::Result as Outcome;
+
+#[derive(Debug)]
+struct ParseError;
+
+/// Result from core, imported under another name
+fn parse(text: &str) -> Outcome<u32, ParseError> {
+    text.parse().map_err(|_error| ParseError)
+}
+
+/// A type whose name merely contains the name of Result
+struct NotAResult<T>(T);
+
+fn wrap(value: u32) -> NotAResult<u32> {
+    NotAResult(value)
+}
+
+async fn lookup(key: &str) -> Option<u32> {
+    let value = parse(key).ok()?;
+    Some(wrap(value).0)
+}
+
+async fn lookup_twice(key: &str) -> Option<u32> {
+    let first = lookup(key).await?;
+    let second = lookup(key).await?;
+    Some(first + second)
+}
+
+fn main() {
+    // Not awaited, only analyzed
+    let _future = lookup_twice("1");
+}
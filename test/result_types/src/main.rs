@@ -0,0 +1,42 @@
+#[derive(Debug)]
+struct AppError;
+
+/// Alias fixing the error, expanded in the analyzed types
+type Res<T> = Result<T, ()>;
+
+/// Newtype around a Result, only recognized when listed using `--result-types`
+struct Outcome<T>(Result<T, AppError>);
+
+/// Result-like enum, whose last type argument is the error
+enum Attempt<T, E> {
+    Done(T),
+    Failed(E),
+}
+
+fn unit_error() -> Res<u32> {
+    Err(())
+}
+
+fn io_error() -> std::io::Result<String> {
+    std::fs::read_to_string("file.txt")
+}
+
+fn outcome() -> Outcome<u32> {
+    Outcome(Err(AppError))
+}
+
+fn attempt() -> Attempt<u32, AppError> {
+    Attempt::Failed(AppError)
+}
+
+fn main() {
+    let _ = unit_error();
+    let _ = io_error();
+    if let Outcome(Err(error)) = outcome() {
+        println!("{error:?}");
+    }
+    match attempt() {
+        Attempt::Done(value) => println!("{value}"),
+        Attempt::Failed(error) => println!("{error:?}"),
+    }
+}
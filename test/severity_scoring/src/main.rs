@@ -0,0 +1,42 @@
+use std::error::Error;
+use std::fs;
+use std::io;
+
+fn main() {
+    // Deep, crossing modules and type-erased, then unwrapped: most severe
+    server::handle_request().unwrap();
+
+    // Shallow and handled next to its origin: least severe
+    match read("local.txt") {
+        Ok(_) => {}
+        Err(e) => eprintln!("{e}"),
+    }
+
+    // Shallow but discarded
+    let _ = read("cache.txt");
+}
+
+fn read(path: &str) -> Result<String, io::Error> {
+    fs::read_to_string(path)
+}
+
+mod server {
+    use super::Error;
+
+    pub fn handle_request() -> Result<String, Box<dyn Error>> {
+        let body = db::query()?;
+        Ok(body)
+    }
+
+    mod db {
+        pub fn query() -> Result<String, std::io::Error> {
+            storage::load()
+        }
+
+        mod storage {
+            pub fn load() -> Result<String, std::io::Error> {
+                crate::read("db.txt")
+            }
+        }
+    }
+}
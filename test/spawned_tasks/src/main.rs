@@ -0,0 +1,60 @@
+use std::thread;
+
+#[derive(Debug)]
+struct SyncError;
+
+#[derive(Debug)]
+struct TaskError;
+
+fn sync_work(fail: bool) -> Result<u32, SyncError> {
+    if fail {
+        Err(SyncError)
+    } else {
+        Ok(1)
+    }
+}
+
+async fn task_work(fail: bool) -> Result<u32, TaskError> {
+    if fail {
+        Err(TaskError)
+    } else {
+        Ok(2)
+    }
+}
+
+async fn awaited_task() -> Result<u32, TaskError> {
+    let handle = tokio::spawn(async { task_work(true).await });
+    handle.await.unwrap()
+}
+
+async fn forgotten_task() {
+    tokio::spawn(async { task_work(false).await });
+}
+
+fn joined_thread() -> Result<u32, SyncError> {
+    let handle = thread::spawn(|| sync_work(true));
+    handle.join().unwrap()
+}
+
+fn detached_thread() {
+    let _ = thread::spawn(|| sync_work(false));
+}
+
+fn unused_handle() {
+    let _handle = thread::Builder::new()
+        .spawn(|| sync_work(true))
+        .unwrap();
+}
+
+fn main() {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    runtime.block_on(async {
+        let _ = awaited_task().await;
+        forgotten_task().await;
+    });
+    let _ = joined_thread();
+    detached_thread();
+    unused_handle();
+}
@@ -0,0 +1,41 @@
+use std::fs;
+use std::io;
+
+fn main() {
+    let _config = config::load();
+    cli::run();
+
+    // Accepted using a comment
+    let _name = read("name.txt").unwrap(); // error-analyzer: allow(unwrap)
+    let _ = read("cache.txt"); // error-analyzer: allow(discard, unwrap)
+
+    // Not accepted, as only discarding is allowed
+    let _data = read("data.txt").unwrap(); // error-analyzer: allow(discard)
+
+    // Not accepted
+    let _ = read("log.txt");
+}
+
+fn read(path: &str) -> Result<String, io::Error> {
+    fs::read_to_string(path)
+}
+
+mod config {
+    pub fn load() -> String {
+        // Ignored in the manifest
+        super::read("config.toml").unwrap()
+    }
+}
+
+mod cli {
+    pub fn run() {
+        // Ignored in the manifest
+        let _ = args::parse();
+    }
+
+    mod args {
+        pub fn parse() -> Result<String, std::io::Error> {
+            super::super::read("args.txt")
+        }
+    }
+}
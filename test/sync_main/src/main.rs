@@ -0,0 +1,30 @@
+use std::num::ParseIntError;
+
+#[derive(Debug)]
+enum AppError {
+    Parse(ParseIntError),
+}
+
+impl From<ParseIntError> for AppError {
+    fn from(error: ParseIntError) -> Self {
+        AppError::Parse(error)
+    }
+}
+
+fn parse(input: &str) -> Result<u32, ParseIntError> {
+    input.trim().parse()
+}
+
+fn load(input: &str) -> Result<u32, AppError> {
+    let value = parse(input)?;
+    Ok(value * 2)
+}
+
+/// The same program as `tokio_main`,
+/// without an async runtime.
+fn main() -> Result<(), AppError> {
+    let port = parse("8080").unwrap();
+    let workers = load("4")?;
+    println!("{port} {workers}");
+    Ok(())
+}
@@ -0,0 +1,11 @@
+use std::num::ParseIntError;
+
+// The crate cannot be parsed, so the compiler stops before it can be analyzed
+
+fn parse(input: &str) -> Result<i32, ParseIntError> {
+    input.parse(
+}
+
+fn main() {
+    parse("42").unwrap();
+}
@@ -0,0 +1,31 @@
+use std::fs;
+use std::io;
+
+fn read(path: &str) -> Result<String, io::Error> {
+    fs::read_to_string(path)
+}
+
+fn unwrapped() -> String {
+    read("a.txt").unwrap()
+}
+
+fn expected() -> String {
+    read("b.txt").expect("b.txt should exist")
+}
+
+fn discarded() {
+    let _ = read("c.txt");
+}
+
+fn handled() -> String {
+    match read("d.txt") {
+        Ok(content) => content,
+        Err(_) => String::new(),
+    }
+}
+
+fn main() {
+    let content = [unwrapped(), expected(), handled()].concat();
+    discarded();
+    println!("{content}");
+}
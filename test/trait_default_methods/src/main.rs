@@ -0,0 +1,38 @@
+#[derive(Debug)]
+struct LoadError;
+
+fn read_source(name: &str) -> Result<String, LoadError> {
+    if name.is_empty() {
+        Err(LoadError)
+    } else {
+        Ok(name.to_owned())
+    }
+}
+
+trait Loader {
+    fn name(&self) -> &str;
+
+    // Default method propagating an error from another local function
+    fn load(&self) -> Result<String, LoadError> {
+        let source = read_source(self.name())?;
+        Ok(source)
+    }
+}
+
+struct FileLoader;
+
+impl Loader for FileLoader {
+    fn name(&self) -> &str {
+        "file"
+    }
+}
+
+fn main() {
+    let loader = FileLoader;
+    // Handling the error here starts a chain through the default method
+    if let Err(e) = loader.load() {
+        eprintln!("{e:?}");
+    }
+    // Calling the default method again should not add a second node
+    let _ = loader.load();
+}
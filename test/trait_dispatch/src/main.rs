@@ -0,0 +1,50 @@
+use std::fs;
+use std::io;
+
+trait Source {
+    fn read(&self) -> Result<String, io::Error>;
+}
+
+struct FileSource {
+    path: String,
+}
+
+struct MemorySource {
+    content: String,
+}
+
+impl Source for FileSource {
+    fn read(&self) -> Result<String, io::Error> {
+        fs::read_to_string(&self.path)
+    }
+}
+
+impl Source for MemorySource {
+    fn read(&self) -> Result<String, io::Error> {
+        Ok(self.content.clone())
+    }
+}
+
+/// Called through generics, the implementation depends on the type it is called with.
+fn length<S: Source>(source: &S) -> Result<usize, io::Error> {
+    Ok(source.read()?.len())
+}
+
+/// Called through a trait object, the implementation is only known at runtime.
+fn length_dyn(source: &dyn Source) -> Result<usize, io::Error> {
+    Ok(source.read()?.len())
+}
+
+fn main() {
+    let file = FileSource {
+        path: String::from("input.txt"),
+    };
+    let memory = MemorySource {
+        content: String::from("abc"),
+    };
+
+    let file_length = length(&file).unwrap();
+    let memory_length = length(&memory).unwrap();
+    let dyn_length = length_dyn(&file).expect("the input should be readable");
+    println!("{file_length} {memory_length} {dyn_length}");
+}
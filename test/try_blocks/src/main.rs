@@ -0,0 +1,20 @@
+#![feature(try_blocks)]
+
+use std::num::ParseIntError;
+
+fn parse(value: &str) -> Result<u32, ParseIntError> {
+    value.parse()
+}
+
+fn sum_or_zero(a: &str, b: &str) -> u32 {
+    let sum: Result<u32, ParseIntError> = try { parse(a)? + parse(b)? };
+    sum.unwrap_or(0)
+}
+
+fn sum(a: &str, b: &str) -> Result<u32, ParseIntError> {
+    Ok(parse(a)? + parse(b)?)
+}
+
+fn main() {
+    println!("{} {:?}", sum_or_zero("1", "x"), sum("1", "2"));
+}
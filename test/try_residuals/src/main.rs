@@ -0,0 +1,55 @@
+use std::ops::ControlFlow;
+use std::task::Poll;
+
+#[derive(Debug)]
+struct ReadError;
+
+struct Reader {
+    remaining: u32,
+}
+
+impl Reader {
+    fn poll_read(&mut self) -> Poll<Result<u32, ReadError>> {
+        match self.remaining {
+            0 => Poll::Ready(Err(ReadError)),
+            n => {
+                self.remaining -= 1;
+                Poll::Ready(Ok(n))
+            }
+        }
+    }
+
+    fn poll_twice(&mut self) -> Poll<Result<u32, ReadError>> {
+        let Poll::Ready(first) = self.poll_read()? else {
+            return Poll::Pending;
+        };
+        let Poll::Ready(second) = self.poll_read()? else {
+            return Poll::Pending;
+        };
+        Poll::Ready(Ok(first + second))
+    }
+}
+
+fn check(value: u32) -> ControlFlow<String, u32> {
+    if value > 10 {
+        ControlFlow::Break(format!("{value} is too big"))
+    } else {
+        ControlFlow::Continue(value * 2)
+    }
+}
+
+fn check_twice(value: u32) -> ControlFlow<String, u32> {
+    let value = check(value)?;
+    check(value)
+}
+
+fn main() {
+    let mut reader = Reader { remaining: 3 };
+    if let Poll::Ready(Err(error)) = reader.poll_twice() {
+        println!("{error:?}");
+    }
+
+    if let ControlFlow::Break(reason) = check_twice(3) {
+        println!("{reason}");
+    }
+}
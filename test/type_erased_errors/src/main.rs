@@ -0,0 +1,29 @@
+use std::error::Error;
+use std::num::ParseIntError;
+
+fn parse(text: &str) -> Result<u16, ParseIntError> {
+    text.trim().parse()
+}
+
+fn read() -> Result<String, Box<dyn Error>> {
+    let text = std::fs::read_to_string("port.txt")?;
+    Ok(text)
+}
+
+fn port() -> Result<u16, Box<dyn Error + Send + Sync>> {
+    let text = std::fs::read_to_string("port.txt")?;
+    let port = parse(&text)?;
+    Ok(port)
+}
+
+fn config() -> Result<(String, u16), Box<dyn Error>> {
+    let name = read()?;
+    let port = port().map_err(|error| error as Box<dyn Error>)?;
+    Ok((name, port))
+}
+
+fn main() {
+    if let Err(error) = config() {
+        eprintln!("{error}");
+    }
+}
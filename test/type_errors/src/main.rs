@@ -0,0 +1,18 @@
+use std::num::ParseIntError;
+
+fn main() {
+    let _ = parse("1");
+    let _ = broken("2");
+}
+
+fn parse(input: &str) -> Result<u32, ParseIntError> {
+    let value = input.trim().parse::<u32>()?;
+    Ok(value)
+}
+
+/// This function does not compile, but the rest of the program should still be analyzed.
+fn broken(input: &str) -> Result<u32, ParseIntError> {
+    let doubled: String = parse(input)? * 2;
+    let halve = |value: u32| value / 2;
+    Ok(halve(doubled.len()))
+}
@@ -0,0 +1,45 @@
+use std::num::ParseIntError;
+
+#[derive(Debug)]
+enum ConfigError {
+    Io(std::io::Error),
+    Parse(ParseIntError),
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl From<std::io::Error> for ConfigError {
+    fn from(error: std::io::Error) -> Self {
+        ConfigError::Io(error)
+    }
+}
+
+fn read() -> Result<String, ConfigError> {
+    // Converted using From by the try operator
+    let text = std::fs::read_to_string("port.txt")?;
+    Ok(text)
+}
+
+fn parse(text: &str) -> Result<u16, ConfigError> {
+    // Converted using map_err, called as an associated function, which is not recognized
+    let port = Result::map_err(text.trim().parse::<u16>(), ConfigError::Parse)?;
+    Ok(port)
+}
+
+fn load() -> anyhow::Result<u16> {
+    let text = read()?;
+    let port = parse(&text)?;
+    Ok(port)
+}
+
+fn main() {
+    if let Err(error) = load() {
+        eprintln!("{error}");
+    }
+}
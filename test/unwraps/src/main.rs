@@ -0,0 +1,21 @@
+use std::num::ParseIntError;
+
+fn parse(value: &str) -> Result<u16, ParseIntError> {
+    value.trim().parse()
+}
+
+fn port() -> Result<u16, ParseIntError> {
+    let port = parse("8080")?;
+    Ok(port)
+}
+
+fn lookup(key: &str) -> Option<&'static str> {
+    (key == "host").then_some("localhost")
+}
+
+fn main() {
+    let port = port().expect("the port should be valid");
+    let retries = parse("3").unwrap();
+    let host = lookup("host").unwrap();
+    println!("{host}:{port} {retries}");
+}
@@ -0,0 +1,60 @@
+// A tree of 1111 functions whose 1000 leaves all call the same function, to check using `--verbose` that
+// building its call graph takes time linear in its size, as each function body is only explored once
+
+#[derive(Debug)]
+struct FanError;
+
+fn shared(n: u32) -> Result<u32, FanError> {
+    let n = validate(n)?;
+    Ok(n * 2)
+}
+
+fn validate(n: u32) -> Result<u32, FanError> {
+    if n > 100 {
+        return Err(FanError);
+    }
+    Ok(n)
+}
+
+// Each `x` adds a level of ten modules, each with a `call` function calling those of the level below
+macro_rules! fan_out {
+    () => {
+        pub fn call(n: u32) -> Result<u32, crate::FanError> {
+            crate::shared(n)
+        }
+    };
+    (x $($rest:tt)*) => {
+        pub mod m0 { fan_out! { $($rest)* } }
+        pub mod m1 { fan_out! { $($rest)* } }
+        pub mod m2 { fan_out! { $($rest)* } }
+        pub mod m3 { fan_out! { $($rest)* } }
+        pub mod m4 { fan_out! { $($rest)* } }
+        pub mod m5 { fan_out! { $($rest)* } }
+        pub mod m6 { fan_out! { $($rest)* } }
+        pub mod m7 { fan_out! { $($rest)* } }
+        pub mod m8 { fan_out! { $($rest)* } }
+        pub mod m9 { fan_out! { $($rest)* } }
+
+        pub fn call(n: u32) -> Result<u32, crate::FanError> {
+            Ok(m0::call(n)?
+                + m1::call(n)?
+                + m2::call(n)?
+                + m3::call(n)?
+                + m4::call(n)?
+                + m5::call(n)?
+                + m6::call(n)?
+                + m7::call(n)?
+                + m8::call(n)?
+                + m9::call(n)?)
+        }
+    };
+}
+
+mod tree {
+    fan_out! { x x x }
+}
+
+fn main() -> Result<(), FanError> {
+    tree::call(1)?;
+    Ok(())
+}
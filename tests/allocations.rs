@@ -0,0 +1,117 @@
+//! Tests of the memory used to write graphs, counting the bytes allocated by this test binary.
+
+use static_error_analyzer::graph::ChainNode;
+use static_error_analyzer::ChainGraph;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::io::{self, Write};
+
+/// An allocator counting the bytes allocated on threads that are measured.
+struct CountingAllocator;
+
+thread_local! {
+    /// The bytes allocated so far, currently allocated and allocated at most, if this thread is measured.
+    static COUNTS: Cell<Option<(usize, usize, usize)>> = const { Cell::new(None) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        count(layout.size() as isize);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        count(-(layout.size() as isize));
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        count(new_size as isize - layout.size() as isize);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Count an allocation (or a deallocation, if negative) of the current thread, if it is measured.
+fn count(size: isize) {
+    // The counts are not available while the thread is being torn down, those allocations are not measured
+    let _ = COUNTS.try_with(|counts| {
+        if let Some((total, current, peak)) = counts.get() {
+            let current = current.saturating_add_signed(size);
+            counts.set(Some((
+                total + size.max(0) as usize,
+                current,
+                peak.max(current),
+            )));
+        }
+    });
+}
+
+/// Run a function, returning the bytes it allocated in total and the most bytes it had allocated at once.
+fn measure(f: impl FnOnce()) -> (usize, usize) {
+    COUNTS.with(|counts| counts.set(Some((0, 0, 0))));
+    f();
+    let (total, _current, peak) = COUNTS.with(|counts| counts.take()).unwrap();
+    (total, peak)
+}
+
+/// A writer counting the bytes written to it, without keeping them.
+#[derive(Default)]
+struct CountingWriter(usize);
+
+impl Write for CountingWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Create a chain graph of a single chain of calls through `length` functions.
+fn long_chain(length: usize) -> ChainGraph {
+    let mut graph = ChainGraph::new(String::from("allocations"));
+    for i in 0..length {
+        let node = graph.add_node(&format!("app::module::function_{i}"));
+        if i > 0 {
+            graph.add_edge(
+                node - 1,
+                node,
+                Some("std::io::Error"),
+                Some(format!("src/module.rs:{i}:5")),
+            );
+        }
+    }
+    graph
+}
+
+#[test]
+fn writing_a_graph_does_not_copy_its_nodes() {
+    let length = 10_000;
+    let graph = long_chain(length);
+
+    let mut writer = CountingWriter::default();
+    let (total, peak) = measure(|| graph.write_dot(&mut writer).unwrap());
+
+    // The rendered nodes are borrowed, so less memory is held at once than copying them would take
+    let copied = length * std::mem::size_of::<ChainNode>();
+    assert!(
+        peak < copied / 2,
+        "{peak} bytes held, copying takes {copied}"
+    );
+    // The graph is streamed to the writer, each of its lines is formatted on its own
+    assert!(
+        peak < writer.0 / 5,
+        "{peak} bytes held, {} written",
+        writer.0
+    );
+    assert!(
+        total < writer.0 * 10,
+        "{total} bytes allocated, {} written",
+        writer.0
+    );
+}
@@ -0,0 +1,210 @@
+//! Tests of finding the rustc invocation of a crate in the output of `cargo build -v`.
+//! The Linux outputs in `tests/cargo_output/` are captured (with the home directory renamed),
+//! the macOS (using `-vv`) and Windows outputs are written after the format cargo prints there.
+
+use static_error_analyzer::cargo_invocation::{parse_build_output, split_args, TargetSelector};
+use static_error_analyzer::AnalyzerError;
+
+/// Read a captured output of `cargo build -v`.
+fn build_output(name: &str) -> String {
+    let path = format!(
+        "{}/tests/cargo_output/{name}.txt",
+        env!("CARGO_MANIFEST_DIR")
+    );
+    std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("Could not read {path}: {e}"))
+}
+
+/// Select the binary (or otherwise library) of a package that is run from the current directory.
+fn target(package: &str, bin: Option<&str>) -> TargetSelector {
+    TargetSelector {
+        package: package.to_owned(),
+        bin: bin.map(str::to_owned),
+        root_dir: String::new(),
+    }
+}
+
+/// Get the value following an option in the arguments.
+fn value_of<'a>(args: &'a [String], option: &str) -> Vec<&'a str> {
+    args.windows(2)
+        .filter(|pair| pair[0] == option)
+        .map(|pair| pair[1].as_str())
+        .collect()
+}
+
+#[test]
+fn linux_selects_the_binary() {
+    let args = parse_build_output(&build_output("linux"), &target("my-app", None)).unwrap();
+
+    assert!(args[0].ends_with("/bin/rustc"));
+    assert_eq!(value_of(&args, "--crate-name"), ["my_app"]);
+    assert_eq!(value_of(&args, "--crate-type"), ["bin"]);
+    assert!(args.contains(&String::from("src/main.rs")));
+    assert_eq!(
+        value_of(&args, "--cfg"),
+        ["feature=\"color\"", "feature=\"default\""]
+    );
+    assert_eq!(
+        value_of(&args, "--check-cfg"),
+        [
+            "cfg(docsrs)",
+            "cfg(feature, values(\"color\", \"default\"))"
+        ]
+    );
+}
+
+#[test]
+fn linux_selects_the_library_without_a_binary() {
+    let output = build_output("linux")
+        .lines()
+        .filter(|line| !line.contains("src/main.rs"))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let args = parse_build_output(&output, &target("my-app", None)).unwrap();
+
+    assert_eq!(value_of(&args, "--crate-type"), ["lib"]);
+    assert!(args.contains(&String::from("src/lib.rs")));
+}
+
+#[test]
+fn rewrites_the_error_format() {
+    let args = parse_build_output(&build_output("linux"), &target("my-app", None)).unwrap();
+
+    assert!(args.contains(&String::from("--error-format=short")));
+    assert!(!args.iter().any(|arg| arg.starts_with("--json=")));
+    assert!(!args.iter().any(|arg| arg == "--error-format=json"));
+}
+
+#[test]
+fn removing_json_keeps_the_next_argument() {
+    // Removing an argument while indexing used to skip the argument after it, leaving the JSON error format here
+    let output = "     Running `rustc --crate-name app src/main.rs --json=artifacts --error-format=json --crate-type bin`";
+    let args = parse_build_output(output, &target("app", None)).unwrap();
+
+    assert_eq!(
+        args,
+        [
+            "rustc",
+            "--crate-name",
+            "app",
+            "src/main.rs",
+            "--error-format=short",
+            "--crate-type",
+            "bin"
+        ]
+    );
+}
+
+#[test]
+fn prepends_the_root_dir_to_the_crate_root() {
+    let target = TargetSelector {
+        root_dir: String::from("projects/my-app/"),
+        ..target("my-app", None)
+    };
+    let args = parse_build_output(&build_output("linux"), &target).unwrap();
+
+    assert!(args.contains(&String::from("projects/my-app/src/main.rs")));
+    // Other paths are absolute, and left as they are
+    assert_eq!(
+        value_of(&args, "--extern"),
+        ["my_app=/home/dev/projects/my-app/target/debug/deps/libmy_app-8c4c732ba9cbc772.rlib"]
+    );
+}
+
+#[test]
+fn workspace_with_renamed_binary_and_spaces() {
+    let target = TargetSelector {
+        root_dir: String::from("../../"),
+        ..target("app", Some("app-cli"))
+    };
+    let args = parse_build_output(&build_output("workspace"), &target).unwrap();
+
+    assert_eq!(value_of(&args, "--crate-name"), ["app_cli"]);
+    assert!(args.contains(&String::from("../../crates/app/src/bin/cli.rs")));
+    assert_eq!(
+        value_of(&args, "--out-dir"),
+        ["/home/dev/my projects/target/debug/deps"]
+    );
+    assert_eq!(
+        value_of(&args, "-L"),
+        ["dependency=/home/dev/my projects/target/debug/deps"]
+    );
+}
+
+#[test]
+fn workspace_library_of_another_member() {
+    let args = parse_build_output(&build_output("workspace"), &target("util", None)).unwrap();
+
+    assert_eq!(value_of(&args, "--crate-type"), ["lib"]);
+    assert!(args.contains(&String::from("crates/util/src/lib.rs")));
+}
+
+#[test]
+fn macos_leaves_out_the_environment() {
+    let args = parse_build_output(&build_output("macos"), &target("parser", None)).unwrap();
+
+    assert_eq!(
+        args[0],
+        "/Users/dev/.rustup/toolchains/nightly-aarch64-apple-darwin/bin/rustc"
+    );
+    assert!(!args.iter().any(|arg| arg.starts_with("CARGO")));
+    assert_eq!(value_of(&args, "--crate-type"), ["lib"]);
+    assert_eq!(value_of(&args, "-C")[0], "embed-bitcode=no");
+}
+
+#[test]
+fn windows_quoting() {
+    let args = parse_build_output(&build_output("windows"), &target("my-app", None)).unwrap();
+
+    assert_eq!(
+        args[0],
+        r"C:\Users\Dev User\.rustup\toolchains\nightly-x86_64-pc-windows-msvc\bin\rustc.exe"
+    );
+    assert_eq!(value_of(&args, "--crate-name"), ["my_app"]);
+    assert!(args.contains(&String::from(r"src\main.rs")));
+    assert_eq!(
+        value_of(&args, "--cfg"),
+        ["feature=\"color\"", "feature=\"default\""]
+    );
+    assert_eq!(
+        value_of(&args, "--check-cfg"),
+        [
+            "cfg(docsrs)",
+            "cfg(feature, values(\"color\", \"default\"))"
+        ]
+    );
+    assert_eq!(
+        value_of(&args, "-L"),
+        [r"dependency=C:\Users\Dev User\projects\my-app\target\debug\deps"]
+    );
+}
+
+#[test]
+fn windows_absolute_crate_root_is_kept() {
+    let output =
+        r#"     Running `rustc.exe --crate-name app C:\src\app\src\main.rs --crate-type bin`"#;
+    let target = TargetSelector {
+        root_dir: String::from(r"..\app\"),
+        ..target("app", None)
+    };
+    let args = parse_build_output(output, &target).unwrap();
+
+    assert_eq!(args[3], r"C:\src\app\src\main.rs");
+}
+
+#[test]
+fn missing_target() {
+    let result = parse_build_output(&build_output("linux"), &target("other", None));
+
+    assert!(matches!(
+        result,
+        Err(AnalyzerError::RustcInvocationNotFound { package }) if package == "other"
+    ));
+}
+
+#[test]
+fn split_args_unix_quotes() {
+    assert_eq!(
+        split_args(r#"rustc 'it'\''s' '' "a b" c"#),
+        ["rustc", "it's", "", "a b", "c"]
+    );
+}
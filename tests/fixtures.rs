@@ -0,0 +1,1449 @@
+//! End-to-end tests, analyzing the fixture crates in `test/` and comparing the outputs with golden files.
+//! These need the nightly toolchain, so they only run using `cargo test --features integration`.
+#![cfg(feature = "integration")]
+#![feature(rustc_private)]
+
+extern crate rustc_hir;
+extern crate rustc_middle;
+
+mod harness;
+
+use regex::Regex;
+use rustc_hir::def_id::DefId;
+use rustc_middle::ty::TyCtxt;
+use static_error_analyzer::analysis::{FilterDecision, GraphKind};
+use static_error_analyzer::graph::{
+    CallEdge, Chain, ChainEnd, Downcast, ErrorFamily, Handling, ImplicitCall, NodeStyles,
+    Visibility,
+};
+use static_error_analyzer::{
+    AnalysisOptions, AnalysisResult, AnalyzerError, CallGraph, ChainGraph,
+};
+use std::path::Path;
+use std::process::Command;
+use std::sync::Mutex;
+
+#[test]
+fn result_detection() {
+    harness::check("result_detection", 2, "result_detection");
+}
+
+#[test]
+fn unwraps() {
+    harness::check("unwraps", 2, "unwraps");
+}
+
+#[test]
+fn map_err() {
+    harness::check("map_err", 1, "map_err");
+}
+
+#[test]
+fn mixed_handling() {
+    harness::check("mixed_handling", 5, "mixed_handling");
+}
+
+#[test]
+fn library_api() {
+    harness::check("library_api", 4, "library_api");
+}
+
+#[test]
+fn trait_dispatch() {
+    harness::check_expected("trait_dispatch");
+}
+
+#[test]
+fn closure_adapters() {
+    harness::check_expected("closure_adapters");
+}
+
+#[test]
+fn error_conversions() {
+    harness::check_expected("error_conversions");
+}
+
+#[test]
+fn terminations() {
+    harness::check_expected("terminations");
+}
+
+#[test]
+fn option_chains() {
+    harness::check_expected("option_chains");
+}
+
+#[test]
+fn let_else() {
+    harness::check_expected("let_else");
+}
+
+#[test]
+fn handled_errors() {
+    harness::check_expected("handled_errors");
+}
+
+#[test]
+fn discarded_results() {
+    harness::check_expected("discarded_results");
+}
+
+#[test]
+fn option_propagation() {
+    harness::check_expected("option_propagation");
+}
+
+#[test]
+fn panic_boundaries() {
+    harness::check_expected("panic_boundaries");
+}
+
+#[test]
+fn excluded_module_is_not_explored() {
+    let options = AnalysisOptions {
+        exclude_paths: vec![String::from("generated")],
+        ..AnalysisOptions::default()
+    };
+    let result = harness::analyze(&harness::fixture_dir("path_filters"), options);
+    let graph = &result.call_graph;
+
+    assert!(!(0..graph.nodes.len()).any(|node| graph.full_path(node).starts_with("generated::")));
+    harness::assert_edge(graph, "app::run", "vendor::checksum", true);
+    // The functions only called from within the excluded module are never reached, so not filtered either
+    assert_eq!(graph.filtered_fns, ["generated::parse_all"]);
+}
+
+/// The functions the node filter of `skipped_subtree_is_not_explored` is consulted for.
+static CONSULTED: Mutex<Vec<String>> = Mutex::new(vec![]);
+
+/// Skip the subtree of `generated::parse_all`, recording the functions the filter is consulted for.
+fn skip_parse_all(context: &TyCtxt, def_id: DefId) -> FilterDecision {
+    let path = context.def_path_str(def_id);
+    let decision = if path == "generated::parse_all" {
+        FilterDecision::SkipSubtree
+    } else {
+        FilterDecision::Keep
+    };
+    CONSULTED.lock().unwrap().push(path);
+    decision
+}
+
+#[test]
+fn skipped_subtree_is_not_explored() {
+    let options = AnalysisOptions {
+        node_filter: Some(skip_parse_all),
+        ..AnalysisOptions::default()
+    };
+    let result = harness::analyze(&harness::fixture_dir("path_filters"), options);
+
+    let consulted = CONSULTED.lock().unwrap();
+    assert!(consulted.contains(&String::from("generated::parse_all")));
+    assert!(consulted.contains(&String::from("vendor::checksum")));
+    // The body of the skipped function is never walked, so the functions it calls are never considered
+    assert!(!consulted.contains(&String::from("generated::parse_one")));
+    assert!(!consulted.contains(&String::from("generated::helpers::only_from_generated")));
+    // The filter is consulted once per function
+    let mut unique = consulted.clone();
+    unique.sort_unstable();
+    unique.dedup();
+    assert_eq!(unique.len(), consulted.len());
+
+    assert_eq!(result.call_graph.filtered_fns, ["generated::parse_all"]);
+}
+
+#[test]
+fn skipped_nodes_attribute_their_calls_to_the_caller() {
+    let options = AnalysisOptions {
+        include_paths: vec![String::from("app"), String::from("vendor::*")],
+        ..AnalysisOptions::default()
+    };
+    let result = harness::analyze(&harness::fixture_dir("path_filters"), options);
+    let graph = &result.call_graph;
+
+    assert!(!(0..graph.nodes.len()).any(|node| graph.full_path(node).starts_with("generated::")));
+    // The calls of the generated functions are made by the included function calling them
+    harness::assert_edge(graph, "app::run", "str::parse", true);
+    harness::assert_edge(graph, "app::run", "vendor::checksum", true);
+    assert_eq!(graph.filtered_fns.len(), 3);
+}
+
+#[test]
+fn functions_have_their_attributes() {
+    let result = harness::analyze(
+        &harness::fixture_dir("function_attributes"),
+        AnalysisOptions::default(),
+    );
+    let graph = &result.call_graph;
+    let node = |label: &str| {
+        let node = (0..graph.nodes.len())
+            .find(|node| graph.label(*node) == label)
+            .unwrap_or_else(|| panic!("No function {label}"));
+        &graph.nodes[node]
+    };
+
+    assert!(node("fetch").is_async && !node("fetch").is_unsafe);
+    assert!(node("read_raw").is_unsafe && !node("read_raw").is_async);
+    assert_eq!(node("config::load").visibility, Some(Visibility::Public));
+    assert_eq!(
+        node("config::defaults::offset").visibility,
+        Some(Visibility::Crate)
+    );
+    assert_eq!(node("config::parse").visibility, Some(Visibility::Private));
+    assert!(!(0..graph.nodes.len()).any(|node| graph.nodes[node].is_test));
+
+    // Unsafe functions are dashed by default, and other attributes can be styled instead
+    let mut dot = vec![];
+    graph.write_dot(&mut dot).unwrap();
+    let dot = String::from_utf8(dot).unwrap();
+    assert!(dot.contains(&format!("n{}[style=\"dashed\"]", node("read_raw").id())));
+
+    let mut styles = NodeStyles::default();
+    styles.set("unsafe", "").unwrap();
+    styles.set("async", "dotted").unwrap();
+    assert!(styles.set("const", "bold").is_err());
+    let mut dot = vec![];
+    graph.write_dot_with_styles(&mut dot, &styles).unwrap();
+    let dot = String::from_utf8(dot).unwrap();
+    assert!(!dot.contains(&format!("n{}[style=\"dashed\"]", node("read_raw").id())));
+    assert!(dot.contains(&format!("n{}[style=\"dotted\"]", node("fetch").id())));
+}
+
+#[test]
+fn must_use_calls_and_track_caller_functions() {
+    let fixture_dir = harness::fixture_dir("must_use_track_caller");
+    let result = harness::analyze(&fixture_dir, AnalysisOptions::default());
+    let graph = &result.call_graph;
+
+    // The function, or the type in its `Result`, is `#[must_use]`, but a `Result` alone does not count
+    assert!(harness::assert_edge(graph, "main", "write", true).must_use);
+    assert!(harness::assert_edge(graph, "main", "connect", true).must_use);
+    assert!(!harness::assert_edge(graph, "main", "log", true).must_use);
+
+    let port_of = (0..graph.nodes.len())
+        .find(|node| graph.label(*node) == "port_of")
+        .unwrap();
+    assert!(graph.nodes[port_of].track_caller);
+
+    // Panics of `#[track_caller]` functions are reported at the calls to them
+    let options = AnalysisOptions {
+        graph: GraphKind::Panic,
+        ..AnalysisOptions::default()
+    };
+    let result = harness::analyze(&fixture_dir, options);
+    let mut dot = vec![];
+    result.chain_graph.write_dot(&mut dot).unwrap();
+    let dot = String::from_utf8(dot).unwrap();
+    assert!(dot.contains("label=\"panic (#[track_caller])\""));
+}
+
+#[test]
+fn generic_callers_type_calls_with_their_own_parameters() {
+    let result = harness::analyze(
+        &harness::fixture_dir("generic_callers"),
+        AnalysisOptions::default(),
+    );
+    let graph = &result.call_graph;
+
+    // The error is that of the caller's `T`, rather than of the generic signature of `str::parse`
+    for caller in ["parse_first", "parse_all"] {
+        let call = harness::assert_edge(graph, caller, "str::parse", true);
+        assert_eq!(graph.ty(call), Some("<T as std::str::FromStr>::Err"));
+        assert!(!call.type_approximate);
+    }
+    let call = harness::assert_edge(graph, "main", "parse_first", true);
+    assert_eq!(graph.ty(call), Some("std::num::ParseIntError"));
+
+    // A body that does not compile only has the generic signature of the functions it calls
+    let result = harness::analyze(
+        &harness::fixture_dir("type_errors"),
+        AnalysisOptions::default(),
+    );
+    let call = harness::assert_edge(&result.call_graph, "broken", "std::prelude::v1::Ok", true);
+    assert_eq!(result.call_graph.ty(call), Some("E"));
+    assert!(call.type_approximate);
+}
+
+#[test]
+fn errors_returned_from_main_reach_the_process_exit() {
+    let result = harness::analyze(
+        &harness::fixture_dir("exit_chains"),
+        AnalysisOptions::default(),
+    );
+    let graph = &result.call_graph;
+
+    // The process exit receives the error of the `Result` returned by `main`
+    let exit = graph.exit_node.unwrap();
+    assert_eq!(graph.label(exit), "process exit");
+    let call = harness::assert_edge(graph, "process exit", "main", true);
+    assert_eq!(graph.ty(call), Some("MyError"));
+
+    // The chains propagating their error out of `main` end at it
+    let mut dot = vec![];
+    result.chain_graph.write_dot(&mut dot).unwrap();
+    let dot = String::from_utf8(dot).unwrap();
+    assert!(dot.contains("[label=\"process exit\"][color=\"red\"][shape=\"doubleoctagon\"]"));
+    let returned: Vec<&Chain> = result
+        .chain_graph
+        .chains
+        .iter()
+        .filter(|chain| chain.termination == ChainEnd::ReturnedFromMain)
+        .collect();
+    assert_eq!(returned.len(), 6);
+    for chain in returned {
+        let end = chain.calls.last().unwrap();
+        let callers = graph.get_incoming_edges(end.from);
+        assert!(callers.iter().all(|caller| caller.from == exit));
+    }
+
+    // An entry point returning nothing has no process exit
+    let result = harness::analyze(
+        &harness::fixture_dir("error_variants"),
+        AnalysisOptions::default(),
+    );
+    assert_eq!(result.call_graph.exit_node, None);
+}
+
+#[test]
+fn no_main_binaries_are_analyzed_from_the_given_entry() {
+    let fixture_dir = harness::fixture_dir("no_main");
+    let error = harness::try_analyze(&fixture_dir, AnalysisOptions::default()).unwrap_err();
+    assert!(matches!(error, AnalyzerError::EntryFnMissing { .. }));
+
+    let options = AnalysisOptions {
+        entry: Some(String::from("no_main::reset_handler")),
+        ..AnalysisOptions::default()
+    };
+    let result = harness::analyze(&fixture_dir, options);
+    let graph = &result.call_graph;
+    assert_eq!(graph.label(graph.entry_node.unwrap()), "reset_handler");
+    assert_eq!(graph.exit_node, None);
+    harness::assert_edge(graph, "reset_handler", "init", true);
+    assert_eq!(result.chain_graph.chains.len(), 1);
+
+    // The functions of the same name are suggested for a path that is not found
+    let options = AnalysisOptions {
+        entry: Some(String::from("boot::reset_handler")),
+        ..AnalysisOptions::default()
+    };
+    match harness::try_analyze(&fixture_dir, options) {
+        Err(AnalyzerError::EntryFnNotFound { path, candidates }) => {
+            assert_eq!(path, "boot::reset_handler");
+            assert_eq!(candidates, ["reset_handler"]);
+        }
+        other => panic!("Expected the entry function not to be found, got {other:?}"),
+    }
+}
+
+#[test]
+fn const_items_are_walked_when_analyzing_all_functions() {
+    let fixture_dir = harness::fixture_dir("const_items");
+    let result = harness::analyze(&fixture_dir, AnalysisOptions::default());
+    let graph = &result.call_graph;
+    assert!(!(0..graph.nodes.len()).any(|node| graph.label(node) == "parse_port"));
+
+    let options = AnalysisOptions {
+        all_functions: true,
+        ..AnalysisOptions::default()
+    };
+    let result = harness::analyze(&fixture_dir, options);
+    let graph = &result.call_graph;
+    for item in ["Config::DEFAULT", "FALLBACK_PORT"] {
+        let call = harness::assert_edge(graph, item, "parse_port", true);
+        assert!(call.const_evaluated);
+        assert_eq!(call.handling, Handling::Handled);
+    }
+    harness::assert_edge(graph, "Level::Low discriminant", "level_base", false);
+    harness::assert_edge(graph, "Level::High discriminant", "level_base", false);
+
+    let mut dot = vec![];
+    graph.write_dot(&mut dot).unwrap();
+    let dot = String::from_utf8(dot).unwrap();
+    assert!(dot.contains("[label=\"ConfigError (const context) (handled)\"]"));
+    assert!(!dot.contains("const-evaluated"));
+}
+
+#[test]
+fn io_errors_are_labeled_with_their_kinds() {
+    let result = harness::analyze(
+        &harness::fixture_dir("io_error_kinds"),
+        AnalysisOptions::default(),
+    );
+    let graph = &result.call_graph;
+    let kinds = |from: &str, to: &str| {
+        let call = harness::assert_edge(graph, from, to, true);
+        call.io_error_kinds.as_deref()
+    };
+
+    // The documented kinds of std functions, including trait methods
+    assert_eq!(
+        kinds("open_config", "std::fs::File::open"),
+        Some("NotFound, PermissionDenied")
+    );
+    assert_eq!(
+        kinds("read_header", "std::io::Read::read_exact"),
+        Some("UnexpectedEof")
+    );
+    // The kinds constructed locally, combined with those of the propagated calls
+    assert_eq!(
+        kinds("load", "check_name"),
+        Some("InvalidInput, Unsupported")
+    );
+    assert_eq!(
+        kinds("main", "read_header"),
+        Some("NotFound, PermissionDenied, UnexpectedEof")
+    );
+    // The kind of an error of the operating system is not known
+    let call = harness::assert_edge(graph, "main", "last_error", true);
+    assert_eq!(call.io_error_kinds.as_deref(), Some("io"));
+    assert_eq!(
+        graph.type_label(call).as_deref(),
+        Some("std::io::Error (io)")
+    );
+}
+
+#[test]
+fn errors_only_logged_are_logged_and_dropped() {
+    let fixture_dir = harness::fixture_dir("logged_errors");
+    let result = harness::analyze(&fixture_dir, AnalysisOptions::default());
+    let graph = &result.call_graph;
+    let handling = |to: &str| harness::assert_edge(graph, "main", to, true).handling;
+    assert_eq!(handling("read_config"), Handling::LoggedAndDropped);
+    assert_eq!(handling("connect"), Handling::LoggedAndDropped);
+    // Calling a function is not logging, unless it is passed with --log-fns
+    assert_eq!(handling("load_cache"), Handling::Handled);
+    // Exiting after logging the error ends the process
+    assert_eq!(handling("sync"), Handling::ProcessExit);
+
+    let options = AnalysisOptions {
+        log_fns: vec![String::from("report")],
+        ..AnalysisOptions::default()
+    };
+    let result = harness::analyze(&fixture_dir, options);
+    let graph = &result.call_graph;
+    let call = harness::assert_edge(graph, "main", "load_cache", true);
+    assert_eq!(call.handling, Handling::LoggedAndDropped);
+
+    let stats = &result.chain_graph.stats;
+    assert!(stats.endings.contains(&(ChainEnd::LoggedAndDropped, 3)));
+    assert!(stats
+        .to_string()
+        .contains("3 chains end in an error that is logged and dropped."));
+    let main = stats
+        .functions
+        .iter()
+        .find(|summary| summary.function == "main")
+        .unwrap();
+    assert_eq!((main.handled, main.logged, main.exited), (0, 3, 1));
+}
+
+#[test]
+fn errors_ending_the_process_are_process_exits() {
+    let result = harness::analyze(
+        &harness::fixture_dir("process_exits"),
+        AnalysisOptions::default(),
+    );
+    let graph = &result.call_graph;
+    let handling = |to: &str| harness::assert_edge(graph, "main", to, true).handling;
+    // Exiting from a match arm, a let-else or an if let, also after printing the error
+    assert_eq!(handling("parse_args"), Handling::ProcessExit);
+    assert_eq!(handling("open_input"), Handling::ProcessExit);
+    assert_eq!(handling("lock"), Handling::ProcessExit);
+    // Errors taking a branch that does not exit are handled
+    assert_eq!(handling("validate"), Handling::Handled);
+
+    let stats = &result.chain_graph.stats;
+    assert!(stats.endings.contains(&(ChainEnd::ProcessExit, 3)));
+    assert!(stats
+        .to_string()
+        .contains("3 chains end in an error that is handled by exiting."));
+    let main = stats
+        .functions
+        .iter()
+        .find(|summary| summary.function == "main")
+        .unwrap();
+    assert_eq!((main.handled, main.exited), (1, 3));
+
+    let mut dot = vec![];
+    result.chain_graph.write_dot(&mut dot).unwrap();
+    let dot = String::from_utf8(dot).unwrap();
+    assert!(dot.contains("[color=\"firebrick\"]"));
+}
+
+#[test]
+fn downcasts_of_type_erased_errors_are_tracked() {
+    let options = AnalysisOptions {
+        chain_types: true,
+        ..AnalysisOptions::default()
+    };
+    let result = harness::analyze(&harness::fixture_dir("downcasts"), options);
+    let graph = &result.call_graph;
+    let main = (0..graph.nodes.len())
+        .find(|node| graph.label(*node) == "main")
+        .unwrap();
+    assert_eq!(
+        graph.nodes[main].downcasts,
+        [
+            Downcast {
+                ty: String::from("SqlxError"),
+                family: Some(ErrorFamily::Anyhow),
+            },
+            Downcast {
+                ty: String::from("ParseError"),
+                family: None,
+            },
+        ]
+    );
+
+    // Each chain gets the type regained from its own kind of type-erased error
+    let stats = &result.chain_graph.stats;
+    let downcasts = |tail: &str| {
+        let summary = stats
+            .deepest_chains
+            .iter()
+            .find(|summary| summary.tail == tail)
+            .unwrap_or_else(|| panic!("No chain from {tail}"));
+        summary.downcasts.clone()
+    };
+    assert_eq!(downcasts("query"), ["SqlxError"]);
+    assert_eq!(downcasts("core::str::<impl str>::parse"), ["ParseError"]);
+    assert!(stats
+        .to_string()
+        .contains("main ← query (depth 3, 6 calls): handled, downcasts to SqlxError, originates"));
+
+    let mut dot = vec![];
+    result.chain_graph.write_dot(&mut dot).unwrap();
+    let dot = String::from_utf8(dot).unwrap();
+    assert!(dot.contains("main\\ndowncasts to SqlxError\\nin: anyhow::Error"));
+}
+
+#[test]
+fn async_runtime_entry_points_are_rooted_at_their_body() {
+    let chain_dot = |fixture: &str| {
+        let result = harness::analyze(&harness::fixture_dir(fixture), AnalysisOptions::default());
+        let mut dot = vec![];
+        result.chain_graph.write_dot(&mut dot).unwrap();
+        (result.call_graph, String::from_utf8(dot).unwrap())
+    };
+    let (sync_graph, sync_dot) = chain_dot("sync_main");
+    let (tokio_graph, tokio_dot) = chain_dot("tokio_main");
+
+    // The calls setting up the runtime are left out, the calls of the body are made by `main` itself
+    let root = tokio_graph.entry_node.unwrap();
+    assert_eq!(tokio_graph.label(root), "main");
+    assert!(!(0..tokio_graph.nodes.len())
+        .any(|node| tokio_graph.full_path(node).starts_with("tokio::")));
+    harness::assert_edge(&tokio_graph, "main", "load", true);
+    assert_eq!(tokio_graph.nodes.len(), sync_graph.nodes.len());
+    // The program exit is located at `fn main`, which follows `async` in the tokio fixture
+    let tokio_dot = tokio_dot
+        .replace("tokio_main", "sync_main")
+        .replace("main.rs:25:7", "main.rs:25:1");
+    assert_eq!(tokio_dot, sync_dot);
+}
+
+#[test]
+fn graphs_round_trip() {
+    for fixture in ["trait_dispatch", "closure_adapters", "mixed_handling"] {
+        let result = harness::analyze(&harness::fixture_dir(fixture), AnalysisOptions::default());
+
+        let json = serde_json::to_string(&result.call_graph).unwrap();
+        let call_graph: CallGraph = serde_json::from_str(&json).unwrap();
+        assert!(result.call_graph.is_resolved() && !call_graph.is_resolved());
+        assert_eq!(
+            serde_json::to_string(&call_graph).unwrap(),
+            json,
+            "The call graph of {fixture} changed by deserializing it"
+        );
+        // The functions are identified by the same stable paths
+        for node in 0..call_graph.nodes.len() {
+            assert_eq!(
+                call_graph.stable_path(node),
+                result.call_graph.stable_path(node)
+            );
+        }
+
+        let json = serde_json::to_string(&result.chain_graph).unwrap();
+        let chain_graph: ChainGraph = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            serde_json::to_string(&chain_graph).unwrap(),
+            json,
+            "The chain graph of {fixture} changed by deserializing it"
+        );
+    }
+}
+
+#[test]
+fn calls_of_followed_dependencies_are_located_in_their_source() {
+    let options = AnalysisOptions {
+        follow_deps: vec![String::from("errlib")],
+        ..AnalysisOptions::default()
+    };
+    let result = harness::analyze(&harness::fixture_dir("follow_deps"), options);
+    let graph = &result.call_graph;
+
+    // The calls are found in the MIR of the dependency, which has the type and span of each call
+    let call = harness::assert_edge(graph, "errlib::sum", "errlib::parse", true);
+    assert_eq!(graph.ty(call), Some("std::num::ParseIntError"));
+    assert!(!call.type_approximate);
+    let location = call.location.as_deref().unwrap();
+    assert!(
+        location.ends_with("errlib/src/lib.rs:8:8"),
+        "{location} is not the call in errlib"
+    );
+
+    // Calls in the MIR keep their id when serialized
+    let json = serde_json::to_string(graph).unwrap();
+    let call_graph: CallGraph = serde_json::from_str(&json).unwrap();
+    assert_eq!(serde_json::to_string(&call_graph).unwrap(), json);
+}
+
+#[test]
+fn calls_from_local_macros_are_located_at_the_invocation() {
+    let result = harness::analyze(
+        &harness::fixture_dir("macro_calls"),
+        AnalysisOptions::default(),
+    );
+    let graph = &result.call_graph;
+
+    // `read` is called in the definition of `read_all!` (line 17), which is invoked on line 28
+    let locations: Vec<&str> = graph
+        .edges
+        .iter()
+        .filter(|edge| graph.label(edge.from) == "run" && graph.label(edge.to) == "read")
+        .filter_map(|edge| edge.location.as_deref()?.split("src/main.rs:").nth(1))
+        .collect();
+    assert_eq!(locations, ["26:20", "28:17", "28:17"]);
+}
+
+#[test]
+fn macro_wrapped_and_multi_line_calls_are_resolved() {
+    let result = harness::analyze(
+        &harness::fixture_dir("call_resolution"),
+        AnalysisOptions::default(),
+    );
+    let graph = &result.call_graph;
+
+    // `validate` is called in the expansion of `checked!`, on line 58
+    let validate = harness::assert_edge(graph, "run", "validate", true);
+    let location = validate.location.as_deref().unwrap();
+    assert!(location.ends_with("src/main.rs:58:22"), "{location}");
+
+    // `build` ends a call expression spanning several lines, its error is propagated
+    let build = harness::assert_edge(graph, "run", "RequestBuilder::build", true);
+    assert!(build.propagates);
+    assert_eq!(build.handling, Handling::Propagated);
+    harness::assert_edge(graph, "RequestBuilder::build", "str::parse", true);
+}
+
+/// Analyze a fixture that cannot be analyzed, and check that the error it fails with starts with `message` and has a hint.
+fn analyze_error(fixture: &str, message: &str) -> AnalyzerError {
+    let error = harness::try_analyze(&harness::fixture_dir(fixture), AnalysisOptions::default())
+        .expect_err(fixture);
+    let text = error.to_string();
+    assert!(
+        text.starts_with(message),
+        "Unexpected error for {fixture}: {text}"
+    );
+    assert!(!error.hint().is_empty());
+    error
+}
+
+#[test]
+fn missing_manifest_is_not_found() {
+    let error = analyze_error("does_not_exist", "Could not read manifest");
+    assert!(matches!(error, AnalyzerError::ManifestNotFound { .. }));
+    assert!(std::error::Error::source(&error).is_some());
+    assert!(error.hint().contains("Cargo.toml"));
+}
+
+#[test]
+fn invalid_manifest_is_reported() {
+    let error = analyze_error("invalid_manifest", "Invalid manifest");
+    assert!(matches!(error, AnalyzerError::ManifestInvalid { .. }));
+    assert!(error.hint().contains("TOML"));
+}
+
+#[test]
+fn failing_cargo_build_is_reported_with_its_errors() {
+    let error = analyze_error("missing_dependency", "`cargo build --manifest-path");
+    match &error {
+        AnalyzerError::CargoFailed { stderr, .. } => {
+            assert!(stderr.contains("failed to get `missing`"), "{stderr}")
+        }
+        other => panic!("Expected cargo to fail, got {other:?}"),
+    }
+    assert!(error.hint().contains("cargo build"));
+}
+
+#[test]
+fn proc_macro_crates_have_no_rustc_invocation() {
+    let error = analyze_error("proc_macro_only", "Could not find how cargo compiles");
+    assert!(
+        matches!(&error, AnalyzerError::RustcInvocationNotFound { package } if package == "proc_macro_only")
+    );
+    assert!(error.hint().contains("procedural macro"));
+}
+
+#[test]
+fn binaries_without_main_are_reported() {
+    let error = analyze_error(
+        "missing_main",
+        "Binary crate missing_main has no main function",
+    );
+    assert!(matches!(error, AnalyzerError::EntryFnMissing { .. }));
+    assert!(error.hint().contains("--entry"));
+}
+
+#[test]
+fn unknown_entry_is_reported_with_its_candidates() {
+    let options = AnalysisOptions {
+        entry: Some(String::from("boot::reset_handler")),
+        ..AnalysisOptions::default()
+    };
+    let error = harness::try_analyze(&harness::fixture_dir("no_main"), options).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Entry function boot::reset_handler is not a local function, candidates:\n  reset_handler"
+    );
+    assert!(error.hint().contains("--entry"));
+}
+
+#[test]
+fn syntax_errors_stop_the_compiler() {
+    let error = analyze_error("syntax_error", "The compiler exited with code");
+    assert!(matches!(error, AnalyzerError::CompilerFailed { exit_code } if exit_code != 0));
+    assert!(error.hint().contains("compiler"));
+}
+
+#[test]
+fn unwritable_output_is_reported() {
+    // Neither the output directory nor the temporary directory the graph falls back to exist
+    let missing_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("does_not_exist");
+    let output = Command::new(env!("CARGO_BIN_EXE_static-result-analyzer"))
+        .current_dir(harness::fixture_dir("map_err"))
+        .env("TMPDIR", &missing_dir)
+        .arg("Cargo.toml")
+        .arg(missing_dir.join("graph.dot"))
+        .arg("--no-cache")
+        .output()
+        .expect("Could not run the analyzer!");
+    assert!(!output.status.success());
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains(&format!(
+            "Error: Could not write output to {}",
+            missing_dir.join("graph.dot").display()
+        )),
+        "{stderr}"
+    );
+    assert!(
+        stderr.contains("Hint: check that the directory of the output path exists"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn output_written_to_the_temporary_directory_is_still_reported() {
+    // Only the output directory is missing, so the graph is written to the temporary directory
+    let temp_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("fallback_output");
+    std::fs::create_dir_all(&temp_dir).expect("Could not create the temporary directory!");
+    let missing_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("does_not_exist");
+    let output = Command::new(env!("CARGO_BIN_EXE_static-result-analyzer"))
+        .current_dir(harness::fixture_dir("map_err"))
+        .env("TMPDIR", &temp_dir)
+        .arg("Cargo.toml")
+        .arg(missing_dir.join("graph.dot"))
+        .arg("--no-cache")
+        .output()
+        .expect("Could not run the analyzer!");
+    assert!(!output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let temp_path = temp_dir.join("graph.dot");
+    assert!(
+        stdout.contains(&format!("Wrote to {} instead", temp_path.display())),
+        "{stdout}"
+    );
+    assert!(temp_path.exists());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains(&format!(
+            "Error: Could not write output to {}",
+            missing_dir.join("graph.dot").display()
+        )),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn nested_error_types_are_extracted_whole() {
+    let result = harness::analyze(
+        &harness::fixture_dir("nested_errors"),
+        AnalysisOptions::default(),
+    );
+    let graph = &result.call_graph;
+
+    // The error is the second argument of the outermost Result, however deep its own arguments nest
+    let expected = [
+        (
+            "outer_only",
+            "nested",
+            "std::result::Result<u8, std::string::String>",
+        ),
+        ("tuple_error", "tuple", "(u8, std::string::String)"),
+        (
+            "map_error",
+            "map",
+            "std::collections::HashMap<std::string::String, std::vec::Vec<u32>>",
+        ),
+        ("generic_error", "coded", "CodedError<u16, &str>"),
+        // The Result is found inside the Option the function returns
+        (
+            "wrapped",
+            "optional",
+            "(std::string::String, std::result::Result<u8, u16>)",
+        ),
+        (
+            "main",
+            "wrapped",
+            "(std::string::String, std::result::Result<u8, u16>)",
+        ),
+    ];
+    for (from, to, ty) in expected {
+        let call = harness::assert_edge(graph, from, to, true);
+        assert_eq!(graph.ty(call), Some(ty), "Unexpected error type of {to}");
+        assert!(!call.type_approximate);
+    }
+}
+
+#[test]
+fn callees_are_labeled_by_the_type_they_are_called_on() {
+    let result = harness::analyze(
+        &harness::fixture_dir("callee_labels"),
+        AnalysisOptions::default(),
+    );
+    let graph = &result.call_graph;
+
+    // Trait methods get a node per type they are called on, whose label is shortened from the full path of the method
+    let expected = [
+        ("run", "std::fs::read_to_string", true, None),
+        (
+            "run",
+            "str::parse",
+            true,
+            Some("core::str::<impl str>::parse"),
+        ),
+        (
+            "run",
+            "std::vec::Vec<T>::with_capacity",
+            false,
+            Some("std::vec::Vec::<T>::with_capacity"),
+        ),
+        (
+            "run",
+            "std::fs::File as std::io::Read::read_to_end",
+            true,
+            Some("std::io::Read::read_to_end"),
+        ),
+        (
+            "run",
+            "std::io::Stdout as std::io::Write::flush",
+            true,
+            Some("std::io::Write::flush"),
+        ),
+        (
+            "run",
+            "std::boxed::Box<dyn std::io::Write> as std::io::Write::flush",
+            true,
+            Some("std::io::Write::flush"),
+        ),
+        (
+            "write_to",
+            "dyn std::io::Write::flush",
+            true,
+            Some("std::io::Write::flush"),
+        ),
+        // Called through a generic parameter, the method is not resolved to a type
+        ("read_from", "std::io::Read::read_to_string", true, None),
+    ];
+    for (from, label, is_error, path) in expected {
+        let call = harness::assert_edge(graph, from, label, is_error);
+        assert_eq!(graph.nodes[call.to].path.as_deref(), path, "{label}");
+    }
+
+    // The edges are labeled by the error type, and how it is converted or dealt with
+    let mut dot = vec![];
+    graph.write_dot(&mut dot).unwrap();
+    let dot = String::from_utf8(dot).unwrap();
+    let node = |label: &str| {
+        (0..graph.nodes.len())
+            .find(|node| graph.label(*node) == label)
+            .unwrap()
+    };
+    let edge_label = |from: &str, to: &str| {
+        let prefix = format!("    n{} -> n{}[label=\"", node(from), node(to));
+        let line = dot
+            .lines()
+            .find(|line| line.starts_with(&prefix))
+            .unwrap_or_else(|| panic!("No edge from {from} to {to} in:\n{dot}"));
+        line[prefix.len()..].split('"').next().unwrap().to_owned()
+    };
+    assert_eq!(
+        edge_label("run", "str::parse"),
+        "std::num::ParseIntError → std::io::Error"
+    );
+    assert_eq!(edge_label("run", "str::trim"), "&str");
+    assert_eq!(
+        edge_label("write_to", "dyn std::io::Write::write_all"),
+        "std::io::Error (WriteZero)"
+    );
+    assert!(edge_label("main", "run").ends_with(" (discarded)"));
+
+    // The full path of a shortened label is its tooltip
+    let tooltip = format!(
+        "    n{}[tooltip=\"std::io::Read::read_to_end\"];",
+        node("std::fs::File as std::io::Read::read_to_end")
+    );
+    assert!(dot.lines().any(|line| line == tooltip), "{dot}");
+    let unshortened = format!("    n{}[tooltip=", node("std::fs::read_to_string"));
+    assert!(!dot.contains(&unshortened));
+}
+
+#[test]
+fn cached_call_graph_is_shown_with_other_chain_options() {
+    let fixture_dir = harness::fixture_dir("mixed_handling");
+    harness::analyze_cached(&fixture_dir, AnalysisOptions::default());
+
+    // Only keeping the chains of an error type, and coloring the call graph by them, does not need the compiler again
+    let options = || AnalysisOptions {
+        error_filters: vec![Regex::new("ParseIntError").unwrap()],
+        heatmap: true,
+        ..AnalysisOptions::default()
+    };
+    let cached = harness::analyze_cached(&fixture_dir, options());
+    assert!(cached.cache_path.is_some());
+    let analyzed = harness::analyze(&fixture_dir, options());
+    assert!(analyzed.cache_path.is_none());
+
+    assert!((1..5).contains(&cached.chain_graph.stats.count));
+    assert_eq!(
+        serde_json::to_string(&cached.chain_graph).unwrap(),
+        serde_json::to_string(&analyzed.chain_graph).unwrap()
+    );
+    let chain_counts = |result: &AnalysisResult| {
+        let graph = &result.call_graph;
+        (0..graph.nodes.len())
+            .map(|node| graph.nodes[node].chain_count)
+            .collect::<Vec<usize>>()
+    };
+    assert_eq!(chain_counts(&cached), chain_counts(&analyzed));
+}
+
+#[test]
+fn accepted_error_losses_are_counted_apart() {
+    let fixture_dir = harness::fixture_dir("suppression");
+    let discarded_calls = |result: &AnalysisResult| -> Vec<String> {
+        let source_dir = fixture_dir.join("src");
+        let stats = &result.chain_graph.stats;
+        stats
+            .discarded_calls
+            .iter()
+            .map(|call| call.replace(&source_dir.display().to_string(), "src"))
+            .collect()
+    };
+
+    // Only the unwrap and discard allowed by a comment are accepted
+    let result = harness::analyze(&fixture_dir, AnalysisOptions::default());
+    let stats = &result.chain_graph.stats;
+    assert_eq!(stats.suppressed_count, 2);
+    assert_eq!(
+        stats.endings,
+        vec![(ChainEnd::Unwrapped, 2), (ChainEnd::Discarded, 2)]
+    );
+    assert_eq!(
+        discarded_calls(&result),
+        vec!["src/main.rs:33", "src/main.rs:16"]
+    );
+
+    // Those of the functions ignored in the manifest are accepted as well
+    let options = AnalysisOptions {
+        ignore: vec![
+            String::from("crate::cli::*"),
+            String::from("crate::config::load"),
+        ],
+        ..AnalysisOptions::default()
+    };
+    let result = harness::analyze(&fixture_dir, options);
+    let stats = &result.chain_graph.stats;
+    assert_eq!(stats.suppressed_count, 4);
+    assert_eq!(
+        stats.endings,
+        vec![(ChainEnd::Unwrapped, 1), (ChainEnd::Discarded, 1)]
+    );
+    assert_eq!(discarded_calls(&result), vec!["src/main.rs:16"]);
+    let listed = stats.deepest_chains.iter().chain(&stats.largest_chains);
+    for summary in listed.chain(&stats.most_severe_chains) {
+        assert_eq!(summary.head, "main");
+        assert!(!summary.suppressed);
+    }
+}
+
+#[test]
+fn accepted_error_losses_are_left_out_of_sarif() {
+    let fixture_dir = harness::fixture_dir("suppression");
+    let out_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("suppression");
+    std::fs::create_dir_all(&out_dir).expect("Could not create the output directory!");
+    let stats_path = out_dir.join("stats.json");
+    let sarif_path = out_dir.join("results.sarif");
+
+    // The binary reads the functions to ignore from the manifest
+    let output = Command::new(env!("CARGO_BIN_EXE_static-result-analyzer"))
+        .current_dir(&fixture_dir)
+        .arg("Cargo.toml")
+        .arg(out_dir.join("chain.dot"))
+        .arg("--stats-json")
+        .arg(&stats_path)
+        .arg("--format")
+        .arg("sarif")
+        .arg(&sarif_path)
+        .arg("--no-cache")
+        .output()
+        .expect("Could not run the analyzer!");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let read = |path: &Path| -> serde_json::Value {
+        serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+    };
+    let stats = read(&stats_path);
+    assert_eq!(stats["suppressed_chains"], 4);
+    assert_eq!(stats["endings"]["unwrapped"], 1);
+    assert_eq!(stats["endings"]["discarded"], 1);
+    assert_eq!(
+        stats["discarded_calls"],
+        serde_json::json!(["src/main.rs:16"])
+    );
+
+    let sarif = read(&sarif_path);
+    let results = sarif["runs"][0]["results"].as_array().unwrap();
+    assert_eq!(results.len(), 2);
+}
+
+#[test]
+fn default_trait_methods_are_walked_once() {
+    let result = harness::analyze(
+        &harness::fixture_dir("trait_default_methods"),
+        AnalysisOptions::default(),
+    );
+    let graph = &result.call_graph;
+
+    // The default method propagates the error of the local function it calls
+    let read = harness::assert_edge(graph, "Loader::load", "read_source", true);
+    assert!(read.propagates);
+    let loads = (0..graph.nodes.len())
+        .filter(|node| graph.label(*node) == "Loader::load")
+        .count();
+    assert_eq!(loads, 1);
+
+    // Both calls of the default method share its propagation path, so they are shown as one chain
+    let stats = &result.chain_graph.stats;
+    assert_eq!((stats.count, stats.merged_count), (1, 1));
+    assert_eq!(
+        stats.endings,
+        vec![(ChainEnd::LoggedAndDropped, 1), (ChainEnd::Discarded, 1)]
+    );
+}
+
+#[test]
+fn try_operators_in_closures_propagate_from_the_closure() {
+    let result = harness::analyze(
+        &harness::fixture_dir("closure_propagation"),
+        AnalysisOptions::default(),
+    );
+    let graph = &result.call_graph;
+
+    // The only `?` of `doubled` is in its closure, so the closure propagates the error rather than `doubled`
+    assert!(!graph
+        .edges
+        .iter()
+        .any(|edge| { graph.label(edge.from) == "doubled" && graph.label(edge.to) == "parse" }));
+    let parse = harness::assert_edge(graph, "doubled::{closure#0}", "parse", true);
+    assert!(parse.propagates);
+    // Nor does the iterator adapter taking the closure, its errors are those of the closure
+    let map = harness::assert_edge(graph, "doubled", "std::iter::Iterator::map", false);
+    assert!(!map.propagates);
+}
+
+#[test]
+fn errors_of_spawned_tasks_whose_handle_is_dropped_are_lost() {
+    let result = harness::analyze(
+        &harness::fixture_dir("spawned_tasks"),
+        AnalysisOptions::default(),
+    );
+    let graph = &result.call_graph;
+
+    // The spawned closures and async blocks are connected to the spawner by a spawn edge, handled if the handle is awaited or joined
+    let spawns = [
+        ("awaited_task", Handling::Handled),
+        ("forgotten_task", Handling::Discarded),
+        ("joined_thread", Handling::Handled),
+        ("detached_thread", Handling::Discarded),
+        ("unused_handle", Handling::Discarded),
+    ];
+    for (spawner, handling) in spawns {
+        let task = if spawner.ends_with("_task") {
+            format!("{spawner}::{{closure#0}}::{{closure#0}}")
+        } else {
+            format!("{spawner}::{{closure#0}}")
+        };
+        let spawn = harness::assert_edge(graph, spawner, &task, true);
+        assert_eq!(spawn.implicit, Some(ImplicitCall::Spawn), "{spawner}");
+        assert_eq!(spawn.handling, handling, "{spawner}");
+    }
+
+    // The chains of the dropped handles end at the spawn
+    let mut lost: Vec<&str> = result
+        .chain_graph
+        .chains
+        .iter()
+        .filter_map(|chain| {
+            let end = chain.calls.last()?;
+            (end.implicit == Some(ImplicitCall::Spawn) && end.handling == Handling::Discarded).then(
+                || {
+                    assert_eq!(chain.termination, ChainEnd::Discarded);
+                    graph.label(end.from)
+                },
+            )
+        })
+        .collect();
+    lost.sort_unstable();
+    assert_eq!(lost, ["detached_thread", "forgotten_task", "unused_handle"]);
+
+    let stats = &result.chain_graph.stats;
+    assert_eq!(stats.lost_to_tasks_count, 3);
+    assert!(stats
+        .to_string()
+        .contains("3 errors are lost in spawned tasks whose handle is dropped."));
+}
+
+#[test]
+fn try_operators_in_try_blocks_propagate_to_the_block() {
+    let result = harness::analyze(
+        &harness::fixture_dir("try_blocks"),
+        AnalysisOptions::default(),
+    );
+    let graph = &result.call_graph;
+
+    // The errors of `sum_or_zero` go to its try block, not to its callers
+    let in_block: Vec<&CallEdge> = graph
+        .edges
+        .iter()
+        .filter(|edge| graph.label(edge.from) == "sum_or_zero" && graph.label(edge.to) == "parse")
+        .collect();
+    assert_eq!(in_block.len(), 2);
+    for edge in in_block {
+        assert!(edge.is_error);
+        assert!(edge.propagates_locally);
+        assert!(!edge.propagates);
+    }
+    // While those of `sum` go to its callers
+    assert!(harness::assert_edge(graph, "sum", "parse", true).propagates);
+}
+
+#[test]
+fn calls_through_pointers_and_closures_are_indirect_calls() {
+    let result = harness::analyze(
+        &harness::fixture_dir("indirect_calls"),
+        AnalysisOptions::default(),
+    );
+    let graph = &result.call_graph;
+
+    // A function item stored in a variable is still a call of that function
+    assert!(graph.edges.iter().any(|edge| {
+        graph.label(edge.from) == "run" && graph.label(edge.to) == "helper" && edge.propagates
+    }));
+
+    // The function pointer in a variable and the one in a field are called through their signature
+    let pointer = "indirect call: fn() -> std::result::Result<(), MyError>";
+    let pointer_calls: Vec<&CallEdge> = graph
+        .edges
+        .iter()
+        .filter(|edge| graph.label(edge.from) == "run" && graph.label(edge.to) == pointer)
+        .collect();
+    assert_eq!(pointer_calls.len(), 2);
+    for edge in pointer_calls {
+        assert!(edge.is_error);
+        assert!(edge.propagates);
+    }
+
+    // As is the closure stored in a variable
+    let closure = harness::assert_edge(
+        graph,
+        "run",
+        "indirect call: fn(bool) -> std::result::Result<(), MyError>",
+        true,
+    );
+    assert!(closure.propagates);
+}
+
+#[test]
+fn overloaded_operators_are_calls_of_their_implementation() {
+    let result = harness::analyze(
+        &harness::fixture_dir("operator_overloads"),
+        AnalysisOptions::default(),
+    );
+    let graph = &result.call_graph;
+
+    // Indexing and adding call the local implementations, which panic
+    for operator in [
+        "<Table as std::ops::Index<usize>>::index",
+        "<Small as std::ops::Add>::add",
+    ] {
+        let edge = harness::assert_edge(graph, "main", operator, false);
+        assert_eq!(edge.implicit, Some(ImplicitCall::Operator), "{operator}");
+        assert!(graph.nodes[edge.to].panics, "{operator}");
+    }
+    // The table is indexed through the wrapper it is in
+    let deref = harness::assert_edge(graph, "main", "<Wrapper as std::ops::Deref>::deref", false);
+    assert_eq!(deref.implicit, Some(ImplicitCall::Deref));
+}
+
+#[test]
+fn for_loops_call_the_iterator_of_their_expression() {
+    let result = harness::analyze(
+        &harness::fixture_dir("for_loops"),
+        AnalysisOptions::default(),
+    );
+    let graph = &result.call_graph;
+
+    // The loop turns the lines into an iterator and calls its `next`, which parses each line
+    let into_iter = harness::assert_edge(
+        graph,
+        "sum",
+        "<Lines as std::iter::IntoIterator>::into_iter",
+        false,
+    );
+    assert_eq!(into_iter.implicit, Some(ImplicitCall::Iterator));
+    let next = harness::assert_edge(graph, "sum", "<Numbers as std::iter::Iterator>::next", true);
+    assert_eq!(next.implicit, Some(ImplicitCall::Iterator));
+    harness::assert_edge(
+        graph,
+        "<Numbers as std::iter::Iterator>::next",
+        "parse",
+        true,
+    );
+}
+
+#[test]
+fn context_added_to_errors_is_shown_on_their_calls() {
+    let result = harness::analyze(
+        &harness::fixture_dir("anyhow_context"),
+        AnalysisOptions::default(),
+    );
+    let graph = &result.call_graph;
+
+    // The message is kept for the call whose error it is added to, whether given directly or by a closure
+    let read = harness::assert_edge(graph, "load", "read_config", true);
+    assert_eq!(
+        read.context_message.as_deref(),
+        Some("failed to load the config")
+    );
+    let parse = harness::assert_edge(graph, "parse_port", "str::parse", true);
+    assert_eq!(
+        parse.context_message.as_deref(),
+        Some("the port is not a number")
+    );
+
+    // And shown in the labels of the chain graph
+    let mut dot = Vec::new();
+    result.chain_graph.write_dot(&mut dot).unwrap();
+    let dot = String::from_utf8(dot).unwrap();
+    for message in [
+        "failed to read the config file",
+        "failed to load the config",
+        "the port is not a number",
+    ] {
+        assert!(
+            dot.contains(&format!("(context \\\"{message}\\\")")),
+            "{message}:\n{dot}"
+        );
+    }
+}
+
+#[test]
+fn aliases_and_listed_result_types_carry_errors() {
+    let fixture_dir = harness::fixture_dir("result_types");
+
+    // The alias is expanded, so its error is found without listing it
+    let result = harness::analyze(&fixture_dir, AnalysisOptions::default());
+    let graph = &result.call_graph;
+    let unit_error = harness::assert_edge(graph, "main", "unit_error", true);
+    assert_eq!(graph.type_label(unit_error).as_deref(), Some("()"));
+    assert_eq!(unit_error.handling, Handling::Discarded);
+    harness::assert_edge(graph, "main", "outcome", false);
+    harness::assert_edge(graph, "main", "attempt", false);
+
+    // While the newtype and the enum carry an error only when listed
+    let options = AnalysisOptions {
+        result_types: vec![
+            String::from("crate::Outcome"),
+            String::from("crate::Attempt"),
+        ],
+        ..AnalysisOptions::default()
+    };
+    let result = harness::analyze(&fixture_dir, options);
+    let graph = &result.call_graph;
+    for function in ["outcome", "attempt"] {
+        let edge = harness::assert_edge(graph, "main", function, true);
+        assert_eq!(
+            graph.type_label(edge).as_deref(),
+            Some("AppError"),
+            "{function}"
+        );
+    }
+}
+
+#[test]
+fn boxed_futures_carry_the_errors_of_their_output() {
+    let result = harness::analyze(
+        &harness::fixture_dir("boxed_futures"),
+        AnalysisOptions::default(),
+    );
+    let graph = &result.call_graph;
+
+    // The output of the boxed futures is the type of their calls, whether behind a trait or not
+    for (from, to) in [("fetch_twice", "Fetcher::fetch"), ("run", "load")] {
+        let edge = harness::assert_edge(graph, from, to, true);
+        assert_eq!(
+            graph.type_label(edge).as_deref(),
+            Some("FetchError"),
+            "{from} -> {to}"
+        );
+        assert!(edge.propagates, "{from} -> {to}");
+    }
+    let count = harness::assert_edge(graph, "total", "Fetcher::count", true);
+    assert_eq!(graph.type_label(count).as_deref(), Some("None"));
+    harness::assert_edge(graph, "total", "ticks", false);
+}
+
+#[test]
+fn method_calls_and_qualified_paths_are_the_same_calls() {
+    let result = harness::analyze(
+        &harness::fixture_dir("qualified_paths"),
+        AnalysisOptions::default(),
+    );
+    let graph = &result.call_graph;
+    let calls = |function: &str| -> Vec<String> {
+        let mut calls: Vec<String> = graph
+            .edges
+            .iter()
+            .filter(|edge| graph.label(edge.from) == function)
+            .map(|edge| {
+                format!(
+                    "{} (error: {}, propagates: {}, {:?}, {:?})",
+                    graph.label(edge.to),
+                    edge.is_error,
+                    edge.propagates,
+                    edge.handling,
+                    graph.type_label(edge)
+                )
+            })
+            .collect();
+        calls.sort_unstable();
+        calls
+    };
+
+    let method_calls = calls("method_calls");
+    assert!(method_calls
+        .iter()
+        .any(|call| call
+            .starts_with("<Config as Validate>::validate (error: true, propagates: true")));
+    assert_eq!(method_calls, calls("path_calls"));
+}
+
+#[test]
+fn errors_collected_from_iterators_are_propagated() {
+    let result = harness::analyze(
+        &harness::fixture_dir("collect_results"),
+        AnalysisOptions::default(),
+    );
+    let graph = &result.call_graph;
+
+    // The parse mapped over the input is called by collecting its results
+    let parse = harness::assert_edge(graph, "parse_path", "str::parse", true);
+    assert_eq!(parse.implicit, Some(ImplicitCall::Collect));
+    assert!(parse.propagates);
+
+    // So its errors are discarded by main, through parse_path
+    let chains = &result.chain_graph.chains;
+    assert_eq!(chains.len(), 3);
+    let chain = chains
+        .iter()
+        .find(|chain| {
+            let end = chain.calls.last().unwrap();
+            graph.label(end.from) == "main" && graph.label(end.to) == "parse_path"
+        })
+        .expect("No chain through parse_path!");
+    assert_eq!(chain.termination, ChainEnd::Discarded);
+    let calls: Vec<(String, String)> = chain
+        .calls
+        .iter()
+        .map(|call| (graph.full_path(call.from), graph.full_path(call.to)))
+        .collect();
+    let called = |from: &str, to: &str| (String::from(from), String::from(to));
+    assert_eq!(
+        calls,
+        [
+            called("parse_path", "std::prelude::v1::Ok"),
+            called("parse_path", "std::ops::Try::branch"),
+            called("parse_path", "std::iter::Iterator::collect"),
+            called("parse_path", "core::str::<impl str>::parse"),
+            called("main", "parse_path"),
+        ]
+    );
+}
+
+#[test]
+fn panics_caught_at_a_boundary_do_not_reach_the_caller() {
+    let options = AnalysisOptions {
+        graph: GraphKind::Panic,
+        ..AnalysisOptions::default()
+    };
+    let result = harness::analyze(&harness::fixture_dir("panic_boundaries"), options);
+    let graph = &result.call_graph;
+
+    // The closures run by `catch_unwind` and in threads have their panics caught
+    for (caller, closure) in [
+        ("guarded", "guarded::{closure#0}"),
+        ("in_thread", "in_thread::{closure#0}"),
+        ("in_named_thread", "in_named_thread::{closure#0}"),
+    ] {
+        let edge = graph
+            .edges
+            .iter()
+            .find(|edge| graph.label(edge.from) == caller && graph.label(edge.to) == closure)
+            .unwrap_or_else(|| panic!("No call from {caller} to {closure}!"));
+        assert!(edge.catches_panic, "{caller}");
+        assert!(graph.nodes[edge.to].may_panic, "{closure}");
+    }
+    let may_panic = |function: &str| {
+        (0..graph.nodes.len())
+            .any(|node| graph.label(node) == function && graph.nodes[node].may_panic)
+    };
+    assert!(!may_panic("guarded"));
+    assert!(!may_panic("in_thread"));
+    assert!(may_panic("unguarded"));
+
+    // So the panic chains through them end at the boundary
+    let stats = result.chain_graph.stats.panics.as_ref().unwrap();
+    // Counting the call wrapping the closure in `AssertUnwindSafe`, which is within the boundary as well
+    assert_eq!(stats.caught_count, 4);
+    let mut dot = vec![];
+    result.chain_graph.write_dot(&mut dot).unwrap();
+    let dot = String::from_utf8(dot).unwrap();
+    assert_eq!(dot.matches("label=\"panic (caught)\"").count(), 3);
+}
@@ -0,0 +1,248 @@
+//! Runs the analyzer on a fixture crate in `test/`, and compares the chain graph and statistics it writes
+//! with the golden files in `tests/golden/`.
+//! Run using `UPDATE_GOLDEN=1` to write the golden files from the current outputs instead, e.g. for a new fixture.
+//!
+//! Fixtures can instead state their expected results in an `expected.toml` next to their manifest:
+//! their number of chains, and calls that should be in their call graph, e.g.
+//!
+//! ```toml
+//! chains = 2
+//!
+//! [[edges]]
+//! from = "load"
+//! to = "std::fs::read_to_string"
+//! error = true
+//! handling = "propagated"
+//! ```
+
+use regex::Regex;
+use serde::Deserialize;
+use static_error_analyzer::graph::{CallEdge, CallGraph};
+use static_error_analyzer::{
+    analyze_crate, AnalysisOptions, AnalysisResult, AnalyzerError, CrateOptions,
+};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Mutex;
+
+/// The expected results of analyzing a fixture, as stated in its `expected.toml`.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Expected {
+    /// The number of chains of the fixture.
+    chains: usize,
+    /// Calls that should be in the call graph of the fixture.
+    #[serde(default)]
+    edges: Vec<ExpectedEdge>,
+}
+
+/// A call that should be in the call graph of a fixture.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ExpectedEdge {
+    /// The label or full path of the calling function.
+    from: String,
+    /// The label or full path of the called function.
+    to: String,
+    /// Whether the call carries an error.
+    error: bool,
+    /// How the caller deals with the error (`propagated`, `handled`, `unwrapped`, `discarded` or `unknown`), if stated.
+    handling: Option<String>,
+}
+
+/// Analyze the fixture crate `test/<fixture>` using the library, and check the results stated in its `expected.toml`.
+pub fn check_expected(fixture: &str) {
+    let fixture_dir = fixture_dir(fixture);
+    let expected_path = fixture_dir.join("expected.toml");
+    let expected: Expected = toml::from_str(&read(&expected_path))
+        .unwrap_or_else(|e| panic!("Could not parse {}: {e}", expected_path.display()));
+
+    let result = analyze(&fixture_dir, AnalysisOptions::default());
+    assert_eq!(
+        result.chain_graph.stats.count, expected.chains,
+        "Unexpected number of chains in {fixture}"
+    );
+    for expected_edge in &expected.edges {
+        let edge = assert_edge(
+            &result.call_graph,
+            &expected_edge.from,
+            &expected_edge.to,
+            expected_edge.error,
+        );
+        if let Some(handling) = &expected_edge.handling {
+            assert_eq!(
+                &format!("{:?}", edge.handling).to_lowercase(),
+                handling,
+                "Unexpected handling of the call from {} to {} in {fixture}",
+                expected_edge.from,
+                expected_edge.to
+            );
+        }
+    }
+}
+
+/// Get the directory of the fixture crate `test/<fixture>`.
+pub fn fixture_dir(fixture: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("test")
+        .join(fixture)
+}
+
+/// Analyze a fixture crate in this process with the given options, returning its graphs.
+pub fn analyze(fixture_dir: &Path, options: AnalysisOptions) -> AnalysisResult {
+    try_analyze(fixture_dir, options).unwrap_or_else(|e| {
+        panic!(
+            "Could not analyze {}: {e} ({})",
+            fixture_dir.display(),
+            e.hint()
+        )
+    })
+}
+
+/// Analyze a fixture crate in this process with the given options, returning its graphs or why it could not be analyzed.
+pub fn try_analyze(
+    fixture_dir: &Path,
+    options: AnalysisOptions,
+) -> Result<AnalysisResult, AnalyzerError> {
+    analyze_crate_options(CrateOptions {
+        manifest_path: fixture_dir.join("Cargo.toml"),
+        options,
+        ..CrateOptions::default()
+    })
+}
+
+/// Analyze a fixture crate in this process with the given options, using its cached call graph if it is unchanged, and caching it otherwise.
+pub fn analyze_cached(fixture_dir: &Path, options: AnalysisOptions) -> AnalysisResult {
+    analyze_crate_options(CrateOptions {
+        manifest_path: fixture_dir.join("Cargo.toml"),
+        options,
+        use_cache: true,
+        ..CrateOptions::default()
+    })
+    .unwrap_or_else(|e| panic!("Could not analyze {}: {e}", fixture_dir.display()))
+}
+
+/// Analyze a crate in this process.
+fn analyze_crate_options(options: CrateOptions) -> Result<AnalysisResult, AnalyzerError> {
+    // The compiler is run in this process, so the fixtures are analyzed one at a time
+    static COMPILER: Mutex<()> = Mutex::new(());
+    let _compiler = COMPILER
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+    analyze_crate(&options)
+}
+
+/// Assert that the call graph has a call from the function `from` to the function `to`, that carries an error if `is_error` is set.
+/// Functions are matched by their label (e.g. `Source::read`) or their full path. Returns the call, to check it further.
+pub fn assert_edge<'a>(graph: &'a CallGraph, from: &str, to: &str, is_error: bool) -> &'a CallEdge {
+    let matches =
+        |node: usize, name: &str| graph.label(node) == name || graph.full_path(node) == name;
+
+    let calls: Vec<&CallEdge> = graph
+        .edges
+        .iter()
+        .filter(|edge| matches(edge.from, from) && matches(edge.to, to))
+        .collect();
+    if let Some(edge) = calls.iter().find(|edge| edge.is_error == is_error) {
+        return edge;
+    }
+
+    let called: Vec<String> = graph
+        .edges
+        .iter()
+        .filter(|edge| matches(edge.from, from))
+        .map(|edge| format!("  {} (error: {})", graph.label(edge.to), edge.is_error))
+        .collect();
+    panic!(
+        "No call from {from} to {to} with error: {is_error}, {from} calls:\n{}",
+        called.join("\n")
+    );
+}
+
+/// Analyze the fixture crate `test/<fixture>`, check that it has the expected number of chains,
+/// and compare its outputs with `tests/golden/<golden>.chain.dot` and `tests/golden/<golden>.stats.json`.
+pub fn check(fixture: &str, chains: usize, golden: &str) {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let fixture_dir = fixture_dir(fixture);
+    let out_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join(fixture);
+    std::fs::create_dir_all(&out_dir).expect("Could not create the output directory!");
+    let chain_path = out_dir.join("chain.dot");
+    let stats_path = out_dir.join("stats.json");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_static-result-analyzer"))
+        .current_dir(&fixture_dir)
+        .arg("Cargo.toml")
+        .arg(&chain_path)
+        .arg("--stats-json")
+        .arg(&stats_path)
+        .arg("--no-cache")
+        .output()
+        .expect("Could not run the analyzer!");
+    assert!(
+        output.status.success(),
+        "The analyzer failed on {fixture}:\n{}\n{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let stats = read(&stats_path);
+    let parsed: serde_json::Value =
+        serde_json::from_str(&stats).expect("Could not parse the statistics!");
+    assert_eq!(
+        parsed["chains"].as_u64(),
+        Some(chains as u64),
+        "Unexpected number of chains in {fixture}"
+    );
+
+    let golden_dir = root.join("tests").join("golden");
+    compare(
+        &normalize(&read(&chain_path), &fixture_dir),
+        &golden_dir.join(format!("{golden}.chain.dot")),
+    );
+    compare(
+        &normalize(&stats, &fixture_dir),
+        &golden_dir.join(format!("{golden}.stats.json")),
+    );
+}
+
+/// Read an output of the analyzer.
+fn read(path: &PathBuf) -> String {
+    std::fs::read_to_string(path)
+        .unwrap_or_else(|e| panic!("Could not read {}: {e}", path.display()))
+}
+
+/// Leave out the parts of an output that differ between machines:
+/// the path of the fixture, the paths of the dependencies in the cargo registry, and line endings.
+fn normalize(output: &str, fixture_dir: &Path) -> String {
+    let registry = Regex::new(r#"[^"\s]*[/\\]registry[/\\]src[/\\][^/\\]+[/\\]"#).unwrap();
+    let output = output
+        .replace("\r\n", "\n")
+        .replace(&fixture_dir.display().to_string(), "$FIXTURE");
+    registry.replace_all(&output, "$$REGISTRY/").into_owned()
+}
+
+/// Compare an output with its golden file, or write the golden file if `UPDATE_GOLDEN` is set.
+fn compare(output: &str, golden_path: &Path) {
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::write(golden_path, output)
+            .unwrap_or_else(|e| panic!("Could not write {}: {e}", golden_path.display()));
+        return;
+    }
+
+    let golden = std::fs::read_to_string(golden_path)
+        .unwrap_or_else(|e| panic!("Could not read {}: {e}", golden_path.display()))
+        .replace("\r\n", "\n");
+    if output != golden {
+        let line = output
+            .lines()
+            .zip(golden.lines())
+            .position(|(output, golden)| output != golden)
+            .unwrap_or(output.lines().count().min(golden.lines().count()));
+        panic!(
+            "The output differs from {} from line {}, run using UPDATE_GOLDEN=1 to accept it:\n{output}",
+            golden_path.display(),
+            line + 1
+        );
+    }
+}
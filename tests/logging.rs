@@ -0,0 +1,86 @@
+//! Tests of the format the messages of the analyzer are printed in.
+
+use static_error_analyzer::logging::MessageFormat;
+use static_error_analyzer::timings::Timings;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use tracing::{debug, info, warn};
+use tracing_subscriber::fmt::MakeWriter;
+
+/// A writer collecting the printed messages.
+#[derive(Clone, Default)]
+struct Captured(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Captured {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a> MakeWriter<'a> for Captured {
+    type Writer = Captured;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self.clone()
+    }
+}
+
+/// Capture the messages emitted by the given function, filtered by the given directives.
+fn capture(filter: &str, emit: impl FnOnce()) -> String {
+    let captured = Captured::default();
+    let subscriber = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(captured.clone())
+        .with_ansi(false)
+        .event_format(MessageFormat)
+        .finish();
+    tracing::subscriber::with_default(subscriber, emit);
+    let bytes = captured.0.lock().unwrap().clone();
+    String::from_utf8(bytes).unwrap()
+}
+
+#[test]
+fn info_messages_are_printed_as_they_are() {
+    let output = capture("info", || {
+        info!(target: "analyzer::cargo", "Building package...");
+        warn!(target: "analyzer::graph", "2 calls could not be resolved");
+    });
+
+    assert_eq!(
+        output,
+        "Building package...\nWarning: 2 calls could not be resolved\n"
+    );
+}
+
+#[test]
+fn messages_are_filtered_by_target() {
+    let output = capture("warn,analyzer::graph=info", || {
+        info!(target: "analyzer::cargo", "Building package...");
+        info!(target: "analyzer::graph", "Created the call graph");
+        debug!(target: "analyzer::graph", "Explored main");
+    });
+
+    assert_eq!(output, "Created the call graph\n");
+}
+
+#[test]
+fn debug_messages_show_their_stage() {
+    let output = capture("debug", || {
+        let mut timings = Timings::default();
+        timings.time("edge typing", || {
+            debug!(target: "analyzer::graph", "Typed 3 calls");
+        });
+    });
+    let lines: Vec<&str> = output.lines().collect();
+
+    assert_eq!(
+        lines[0],
+        "DEBUG analyzer::graph [stage=edge typing]: Typed 3 calls"
+    );
+    assert!(lines[1].starts_with("DEBUG analyzer::timings [stage=edge typing]: edge typing took "));
+}
@@ -0,0 +1,94 @@
+//! Tests of the versioned schema the graphs are (de)serialized in.
+//! The graphs with functions and calls are round-tripped by the end-to-end tests, as these need the compiler to create.
+
+use static_error_analyzer::schema::SCHEMA_VERSION;
+use static_error_analyzer::{CallGraph, ChainGraph};
+
+#[test]
+fn empty_graphs_round_trip() {
+    let json = serde_json::to_string(&CallGraph::new(String::from("app"))).unwrap();
+    let graph: CallGraph = serde_json::from_str(&json).unwrap();
+    assert_eq!(serde_json::to_string(&graph).unwrap(), json);
+    assert!(!graph.is_resolved());
+
+    let json = serde_json::to_string(&ChainGraph::new(String::from("app"))).unwrap();
+    let graph: ChainGraph = serde_json::from_str(&json).unwrap();
+    assert_eq!(serde_json::to_string(&graph).unwrap(), json);
+}
+
+#[test]
+fn schema_version_comes_first() {
+    let json = serde_json::to_string(&CallGraph::new(String::from("app"))).unwrap();
+
+    assert!(json.starts_with(&format!(
+        "{{\"schema_version\":{SCHEMA_VERSION},\"graph\":{{"
+    )));
+}
+
+#[test]
+fn newer_schema_version_is_rejected() {
+    // The graph of a newer version is not read, so its contents do not matter
+    let json = format!(
+        r#"{{"schema_version": {}, "graph": {{"nodes": "changed"}}}}"#,
+        SCHEMA_VERSION + 1
+    );
+
+    let error = serde_json::from_str::<CallGraph>(&json).unwrap_err();
+    assert!(error.to_string().contains("newer"), "{error}");
+    let error = serde_json::from_str::<ChainGraph>(&json).unwrap_err();
+    assert!(error.to_string().contains("newer"), "{error}");
+}
+
+#[test]
+fn older_schema_version_is_rejected() {
+    let json = r#"{"schema_version": 0, "graph": {}}"#;
+
+    let error = serde_json::from_str::<CallGraph>(json).unwrap_err();
+    assert!(error.to_string().contains("older"), "{error}");
+}
+
+#[test]
+fn missing_schema_version_is_rejected() {
+    // E.g. a graph serialized before the schema was versioned
+    let json = serde_json::to_string(&CallGraph::new(String::from("app"))).unwrap();
+    let (_version, graph) = json.split_once(",\"graph\":").unwrap();
+    let unversioned = graph.strip_suffix('}').unwrap();
+
+    assert!(serde_json::from_str::<CallGraph>(unversioned).is_err());
+    let error =
+        serde_json::from_str::<CallGraph>(&format!("{{\"graph\":{unversioned}}}")).unwrap_err();
+    assert!(error.to_string().contains("schema_version"), "{error}");
+}
+
+#[test]
+fn malformed_call_id_is_rejected() {
+    let json = serde_json::to_string(&CallGraph::new(String::from("app"))).unwrap();
+    let edge = r#"{"from":0,"to":0,"call_id":"app::main"}"#;
+    let json = json.replace("\"edges\":[]", &format!("\"edges\":[{edge}]"));
+
+    let error = serde_json::from_str::<CallGraph>(&json).unwrap_err();
+    assert!(error.to_string().contains("owner@index"), "{error}");
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn load_checks_the_schema_version() {
+    let dir = std::env::temp_dir().join(format!("schema-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("call.json");
+
+    CallGraph::new(String::from("app")).save(&path).unwrap();
+    let graph = CallGraph::load(&path).unwrap();
+    assert_eq!(graph.crate_name, "app");
+
+    let newer = std::fs::read_to_string(&path).unwrap().replacen(
+        &format!("\"schema_version\":{SCHEMA_VERSION}"),
+        &format!("\"schema_version\":{}", SCHEMA_VERSION + 1),
+        1,
+    );
+    std::fs::write(&path, newer).unwrap();
+    let error = CallGraph::load(&path).unwrap_err();
+    assert!(error.contains("newer"), "{error}");
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,283 @@
+//! Tests of the summary printed to the terminal: its colors, and the table of the functions fit in the width of the terminal.
+
+use static_error_analyzer::graph::ChainEnd;
+use static_error_analyzer::stats::{ChainHop, ChainStats, ChainSummary, FunctionSummary};
+use static_error_analyzer::summary::{truncate_path, ColorChoice, Summary};
+
+fn function(function: &str, handled: usize, unwrapped: usize) -> FunctionSummary {
+    FunctionSummary {
+        function: String::from(function),
+        fallible_calls: handled + unwrapped,
+        propagated: 0,
+        handled,
+        logged: 0,
+        exited: 0,
+        unwrapped,
+        discarded: 0,
+        returns_result: false,
+        deepest_chain: 1,
+    }
+}
+
+fn stats() -> ChainStats {
+    ChainStats {
+        count: 2,
+        raw_count: 2,
+        endings: vec![(ChainEnd::Unwrapped, 1), (ChainEnd::Handled, 1)],
+        functions: vec![
+            function(
+                "app::storage::backends::postgres::connection::Connection::fetch_all_rows",
+                0,
+                1,
+            ),
+            function("app::main", 1, 0),
+        ],
+        ..ChainStats::default()
+    }
+}
+
+/// Get the lines of the table of the functions in the summary.
+fn table_lines(summary: &str) -> Vec<&str> {
+    summary
+        .lines()
+        .skip_while(|line| !line.starts_with("How the functions"))
+        .skip(1)
+        .take_while(|line| line.starts_with("  "))
+        .collect()
+}
+
+#[test]
+fn short_paths_are_not_truncated() {
+    assert_eq!(truncate_path("app::main", 20), "app::main");
+}
+
+#[test]
+fn truncation_keeps_the_tail_of_the_path() {
+    assert_eq!(
+        truncate_path("app::db::pool::Connection::fetch", 22),
+        "…::Connection::fetch"
+    );
+    assert_eq!(
+        truncate_path("app::db::pool::Connection::fetch", 12),
+        "…::fetch"
+    );
+}
+
+#[test]
+fn long_last_segments_are_cut_off_at_their_start() {
+    let truncated = truncate_path("app::a_function_with_a_very_long_name", 10);
+    assert_eq!(truncated, "…long_name");
+    assert_eq!(truncated.chars().count(), 10);
+}
+
+#[test]
+fn plain_summary_has_no_colors() {
+    let stats = stats();
+    let summary = Summary::plain(&stats).to_string();
+
+    assert!(!summary.contains('\x1b'));
+    assert_eq!(summary, stats.to_string());
+    assert!(summary
+        .contains("app::storage::backends::postgres::connection::Connection::fetch_all_rows"));
+}
+
+#[test]
+fn never_colors() {
+    assert!(!ColorChoice::Never.colors());
+    assert!(ColorChoice::Always.colors());
+    assert_eq!(ColorChoice::from_label("never"), Some(ColorChoice::Never));
+    assert_eq!(ColorChoice::from_label("sometimes"), None);
+}
+
+#[test]
+fn lost_errors_are_red_and_handled_errors_green() {
+    let stats = stats();
+    let summary = Summary {
+        stats: &stats,
+        colors: true,
+        width: None,
+    }
+    .to_string();
+
+    assert!(summary.contains("\x1b[31m1\x1b[0m chains end in an error that is unwrapped."));
+    assert!(summary.contains("\x1b[32m1\x1b[0m chains end in an error that is handled."));
+    // Counts of problems that do not occur are not emphasized
+    assert!(summary.contains("\n0 errors are dropped by converting them"));
+}
+
+#[test]
+fn table_fits_in_the_width() {
+    let stats = stats();
+    let summary = Summary {
+        stats: &stats,
+        colors: false,
+        width: Some(120),
+    }
+    .to_string();
+    let lines = table_lines(&summary);
+
+    assert_eq!(lines.len(), 3);
+    assert!(lines.iter().all(|line| line.chars().count() <= 120));
+    assert!(lines[1].starts_with("  …::fetch_all_rows"));
+    // The columns stay aligned
+    let separators: Vec<Option<usize>> = lines
+        .iter()
+        .map(|line| line.chars().position(|c| c == '|'))
+        .collect();
+    assert!(separators.iter().all(|position| *position == separators[0]));
+}
+
+#[test]
+fn colored_table_stays_aligned() {
+    let stats = stats();
+    let colored = Summary {
+        stats: &stats,
+        colors: true,
+        width: Some(120),
+    }
+    .to_string();
+    let plain = Summary {
+        stats: &stats,
+        colors: false,
+        width: Some(120),
+    }
+    .to_string();
+
+    let strip = |line: &str| {
+        line.replace("\x1b[1m", "")
+            .replace("\x1b[31m", "")
+            .replace("\x1b[32m", "")
+            .replace("\x1b[0m", "")
+    };
+    let colored: Vec<String> = colored.lines().map(strip).collect();
+    let colored = colored.join("\n") + "\n";
+    assert_eq!(table_lines(&colored), table_lines(&plain));
+}
+
+fn chain(index: usize, depth: usize, size: usize, termination: ChainEnd) -> ChainSummary {
+    ChainSummary {
+        index,
+        head: String::from("main"),
+        tail: String::from("parse"),
+        functions: vec![String::from("main"), String::from("parse")],
+        depth,
+        size,
+        termination,
+        location: Some(String::from("src/main.rs:4:5")),
+        hops: vec![ChainHop {
+            function: String::from("parse"),
+            ty: Some(String::from("std::num::ParseIntError")),
+            location: Some(String::from("src/main.rs:4:5")),
+        }],
+        end_location: None,
+        lost_in_task: false,
+        suppressed: false,
+        type_erased: false,
+        downcasts: vec![],
+        must_use: false,
+        score: 12.5,
+    }
+}
+
+/// Statistics of three chains, of 2, 4 and 4 calls, one of which shares its propagation path with another.
+fn histogram_stats() -> ChainStats {
+    let deepest = chain(2, 3, 4, ChainEnd::Unwrapped);
+    ChainStats {
+        count: 3,
+        raw_count: 5,
+        merged_count: 1,
+        max_size: 4,
+        max_depth: 3,
+        total_size: 12,
+        depth_histogram: vec![1, 0, 2],
+        size_histogram: vec![0, 1, 0, 2],
+        deepest_chains: vec![deepest.clone(), chain(0, 1, 2, ChainEnd::Handled)],
+        largest_chains: vec![deepest.clone()],
+        most_severe_chains: vec![deepest],
+        endings: vec![(ChainEnd::Unwrapped, 2), (ChainEnd::Handled, 1)],
+        ..ChainStats::default()
+    }
+}
+
+#[test]
+fn average_size_is_computed_from_the_size_histogram() {
+    assert_eq!(histogram_stats().average_size(), 10.0 / 3.0);
+    assert_eq!(ChainStats::default().average_size(), 0.0);
+}
+
+#[test]
+fn chain_summary_is_one_line() {
+    let mut chain = chain(2, 3, 4, ChainEnd::Handled);
+    assert_eq!(
+        chain.to_string(),
+        "#2 main ← parse (depth 3, 4 calls): handled, originates at src/main.rs:4:5"
+    );
+    chain.location = None;
+    chain.downcasts = vec![String::from("ParseIntError")];
+    assert_eq!(
+        chain.to_string(),
+        "#2 main ← parse (depth 3, 4 calls): handled, downcasts to ParseIntError"
+    );
+}
+
+#[test]
+fn summary_renders_the_counts_and_histograms() {
+    let summary = histogram_stats().to_string();
+    let lines: Vec<&str> = summary.lines().collect();
+
+    assert_eq!(
+        lines[0],
+        "There are 3 error propagation chains in this program (5 before deduplication)."
+    );
+    assert_eq!(
+        lines[1],
+        "1 chains share their propagation path with another chain, and are shown as part of it."
+    );
+    assert!(summary.contains("The biggest chain consists of 4 function calls.\n"));
+    assert!(summary.contains("The longest error path consists of 3 chained function calls.\n"));
+    // Rows are only written for values that occur, the widest bar is that of the most common value
+    let bar = "#".repeat(40);
+    let half = "#".repeat(20);
+    assert!(summary.contains(&format!(
+        "Chains per number of chained function calls:\n  1 | {half} 1\n  3 | {bar} 2\n"
+    )));
+    assert!(summary.contains(&format!(
+        "Chains per number of function calls:\n  2 | {half} 1\n  4 | {bar} 2\n"
+    )));
+    assert!(summary.contains(
+        "The chains with the longest error paths:\n  \
+         #2 main ← parse (depth 3, 4 calls): unwrapped, originates at src/main.rs:4:5\n  \
+         #0 main ← parse (depth 1, 2 calls): handled, originates at src/main.rs:4:5\n"
+    ));
+    assert!(summary.contains("  severity 12.5: #2 main ← parse"));
+    assert!(summary.contains("2 chains end in an error that is unwrapped.\n"));
+    assert!(summary.contains("0 of the 12 calls in these chains return a type-erased error"));
+}
+
+#[test]
+fn summary_without_chains_has_no_histograms() {
+    let summary = ChainStats::default().to_string();
+    assert!(summary.contains("The average chain consists of 0 function calls.\n"));
+    assert!(!summary.contains("Chains per number"));
+    assert!(!summary.contains("share their propagation path"));
+}
+
+#[test]
+fn json_has_the_computed_fields() {
+    let json: serde_json::Value = serde_json::from_str(&histogram_stats().to_json(None)).unwrap();
+    assert_eq!(json["chains"], 3);
+    assert_eq!(json["chains_before_deduplication"], 5);
+    assert_eq!(json["merged_chains"], 1);
+    assert_eq!(json["average_size"], 10.0 / 3.0);
+    assert_eq!(json["depth_histogram"], serde_json::json!([1, 0, 2]));
+    assert_eq!(json["size_histogram"], serde_json::json!([0, 1, 0, 2]));
+    assert_eq!(
+        json["endings"],
+        serde_json::json!({ "unwrapped": 2, "handled": 1 })
+    );
+    assert_eq!(
+        json["deepest_chains"][0]["hops"][0]["type"],
+        "std::num::ParseIntError"
+    );
+    assert!(json.get("timings").is_none());
+}